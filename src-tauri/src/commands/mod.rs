@@ -1,7 +1,7 @@
 use crate::models::{Account, AppConfig, QuotaData};
 use crate::modules;
 use tauri_plugin_opener::OpenerExt;
-use tauri::{Emitter, Manager};
+use tauri::Manager;
 
 // 导出 proxy 命令
 pub mod proxy;
@@ -146,12 +146,32 @@ pub async fn get_current_account() -> Result<Option<Account>, String> {
     }
 }
 
-/// 导出账号（包含 refresh_token）
-use crate::models::AccountExportResponse;
+/// 导出账号（包含 refresh_token，必须提供 passphrase，导出为加密包）
+use crate::models::{AccountExportItem, AccountExportResponse, EncryptedAccountBundle};
 
 #[tauri::command]
-pub async fn export_accounts(account_ids: Vec<String>) -> Result<AccountExportResponse, String> {
-    modules::account::export_accounts_by_ids(&account_ids)
+pub async fn export_accounts(
+    account_ids: Vec<String>,
+    passphrase: Option<String>,
+) -> Result<AccountExportResponse, String> {
+    tokio::task::spawn_blocking(move || {
+        modules::account::export_accounts_by_ids(&account_ids, &passphrase.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 解密账号导出包，返回其中的 email / refresh_token 列表供前端导入
+#[tauri::command]
+pub async fn open_account_bundle(
+    bundle: EncryptedAccountBundle,
+    passphrase: String,
+) -> Result<Vec<AccountExportItem>, String> {
+    tokio::task::spawn_blocking(move || {
+        modules::account::open_account_bundle(&bundle, &passphrase)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 /// 内部辅助功能：在添加或导入账号后自动刷新一次额度
@@ -211,7 +231,6 @@ pub use modules::account::RefreshStats;
 /// 刷新所有账号配额 (内部实现)
 pub async fn refresh_all_quotas_internal(
     proxy_state: &crate::commands::proxy::ProxyServiceState,
-    app_handle: Option<tauri::AppHandle>,
 ) -> Result<RefreshStats, String> {
     let stats = modules::account::refresh_all_quotas_logic().await?;
 
@@ -221,11 +240,11 @@ pub async fn refresh_all_quotas_internal(
         let _ = instance.token_manager.reload_all_accounts().await;
     }
 
-    // 发送全局刷新事件给 UI (如果需要)
-    if let Some(handle) = app_handle {
-        use tauri::Emitter;
-        let _ = handle.emit("accounts://refreshed", ());
-    }
+    // 发送全局刷新事件给 UI (经事件总线转发给 Tauri 前端与 /api/events)
+    modules::event_bus::publish(modules::event_bus::AppEvent::new(
+        modules::event_bus::EVENT_ACCOUNTS_REFRESHED,
+        (),
+    ));
 
     Ok(stats)
 }
@@ -234,9 +253,8 @@ pub async fn refresh_all_quotas_internal(
 #[tauri::command]
 pub async fn refresh_all_quotas(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
-    app_handle: tauri::AppHandle,
 ) -> Result<RefreshStats, String> {
-    refresh_all_quotas_internal(&proxy_state, Some(app_handle)).await
+    refresh_all_quotas_internal(&proxy_state).await
 }
 /// 获取设备指纹（当前 storage.json + 账号绑定）
 #[tauri::command]
@@ -330,14 +348,29 @@ pub async fn load_config() -> Result<AppConfig, String> {
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
-    app: tauri::AppHandle,
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
     modules::save_app_config(&config)?;
+    internal_apply_config(&proxy_state, &config).await;
+    Ok(())
+}
 
+/// 将已保存的配置热更新到运行中的服务 (供 save_config 与声明式配置文件重载共用)
+pub async fn internal_apply_config(
+    proxy_state: &crate::commands::proxy::ProxyServiceState,
+    config: &AppConfig,
+) {
     // 通知托盘配置已更新
-    let _ = app.emit("config://updated", ());
+    modules::event_bus::publish(modules::event_bus::AppEvent::new(
+        modules::event_bus::EVENT_CONFIG_UPDATED,
+        (),
+    ));
+
+    // [NEW] Webhook / 审计日志 / 响应缓存不依赖反代服务运行状态，始终热更新
+    crate::proxy::update_webhooks(config.proxy.webhooks.clone());
+    crate::proxy::update_audit_log_config(config.proxy.audit_log.clone());
+    crate::proxy::update_response_cache_config(config.proxy.response_cache.clone());
 
     // 热更新正在运行的服务
     let instance_lock = proxy_state.instance.read().await;
@@ -349,10 +382,17 @@ pub async fn save_config(
             .axum_server
             .update_proxy(config.proxy.upstream_proxy.clone())
             .await;
+        // [NEW] 更新代理池配置
+        instance
+            .axum_server
+            .update_proxy_pool(config.proxy.proxy_pool.clone())
+            .await;
         // 更新安全策略 (auth)
         instance.axum_server.update_security(&config.proxy).await;
         // 更新 z.ai 配置
         instance.axum_server.update_zai(&config.proxy).await;
+        // [NEW] 更新批处理执行器配置
+        instance.axum_server.update_batch(&config.proxy);
         // 更新实验性配置
         instance.axum_server.update_experimental(&config.proxy).await;
         // 更新调试日志配置
@@ -365,10 +405,10 @@ pub async fn save_config(
         crate::proxy::update_antigravity_identity_config(config.proxy.antigravity_identity.clone());
         // 更新熔断配置
         instance.token_manager.update_circuit_breaker_config(config.circuit_breaker.clone()).await;
+        // [NEW] 更新调度配置 (含会话持久化开关)
+        instance.token_manager.update_sticky_config(config.proxy.scheduling.clone()).await;
         tracing::debug!("已同步热更新反代服务配置");
     }
-
-    Ok(())
 }
 
 // --- OAuth 命令 ---
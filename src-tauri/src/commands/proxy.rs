@@ -106,13 +106,7 @@ pub async fn internal_start_proxy_service(
     {
         let mut monitor_lock = state.monitor.write().await;
         if monitor_lock.is_none() {
-            let app_handle =
-                if let crate::modules::integration::SystemManager::Desktop(ref h) = integration {
-                    Some(h.clone())
-                } else {
-                    None
-                };
-            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000, app_handle)));
+            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000)));
         }
         // Sync enabled state from config
         if let Some(monitor) = monitor_lock.as_ref() {
@@ -174,7 +168,7 @@ pub async fn internal_start_proxy_service(
             return Ok(ProxyStatus {
                 running: false,
                 port: config.port,
-                base_url: format!("http://127.0.0.1:{}", config.port),
+                base_url: local_base_url(&config),
                 active_accounts: 0,
             });
         }
@@ -202,11 +196,22 @@ pub async fn internal_start_proxy_service(
     Ok(ProxyStatus {
         running: true,
         port: config.port,
-        base_url: format!("http://127.0.0.1:{}", config.port),
+        base_url: local_base_url(&config),
         active_accounts,
     })
 }
 
+/// 本机访问地址 (仅监听 Unix 套接字时返回 unix:// 地址)
+fn local_base_url(config: &ProxyConfig) -> String {
+    let socket = &config.unix_socket;
+    if cfg!(unix) && socket.enabled && socket.exclusive {
+        if let Ok(path) = crate::proxy::unix_socket::socket_path(socket) {
+            return crate::proxy::unix_socket::socket_base_url(&path, "");
+        }
+    }
+    format!("http://127.0.0.1:{}", config.port)
+}
+
 /// 确保管理服务器正在运行
 pub async fn ensure_admin_server(
     config: ProxyConfig,
@@ -223,13 +228,7 @@ pub async fn ensure_admin_server(
     let monitor = {
         let mut monitor_lock = state.monitor.write().await;
         if monitor_lock.is_none() {
-            let app_handle =
-                if let crate::modules::integration::SystemManager::Desktop(ref h) = integration {
-                    Some(h.clone())
-                } else {
-                    None
-                };
-            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000, app_handle)));
+            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000)));
         }
         monitor_lock.as_ref().unwrap().clone()
     };
@@ -256,6 +255,8 @@ pub async fn ensure_admin_server(
         integration.clone(),
         cloudflared_state,
         config.proxy_pool.clone(),
+        config.tls.clone(),
+        config.unix_socket.clone(),
     )
     .await
     {
@@ -284,6 +285,18 @@ pub async fn ensure_admin_server(
     crate::proxy::update_stream_handling_config(config.stream_handling.clone());
     // [NEW] 初始化标点规范化配置
     crate::proxy::update_punctuation_config(config.punctuation.clone());
+    // [NEW] 初始化模型降级链配置
+    crate::proxy::update_model_fallbacks(config.model_fallbacks.clone());
+    // [NEW] 初始化通用上游提供商配置
+    crate::proxy::update_upstream_providers(config.providers.clone());
+    // [NEW] 初始化批处理执行器配置
+    crate::proxy::update_batch_config(config.batch.clone());
+    // [NEW] 初始化 Webhook 配置
+    crate::proxy::update_webhooks(config.webhooks.clone());
+    // [NEW] 初始化审计日志配置
+    crate::proxy::update_audit_log_config(config.audit_log.clone());
+    // [NEW] 初始化响应缓存配置
+    crate::proxy::update_response_cache_config(config.response_cache.clone());
 
     Ok(())
 }
@@ -328,7 +341,7 @@ pub async fn get_proxy_status(state: State<'_, ProxyServiceState>) -> Result<Pro
             Some(instance) => Ok(ProxyStatus {
                 running: true,
                 port: instance.config.port,
-                base_url: format!("http://127.0.0.1:{}", instance.config.port),
+                base_url: local_base_url(&instance.config),
                 active_accounts: instance.token_manager.len(),
             }),
             None => Ok(ProxyStatus {
@@ -413,6 +426,15 @@ pub async fn get_proxy_log_detail(log_id: String) -> Result<ProxyRequestLog, Str
     crate::modules::proxy_db::get_log_detail(&log_id)
 }
 
+/// 按当前映射与配置重放一条日志中的请求，返回新旧响应对比
+#[tauri::command]
+pub async fn replay_proxy_log(
+    log_id: String,
+    account: Option<String>,
+) -> Result<crate::proxy::replay::ReplayResult, String> {
+    crate::proxy::replay::replay_log(&log_id, crate::proxy::replay::ReplayOptions { account }).await
+}
+
 /// 获取日志总数
 #[tauri::command]
 pub async fn get_proxy_logs_count() -> Result<u64, String> {
@@ -537,6 +559,7 @@ pub async fn update_model_mapping(
     // 2. 无论是否运行，都保存到全局配置持久化
     let mut app_config = crate::modules::config::load_app_config().map_err(|e| e)?;
     app_config.proxy.custom_mapping = config.custom_mapping;
+    app_config.proxy.model_fallbacks = config.model_fallbacks;
     crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
 
     Ok(())
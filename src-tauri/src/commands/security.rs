@@ -96,7 +96,7 @@ pub async fn add_ip_to_blacklist(request: AddBlacklistRequest) -> Result<(), Str
     // 验证 IP 格式
     if !is_valid_ip_pattern(&request.ip_pattern) {
         return Err(
-            "Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)"
+            "Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24, 2001:db8::/32)"
                 .to_string(),
         );
     }
@@ -155,7 +155,7 @@ pub async fn add_ip_to_whitelist(request: AddWhitelistRequest) -> Result<(), Str
     // 验证 IP 格式
     if !is_valid_ip_pattern(&request.ip_pattern) {
         return Err(
-            "Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)"
+            "Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24, 2001:db8::/32)"
                 .to_string(),
         );
     }
@@ -255,47 +255,20 @@ pub async fn get_ip_token_stats(
     crate::modules::proxy_db::get_token_usage_by_ip(limit.unwrap_or(100), hours.unwrap_or(720))
 }
 
-// ==================== 辅助函数 ====================
-
-/// 验证 IP 模式格式 (支持单个 IP 和 CIDR)
-fn is_valid_ip_pattern(pattern: &str) -> bool {
-    // 检查是否为 CIDR 格式
-    if pattern.contains('/') {
-        let parts: Vec<&str> = pattern.split('/').collect();
-        if parts.len() != 2 {
-            return false;
-        }
-
-        // 验证 IP 部分
-        if !is_valid_ip(parts[0]) {
-            return false;
-        }
-
-        // 验证掩码部分
-        if let Ok(mask) = parts[1].parse::<u8>() {
-            return mask <= 32;
-        }
-        return false;
-    }
-
-    // 单个 IP 地址
-    is_valid_ip(pattern)
+/// 获取自动封禁记录
+#[tauri::command]
+pub async fn get_auto_bans(
+    active_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<security_db::AutoBanRecord>, String> {
+    security_db::get_auto_bans(active_only.unwrap_or(true), limit.unwrap_or(200))
 }
 
-/// 验证 IP 地址格式
-fn is_valid_ip(ip: &str) -> bool {
-    let parts: Vec<&str> = ip.split('.').collect();
-    if parts.len() != 4 {
-        return false;
-    }
-
-    for part in parts {
-        if part.parse::<u8>().is_err() {
-            return false;
-        }
-    }
+// ==================== 辅助函数 ====================
 
-    true
+/// 验证 IP 模式格式 (支持单个 IP 和 CIDR，IPv4 / IPv6)
+fn is_valid_ip_pattern(pattern: &str) -> bool {
+    security_db::normalize_ip_pattern(pattern).is_ok()
 }
 
 #[cfg(test)]
@@ -309,6 +282,8 @@ mod tests {
         assert!(is_valid_ip_pattern("172.16.0.0/16"));
         assert!(is_valid_ip_pattern("192.168.1.0/24"));
         assert!(is_valid_ip_pattern("8.8.8.8/32"));
+        assert!(is_valid_ip_pattern("2001:db8::/32"));
+        assert!(is_valid_ip_pattern("::ffff:10.0.0.5"));
     }
 
     #[test]
@@ -318,5 +293,6 @@ mod tests {
         assert!(!is_valid_ip_pattern("192.168.1.1/33"));
         assert!(!is_valid_ip_pattern("192.168.1.1/"));
         assert!(!is_valid_ip_pattern("invalid"));
+        assert!(!is_valid_ip_pattern("2001:db8::/129"));
     }
 }
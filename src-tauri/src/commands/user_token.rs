@@ -1,4 +1,4 @@
-use crate::modules::user_token_db::{self, TokenIpBinding, UserToken};
+use crate::modules::user_token_db::{self, TokenIpBinding, TokenLimitsUpdate, UserToken};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +20,9 @@ pub struct UpdateTokenRequest {
     pub max_ips: Option<i32>,
     pub curfew_start: Option<Option<String>>,
     pub curfew_end: Option<Option<String>>,
+    // [NEW] 用量限制与模型白名单/黑名单
+    #[serde(flatten)]
+    pub limits: TokenLimitsUpdate,
 }
 
 // 命令实现
@@ -55,7 +58,8 @@ pub async fn update_user_token(id: String, request: UpdateTokenRequest) -> Resul
         request.max_ips,
         request.curfew_start,
         request.curfew_end,
-    )
+    )?;
+    user_token_db::update_token_limits(&id, &request.limits)
 }
 
 /// 删除令牌
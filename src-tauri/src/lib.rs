@@ -8,7 +8,7 @@ pub mod constants;
 
 use tauri::Manager;
 use modules::logger;
-use tracing::{info, warn, error};
+use tracing::{info, error};
 use std::sync::Arc;
 
 /// Increase file descriptor limit for macOS to prevent "Too many open files" errors
@@ -30,7 +30,7 @@ fn increase_nofile_limit() {
                 if libc::setrlimit(libc::RLIMIT_NOFILE, &rl) == 0 {
                     info!("Successfully increased hard file limit to {}", target);
                 } else {
-                    warn!("Failed to increase file descriptor limit");
+                    tracing::warn!("Failed to increase file descriptor limit");
                 }
             }
         }
@@ -71,6 +71,36 @@ pub fn run() {
         error!("Failed to initialize user token database: {}", e);
     }
 
+    // Initialize responses database
+    if let Err(e) = modules::responses_db::init_db() {
+        error!("Failed to initialize responses database: {}", e);
+    }
+
+    // Initialize session cache database (signature cache / sticky session persistence)
+    if let Err(e) = modules::session_cache_db::init_db() {
+        error!("Failed to initialize session cache database: {}", e);
+    }
+
+    // Initialize batch database (OpenAI Batch API jobs)
+    if let Err(e) = modules::batch_db::init_db() {
+        error!("Failed to initialize batch database: {}", e);
+    }
+
+    // Initialize admin audit log database
+    if let Err(e) = modules::audit_db::init_db() {
+        error!("Failed to initialize audit database: {}", e);
+    }
+
+    // Initialize response cache database
+    if let Err(e) = modules::response_cache_db::init_db() {
+        error!("Failed to initialize response cache database: {}", e);
+    }
+
+    // [NEW] Encrypt account tokens at rest (migrates legacy plaintext files)
+    if let Err(e) = modules::migration::migrate_account_token_encryption() {
+        error!("Failed to migrate account token encryption: {}", e);
+    }
+
     if is_headless {
         info!("Starting in HEADLESS mode...");
 
@@ -80,63 +110,35 @@ pub fn run() {
             let proxy_state = commands::proxy::ProxyServiceState::new();
             let cf_state = Arc::new(commands::cloudflared::CloudflaredState::new());
 
+            // [NEW] 声明式配置文件 (--config / ABV_CONFIG_FILE / 数据目录下的 config.toml)
+            let config_path = modules::config_file::resolve_config_path(&args);
+
             // Load config
             match modules::config::load_app_config() {
                 Ok(mut config) => {
                     let mut modified = false;
-                    // Force LAN access in headless/docker mode so it binds to 0.0.0.0
-                    config.proxy.allow_lan_access = true;
 
-                    // [NEW] 支持通过环境变量注入 API Key
-                    // 优先级：ABV_API_KEY > API_KEY > 配置文件
-                    let env_key = std::env::var("ABV_API_KEY")
-                        .or_else(|_| std::env::var("API_KEY"))
-                        .ok();
-
-                    if let Some(key) = env_key {
-                        if !key.trim().is_empty() {
-                            info!("Using API Key from environment variable");
-                            config.proxy.api_key = key;
+                    // 配置文件与 ABV__* 环境变量覆盖，校验失败时沿用已保存的配置
+                    match modules::config_file::load(&config, config_path.as_deref()) {
+                        Ok(Some(declared)) => {
+                            for e in modules::config_file::reconcile(&declared) {
+                                error!("[ConfigFile] {}", e);
+                            }
+                            config = declared.config;
                             modified = true;
+                            if let Some(ref path) = config_path {
+                                info!("Loaded declarative config from {}", path.display());
+                            }
                         }
+                        Ok(None) => {}
+                        Err(e) => error!("Failed to apply declarative config, using saved config: {}", e),
                     }
 
-                    // [NEW] 支持通过环境变量注入 Web UI 密码
-                    // 优先级：ABV_WEB_PASSWORD > WEB_PASSWORD > 配置文件
-                    let env_web_password = std::env::var("ABV_WEB_PASSWORD")
-                        .or_else(|_| std::env::var("WEB_PASSWORD"))
-                        .ok();
-
-                    if let Some(pwd) = env_web_password {
-                        if !pwd.trim().is_empty() {
-                            info!("Using Web UI Password from environment variable");
-                            config.proxy.admin_password = Some(pwd);
-                            modified = true;
-                        }
-                    }
+                    // Force LAN access in headless/docker mode so it binds to 0.0.0.0
+                    config.proxy.allow_lan_access = true;
 
-                    // [NEW] 支持通过环境变量注入鉴权模式
-                    // 优先级：ABV_AUTH_MODE > AUTH_MODE > 配置文件
-                    let env_auth_mode = std::env::var("ABV_AUTH_MODE")
-                        .or_else(|_| std::env::var("AUTH_MODE"))
-                        .ok();
-
-                    if let Some(mode_str) = env_auth_mode {
-                        let mode = match mode_str.to_lowercase().as_str() {
-                            "off" => Some(crate::proxy::ProxyAuthMode::Off),
-                            "strict" => Some(crate::proxy::ProxyAuthMode::Strict),
-                            "all_except_health" => Some(crate::proxy::ProxyAuthMode::AllExceptHealth),
-                            "auto" => Some(crate::proxy::ProxyAuthMode::Auto),
-                            _ => {
-                                warn!("Invalid AUTH_MODE: {}, ignoring", mode_str);
-                                None
-                            }
-                        };
-                        if let Some(m) = mode {
-                            info!("Using Auth Mode from environment variable: {:?}", m);
-                            config.proxy.auth_mode = m;
-                            modified = true;
-                        }
+                    if modules::config_file::apply_legacy_env_overrides(&mut config) {
+                        modified = true;
                     }
 
                     info!("--------------------------------------------------");
@@ -177,6 +179,11 @@ pub fn run() {
                     // Start smart scheduler
                     modules::scheduler::start_scheduler(None, proxy_state.clone());
                     info!("Smart scheduler started in headless mode.");
+
+                    // [NEW] 监听配置文件变更并热更新
+                    if let Some(path) = config_path {
+                        modules::config_file::start_watcher(path, proxy_state.clone());
+                    }
                 }
                 Err(e) => {
                     error!("Failed to load config for headless mode: {}", e);
@@ -219,6 +226,9 @@ pub fn run() {
             // Initialize log bridge with app handle for debug console
             modules::log_bridge::init_log_bridge(app.handle().clone());
 
+            // Forward event bus (proxy://request, accounts://refreshed, ...) to the frontend
+            modules::event_bus::start_tauri_forwarder(app.handle().clone());
+
             // Linux: Workaround for transparent window crash/freeze
             // The transparent window feature is unstable on Linux with WebKitGTK
             // We disable the visual alpha channel to prevent softbuffer-related crashes
@@ -311,6 +321,7 @@ pub fn run() {
             commands::reorder_accounts,
             commands::switch_account,
             commands::export_accounts,
+            commands::open_account_bundle,
             // Device fingerprint
             commands::get_device_profiles,
             commands::bind_device_profile,
@@ -364,6 +375,7 @@ pub fn run() {
             commands::proxy::get_proxy_logs,
             commands::proxy::get_proxy_logs_paginated,
             commands::proxy::get_proxy_log_detail,
+            commands::proxy::replay_proxy_log,
             commands::proxy::get_proxy_logs_count,
             commands::proxy::export_proxy_logs,
             commands::proxy::export_proxy_logs_json,
@@ -427,6 +439,7 @@ pub fn run() {
             commands::security::check_ip_in_whitelist,
             commands::security::get_security_config,
             commands::security::update_security_config,
+            commands::security::get_auto_bans,
             // Cloudflared commands
             commands::cloudflared::cloudflared_check,
             commands::cloudflared::cloudflared_install,
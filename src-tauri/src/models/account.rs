@@ -168,5 +168,21 @@ pub struct AccountExportItem {
 /// 导出账号响应
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountExportResponse {
-    pub accounts: Vec<AccountExportItem>,
+    /// 导出的账号数量
+    pub count: usize,
+    /// [NEW] 口令加密的导出包 (refresh_token 不以明文导出)
+    pub bundle: EncryptedAccountBundle,
+}
+
+/// [NEW] 加密的账号导出包 (口令派生密钥 + AES-GCM)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedAccountBundle {
+    /// 固定为 `antigravity-accounts`，用于导入时识别格式
+    pub format: String,
+    pub version: u32,
+    pub iterations: u32,
+    /// base64 编码的随机盐
+    pub salt: String,
+    /// base64(nonce || ciphertext)，明文为 `AccountExportItem` 数组的 JSON
+    pub data: String,
 }
@@ -5,7 +5,7 @@ pub mod token;
 
 pub use account::{
     Account, AccountExportItem, AccountExportResponse, AccountIndex, AccountSummary, DeviceProfile,
-    DeviceProfileVersion,
+    DeviceProfileVersion, EncryptedAccountBundle,
 };
 pub use config::{AppConfig, CircuitBreakerConfig, QuotaProtectionConfig};
 pub use quota::QuotaData;
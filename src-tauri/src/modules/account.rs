@@ -254,7 +254,7 @@ mod tests {
                     name: Some("User One".to_string()),
                     disabled: false,
                     proxy_disabled: false,
-                    protected_models: HashSet::new(),
+                    protected_models: std::collections::HashSet::new(),
                     created_at: now,
                     last_used: now,
                 },
@@ -264,7 +264,7 @@ mod tests {
                     name: None,
                     disabled: true,
                     proxy_disabled: true,
-                    protected_models: HashSet::new(),
+                    protected_models: std::collections::HashSet::new(),
                     created_at: now - 100,
                     last_used: now - 50,
                 },
@@ -314,6 +314,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_token_fields_encrypted_at_rest() {
+        let mut account_json = serde_json::json!({
+            "token": { "access_token": "ya29.plain", "refresh_token": "1//plain", "expires_in": 3600 }
+        });
+
+        assert!(encrypt_account_token_fields(&mut account_json).unwrap());
+        let stored = account_json["token"]["refresh_token"].as_str().unwrap();
+        assert!(stored.starts_with("ag_tok_"));
+        // 已加密字段不重复加密
+        assert!(!encrypt_account_token_fields(&mut account_json).unwrap());
+
+        decrypt_account_token_fields(&mut account_json).unwrap();
+        assert_eq!(account_json["token"]["access_token"], "ya29.plain");
+        assert_eq!(account_json["token"]["refresh_token"], "1//plain");
+    }
+
+    #[test]
+    fn test_account_bundle_roundtrip() {
+        let items = vec![crate::models::AccountExportItem {
+            email: "a@example.com".to_string(),
+            refresh_token: "1//secret".to_string(),
+        }];
+        let mut bundle = seal_account_bundle(&items, "passphrase").unwrap();
+        assert!(!bundle.data.contains("1//secret"));
+        assert_eq!(
+            bundle.iterations,
+            crate::utils::crypto::PASSPHRASE_KDF_ITERATIONS
+        );
+
+        // 每个导出包使用独立的随机盐值
+        assert_ne!(
+            bundle.salt,
+            seal_account_bundle(&items, "passphrase").unwrap().salt
+        );
+
+        let opened = open_account_bundle(&bundle, "passphrase").unwrap();
+        assert_eq!(opened[0].refresh_token, "1//secret");
+        assert!(open_account_bundle(&bundle, "wrong").is_err());
+
+        // 篡改迭代次数的导出包直接拒绝
+        bundle.iterations = u32::MAX;
+        assert!(open_account_bundle(&bundle, "passphrase").is_err());
+        // 未提供口令时拒绝导出明文
+        assert_eq!(
+            export_accounts_by_ids(&[], "").unwrap_err(),
+            "export_passphrase_required"
+        );
+    }
+
     #[test]
     fn test_backup_created_on_parse_failure() {
         let _guard = TEST_MUTEX.lock().unwrap();
@@ -561,7 +611,57 @@ fn rebuild_index_from_accounts_in_dir(data_dir: &PathBuf) -> Result<AccountIndex
 fn load_account_at_path(account_path: &PathBuf) -> Result<Account, String> {
     let content = fs::read_to_string(account_path)
         .map_err(|e| format!("failed_to_read_account_data: {}", e))?;
-    serde_json::from_str(&content).map_err(|e| format!("failed_to_parse_account_data: {}", e))
+    let mut account_json: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("failed_to_parse_account_data: {}", e))?;
+    decrypt_account_token_fields(&mut account_json)?;
+    serde_json::from_value(account_json).map_err(|e| format!("failed_to_parse_account_data: {}", e))
+}
+
+/// [NEW] 账号文件中需要落盘加密的 Token 字段
+const ENCRYPTED_TOKEN_FIELDS: [&str; 2] = ["access_token", "refresh_token"];
+
+/// 加密账号 JSON 中的 Token 字段，返回是否有字段被改写
+///
+/// 已按当前方式加密的字段保持不变；明文或旧密钥加密的字段会被 (重新) 加密。
+pub fn encrypt_account_token_fields(account_json: &mut serde_json::Value) -> Result<bool, String> {
+    let token = match account_json
+        .get_mut("token")
+        .and_then(|t| t.as_object_mut())
+    {
+        Some(token) => token,
+        None => return Ok(false),
+    };
+    let mut changed = false;
+    for field in ENCRYPTED_TOKEN_FIELDS {
+        if let Some(value) = token.get(field).and_then(|v| v.as_str()) {
+            if crate::utils::crypto::token_needs_reencrypt(value) {
+                let plaintext = crate::utils::crypto::decrypt_token(value)?;
+                let encrypted = crate::utils::crypto::encrypt_token(&plaintext)?;
+                token.insert(field.to_string(), serde_json::Value::String(encrypted));
+                changed = true;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+/// 解密账号 JSON 中的 Token 字段 (兼容旧版明文)
+pub fn decrypt_account_token_fields(account_json: &mut serde_json::Value) -> Result<(), String> {
+    let token = match account_json
+        .get_mut("token")
+        .and_then(|t| t.as_object_mut())
+    {
+        Some(token) => token,
+        None => return Ok(()),
+    };
+    for field in ENCRYPTED_TOKEN_FIELDS {
+        if let Some(value) = token.get(field).and_then(|v| v.as_str()) {
+            let plaintext = crate::utils::crypto::decrypt_token(value)
+                .map_err(|e| format!("failed_to_decrypt_account_token: {}", e))?;
+            token.insert(field.to_string(), serde_json::Value::String(plaintext));
+        }
+    }
+    Ok(())
 }
 
 /// Load account index with recovery support
@@ -706,7 +806,10 @@ pub fn save_account(account: &Account) -> Result<(), String> {
     let accounts_dir = get_accounts_dir()?;
     let account_path = accounts_dir.join(format!("{}.json", account.id));
 
-    let content = serde_json::to_string_pretty(account)
+    let mut account_json = serde_json::to_value(account)
+        .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
+    encrypt_account_token_fields(&mut account_json)?;
+    let content = serde_json::to_string_pretty(&account_json)
         .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
 
     fs::write(&account_path, content).map_err(|e| format!("failed_to_save_account_data: {}", e))
@@ -1404,12 +1507,24 @@ pub fn toggle_proxy_status(
     Ok(())
 }
 
+/// 加密导出包的格式标识 (v2: PBKDF2-HMAC-SHA256 派生密钥)
+const ACCOUNT_BUNDLE_FORMAT: &str = "antigravity-accounts";
+const ACCOUNT_BUNDLE_VERSION: u32 = 2;
+
 /// Export accounts by IDs (for backup/migration)
+///
+/// 导出内容包含 refresh_token，始终使用口令加密为导出包。
+/// 口令密钥派生为 CPU 密集操作，异步调用方需放入 `spawn_blocking`。
 pub fn export_accounts_by_ids(
     account_ids: &[String],
+    passphrase: &str,
 ) -> Result<crate::models::AccountExportResponse, String> {
     use crate::models::{AccountExportItem, AccountExportResponse};
 
+    if passphrase.is_empty() {
+        return Err("export_passphrase_required".to_string());
+    }
+
     let accounts = list_accounts()?;
 
     let export_items: Vec<AccountExportItem> = accounts
@@ -1422,10 +1537,62 @@ pub fn export_accounts_by_ids(
         .collect();
 
     Ok(AccountExportResponse {
-        accounts: export_items,
+        count: export_items.len(),
+        bundle: seal_account_bundle(&export_items, passphrase)?,
+    })
+}
+
+/// 使用口令加密导出项
+fn seal_account_bundle(
+    items: &[crate::models::AccountExportItem],
+    passphrase: &str,
+) -> Result<crate::models::EncryptedAccountBundle, String> {
+    use base64::Engine as _;
+
+    let salt = crate::utils::crypto::random_kdf_salt();
+    let iterations = crate::utils::crypto::PASSPHRASE_KDF_ITERATIONS;
+    let plaintext = serde_json::to_vec(items).map_err(|e| e.to_string())?;
+    let data =
+        crate::utils::crypto::seal_with_passphrase(&plaintext, passphrase, &salt, iterations)?;
+
+    Ok(crate::models::EncryptedAccountBundle {
+        format: ACCOUNT_BUNDLE_FORMAT.to_string(),
+        version: ACCOUNT_BUNDLE_VERSION,
+        iterations,
+        salt: base64::engine::general_purpose::STANDARD.encode(salt),
+        data,
     })
 }
 
+/// 解密账号导出包，返回其中的导出项 (供导入使用，同样需放入 `spawn_blocking`)
+pub fn open_account_bundle(
+    bundle: &crate::models::EncryptedAccountBundle,
+    passphrase: &str,
+) -> Result<Vec<crate::models::AccountExportItem>, String> {
+    use base64::Engine as _;
+
+    if bundle.format != ACCOUNT_BUNDLE_FORMAT || bundle.version != ACCOUNT_BUNDLE_VERSION {
+        return Err(format!(
+            "unsupported_bundle_format: {} v{}",
+            bundle.format, bundle.version
+        ));
+    }
+    let salt = base64::engine::general_purpose::STANDARD
+        .decode(&bundle.salt)
+        .map_err(|e| format!("invalid_bundle_salt: {}", e))?;
+    if salt.len() < crate::utils::crypto::KDF_SALT_LEN {
+        return Err("invalid_bundle_salt: too short".to_string());
+    }
+    let plaintext = crate::utils::crypto::open_with_passphrase(
+        &bundle.data,
+        passphrase,
+        &salt,
+        bundle.iterations,
+    )
+    .map_err(|_| "invalid_bundle_passphrase".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("invalid_bundle_content: {}", e))
+}
+
 /// Export all accounts' refresh_tokens (legacy, kept for compatibility)
 #[allow(dead_code)]
 pub fn export_accounts() -> Result<Vec<(String, String)>, String> {
@@ -0,0 +1,278 @@
+//! Audit Database Module
+//! 管理接口变更操作的审计日志 (仅追加，按保留天数清理)
+
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// 单条审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    pub timestamp: i64,
+    pub client_ip: String,
+    /// 鉴权主体 (admin_password / api_key / anonymous / unauthenticated)
+    pub principal: String,
+    pub method: String,
+    /// 路由模板 (如 /api/accounts/:accountId)
+    pub route: String,
+    /// 实际请求路径 (含查询参数)
+    pub path: String,
+    pub status: u16,
+    /// 脱敏后的请求摘要
+    pub summary: Option<Value>,
+    /// 配置变更前后差异 ([{path, before, after}])
+    pub diff: Option<Value>,
+}
+
+/// 审计日志查询条件
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditLogFilter {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub principal: Option<String>,
+    pub client_ip: Option<String>,
+    pub method: Option<String>,
+    /// 路由或路径包含该字符串
+    pub route: Option<String>,
+    pub status: Option<u16>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// 获取审计数据库路径
+pub fn get_audit_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("audit.db"))
+}
+
+/// 连接数据库
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_audit_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化审计数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS admin_audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            client_ip TEXT NOT NULL,
+            principal TEXT NOT NULL,
+            method TEXT NOT NULL,
+            route TEXT NOT NULL,
+            path TEXT NOT NULL,
+            status INTEGER NOT NULL,
+            summary TEXT,
+            diff TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON admin_audit_log (timestamp);
+        CREATE INDEX IF NOT EXISTS idx_audit_principal ON admin_audit_log (principal);
+        -- 仅追加: 禁止修改已有记录
+        CREATE TRIGGER IF NOT EXISTS admin_audit_log_no_update
+            BEFORE UPDATE ON admin_audit_log
+            BEGIN
+                SELECT RAISE(ABORT, 'admin_audit_log is append-only');
+            END;",
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn json_text(value: &Option<Value>) -> Option<String> {
+    value.as_ref().map(|v| v.to_string())
+}
+
+fn json_column(text: Option<String>) -> Option<Value> {
+    text.and_then(|t| serde_json::from_str(&t).ok())
+}
+
+/// 追加一条审计记录，返回记录 ID
+pub fn insert_entry(entry: &AuditLogEntry) -> Result<i64, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO admin_audit_log (timestamp, client_ip, principal, method, route, path, status, summary, diff)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            entry.timestamp,
+            entry.client_ip,
+            entry.principal,
+            entry.method,
+            entry.route,
+            entry.path,
+            entry.status,
+            json_text(&entry.summary),
+            json_text(&entry.diff),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn row_to_entry(row: &Row) -> rusqlite::Result<AuditLogEntry> {
+    Ok(AuditLogEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        client_ip: row.get(2)?,
+        principal: row.get(3)?,
+        method: row.get(4)?,
+        route: row.get(5)?,
+        path: row.get(6)?,
+        status: row.get(7)?,
+        summary: json_column(row.get(8)?),
+        diff: json_column(row.get(9)?),
+    })
+}
+
+/// 按条件查询审计记录 (按时间倒序)，返回 (总数, 当前页)
+pub fn query_entries(filter: &AuditLogFilter) -> Result<(u64, Vec<AuditLogEntry>), String> {
+    let conn = connect_db()?;
+
+    let route_pattern = filter
+        .route
+        .as_deref()
+        .filter(|r| !r.is_empty())
+        .map(|r| format!("%{}%", r));
+    let method = filter
+        .method
+        .as_deref()
+        .filter(|m| !m.is_empty())
+        .map(|m| m.to_uppercase());
+    let principal = filter.principal.as_deref().filter(|p| !p.is_empty());
+    let client_ip = filter.client_ip.as_deref().filter(|ip| !ip.is_empty());
+
+    const WHERE: &str = "WHERE (?1 IS NULL OR timestamp >= ?1)
+          AND (?2 IS NULL OR timestamp <= ?2)
+          AND (?3 IS NULL OR principal = ?3)
+          AND (?4 IS NULL OR client_ip = ?4)
+          AND (?5 IS NULL OR method = ?5)
+          AND (?6 IS NULL OR route LIKE ?6 OR path LIKE ?6)
+          AND (?7 IS NULL OR status = ?7)";
+
+    let total: u64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM admin_audit_log {}", WHERE),
+            params![
+                filter.since,
+                filter.until,
+                principal,
+                client_ip,
+                method,
+                route_pattern,
+                filter.status
+            ],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let limit = filter.limit.unwrap_or(100).clamp(1, 1000);
+    let offset = filter.offset.unwrap_or(0);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, timestamp, client_ip, principal, method, route, path, status, summary, diff
+             FROM admin_audit_log {} ORDER BY id DESC LIMIT ?8 OFFSET ?9",
+            WHERE
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![
+                filter.since,
+                filter.until,
+                principal,
+                client_ip,
+                method,
+                route_pattern,
+                filter.status,
+                limit as i64,
+                offset as i64
+            ],
+            row_to_entry,
+        )
+        .map_err(|e| e.to_string())?;
+    let entries = rows
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    Ok((total, entries))
+}
+
+/// 删除超过保留天数的记录
+pub fn cleanup_old_entries(retention_days: u32) -> Result<usize, String> {
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now().timestamp() - retention_days as i64 * 86400;
+    conn.execute("DELETE FROM admin_audit_log WHERE timestamp < ?1", [cutoff])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_audit_log_append_and_query() {
+        let _ = init_db();
+
+        let marker = format!("test-{}", uuid::Uuid::new_v4().simple());
+        let mut entry = AuditLogEntry {
+            id: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+            client_ip: marker.clone(),
+            principal: "admin_password".to_string(),
+            method: "POST".to_string(),
+            route: "/api/config".to_string(),
+            path: "/api/config".to_string(),
+            status: 200,
+            summary: Some(json!({ "config": { "proxy": { "api_key": "[REDACTED]" } } })),
+            diff: Some(json!([{ "path": "proxy.port", "before": 8045, "after": 8046 }])),
+        };
+        let id = insert_entry(&entry).unwrap();
+        entry.method = "DELETE".to_string();
+        entry.route = "/api/accounts/:accountId".to_string();
+        entry.path = "/api/accounts/abc".to_string();
+        entry.diff = None;
+        insert_entry(&entry).unwrap();
+
+        let filter = AuditLogFilter {
+            client_ip: Some(marker.clone()),
+            ..Default::default()
+        };
+        let (total, entries) = query_entries(&filter).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(entries[0].method, "DELETE");
+        assert_eq!(entries[1].diff.as_ref().unwrap()[0]["after"], 8046);
+
+        let (total, _) = query_entries(&AuditLogFilter {
+            client_ip: Some(marker),
+            route: Some("accounts".to_string()),
+            method: Some("delete".to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(total, 1);
+
+        // 仅追加: 修改已有记录会被触发器拒绝
+        let conn = connect_db().unwrap();
+        assert!(conn
+            .execute(
+                "UPDATE admin_audit_log SET principal = 'x' WHERE id = ?1",
+                [id]
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,795 @@
+//! Batch Database Module
+//! 批处理任务 (OpenAI Batch API / Anthropic Message Batches) 的文件、任务与单条请求持久化，支持重启后续跑
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// 单条请求状态
+pub const REQ_PENDING: &str = "pending";
+pub const REQ_RUNNING: &str = "running";
+pub const REQ_SUCCEEDED: &str = "succeeded";
+pub const REQ_FAILED: &str = "failed";
+pub const REQ_CANCELLED: &str = "cancelled";
+pub const REQ_EXPIRED: &str = "expired";
+
+/// 已上传 / 生成的文件元数据
+#[derive(Debug, Clone)]
+pub struct BatchFile {
+    pub id: String,
+    pub purpose: String,
+    pub filename: String,
+    pub bytes: i64,
+    pub created_at: i64,
+    /// 上传者的 UserToken ID (未使用 UserToken 的请求为 None)，所有读取按此隔离
+    pub owner_token_id: Option<String>,
+}
+
+/// 批处理任务
+#[derive(Debug, Clone, Default)]
+pub struct BatchRecord {
+    pub id: String,
+    /// 任务类型 ("openai" / "anthropic")，决定执行方式与结果格式
+    pub kind: String,
+    pub endpoint: String,
+    pub input_file_id: String,
+    pub completion_window: String,
+    pub status: String,
+    /// 校验错误 (OpenAI errors 对象)
+    pub errors: Option<Value>,
+    pub metadata: Option<Value>,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub created_at: i64,
+    pub in_progress_at: Option<i64>,
+    pub expires_at: Option<i64>,
+    pub finalizing_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub failed_at: Option<i64>,
+    pub expired_at: Option<i64>,
+    pub cancelling_at: Option<i64>,
+    pub cancelled_at: Option<i64>,
+    /// 创建者的 UserToken ID，查询按此隔离，执行时以该令牌的限制与用量计
+    pub owner_token_id: Option<String>,
+    /// 创建时的客户端 IP (执行时记录令牌用量使用)
+    pub owner_ip: Option<String>,
+}
+
+/// 批处理中的单条请求
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub batch_id: String,
+    pub idx: i64,
+    pub custom_id: String,
+    pub body: Value,
+    pub status: String,
+    pub status_code: Option<u16>,
+    pub response: Option<Value>,
+    pub error: Option<Value>,
+    pub attempts: u32,
+}
+
+/// 已认领的待执行请求 (附带所属任务的类型与端点)
+#[derive(Debug, Clone)]
+pub struct ClaimedRequest {
+    pub request: BatchRequest,
+    pub kind: String,
+    pub endpoint: String,
+    pub owner_token_id: Option<String>,
+    pub owner_ip: Option<String>,
+}
+
+/// 按状态统计的请求数量
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestCounts {
+    pub total: i64,
+    pub pending: i64,
+    pub running: i64,
+    pub succeeded: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub expired: i64,
+}
+
+/// 获取批处理数据库路径
+pub fn get_batch_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("batches.db"))
+}
+
+/// 连接数据库
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_batch_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化批处理数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS batch_files (
+            id TEXT PRIMARY KEY,
+            purpose TEXT NOT NULL,
+            filename TEXT NOT NULL,
+            bytes INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            content BLOB NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS batches (
+            id TEXT PRIMARY KEY,
+            kind TEXT NOT NULL,
+            endpoint TEXT NOT NULL,
+            input_file_id TEXT NOT NULL,
+            completion_window TEXT NOT NULL,
+            status TEXT NOT NULL,
+            errors TEXT,
+            metadata TEXT,
+            output_file_id TEXT,
+            error_file_id TEXT,
+            created_at INTEGER NOT NULL,
+            in_progress_at INTEGER,
+            expires_at INTEGER,
+            finalizing_at INTEGER,
+            completed_at INTEGER,
+            failed_at INTEGER,
+            expired_at INTEGER,
+            cancelling_at INTEGER,
+            cancelled_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_batches_kind_created ON batches (kind, created_at DESC);
+        CREATE TABLE IF NOT EXISTS batch_requests (
+            batch_id TEXT NOT NULL,
+            idx INTEGER NOT NULL,
+            custom_id TEXT NOT NULL,
+            body TEXT NOT NULL,
+            status TEXT NOT NULL,
+            status_code INTEGER,
+            response TEXT,
+            error TEXT,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (batch_id, idx)
+        );
+        CREATE INDEX IF NOT EXISTS idx_batch_requests_status ON batch_requests (status, batch_id);",
+    )
+    .map_err(|e| e.to_string())?;
+
+    // [NEW] 归属隔离: 文件与任务记录创建者令牌 (旧库升级时补列，已存在则忽略)
+    let _ = conn.execute("ALTER TABLE batch_files ADD COLUMN owner_token_id TEXT", []);
+    let _ = conn.execute("ALTER TABLE batches ADD COLUMN owner_token_id TEXT", []);
+    let _ = conn.execute("ALTER TABLE batches ADD COLUMN owner_ip TEXT", []);
+
+    Ok(())
+}
+
+fn json_column(value: Option<String>) -> Option<Value> {
+    value.and_then(|s| serde_json::from_str(&s).ok())
+}
+
+fn json_text(value: &Option<Value>) -> Option<String> {
+    value.as_ref().map(|v| v.to_string())
+}
+
+// ===== 文件 =====
+
+fn row_to_file(row: &Row) -> rusqlite::Result<BatchFile> {
+    Ok(BatchFile {
+        id: row.get(0)?,
+        purpose: row.get(1)?,
+        filename: row.get(2)?,
+        bytes: row.get(3)?,
+        created_at: row.get(4)?,
+        owner_token_id: row.get(5)?,
+    })
+}
+
+const FILE_COLUMNS: &str = "id, purpose, filename, bytes, created_at, owner_token_id";
+
+/// 保存文件
+pub fn save_file(file: &BatchFile, content: &[u8]) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT OR REPLACE INTO batch_files (id, purpose, filename, bytes, created_at, owner_token_id, content)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            file.id,
+            file.purpose,
+            file.filename,
+            file.bytes,
+            file.created_at,
+            file.owner_token_id,
+            content
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 获取文件元数据 (`owner` 为调用方的 UserToken ID，仅返回其名下的文件)
+pub fn get_file(id: &str, owner: Option<&str>) -> Result<Option<BatchFile>, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM batch_files WHERE id = ?1 AND owner_token_id IS ?2",
+            FILE_COLUMNS
+        ),
+        params![id, owner],
+        row_to_file,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 获取文件内容
+pub fn get_file_content(id: &str, owner: Option<&str>) -> Result<Option<Vec<u8>>, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT content FROM batch_files WHERE id = ?1 AND owner_token_id IS ?2",
+        params![id, owner],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 列出调用方名下的文件 (按创建时间倒序)
+pub fn list_files(
+    owner: Option<&str>,
+    purpose: Option<&str>,
+    limit: usize,
+) -> Result<Vec<BatchFile>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM batch_files
+             WHERE owner_token_id IS ?1 AND (?2 IS NULL OR purpose = ?2)
+             ORDER BY created_at DESC LIMIT ?3",
+            FILE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![owner, purpose, limit as i64], row_to_file)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 删除调用方名下的文件
+pub fn delete_file(id: &str, owner: Option<&str>) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let affected = conn
+        .execute(
+            "DELETE FROM batch_files WHERE id = ?1 AND owner_token_id IS ?2",
+            params![id, owner],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(affected > 0)
+}
+
+// ===== 批处理任务 =====
+
+const BATCH_COLUMNS: &str = "id, kind, endpoint, input_file_id, completion_window, status, errors, metadata,
+    output_file_id, error_file_id, created_at, in_progress_at, expires_at, finalizing_at, completed_at,
+    failed_at, expired_at, cancelling_at, cancelled_at, owner_token_id, owner_ip";
+
+fn row_to_batch(row: &Row) -> rusqlite::Result<BatchRecord> {
+    Ok(BatchRecord {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        endpoint: row.get(2)?,
+        input_file_id: row.get(3)?,
+        completion_window: row.get(4)?,
+        status: row.get(5)?,
+        errors: json_column(row.get(6)?),
+        metadata: json_column(row.get(7)?),
+        output_file_id: row.get(8)?,
+        error_file_id: row.get(9)?,
+        created_at: row.get(10)?,
+        in_progress_at: row.get(11)?,
+        expires_at: row.get(12)?,
+        finalizing_at: row.get(13)?,
+        completed_at: row.get(14)?,
+        failed_at: row.get(15)?,
+        expired_at: row.get(16)?,
+        cancelling_at: row.get(17)?,
+        cancelled_at: row.get(18)?,
+        owner_token_id: row.get(19)?,
+        owner_ip: row.get(20)?,
+    })
+}
+
+/// 创建批处理任务及其全部请求 (单事务)
+///
+/// `requests` 为 (custom_id, 请求体) 列表
+pub fn create_batch(batch: &BatchRecord, requests: &[(String, Value)]) -> Result<(), String> {
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        &format!(
+            "INSERT INTO batches ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+            BATCH_COLUMNS
+        ),
+        params![
+            batch.id,
+            batch.kind,
+            batch.endpoint,
+            batch.input_file_id,
+            batch.completion_window,
+            batch.status,
+            json_text(&batch.errors),
+            json_text(&batch.metadata),
+            batch.output_file_id,
+            batch.error_file_id,
+            batch.created_at,
+            batch.in_progress_at,
+            batch.expires_at,
+            batch.finalizing_at,
+            batch.completed_at,
+            batch.failed_at,
+            batch.expired_at,
+            batch.cancelling_at,
+            batch.cancelled_at,
+            batch.owner_token_id,
+            batch.owner_ip,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO batch_requests (batch_id, idx, custom_id, body, status, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(|e| e.to_string())?;
+        for (idx, (custom_id, body)) in requests.iter().enumerate() {
+            stmt.execute(params![
+                batch.id,
+                idx as i64,
+                custom_id,
+                body.to_string(),
+                REQ_PENDING,
+                batch.created_at
+            ])
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 获取调用方名下的批处理任务
+pub fn get_batch(id: &str, owner: Option<&str>) -> Result<Option<BatchRecord>, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM batches WHERE id = ?1 AND owner_token_id IS ?2",
+            BATCH_COLUMNS
+        ),
+        params![id, owner],
+        row_to_batch,
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 按创建时间倒序列出调用方名下的某类任务，`after` 为分页游标 (上一页最后一个 ID)
+pub fn list_batches(
+    kind: &str,
+    owner: Option<&str>,
+    after: Option<&str>,
+    limit: usize,
+) -> Result<Vec<BatchRecord>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM batches
+             WHERE kind = ?1 AND owner_token_id IS ?4
+               AND (?2 IS NULL OR created_at < (SELECT created_at FROM batches WHERE id = ?2)
+                    OR (created_at = (SELECT created_at FROM batches WHERE id = ?2) AND id < ?2))
+             ORDER BY created_at DESC, id DESC LIMIT ?3",
+            BATCH_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![kind, after, limit as i64, owner], row_to_batch)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 列出比游标更新的任务 (按创建时间倒序返回，用于向前翻页)
+pub fn list_batches_before(
+    kind: &str,
+    owner: Option<&str>,
+    before: &str,
+    limit: usize,
+) -> Result<Vec<BatchRecord>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM batches
+             WHERE kind = ?1 AND owner_token_id IS ?4
+               AND (created_at > (SELECT created_at FROM batches WHERE id = ?2)
+                    OR (created_at = (SELECT created_at FROM batches WHERE id = ?2) AND id > ?2))
+             ORDER BY created_at ASC, id ASC LIMIT ?3",
+            BATCH_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![kind, before, limit as i64, owner], row_to_batch)
+        .map_err(|e| e.to_string())?;
+    let mut batches = rows
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    batches.reverse();
+    Ok(batches)
+}
+
+/// 删除任务及其全部请求记录
+pub fn delete_batch(id: &str) -> Result<bool, String> {
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM batch_requests WHERE batch_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    let affected = tx
+        .execute("DELETE FROM batches WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(affected > 0)
+}
+
+/// 列出尚未结束的任务 (供后台执行器调度)
+pub fn list_active_batches() -> Result<Vec<BatchRecord>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM batches WHERE status IN ('in_progress', 'cancelling', 'finalizing')
+             ORDER BY created_at ASC",
+            BATCH_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], row_to_batch)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 更新任务的可变字段 (状态、结果文件与时间戳)
+pub fn update_batch(batch: &BatchRecord) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE batches SET status = ?2, errors = ?3, output_file_id = ?4, error_file_id = ?5,
+            in_progress_at = ?6, finalizing_at = ?7, completed_at = ?8, failed_at = ?9,
+            expired_at = ?10, cancelling_at = ?11, cancelled_at = ?12
+         WHERE id = ?1",
+        params![
+            batch.id,
+            batch.status,
+            json_text(&batch.errors),
+            batch.output_file_id,
+            batch.error_file_id,
+            batch.in_progress_at,
+            batch.finalizing_at,
+            batch.completed_at,
+            batch.failed_at,
+            batch.expired_at,
+            batch.cancelling_at,
+            batch.cancelled_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ===== 单条请求 =====
+
+fn row_to_request(row: &Row) -> rusqlite::Result<BatchRequest> {
+    let body: String = row.get(3)?;
+    let status_code: Option<i64> = row.get(5)?;
+    let attempts: i64 = row.get(8)?;
+    Ok(BatchRequest {
+        batch_id: row.get(0)?,
+        idx: row.get(1)?,
+        custom_id: row.get(2)?,
+        body: serde_json::from_str(&body).unwrap_or(Value::Null),
+        status: row.get(4)?,
+        status_code: status_code.map(|c| c as u16),
+        response: json_column(row.get(6)?),
+        error: json_column(row.get(7)?),
+        attempts: attempts.max(0) as u32,
+    })
+}
+
+const REQUEST_COLUMNS: &str =
+    "batch_id, idx, custom_id, body, status, status_code, response, error, attempts";
+
+/// 统计任务中各状态的请求数量
+pub fn request_counts(batch_id: &str) -> Result<RequestCounts, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare("SELECT status, COUNT(*) FROM batch_requests WHERE batch_id = ?1 GROUP BY status")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([batch_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut counts = RequestCounts::default();
+    for row in rows {
+        let (status, n) = row.map_err(|e| e.to_string())?;
+        counts.total += n;
+        match status.as_str() {
+            REQ_PENDING => counts.pending += n,
+            REQ_RUNNING => counts.running += n,
+            REQ_SUCCEEDED => counts.succeeded += n,
+            REQ_FAILED => counts.failed += n,
+            REQ_CANCELLED => counts.cancelled += n,
+            REQ_EXPIRED => counts.expired += n,
+            _ => {}
+        }
+    }
+    Ok(counts)
+}
+
+/// 认领最多 limit 条待执行请求并标记为 running (按任务创建顺序先进先出)
+pub fn claim_pending(limit: usize) -> Result<Vec<ClaimedRequest>, String> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let claimed = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT r.batch_id, r.idx, r.custom_id, r.body, r.status, r.status_code, r.response,
+                        r.error, r.attempts, b.kind, b.endpoint, b.owner_token_id, b.owner_ip
+                 FROM batch_requests r
+                 JOIN batches b ON b.id = r.batch_id
+                 WHERE r.status = 'pending' AND b.status = 'in_progress'
+                 ORDER BY b.created_at ASC, r.idx ASC LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([limit as i64], |row| {
+                Ok(ClaimedRequest {
+                    request: row_to_request(row)?,
+                    kind: row.get(9)?,
+                    endpoint: row.get(10)?,
+                    owner_token_id: row.get(11)?,
+                    owner_ip: row.get(12)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    for claimed in &claimed {
+        tx.execute(
+            "UPDATE batch_requests SET status = ?3, updated_at = ?4 WHERE batch_id = ?1 AND idx = ?2",
+            params![claimed.request.batch_id, claimed.request.idx, REQ_RUNNING, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(claimed
+        .into_iter()
+        .map(|mut c| {
+            c.request.status = REQ_RUNNING.to_string();
+            c
+        })
+        .collect())
+}
+
+/// 记录请求的最终结果
+pub fn finish_request(
+    batch_id: &str,
+    idx: i64,
+    status: &str,
+    status_code: Option<u16>,
+    response: Option<&Value>,
+    error: Option<&Value>,
+) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE batch_requests SET status = ?3, status_code = ?4, response = ?5, error = ?6,
+            attempts = attempts + 1, updated_at = ?7
+         WHERE batch_id = ?1 AND idx = ?2",
+        params![
+            batch_id,
+            idx,
+            status,
+            status_code.map(|c| c as i64),
+            response.map(|v| v.to_string()),
+            error.map(|v| v.to_string()),
+            chrono::Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 将请求放回待执行队列，`count_attempt` 为 false 时不计入重试次数 (如限流)
+pub fn requeue_request(batch_id: &str, idx: i64, count_attempt: bool) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE batch_requests SET status = 'pending', attempts = attempts + ?3, updated_at = ?4
+         WHERE batch_id = ?1 AND idx = ?2",
+        params![
+            batch_id,
+            idx,
+            count_attempt as i64,
+            chrono::Utc::now().timestamp()
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 将某任务所有待执行请求批量标记为指定状态 (取消 / 过期)
+pub fn mark_pending_requests(batch_id: &str, status: &str) -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE batch_requests SET status = ?2, updated_at = ?3 WHERE batch_id = ?1 AND status = 'pending'",
+        params![batch_id, status, chrono::Utc::now().timestamp()],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 进程重启后将中断的 running 请求放回队列
+pub fn reset_running_requests() -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE batch_requests SET status = 'pending' WHERE status = 'running'",
+        [],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 列出任务的全部请求 (按输入顺序)
+pub fn list_requests(batch_id: &str) -> Result<Vec<BatchRequest>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM batch_requests WHERE batch_id = ?1 ORDER BY idx ASC",
+            REQUEST_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([batch_id], row_to_request)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_batch_request_lifecycle() {
+        let _ = init_db();
+
+        let id = format!("batch_test_{}", uuid::Uuid::new_v4().simple());
+        let batch = BatchRecord {
+            id: id.clone(),
+            kind: "test".to_string(),
+            endpoint: "/v1/chat/completions".to_string(),
+            input_file_id: "file-test".to_string(),
+            completion_window: "24h".to_string(),
+            status: "in_progress".to_string(),
+            // 排在所有真实任务之前，确保 claim_pending 优先认领
+            created_at: 0,
+            owner_token_id: Some("token-a".to_string()),
+            ..Default::default()
+        };
+        let requests: Vec<(String, Value)> = (0..3)
+            .map(|i| (format!("req-{}", i), json!({ "n": i })))
+            .collect();
+        create_batch(&batch, &requests).unwrap();
+
+        let claimed = claim_pending(2).unwrap();
+        let mine: Vec<_> = claimed
+            .iter()
+            .filter(|c| c.request.batch_id == id)
+            .collect();
+        assert_eq!(mine.len(), 2);
+        assert_eq!(mine[0].request.custom_id, "req-0");
+        assert_eq!(mine[0].endpoint, "/v1/chat/completions");
+        assert_eq!(mine[0].owner_token_id.as_deref(), Some("token-a"));
+
+        finish_request(
+            &id,
+            0,
+            REQ_SUCCEEDED,
+            Some(200),
+            Some(&json!({"ok": true})),
+            None,
+        )
+        .unwrap();
+        requeue_request(&id, 1, false).unwrap();
+        assert_eq!(mark_pending_requests(&id, REQ_CANCELLED).unwrap(), 2);
+
+        let counts = request_counts(&id).unwrap();
+        assert_eq!(
+            (counts.total, counts.succeeded, counts.cancelled),
+            (3, 1, 2)
+        );
+
+        let stored = list_requests(&id).unwrap();
+        assert_eq!(stored[0].status_code, Some(200));
+        assert_eq!(stored[0].attempts, 1);
+        assert_eq!(stored[1].attempts, 0);
+
+        // 其他令牌 (及未使用令牌的调用方) 看不到该任务
+        assert!(get_batch(&id, Some("token-b")).unwrap().is_none());
+        assert!(get_batch(&id, None).unwrap().is_none());
+        assert!(!list_batches("test", Some("token-b"), None, 1000)
+            .unwrap()
+            .iter()
+            .any(|b| b.id == id));
+
+        let mut updated = get_batch(&id, Some("token-a")).unwrap().unwrap();
+        updated.status = "cancelled".to_string();
+        update_batch(&updated).unwrap();
+        assert!(list_batches("test", Some("token-a"), None, 1000)
+            .unwrap()
+            .iter()
+            .any(|b| b.id == id && b.status == "cancelled"));
+
+        assert!(delete_batch(&id).unwrap());
+        assert!(get_batch(&id, Some("token-a")).unwrap().is_none());
+        assert!(list_requests(&id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_files_are_scoped_to_owner() {
+        let _ = init_db();
+
+        let file = BatchFile {
+            id: format!("file-test-{}", uuid::Uuid::new_v4().simple()),
+            purpose: "batch".to_string(),
+            filename: "input.jsonl".to_string(),
+            bytes: 2,
+            created_at: 0,
+            owner_token_id: Some("token-a".to_string()),
+        };
+        save_file(&file, b"{}").unwrap();
+
+        assert!(get_file(&file.id, Some("token-a")).unwrap().is_some());
+        assert!(get_file(&file.id, Some("token-b")).unwrap().is_none());
+        assert!(get_file(&file.id, None).unwrap().is_none());
+        assert!(get_file_content(&file.id, Some("token-b"))
+            .unwrap()
+            .is_none());
+        assert!(!list_files(Some("token-b"), None, 10_000)
+            .unwrap()
+            .iter()
+            .any(|f| f.id == file.id));
+        assert!(!delete_file(&file.id, Some("token-b")).unwrap());
+
+        assert_eq!(
+            get_file_content(&file.id, Some("token-a")).unwrap(),
+            Some(b"{}".to_vec())
+        );
+        assert!(delete_file(&file.id, Some("token-a")).unwrap());
+    }
+}
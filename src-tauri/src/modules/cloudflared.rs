@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
@@ -15,6 +16,13 @@ const DETACHED_PROCESS: u32 = 0x00000008;
 #[cfg(target_os = "windows")]
 const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
 
+/// [NEW] 隧道是否运行中 (客户端 IP 解析据此决定是否信任 CF-Connecting-IP)
+static TUNNEL_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub fn is_tunnel_active() -> bool {
+    TUNNEL_ACTIVE.load(Ordering::Relaxed)
+}
+
 /// Cloudflared隧道模式
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -253,7 +261,16 @@ impl CloudflaredManager {
 
         match config.mode {
             TunnelMode::Quick => {
-                cmd.arg("tunnel").arg("--url").arg(&local_url);
+                // [NEW] 反代仅监听 Unix 套接字时，隧道回源到套接字
+                match crate::proxy::unix_socket::exclusive_socket_path() {
+                    Some(socket) => {
+                        info!("[cloudflared] Using unix socket origin: {}", socket.display());
+                        cmd.arg("tunnel").arg("--unix-socket").arg(&socket);
+                    }
+                    None => {
+                        cmd.arg("tunnel").arg("--url").arg(&local_url);
+                    }
+                }
 
                 // 注意：--no-autoupdate 参数在较新版本的 cloudflared 中已不被支持，会导致进程立即退出
                 // cmd.arg("--no-autoupdate");
@@ -310,6 +327,7 @@ impl CloudflaredManager {
         }
 
         *self.process.write().await = Some(child);
+        TUNNEL_ACTIVE.store(true, Ordering::Relaxed);
         self.update_status(|s| {
             s.installed = installed.clone();
             s.version = version.clone();
@@ -342,6 +360,7 @@ impl CloudflaredManager {
                                     info!("[cloudflared] Process exited with status: {:?}", exit_status);
                                     *proc_lock = None;
                                     drop(proc_lock);
+                                    TUNNEL_ACTIVE.store(false, Ordering::Relaxed);
 
                                     let mut s = status_ref.write().await;
                                     s.running = false;
@@ -355,6 +374,7 @@ impl CloudflaredManager {
                                     info!("[cloudflared] Error checking process: {}", e);
                                     *proc_lock = None;
                                     drop(proc_lock);
+                                    TUNNEL_ACTIVE.store(false, Ordering::Relaxed);
 
                                     let mut s = status_ref.write().await;
                                     s.running = false;
@@ -365,6 +385,7 @@ impl CloudflaredManager {
                         } else {
                             // 进程不存在
                             drop(proc_lock);
+                            TUNNEL_ACTIVE.store(false, Ordering::Relaxed);
                             let mut s = status_ref.write().await;
                             if s.running {
                                 s.running = false;
@@ -387,6 +408,7 @@ impl CloudflaredManager {
             let _ = child.kill().await;
             info!("[cloudflared] Tunnel stopped");
         }
+        TUNNEL_ACTIVE.store(false, Ordering::Relaxed);
 
         self.update_status(|s| {
             s.running = false;
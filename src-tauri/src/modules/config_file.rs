@@ -0,0 +1,706 @@
+//! Declarative Config File Module
+//! Headless 模式的声明式 TOML 配置文件: 启动时加载、运行中监听变更并热更新，支持 ABV__* 环境变量覆盖任意键
+//!
+//! 文件结构与 gui_config.json 一致 (可只写需要管理的键)，额外支持 `[[user_tokens]]` 与 `[security]` 两个声明段:
+//!
+//! ```toml
+//! [proxy]
+//! port = 8045
+//! api_key = "sk-ci"
+//! auth_mode = "strict"
+//!
+//! [proxy.custom_mapping]
+//! "gpt-4o" = "gemini-3-flash"
+//!
+//! [proxy.scheduling]
+//! mode = "Balance"
+//!
+//! [[user_tokens]]
+//! username = "ci"
+//! token = "sk-ci-pipeline"
+//! rate_limit_rpm = 60
+//! allowed_models = ["gemini-*"]
+//!
+//! [[security.ip_blacklist]]
+//! pattern = "203.0.113.0/24"
+//! note = "scanner"
+//! ```
+//!
+//! - 文件中的键覆盖当前配置，未写出的键保持原值 (从文件中删除某个键不会恢复默认值)
+//! - `ABV__PROXY__PORT=8046` 形式的环境变量优先级高于文件，`__` 分隔层级，键名不区分大小写
+//! - 用户令牌按 username、IP 名单按 pattern 对齐，只新增或更新，不会删除未声明的条目
+//! - 校验失败时保留当前配置并记录错误，不会中断服务
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+use crate::models::AppConfig;
+
+/// 环境变量覆盖前缀 (`ABV__PROXY__PORT=8046`)
+pub const ENV_OVERRIDE_PREFIX: &str = "ABV__";
+/// 配置文件路径环境变量
+pub const CONFIG_FILE_ENV: &str = "ABV_CONFIG_FILE";
+/// 未指定路径时在数据目录下查找的默认文件名
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+/// 文件变更轮询间隔 (兼容 Docker 挂载卷与 Kubernetes ConfigMap 的原子替换)
+const WATCH_INTERVAL_SECS: u64 = 3;
+
+/// 不属于 AppConfig 的声明段
+const USER_TOKENS_KEY: &str = "user_tokens";
+const SECURITY_KEY: &str = "security";
+
+fn default_true() -> bool {
+    true
+}
+
+/// 声明式用户令牌 (按 username 对齐)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredUserToken {
+    pub username: String,
+    /// 固定令牌值，留空时首次创建随机生成
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub max_ips: i32,
+    #[serde(default)]
+    pub curfew_start: Option<String>,
+    #[serde(default)]
+    pub curfew_end: Option<String>,
+    #[serde(default)]
+    pub rate_limit_rpm: i64,
+    #[serde(default)]
+    pub daily_token_limit: i64,
+    #[serde(default)]
+    pub monthly_token_limit: i64,
+    #[serde(default)]
+    pub total_token_limit: i64,
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub denied_models: Vec<String>,
+}
+
+/// 声明式 IP 名单条目 (按 pattern 对齐)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredIpEntry {
+    pub pattern: String,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclaredSecurity {
+    #[serde(default)]
+    pub ip_blacklist: Vec<DeclaredIpEntry>,
+    #[serde(default)]
+    pub ip_whitelist: Vec<DeclaredIpEntry>,
+}
+
+/// 合并后的声明式配置
+#[derive(Debug, Clone)]
+pub struct DeclarativeConfig {
+    pub config: AppConfig,
+    pub user_tokens: Vec<DeclaredUserToken>,
+    pub security: DeclaredSecurity,
+    /// 未识别的键 (通常是拼写错误)，仅告警
+    pub unknown_keys: Vec<String>,
+}
+
+/// 解析配置文件路径: `--config <path>` > ABV_CONFIG_FILE > 数据目录下的 config.toml (存在时)
+pub fn resolve_config_path(args: &[String]) -> Option<PathBuf> {
+    let from_args = args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--config" {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix("--config=").map(String::from)
+        }
+    });
+    if let Some(path) = from_args.or_else(|| std::env::var(CONFIG_FILE_ENV).ok()) {
+        if !path.trim().is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+    let default_path = crate::modules::account::get_data_dir()
+        .ok()?
+        .join(DEFAULT_CONFIG_FILE);
+    default_path.exists().then_some(default_path)
+}
+
+/// 递归合并: 对象逐键合并，其余类型整体替换
+fn merge(target: &mut Value, overlay: Value) {
+    match (target, overlay) {
+        (Value::Object(target), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, overlay) => *target = overlay,
+    }
+}
+
+/// 应用 ABV__* 环境变量覆盖。值优先按 JSON 解析 (数字 / 布尔 / 数组)，原值为字符串时保持字符串
+///
+/// 原值为 null 或缺失时无法得知字段类型: 解析结果若使 `accepts` 校验失败 (如纯数字的
+/// `admin_password`)，回退为字符串。
+pub fn apply_env_overrides<I, F>(target: &mut Value, vars: I, accepts: F) -> Vec<String>
+where
+    I: IntoIterator<Item = (String, String)>,
+    F: Fn(&Value) -> bool,
+{
+    let mut applied = Vec::new();
+    for (name, raw) in vars {
+        let Some(path) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+        let keys: Vec<String> = path
+            .split("__")
+            .filter(|k| !k.is_empty())
+            .map(|k| k.to_ascii_lowercase())
+            .collect();
+        let Some((last, parents)) = keys.split_last() else {
+            continue;
+        };
+
+        let mut node = &mut *target;
+        for key in parents {
+            if !node.is_object() {
+                *node = Value::Object(Default::default());
+            }
+            node = node
+                .as_object_mut()
+                .map(|map| map.entry(key.clone()).or_insert(Value::Null))
+                .expect("node is an object");
+        }
+        if !node.is_object() {
+            *node = Value::Object(Default::default());
+        }
+        let Some(map) = node.as_object_mut() else {
+            continue;
+        };
+        let parsed = match map.get(last) {
+            Some(Value::String(_)) => None,
+            _ => serde_json::from_str::<Value>(&raw)
+                .ok()
+                .filter(|v| !v.is_string()),
+        };
+        let coerced = parsed.is_some();
+        map.insert(
+            last.clone(),
+            parsed.unwrap_or_else(|| Value::String(raw.clone())),
+        );
+        if coerced && !accepts(target) {
+            let pointer = format!("/{}", keys.join("/"));
+            if let Some(slot) = target.pointer_mut(&pointer) {
+                *slot = Value::String(raw);
+            }
+        }
+        applied.push(keys.join("."));
+    }
+    applied
+}
+
+/// 收集声明中存在但反序列化后被忽略的键
+fn collect_unknown_keys(declared: &Value, parsed: &Value, path: &str, out: &mut Vec<String>) {
+    if let (Value::Object(declared), Value::Object(parsed)) = (declared, parsed) {
+        for (key, value) in declared {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            match parsed.get(key) {
+                Some(parsed_value) => collect_unknown_keys(value, parsed_value, &child, out),
+                None => out.push(child),
+            }
+        }
+    }
+}
+
+fn validate(declared: &DeclarativeConfig) -> Result<(), String> {
+    let mut errors = Vec::new();
+    let proxy = &declared.config.proxy;
+
+    if proxy.port == 0 {
+        errors.push("proxy.port must be between 1 and 65535".to_string());
+    }
+    for (from, to) in &proxy.custom_mapping {
+        if from.trim().is_empty() || to.trim().is_empty() {
+            errors.push(format!(
+                "proxy.custom_mapping contains an empty entry ({:?} -> {:?})",
+                from, to
+            ));
+        }
+    }
+
+    let mut usernames = std::collections::HashSet::new();
+    for (i, token) in declared.user_tokens.iter().enumerate() {
+        if token.username.trim().is_empty() {
+            errors.push(format!("user_tokens[{}].username must not be empty", i));
+        } else if !usernames.insert(token.username.as_str()) {
+            errors.push(format!(
+                "user_tokens: duplicate username {:?}",
+                token.username
+            ));
+        }
+        if token.token.as_deref().is_some_and(|t| t.trim().len() < 8) {
+            errors.push(format!(
+                "user_tokens[{}].token must be at least 8 characters",
+                i
+            ));
+        }
+    }
+
+    let lists = [
+        ("security.ip_blacklist", &declared.security.ip_blacklist),
+        ("security.ip_whitelist", &declared.security.ip_whitelist),
+    ];
+    for (name, entries) in lists {
+        for (i, entry) in entries.iter().enumerate() {
+            if let Err(e) = crate::modules::security_db::normalize_ip_pattern(&entry.pattern) {
+                errors.push(format!("{}[{}]: {}", name, i, e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// 将配置文件内容 (可选) 与环境变量覆盖合并到当前配置之上并校验
+pub fn build_config<I>(
+    base: &AppConfig,
+    content: Option<&str>,
+    env_vars: I,
+) -> Result<DeclarativeConfig, String>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut file_value = match content {
+        Some(content) => {
+            let table: toml::Table =
+                toml::from_str(content).map_err(|e| format!("invalid TOML: {}", e))?;
+            serde_json::to_value(table).map_err(|e| e.to_string())?
+        }
+        None => Value::Object(Default::default()),
+    };
+
+    let sections = file_value
+        .as_object_mut()
+        .ok_or_else(|| "config file must be a TOML table".to_string())?;
+    let user_tokens: Vec<DeclaredUserToken> = match sections.remove(USER_TOKENS_KEY) {
+        Some(v) => serde_json::from_value(v).map_err(|e| format!("user_tokens: {}", e))?,
+        None => Vec::new(),
+    };
+    let security: DeclaredSecurity = match sections.remove(SECURITY_KEY) {
+        Some(v) => serde_json::from_value(v).map_err(|e| format!("security: {}", e))?,
+        None => DeclaredSecurity::default(),
+    };
+
+    let mut declared_keys = file_value.clone();
+    let mut merged = serde_json::to_value(base).map_err(|e| e.to_string())?;
+    merge(&mut merged, file_value);
+    let applied = apply_env_overrides(&mut merged, env_vars, |value| {
+        serde_json::from_value::<AppConfig>(value.clone()).is_ok()
+    });
+    for key in &applied {
+        tracing::info!("[ConfigFile] Environment override applied: {}", key);
+    }
+    // 环境变量覆盖的键同样参与未知键检查
+    let mut env_keys = Value::Object(Default::default());
+    apply_env_overrides(
+        &mut env_keys,
+        applied.iter().map(|k| {
+            (
+                format!("{}{}", ENV_OVERRIDE_PREFIX, k.replace('.', "__")),
+                "null".to_string(),
+            )
+        }),
+        |_| true,
+    );
+    merge(&mut declared_keys, env_keys);
+
+    let config: AppConfig =
+        serde_json::from_value(merged).map_err(|e| format!("invalid config: {}", e))?;
+
+    let parsed = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    let mut unknown_keys = Vec::new();
+    collect_unknown_keys(&declared_keys, &parsed, "", &mut unknown_keys);
+
+    let declared = DeclarativeConfig {
+        config,
+        user_tokens,
+        security,
+        unknown_keys,
+    };
+    validate(&declared)?;
+    Ok(declared)
+}
+
+fn env_override_vars() -> Vec<(String, String)> {
+    std::env::vars()
+        .filter(|(name, _)| name.starts_with(ENV_OVERRIDE_PREFIX))
+        .collect()
+}
+
+/// 启动时加载: 没有配置文件且没有 ABV__* 环境变量时返回 None
+pub fn load(base: &AppConfig, path: Option<&Path>) -> Result<Option<DeclarativeConfig>, String> {
+    let env_vars = env_override_vars();
+    let content = match path {
+        Some(path) => Some(
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))?,
+        ),
+        None if env_vars.is_empty() => return Ok(None),
+        None => None,
+    };
+    let declared = build_config(base, content.as_deref(), env_vars)?;
+    for key in &declared.unknown_keys {
+        tracing::warn!("[ConfigFile] Unknown key ignored: {}", key);
+    }
+    Ok(Some(declared))
+}
+
+/// 对齐声明的用户令牌与 IP 名单 (只新增或更新)，返回失败项
+pub fn reconcile(declared: &DeclarativeConfig) -> Vec<String> {
+    use crate::modules::{security_db, user_token_db};
+    let mut errors = Vec::new();
+
+    if !declared.user_tokens.is_empty() {
+        match user_token_db::list_tokens() {
+            Ok(existing) => {
+                for token in &declared.user_tokens {
+                    if let Err(e) = reconcile_user_token(token, &existing) {
+                        errors.push(format!("user token {}: {}", token.username, e));
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("user tokens: {}", e)),
+        }
+    }
+
+    if !declared.security.ip_blacklist.is_empty() {
+        match security_db::get_blacklist() {
+            Ok(existing) => {
+                for entry in &declared.security.ip_blacklist {
+                    let pattern = security_db::normalize_ip_pattern(&entry.pattern)
+                        .unwrap_or_else(|_| entry.pattern.clone());
+                    let pattern = pattern.as_str();
+                    if existing.iter().any(|e| e.ip_pattern == pattern) {
+                        continue;
+                    }
+                    if let Err(e) = security_db::add_to_blacklist(
+                        pattern,
+                        entry.note.as_deref(),
+                        None,
+                        "config_file",
+                    ) {
+                        errors.push(format!("blacklist {}: {}", pattern, e));
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("blacklist: {}", e)),
+        }
+    }
+
+    if !declared.security.ip_whitelist.is_empty() {
+        match security_db::get_whitelist() {
+            Ok(existing) => {
+                for entry in &declared.security.ip_whitelist {
+                    let pattern = security_db::normalize_ip_pattern(&entry.pattern)
+                        .unwrap_or_else(|_| entry.pattern.clone());
+                    let pattern = pattern.as_str();
+                    if existing.iter().any(|e| e.ip_pattern == pattern) {
+                        continue;
+                    }
+                    if let Err(e) = security_db::add_to_whitelist(pattern, entry.note.as_deref()) {
+                        errors.push(format!("whitelist {}: {}", pattern, e));
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("whitelist: {}", e)),
+        }
+    }
+
+    errors
+}
+
+fn reconcile_user_token(
+    declared: &DeclaredUserToken,
+    existing: &[crate::modules::user_token_db::UserToken],
+) -> Result<(), String> {
+    use crate::modules::user_token_db;
+
+    let id = match existing.iter().find(|t| t.username == declared.username) {
+        Some(token) => token.id.clone(),
+        None => {
+            let created = user_token_db::create_token(
+                declared.username.clone(),
+                "never".to_string(),
+                declared.description.clone(),
+                declared.max_ips,
+                declared.curfew_start.clone(),
+                declared.curfew_end.clone(),
+                None,
+            )?;
+            tracing::info!("[ConfigFile] Created user token for {}", declared.username);
+            created.id
+        }
+    };
+
+    if let Some(token) = declared.token.as_deref().map(str::trim) {
+        let current = existing
+            .iter()
+            .find(|t| t.id == id)
+            .map(|t| t.token.as_str());
+        if current != Some(token) {
+            user_token_db::set_token_value(&id, token)?;
+        }
+    }
+    user_token_db::update_token(
+        &id,
+        None,
+        declared.description.clone(),
+        Some(declared.enabled),
+        Some(declared.max_ips),
+        Some(declared.curfew_start.clone()),
+        Some(declared.curfew_end.clone()),
+    )?;
+    user_token_db::update_token_limits(
+        &id,
+        &user_token_db::TokenLimitsUpdate {
+            rate_limit_rpm: Some(declared.rate_limit_rpm),
+            daily_token_limit: Some(declared.daily_token_limit),
+            monthly_token_limit: Some(declared.monthly_token_limit),
+            total_token_limit: Some(declared.total_token_limit),
+            allowed_models: Some(declared.allowed_models.clone()),
+            denied_models: Some(declared.denied_models.clone()),
+        },
+    )
+}
+
+/// Headless 模式的环境变量覆盖 (ABV_API_KEY / ABV_WEB_PASSWORD / ABV_AUTH_MODE)，返回是否有修改
+pub fn apply_legacy_env_overrides(config: &mut AppConfig) -> bool {
+    let mut modified = false;
+
+    // [NEW] 支持通过环境变量注入 API Key
+    // 优先级：ABV_API_KEY > API_KEY > 配置文件
+    let env_key = std::env::var("ABV_API_KEY")
+        .or_else(|_| std::env::var("API_KEY"))
+        .ok();
+
+    if let Some(key) = env_key {
+        if !key.trim().is_empty() {
+            tracing::info!("Using API Key from environment variable");
+            config.proxy.api_key = key;
+            modified = true;
+        }
+    }
+
+    // [NEW] 支持通过环境变量注入 Web UI 密码
+    // 优先级：ABV_WEB_PASSWORD > WEB_PASSWORD > 配置文件
+    let env_web_password = std::env::var("ABV_WEB_PASSWORD")
+        .or_else(|_| std::env::var("WEB_PASSWORD"))
+        .ok();
+
+    if let Some(pwd) = env_web_password {
+        if !pwd.trim().is_empty() {
+            tracing::info!("Using Web UI Password from environment variable");
+            config.proxy.admin_password = Some(pwd);
+            modified = true;
+        }
+    }
+
+    // [NEW] 支持通过环境变量注入鉴权模式
+    // 优先级：ABV_AUTH_MODE > AUTH_MODE > 配置文件
+    let env_auth_mode = std::env::var("ABV_AUTH_MODE")
+        .or_else(|_| std::env::var("AUTH_MODE"))
+        .ok();
+
+    if let Some(mode_str) = env_auth_mode {
+        let mode = match mode_str.to_lowercase().as_str() {
+            "off" => Some(crate::proxy::ProxyAuthMode::Off),
+            "strict" => Some(crate::proxy::ProxyAuthMode::Strict),
+            "all_except_health" => Some(crate::proxy::ProxyAuthMode::AllExceptHealth),
+            "auto" => Some(crate::proxy::ProxyAuthMode::Auto),
+            _ => {
+                tracing::warn!("Invalid AUTH_MODE: {}, ignoring", mode_str);
+                None
+            }
+        };
+        if let Some(m) = mode {
+            tracing::info!("Using Auth Mode from environment variable: {:?}", m);
+            config.proxy.auth_mode = m;
+            modified = true;
+        }
+    }
+
+    modified
+}
+
+/// 重新加载配置文件并热更新 (Headless 模式)
+async fn reload(
+    path: &Path,
+    content: &str,
+    proxy_state: &crate::commands::proxy::ProxyServiceState,
+) -> Result<(), String> {
+    let base = crate::modules::config::load_app_config()?;
+    let mut declared = build_config(&base, Some(content), env_override_vars())?;
+    for key in &declared.unknown_keys {
+        tracing::warn!("[ConfigFile] Unknown key ignored: {}", key);
+    }
+
+    // Headless 模式始终监听 0.0.0.0，且 ABV_API_KEY 等环境变量优先于文件
+    declared.config.proxy.allow_lan_access = true;
+    apply_legacy_env_overrides(&mut declared.config);
+
+    if declared.config.proxy.port != base.proxy.port {
+        tracing::warn!(
+            "[ConfigFile] proxy.port changed ({} -> {}), takes effect after restart",
+            base.proxy.port,
+            declared.config.proxy.port
+        );
+    }
+
+    crate::modules::config::save_app_config(&declared.config)?;
+    crate::commands::internal_apply_config(proxy_state, &declared.config).await;
+    for error in reconcile(&declared) {
+        tracing::error!("[ConfigFile] {}", error);
+    }
+    tracing::info!("[ConfigFile] Reloaded {}", path.display());
+    Ok(())
+}
+
+/// 监听配置文件变更 (按内容比较)，校验失败时保留当前配置
+pub fn start_watcher(path: PathBuf, proxy_state: crate::commands::proxy::ProxyServiceState) {
+    tokio::spawn(async move {
+        let mut last = std::fs::read_to_string(&path).ok();
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(WATCH_INTERVAL_SECS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        tracing::info!("[ConfigFile] Watching {} for changes", path.display());
+
+        loop {
+            interval.tick().await;
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                // 文件暂时缺失 (编辑器替换中) 时保留当前配置
+                Err(_) => continue,
+            };
+            if last.as_deref() == Some(content.as_str()) {
+                continue;
+            }
+            last = Some(content.clone());
+
+            if let Err(e) = reload(&path, &content, &proxy_state).await {
+                tracing::error!(
+                    "[ConfigFile] Rejected {}, keeping current config: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+[proxy]
+port = 9000
+request_timeout = 300
+
+[proxy.custom_mapping]
+"gpt-4o" = "gemini-3-flash"
+
+[proxy.response_cache]
+enabled = true
+
+[[user_tokens]]
+username = "ci"
+rate_limit_rpm = 60
+allowed_models = ["gemini-*"]
+
+[[security.ip_blacklist]]
+pattern = "203.0.113.0/24"
+note = "scanner"
+"#;
+
+    #[test]
+    fn test_build_config_merges_file_and_env() {
+        let mut base = AppConfig::new();
+        base.proxy.api_key = "sk-base".to_string();
+        base.proxy
+            .custom_mapping
+            .insert("claude-*".to_string(), "claude-sonnet-4-5".to_string());
+
+        let env = vec![
+            ("ABV__PROXY__REQUEST_TIMEOUT".to_string(), "600".to_string()),
+            ("ABV__PROXY__API_KEY".to_string(), "12345678".to_string()),
+            // 原值为 null 的字符串字段: 纯数字不能按 JSON 数字写入
+            (
+                "ABV__PROXY__ADMIN_PASSWORD".to_string(),
+                "123456".to_string(),
+            ),
+            ("UNRELATED".to_string(), "x".to_string()),
+        ];
+        let declared = build_config(&base, Some(SAMPLE), env).unwrap();
+        let proxy = &declared.config.proxy;
+
+        assert_eq!(proxy.port, 9000);
+        // 环境变量优先于文件，字符串字段保持字符串
+        assert_eq!(proxy.request_timeout, 600);
+        assert_eq!(proxy.api_key, "12345678");
+        assert_eq!(proxy.admin_password.as_deref(), Some("123456"));
+        // 文件未写出的键保持原值
+        assert_eq!(proxy.custom_mapping.len(), 2);
+        assert_eq!(proxy.custom_mapping["gpt-4o"], "gemini-3-flash");
+        assert!(proxy.response_cache.enabled);
+
+        assert_eq!(declared.user_tokens.len(), 1);
+        assert_eq!(declared.user_tokens[0].rate_limit_rpm, 60);
+        assert!(declared.user_tokens[0].enabled);
+        assert_eq!(declared.security.ip_blacklist[0].pattern, "203.0.113.0/24");
+        assert!(declared.unknown_keys.is_empty());
+    }
+
+    #[test]
+    fn test_build_config_reports_errors() {
+        let base = AppConfig::new();
+        let none = Vec::<(String, String)>::new;
+
+        let err = build_config(&base, Some("[proxy\nport = 1"), none()).unwrap_err();
+        assert!(err.starts_with("invalid TOML"));
+
+        let err = build_config(&base, Some("[proxy]\nport = \"abc\""), none()).unwrap_err();
+        assert!(err.starts_with("invalid config"));
+
+        let err = build_config(&base, Some("[proxy]\nport = 0"), none()).unwrap_err();
+        assert!(err.contains("proxy.port"));
+
+        let dup = "[[user_tokens]]\nusername = \"a\"\n[[user_tokens]]\nusername = \"a\"";
+        assert!(build_config(&base, Some(dup), none())
+            .unwrap_err()
+            .contains("duplicate username"));
+
+        let typo = build_config(&base, Some("[proxy]\nprot = 1"), none()).unwrap();
+        assert_eq!(typo.unknown_keys, vec!["proxy.prot".to_string()]);
+    }
+}
@@ -0,0 +1,210 @@
+//! Event Bus Module
+//! 应用事件总线: Tauri 前端 (Emitter) 与 /api/events (SSE / WebSocket) 共同订阅同一广播通道，
+//! 使 headless / Docker 模式下的 Web UI 也能实时收到事件而无需轮询
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// 反代请求完成 (payload: 不含 body 的 ProxyRequestLog)
+pub const EVENT_PROXY_REQUEST: &str = "proxy://request";
+/// 账号配额已刷新
+pub const EVENT_ACCOUNTS_REFRESHED: &str = "accounts://refreshed";
+/// 配置已更新
+pub const EVENT_CONFIG_UPDATED: &str = "config://updated";
+/// 托盘触发刷新当前账号
+pub const EVENT_TRAY_REFRESH_CURRENT: &str = "tray://refresh-current";
+/// 托盘切换账号 (payload: account_id)
+pub const EVENT_TRAY_ACCOUNT_SWITCHED: &str = "tray://account-switched";
+/// 账号被禁用 (payload: account_id, email, reason)
+pub const EVENT_ACCOUNT_DISABLED: &str = "account://disabled";
+/// 账号被上游拒绝访问 (403) (payload: account_id, email, reason)
+pub const EVENT_ACCOUNT_FORBIDDEN: &str = "account://forbidden";
+/// 账号需要验证，暂时屏蔽 (payload: account_id, email, reason, blocked_until)
+pub const EVENT_ACCOUNT_VALIDATION_BLOCKED: &str = "account://validation-blocked";
+/// refresh_token 失效 (invalid_grant) (payload: account_id, error)
+pub const EVENT_ACCOUNT_INVALID_GRANT: &str = "account://invalid-grant";
+/// 模型触发配额保护 (payload: account_id, email, model, percentage, threshold)
+pub const EVENT_QUOTA_PROTECTED: &str = "quota://protected";
+/// IP 被自动封禁 (payload: ip, rule, reason, level, ban_seconds, blocked_until)
+pub const EVENT_SECURITY_AUTO_BAN: &str = "security://auto-ban";
+
+/// 广播通道容量，慢订阅者落后超过该值时会丢弃最旧的事件
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// 总线上的单个事件
+#[derive(Debug, Clone, Serialize)]
+pub struct AppEvent {
+    /// 事件类型 (与 Tauri 事件名一致，如 "proxy://request")
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub payload: Value,
+    /// 关联账号 (邮箱)，用于按账号过滤
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    /// 关联的 User Token 用户名，用于按令牌过滤
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_token: Option<String>,
+    pub timestamp: i64,
+}
+
+impl AppEvent {
+    pub fn new(event_type: &str, payload: impl Serialize) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            payload: serde_json::to_value(payload).unwrap_or(Value::Null),
+            account: None,
+            user_token: None,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    pub fn with_account(mut self, account: Option<String>) -> Self {
+        self.account = account;
+        self
+    }
+
+    pub fn with_user_token(mut self, user_token: Option<String>) -> Self {
+        self.user_token = user_token;
+        self
+    }
+}
+
+fn sender() -> &'static broadcast::Sender<AppEvent> {
+    static SENDER: OnceLock<broadcast::Sender<AppEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(EVENT_CHANNEL_CAPACITY).0)
+}
+
+/// 发布事件 (无订阅者时直接丢弃)
+pub fn publish(event: AppEvent) {
+    let _ = sender().send(event);
+}
+
+/// 订阅事件流
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    sender().subscribe()
+}
+
+/// 将总线事件转发给 Tauri 前端 (桌面模式下在 setup 中调用一次)
+pub fn start_tauri_forwarder(app_handle: tauri::AppHandle) {
+    use tauri::Emitter;
+
+    let mut rx = subscribe();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    let _ = app_handle.emit(&event.event_type, &event.payload);
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "[EventBus] Tauri forwarder lagged, skipped {} events",
+                        skipped
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// 订阅过滤条件 (/api/events 查询参数)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EventFilter {
+    /// 逗号分隔的事件类型，支持前缀通配 (如 "proxy://*")
+    #[serde(default)]
+    pub types: Option<String>,
+    /// 仅接收该账号 (邮箱) 的事件
+    #[serde(default)]
+    pub account: Option<String>,
+    /// 仅接收该 User Token 用户名的事件
+    #[serde(default)]
+    pub user_token: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &AppEvent) -> bool {
+        if let Some(types) = self.types.as_deref().filter(|t| !t.trim().is_empty()) {
+            let type_matched = types
+                .split(',')
+                .map(str::trim)
+                .any(|t| match t.strip_suffix('*') {
+                    Some(prefix) => event.event_type.starts_with(prefix),
+                    None => event.event_type == t,
+                });
+            if !type_matched {
+                return false;
+            }
+        }
+
+        if let Some(account) = self.account.as_deref().filter(|a| !a.is_empty()) {
+            if !event
+                .account
+                .as_deref()
+                .is_some_and(|a| a.eq_ignore_ascii_case(account))
+            {
+                return false;
+            }
+        }
+
+        if let Some(user_token) = self.user_token.as_deref().filter(|u| !u.is_empty()) {
+            if event.user_token.as_deref() != Some(user_token) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_filter() {
+        let event = AppEvent::new(EVENT_PROXY_REQUEST, serde_json::json!({ "status": 200 }))
+            .with_account(Some("User@Example.com".to_string()))
+            .with_user_token(Some("alice".to_string()));
+
+        assert!(EventFilter::default().matches(&event));
+
+        let by_type = |types: &str| EventFilter {
+            types: Some(types.to_string()),
+            ..Default::default()
+        };
+        assert!(by_type("proxy://*").matches(&event));
+        assert!(by_type("config://updated, proxy://request").matches(&event));
+        assert!(!by_type("accounts://refreshed").matches(&event));
+
+        let by_account = EventFilter {
+            account: Some("user@example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(by_account.matches(&event));
+        assert!(!by_account.matches(&AppEvent::new(EVENT_CONFIG_UPDATED, ())));
+
+        let by_token = EventFilter {
+            user_token: Some("bob".to_string()),
+            ..Default::default()
+        };
+        assert!(!by_token.matches(&event));
+    }
+
+    #[tokio::test]
+    async fn test_publish_subscribe() {
+        let mut rx = subscribe();
+        let marker = uuid::Uuid::new_v4().to_string();
+        publish(AppEvent::new(EVENT_ACCOUNTS_REFRESHED, &marker));
+
+        // 其他测试可能并发发布事件，跳过无关事件
+        loop {
+            let event = rx.recv().await.unwrap();
+            if event.payload == Value::String(marker.clone()) {
+                assert_eq!(event.event_type, EVENT_ACCOUNTS_REFRESHED);
+                break;
+            }
+        }
+    }
+}
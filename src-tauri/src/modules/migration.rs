@@ -369,3 +369,52 @@ pub fn get_refresh_token_from_db() -> Result<String, String> {
     let db_path = db::get_db_path()?;
     extract_refresh_token_from_file(&db_path)
 }
+
+/// [NEW] 将账号文件中的明文 Token 迁移为落盘加密格式
+///
+/// 同时处理加密方式变更 (如新设置了 `ABV_TOKEN_PASSPHRASE`，或旧版固定盐值的口令密文、
+/// 从其他安装恢复的密文)：使用密文自带的盐值解密后按本机盐值重新加密。
+/// 返回被改写的账号文件数量。
+pub fn migrate_account_token_encryption() -> Result<usize, String> {
+    let accounts_dir = account::get_accounts_dir()?;
+    let entries =
+        fs::read_dir(&accounts_dir).map_err(|e| format!("Failed to read accounts dir: {}", e))?;
+
+    let mut migrated = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let result = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()))
+            .and_then(|mut account_json| {
+                if !account::encrypt_account_token_fields(&mut account_json)? {
+                    return Ok(false);
+                }
+                let content =
+                    serde_json::to_string_pretty(&account_json).map_err(|e| e.to_string())?;
+                fs::write(&path, content).map_err(|e| e.to_string())?;
+                Ok(true)
+            });
+
+        match result {
+            Ok(true) => migrated += 1,
+            Ok(false) => {}
+            Err(e) => crate::modules::logger::log_warn(&format!(
+                "Failed to encrypt tokens in {:?}: {}",
+                path, e
+            )),
+        }
+    }
+
+    if migrated > 0 {
+        crate::modules::logger::log_info(&format!(
+            "Encrypted tokens at rest for {} account(s)",
+            migrated
+        ));
+    }
+    Ok(migrated)
+}
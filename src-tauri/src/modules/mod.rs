@@ -1,10 +1,14 @@
 pub mod account;
 pub mod account_service;
+pub mod audit_db;
+pub mod batch_db;
 pub mod cache;
 pub mod cloudflared;
 pub mod config;
+pub mod config_file;
 pub mod db;
 pub mod device;
+pub mod event_bus;
 #[allow(dead_code)]
 pub mod http_api;
 pub mod i18n;
@@ -17,13 +21,17 @@ pub mod oauth_server;
 pub mod process;
 pub mod proxy_db;
 pub mod quota;
+pub mod response_cache_db;
+pub mod responses_db;
 pub mod scheduler;
 pub mod security_db;
+pub mod session_cache_db;
 pub mod token_stats;
 pub mod tray;
 pub mod update_checker;
 pub mod user_token_db;
 pub mod version;
+pub mod webhook;
 
 use crate::models;
 
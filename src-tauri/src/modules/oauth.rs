@@ -73,6 +73,7 @@ fn record_token_refresh_log(
         output_tokens: None,
         protocol: Some("oauth".to_string()),
         username: None,
+        cache_hit: false,
     };
 
     if let Err(e) = crate::modules::proxy_db::save_log(&log) {
@@ -271,6 +272,23 @@ pub async fn refresh_access_token(
         Ok(token_data)
     } else {
         let error_text = response.text().await.unwrap_or_default();
+        // [NEW] refresh_token 失效时推送事件 (Webhook / /api/events)
+        if error_text.contains("invalid_grant") {
+            let email = account_id
+                .and_then(|id| crate::modules::account::load_account(id).ok())
+                .map(|account| account.email);
+            crate::modules::event_bus::publish(
+                crate::modules::event_bus::AppEvent::new(
+                    crate::modules::event_bus::EVENT_ACCOUNT_INVALID_GRANT,
+                    serde_json::json!({
+                        "account_id": account_id,
+                        "email": email,
+                        "error": error_text
+                    }),
+                )
+                .with_account(email),
+            );
+        }
         Err(format!("Refresh failed: {}", error_text))
     }
 }
@@ -61,6 +61,10 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN protocol TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_ip TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN username TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE request_logs ADD COLUMN cache_hit INTEGER DEFAULT 0",
+        [],
+    );
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
@@ -82,8 +86,8 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let conn = connect_db()?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
         params![
             log.id,
             log.timestamp,
@@ -102,6 +106,7 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.protocol,
             log.client_ip,
             log.username,
+            log.cache_hit,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -116,7 +121,7 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
         .prepare(
             "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2",
@@ -143,6 +148,7 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                cache_hit: row.get(17).unwrap_or(false),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -191,7 +197,7 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
         .prepare(
             "SELECT id, timestamp, method, url, status, duration, model, error,
                 request_body, response_body, input_tokens, output_tokens,
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username, cache_hit
          FROM request_logs
          WHERE id = ?1",
         )
@@ -216,6 +222,7 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
             protocol: row.get(14).unwrap_or(None),
             client_ip: row.get(15).unwrap_or(None),
             username: row.get(16).unwrap_or(None),
+            cache_hit: row.get(17).unwrap_or(false),
         })
     })
     .map_err(|e| e.to_string())
@@ -396,7 +403,7 @@ pub fn get_logs_filtered(
     let sql = if errors_only {
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
          FROM request_logs
          WHERE (status < 200 OR status >= 400)
          ORDER BY timestamp DESC
@@ -404,14 +411,14 @@ pub fn get_logs_filtered(
     } else if filter.is_empty() {
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
          FROM request_logs
          ORDER BY timestamp DESC
          LIMIT ?1 OFFSET ?2"
     } else {
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
          FROM request_logs
          WHERE (url LIKE ?3 OR method LIKE ?3 OR model LIKE ?3 OR CAST(status AS TEXT) LIKE ?3 OR account_email LIKE ?3 OR client_ip LIKE ?3)
          ORDER BY timestamp DESC
@@ -440,6 +447,7 @@ pub fn get_logs_filtered(
                     protocol: row.get(14).unwrap_or(None),
                     client_ip: row.get(15).unwrap_or(None),
                     username: row.get(16).unwrap_or(None),
+                    cache_hit: row.get(17).unwrap_or(false),
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -466,6 +474,7 @@ pub fn get_logs_filtered(
                     protocol: row.get(14).unwrap_or(None),
                     client_ip: row.get(15).unwrap_or(None),
                     username: row.get(16).unwrap_or(None),
+                    cache_hit: row.get(17).unwrap_or(false),
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -492,6 +501,7 @@ pub fn get_logs_filtered(
                     protocol: row.get(14).unwrap_or(None),
                     client_ip: row.get(15).unwrap_or(None),
                     username: row.get(16).unwrap_or(None),
+                    cache_hit: row.get(17).unwrap_or(false),
                 })
             })
             .map_err(|e| e.to_string())?;
@@ -519,7 +529,7 @@ pub fn get_logs_between_refreshes(
             (
                 "SELECT id, timestamp, method, url, status, duration, model, error,
                         NULL as request_body, NULL as response_body,
-                        input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                        input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
                  FROM request_logs
                  WHERE timestamp >= ?1 AND timestamp <= ?2
                    AND method != 'TOKEN_REFRESH'
@@ -532,7 +542,7 @@ pub fn get_logs_between_refreshes(
             (
                 "SELECT id, timestamp, method, url, status, duration, model, error,
                         NULL as request_body, NULL as response_body,
-                        input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                        input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
                  FROM request_logs
                  WHERE timestamp >= ?1 AND timestamp <= ?2
                    AND method != 'TOKEN_REFRESH'
@@ -553,7 +563,7 @@ pub fn get_logs_between_refreshes(
         (
             "SELECT id, timestamp, method, url, status, duration, model, error,
                     NULL as request_body, NULL as response_body,
-                    input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                    input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
              FROM request_logs
              WHERE timestamp >= ?1 AND timestamp <= ?2
                AND method != 'TOKEN_REFRESH'
@@ -565,7 +575,7 @@ pub fn get_logs_between_refreshes(
         (
             "SELECT id, timestamp, method, url, status, duration, model, error,
                     NULL as request_body, NULL as response_body,
-                    input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                    input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit
              FROM request_logs
              WHERE timestamp >= ?1 AND timestamp <= ?2
                AND method != 'TOKEN_REFRESH'
@@ -603,6 +613,7 @@ pub fn get_logs_between_refreshes(
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                cache_hit: row.get(17).unwrap_or(false),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -618,7 +629,7 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
         .prepare(
             "SELECT id, timestamp, method, url, status, duration, model, error,
                 request_body, response_body, input_tokens, output_tokens,
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username, cache_hit
          FROM request_logs
          ORDER BY timestamp DESC",
         )
@@ -644,6 +655,7 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                cache_hit: row.get(17).unwrap_or(false),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -406,23 +406,17 @@ pub async fn warmup_model_directly(
         "project_id": project_id
     });
 
-    // Use a no-proxy client for local loopback requests
-    // This prevents Docker environments from routing localhost through external proxies
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(60))
-        .no_proxy()
-        .build()
-        .unwrap_or_else(|_| reqwest::Client::new());
-    let resp = client
-        .post(&warmup_url)
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await;
+    // [NEW] 仅监听 Unix 套接字时通过套接字调用内部端点
+    #[cfg(unix)]
+    let resp = match crate::proxy::unix_socket::exclusive_socket_path() {
+        Some(socket) => send_warmup_via_socket(&socket, &body).await,
+        None => send_warmup_via_tcp(&warmup_url, &body).await,
+    };
+    #[cfg(not(unix))]
+    let resp = send_warmup_via_tcp(&warmup_url, &body).await;
 
     match resp {
-        Ok(response) => {
-            let status = response.status();
+        Ok((status, text)) => {
             if status.is_success() {
                 crate::modules::logger::log_info(&format!(
                     "[Warmup] ✓ Triggered {} for {} (was {}%)",
@@ -430,7 +424,6 @@ pub async fn warmup_model_directly(
                 ));
                 true
             } else {
-                let text = response.text().await.unwrap_or_default();
                 crate::modules::logger::log_warn(&format!(
                     "[Warmup] ✗ {} for {} (was {}%): HTTP {} - {}",
                     model_name, email, percentage, status, text
@@ -448,6 +441,60 @@ pub async fn warmup_model_directly(
     }
 }
 
+async fn send_warmup_via_tcp(
+    warmup_url: &str,
+    body: &serde_json::Value,
+) -> Result<(reqwest::StatusCode, String), String> {
+    // Use a no-proxy client for local loopback requests
+    // This prevents Docker environments from routing localhost through external proxies
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(60))
+        .no_proxy()
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new());
+    let response = client
+        .post(warmup_url)
+        .header("Content-Type", "application/json")
+        // [NEW] 内部端点要求携带进程级密钥
+        .header(
+            crate::proxy::middleware::auth::INTERNAL_SECRET_HEADER,
+            crate::proxy::middleware::auth::internal_secret(),
+        )
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let text = if status.is_success() {
+        String::new()
+    } else {
+        response.text().await.unwrap_or_default()
+    };
+    Ok((status, text))
+}
+
+#[cfg(unix)]
+async fn send_warmup_via_socket(
+    socket: &std::path::Path,
+    body: &serde_json::Value,
+) -> Result<(reqwest::StatusCode, String), String> {
+    let request = axum::http::Request::post("/internal/warmup")
+        .header("host", "localhost")
+        .header("content-type", "application/json")
+        .header(
+            crate::proxy::middleware::auth::INTERNAL_SECRET_HEADER,
+            crate::proxy::middleware::auth::internal_secret(),
+        )
+        .body(axum::body::Body::from(body.to_string()))
+        .map_err(|e| e.to_string())?;
+    tokio::time::timeout(
+        std::time::Duration::from_secs(60),
+        crate::proxy::unix_socket::send_request(socket, request),
+    )
+    .await
+    .map_err(|_| "request timed out".to_string())?
+}
+
 /// Smart warmup for all accounts
 pub async fn warm_up_all_accounts() -> Result<String, String> {
     let mut retry_count = 0;
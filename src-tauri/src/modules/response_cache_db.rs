@@ -0,0 +1,202 @@
+//! Response Cache Database Module
+//! 确定性请求的响应缓存 (按规范化请求哈希精确匹配，TTL + 条目数上限)
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 一条缓存的上游响应
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub key: String,
+    pub protocol: String,
+    pub model: String,
+    pub stream: bool,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseCacheStats {
+    pub entries: u64,
+    pub total_bytes: u64,
+    pub total_hits: u64,
+}
+
+/// 获取缓存数据库路径
+pub fn get_response_cache_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("response_cache.db"))
+}
+
+/// 连接数据库
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_response_cache_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化缓存数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS response_cache (
+            key TEXT PRIMARY KEY,
+            protocol TEXT NOT NULL,
+            model TEXT NOT NULL,
+            stream INTEGER NOT NULL,
+            content_type TEXT NOT NULL,
+            body BLOB NOT NULL,
+            size INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            last_hit_at INTEGER NOT NULL,
+            hits INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS idx_response_cache_expires ON response_cache (expires_at);
+        CREATE INDEX IF NOT EXISTS idx_response_cache_last_hit ON response_cache (last_hit_at);",
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 查询未过期的缓存条目，命中时累加命中次数
+pub fn get_entry(key: &str) -> Result<Option<CachedResponse>, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+
+    let entry = conn
+        .query_row(
+            "SELECT key, protocol, model, stream, content_type, body
+             FROM response_cache WHERE key = ?1 AND expires_at > ?2",
+            params![key, now],
+            |row| {
+                Ok(CachedResponse {
+                    key: row.get(0)?,
+                    protocol: row.get(1)?,
+                    model: row.get(2)?,
+                    stream: row.get(3)?,
+                    content_type: row.get(4)?,
+                    body: row.get(5)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if entry.is_some() {
+        conn.execute(
+            "UPDATE response_cache SET hits = hits + 1, last_hit_at = ?2 WHERE key = ?1",
+            params![key, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(entry)
+}
+
+/// 写入缓存条目，并清理过期条目与超出上限的最久未命中条目
+pub fn put_entry(entry: &CachedResponse, ttl_seconds: u64, max_entries: u64) -> Result<(), String> {
+    let mut conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = now.saturating_add(ttl_seconds.min(i64::MAX as u64) as i64);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT OR REPLACE INTO response_cache
+            (key, protocol, model, stream, content_type, body, size, created_at, expires_at, last_hit_at, hits)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?8, 0)",
+        params![
+            entry.key,
+            entry.protocol,
+            entry.model,
+            entry.stream,
+            entry.content_type,
+            entry.body,
+            entry.body.len() as i64,
+            now,
+            expires_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM response_cache WHERE expires_at <= ?1", [now])
+        .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM response_cache WHERE key IN (
+            SELECT key FROM response_cache ORDER BY last_hit_at DESC LIMIT -1 OFFSET ?1
+        )",
+        [max_entries.max(1) as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 清空缓存，返回删除的条目数
+pub fn clear_cache() -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM response_cache", [])
+        .map_err(|e| e.to_string())
+}
+
+/// 缓存统计 (仅统计未过期条目)
+pub fn get_stats() -> Result<ResponseCacheStats, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(size), 0), COALESCE(SUM(hits), 0)
+         FROM response_cache WHERE expires_at > ?1",
+        [now],
+        |row| {
+            Ok(ResponseCacheStats {
+                entries: row.get(0)?,
+                total_bytes: row.get(1)?,
+                total_hits: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_cache_put_get() {
+        let _ = init_db();
+
+        let key = format!("test-{}", uuid::Uuid::new_v4().simple());
+        let entry = CachedResponse {
+            key: key.clone(),
+            protocol: "openai".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            stream: true,
+            content_type: "text/event-stream".to_string(),
+            body: b"data: {\"choices\":[]}\n\ndata: [DONE]\n\n".to_vec(),
+        };
+        put_entry(&entry, 60, 100_000).unwrap();
+
+        let cached = get_entry(&key).unwrap().unwrap();
+        assert!(cached.stream);
+        assert_eq!(cached.body, entry.body);
+        assert!(get_entry("missing-key").unwrap().is_none());
+
+        // TTL 为 0 的条目立即过期
+        let expired = CachedResponse {
+            key: format!("{}-expired", key),
+            ..entry
+        };
+        put_entry(&expired, 0, 100_000).unwrap();
+        assert!(get_entry(&expired.key).unwrap().is_none());
+    }
+}
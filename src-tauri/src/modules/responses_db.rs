@@ -0,0 +1,248 @@
+//! Responses Database Module
+//! OpenAI Responses API 的会话存储 (previous_response_id 链式对话)
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// 已存储响应的保留天数 (与 OpenAI 官方 30 天保留策略一致)
+const RESPONSE_RETENTION_DAYS: i64 = 30;
+
+/// previous_response_id 链的最大回溯深度，防止异常数据导致无限循环
+const MAX_CHAIN_DEPTH: usize = 1000;
+
+/// 已存储的 Response 记录
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    pub id: String,
+    pub created_at: i64,
+    pub model: String,
+    pub previous_response_id: Option<String>,
+    /// 本轮请求新增的 input items (已标准化为 item 数组)
+    pub input_items: Value,
+    /// 本轮模型产生的 output items
+    pub output_items: Value,
+    /// 完整的 Response 对象 (用于 GET /v1/responses/{id})
+    pub response: Value,
+    /// 创建者的 UserToken ID (未使用 UserToken 的请求为 None)，查询、删除与链式回溯均按此隔离
+    pub owner_token_id: Option<String>,
+}
+
+/// 获取 Responses 数据库路径
+pub fn get_responses_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("responses.db"))
+}
+
+/// 连接数据库
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_responses_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化 Responses 数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS responses (
+            id TEXT PRIMARY KEY,
+            created_at INTEGER NOT NULL,
+            model TEXT NOT NULL,
+            previous_response_id TEXT,
+            input_items TEXT NOT NULL,
+            output_items TEXT NOT NULL,
+            response TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_responses_created_at ON responses (created_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // [NEW] 归属隔离 (旧库升级时补列，已存在则忽略)
+    let _ = conn.execute("ALTER TABLE responses ADD COLUMN owner_token_id TEXT", []);
+
+    // 启动时清理过期记录
+    let _ = cleanup_old_responses(RESPONSE_RETENTION_DAYS);
+
+    Ok(())
+}
+
+/// 保存 Response
+pub fn save_response(record: &StoredResponse) -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO responses
+            (id, created_at, model, previous_response_id, input_items, output_items, response, owner_token_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            record.id,
+            record.created_at,
+            record.model,
+            record.previous_response_id,
+            record.input_items.to_string(),
+            record.output_items.to_string(),
+            record.response.to_string(),
+            record.owner_token_id,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 根据 ID 获取调用方名下的 Response (`owner` 为调用方的 UserToken ID)
+pub fn get_response(id: &str, owner: Option<&str>) -> Result<Option<StoredResponse>, String> {
+    let conn = connect_db()?;
+
+    conn.query_row(
+        "SELECT id, created_at, model, previous_response_id, input_items, output_items, response,
+                owner_token_id
+         FROM responses WHERE id = ?1 AND owner_token_id IS ?2",
+        params![id, owner],
+        |row| {
+            let input_items: String = row.get(4)?;
+            let output_items: String = row.get(5)?;
+            let response: String = row.get(6)?;
+            Ok(StoredResponse {
+                id: row.get(0)?,
+                created_at: row.get(1)?,
+                model: row.get(2)?,
+                previous_response_id: row.get(3)?,
+                input_items: serde_json::from_str(&input_items).unwrap_or(Value::Array(vec![])),
+                output_items: serde_json::from_str(&output_items).unwrap_or(Value::Array(vec![])),
+                response: serde_json::from_str(&response).unwrap_or(Value::Null),
+                owner_token_id: row.get(7)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| e.to_string())
+}
+
+/// 删除调用方名下的 Response，返回是否存在该记录
+pub fn delete_response(id: &str, owner: Option<&str>) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let affected = conn
+        .execute(
+            "DELETE FROM responses WHERE id = ?1 AND owner_token_id IS ?2",
+            params![id, owner],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(affected > 0)
+}
+
+/// 沿 previous_response_id 链回溯，按时间顺序返回完整的对话 items
+///
+/// 只回溯调用方名下的记录；返回 `Ok(None)` 表示链的起点 ID 不存在 (已删除、过期、store=false 或属于其他令牌)。
+pub fn load_conversation_items(
+    id: &str,
+    owner: Option<&str>,
+) -> Result<Option<Vec<Value>>, String> {
+    let mut chain = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = Some(id.to_string());
+
+    while let Some(current_id) = current {
+        if chain.len() >= MAX_CHAIN_DEPTH || !visited.insert(current_id.clone()) {
+            break;
+        }
+        match get_response(&current_id, owner)? {
+            Some(record) => {
+                current = record.previous_response_id.clone();
+                chain.push(record);
+            }
+            // 起点不存在视为错误；中间节点缺失则截断历史
+            None if chain.is_empty() => return Ok(None),
+            None => break,
+        }
+    }
+
+    let mut items = Vec::new();
+    for record in chain.iter().rev() {
+        if let Some(arr) = record.input_items.as_array() {
+            items.extend(arr.iter().cloned());
+        }
+        if let Some(arr) = record.output_items.as_array() {
+            items.extend(arr.iter().cloned());
+        }
+    }
+    Ok(Some(items))
+}
+
+/// 清理过期的 Response 记录
+pub fn cleanup_old_responses(days: i64) -> Result<usize, String> {
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now().timestamp() - days * 24 * 3600;
+    conn.execute("DELETE FROM responses WHERE created_at < ?1", [cutoff])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: &str, previous: Option<&str>, input: &str, output: &str) -> StoredResponse {
+        StoredResponse {
+            id: id.to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            model: "gemini-2.5-flash".to_string(),
+            previous_response_id: previous.map(|s| s.to_string()),
+            input_items: json!([{ "type": "message", "role": "user", "content": input }]),
+            output_items: json!([{ "type": "message", "role": "assistant", "content": output }]),
+            response: json!({ "id": id, "object": "response" }),
+            owner_token_id: Some("token-a".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_conversation_chain_roundtrip() {
+        let _ = init_db();
+
+        let first = format!("resp_test_{}", uuid::Uuid::new_v4().simple());
+        let second = format!("resp_test_{}", uuid::Uuid::new_v4().simple());
+        save_response(&record(&first, None, "hi", "hello")).unwrap();
+        save_response(&record(&second, Some(&first), "again", "hello again")).unwrap();
+
+        let items = load_conversation_items(&second, Some("token-a"))
+            .unwrap()
+            .unwrap();
+        let contents: Vec<&str> = items
+            .iter()
+            .filter_map(|i| i.get("content").and_then(|c| c.as_str()))
+            .collect();
+        assert_eq!(contents, vec!["hi", "hello", "again", "hello again"]);
+
+        // 其他令牌既读不到也删不掉，也不能以此为 previous_response_id 续接
+        assert!(get_response(&second, Some("token-b")).unwrap().is_none());
+        assert!(get_response(&second, None).unwrap().is_none());
+        assert!(load_conversation_items(&second, Some("token-b"))
+            .unwrap()
+            .is_none());
+        assert!(!delete_response(&second, Some("token-b")).unwrap());
+
+        assert!(delete_response(&second, Some("token-a")).unwrap());
+        assert!(!delete_response(&second, Some("token-a")).unwrap());
+        assert!(load_conversation_items(&second, Some("token-a"))
+            .unwrap()
+            .is_none());
+        let _ = delete_response(&first, Some("token-a"));
+    }
+}
@@ -189,7 +189,6 @@ pub fn start_scheduler(
                     total
                 ));
 
-                let handle_for_warmup = app_handle.clone();
                 let state_for_warmup = proxy_state.clone();
 
                 tokio::spawn(async move {
@@ -254,11 +253,7 @@ pub fn start_scheduler(
 
                     // Refresh quota
                     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                    let _ = crate::commands::refresh_all_quotas_internal(
-                        &state_for_warmup,
-                        handle_for_warmup,
-                    )
-                    .await;
+                    let _ = crate::commands::refresh_all_quotas_internal(&state_for_warmup).await;
                 });
             } else if skipped_cooldown > 0 {
                 logger::log_info(&format!(
@@ -272,16 +267,11 @@ pub fn start_scheduler(
             }
 
             // Sync to frontend if handle exists
-            if let Some(handle) = app_handle.as_ref() {
-                let handle_inner = handle.clone();
+            if app_handle.is_some() {
                 let state_inner = proxy_state.clone();
                 tokio::spawn(async move {
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                    let _ = crate::commands::refresh_all_quotas_internal(
-                        &state_inner,
-                        Some(handle_inner),
-                    )
-                    .await;
+                    let _ = crate::commands::refresh_all_quotas_internal(&state_inner).await;
                     logger::log_info("[Scheduler] Quota data synced to frontend");
                 });
             }
@@ -3,6 +3,7 @@
 
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use std::path::PathBuf;
 
 /// IP 访问日志
@@ -35,6 +36,26 @@ pub struct IpBlacklistEntry {
     pub hit_count: i64,
 }
 
+/// 自动封禁条目的 created_by 标记
+pub const AUTO_BAN_CREATED_BY: &str = "auto";
+
+/// 自动封禁记录 (触发规则与原因)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBanRecord {
+    pub id: String,
+    pub client_ip: String,
+    pub rule: String,
+    pub reason: String,
+    /// 该 IP 近期第几次被自动封禁 (从 1 开始)
+    pub level: u32,
+    pub ban_seconds: i64,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub blacklist_id: String,
+    /// 对应黑名单条目仍然存在且未过期
+    pub active: bool,
+}
+
 /// IP 白名单条目
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpWhitelistEntry {
@@ -167,6 +188,29 @@ pub fn init_db() -> Result<(), String> {
     // Migration: Add username column to ip_access_logs
     let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN username TEXT", []);
 
+    // [NEW] 自动封禁记录表 (封禁时长递增依据)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ip_auto_bans (
+            id TEXT PRIMARY KEY,
+            client_ip TEXT NOT NULL,
+            rule TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            level INTEGER NOT NULL,
+            ban_seconds INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            blacklist_id TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_auto_bans_ip ON ip_auto_bans (client_ip, created_at DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -389,6 +433,8 @@ pub fn add_to_blacklist(
     expires_at: Option<i64>,
     created_by: &str,
 ) -> Result<IpBlacklistEntry, String> {
+    let ip_pattern = normalize_ip_pattern(ip_pattern)?;
+    let ip_pattern = ip_pattern.as_str();
     let conn = connect_db()?;
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -471,11 +517,12 @@ pub fn get_blacklist_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>,
         [now],
     );
 
-    // 精确匹配
+    // 精确匹配 (原始字符串与规范化地址)
+    let normalized = normalized_ip_string(ip);
     let entry_result = conn.query_row(
         "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count
-         FROM ip_blacklist WHERE ip_pattern = ?1",
-        [ip],
+         FROM ip_blacklist WHERE ip_pattern = ?1 OR ip_pattern = ?2",
+        [ip, normalized.as_str()],
         |row| {
             Ok(IpBlacklistEntry {
                 id: row.get(0)?,
@@ -489,63 +536,100 @@ pub fn get_blacklist_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>,
         },
     );
 
-    if let Ok(entry) = entry_result {
+    let matched = match entry_result {
+        Ok(entry) => Some(entry),
+        // CIDR / 不同书写形式的 IPv6 匹配
+        Err(_) => match parse_ip(ip) {
+            Some(addr) => get_blacklist()?
+                .into_iter()
+                .find(|entry| ip_matches_pattern(addr, &entry.ip_pattern)),
+            None => None,
+        },
+    };
+
+    if let Some(entry) = &matched {
         // 增加命中计数
         let _ = conn.execute(
-            "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE ip_pattern = ?1",
-            [ip],
+            "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE id = ?1",
+            [&entry.id],
         );
-        return Ok(Some(entry));
     }
 
-    // CIDR 匹配
-    let entries = get_blacklist()?;
-    for entry in entries {
-        if entry.ip_pattern.contains('/') {
-            if cidr_match(ip, &entry.ip_pattern) {
-                // 增加命中计数
-                let _ = conn.execute(
-                    "UPDATE ip_blacklist SET hit_count = hit_count + 1 WHERE id = ?1",
-                    [&entry.id],
-                );
-                return Ok(Some(entry));
-            }
-        }
-    }
+    Ok(matched)
+}
+
+/// 解析 IP 地址 (IPv4 映射的 IPv6 地址统一为 IPv4)
+fn parse_ip(ip: &str) -> Option<IpAddr> {
+    ip.trim().parse().ok().map(normalize_ip)
+}
 
-    Ok(None)
+fn normalized_ip_string(ip: &str) -> String {
+    parse_ip(ip)
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|| ip.to_string())
 }
 
-/// 简单的 CIDR 匹配
-fn cidr_match(ip: &str, cidr: &str) -> bool {
-    let parts: Vec<&str> = cidr.split('/').collect();
-    if parts.len() != 2 {
-        return false;
+/// IPv4 映射地址 (::ffff:a.b.c.d) 统一为 IPv4
+pub fn normalize_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        v4 => v4,
     }
+}
 
-    let network = parts[0];
-    let prefix_len: u8 = match parts[1].parse() {
-        Ok(p) => p,
-        Err(_) => return false,
+/// 解析 IP 模式 (单个 IP 或 CIDR，支持 IPv4 / IPv6)，返回 (网络地址, 前缀长度)
+pub fn parse_ip_pattern(pattern: &str) -> Option<(IpAddr, u8)> {
+    let pattern = pattern.trim();
+    let (addr, prefix) = match pattern.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+        None => (pattern, None),
     };
-
-    let ip_parts: Vec<u8> = ip.split('.').filter_map(|s| s.parse().ok()).collect();
-    let net_parts: Vec<u8> = network.split('.').filter_map(|s| s.parse().ok()).collect();
-
-    if ip_parts.len() != 4 || net_parts.len() != 4 {
-        return false;
+    let addr: IpAddr = addr.parse().ok()?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    if prefix > max {
+        return None;
     }
 
-    let ip_u32 = u32::from_be_bytes([ip_parts[0], ip_parts[1], ip_parts[2], ip_parts[3]]);
-    let net_u32 = u32::from_be_bytes([net_parts[0], net_parts[1], net_parts[2], net_parts[3]]);
+    // ::ffff:a.b.c.d/n (n >= 96) 等价于 a.b.c.d/(n - 96)
+    match normalize_ip(addr) {
+        IpAddr::V4(v4) if addr.is_ipv6() => prefix.checked_sub(96).map(|p| (IpAddr::V4(v4), p)),
+        normalized => Some((normalized, prefix)),
+    }
+}
 
-    let mask = if prefix_len == 0 {
-        0
+/// 校验 IP 模式并返回规范写法 (小写 IPv6、IPv4 映射地址转换为 IPv4)
+pub fn normalize_ip_pattern(pattern: &str) -> Result<String, String> {
+    let (addr, prefix) =
+        parse_ip_pattern(pattern).ok_or_else(|| format!("Invalid IP pattern: {:?}", pattern))?;
+    let full = if addr.is_ipv4() { 32 } else { 128 };
+    if prefix == full && !pattern.contains('/') {
+        Ok(addr.to_string())
     } else {
-        !0u32 << (32 - prefix_len)
-    };
+        Ok(format!("{}/{}", addr, prefix))
+    }
+}
 
-    (ip_u32 & mask) == (net_u32 & mask)
+/// 判断 IP 是否属于指定网段 (地址族不同时不匹配)
+pub fn ip_in_cidr(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (normalize_ip(ip), network) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// 判断 IP 是否匹配名单条目 (单个 IP 或 CIDR)
+fn ip_matches_pattern(ip: IpAddr, pattern: &str) -> bool {
+    parse_ip_pattern(pattern)
+        .map(|(network, prefix)| ip_in_cidr(ip, network, prefix))
+        .unwrap_or(false)
 }
 
 // ============================================================================
@@ -557,6 +641,8 @@ pub fn add_to_whitelist(
     ip_pattern: &str,
     description: Option<&str>,
 ) -> Result<IpWhitelistEntry, String> {
+    let ip_pattern = normalize_ip_pattern(ip_pattern)?;
+    let ip_pattern = ip_pattern.as_str();
     let conn = connect_db()?;
 
     let id = uuid::Uuid::new_v4().to_string();
@@ -621,11 +707,12 @@ pub fn get_whitelist() -> Result<Vec<IpWhitelistEntry>, String> {
 pub fn is_ip_in_whitelist(ip: &str) -> Result<bool, String> {
     let conn = connect_db()?;
 
-    // 精确匹配
+    // 精确匹配 (原始字符串与规范化地址)
+    let normalized = normalized_ip_string(ip);
     let count: i64 = conn
         .query_row(
-            "SELECT COUNT(*) FROM ip_whitelist WHERE ip_pattern = ?1",
-            [ip],
+            "SELECT COUNT(*) FROM ip_whitelist WHERE ip_pattern = ?1 OR ip_pattern = ?2",
+            [ip, normalized.as_str()],
             |row| row.get(0),
         )
         .map_err(|e| e.to_string())?;
@@ -634,17 +721,181 @@ pub fn is_ip_in_whitelist(ip: &str) -> Result<bool, String> {
         return Ok(true);
     }
 
-    // CIDR 匹配
-    let entries = get_whitelist()?;
-    for entry in entries {
-        if entry.ip_pattern.contains('/') {
-            if cidr_match(ip, &entry.ip_pattern) {
-                return Ok(true);
-            }
-        }
+    // CIDR / 不同书写形式的 IPv6 匹配
+    let Some(addr) = parse_ip(ip) else {
+        return Ok(false);
+    };
+    Ok(get_whitelist()?
+        .iter()
+        .any(|entry| ip_matches_pattern(addr, &entry.ip_pattern)))
+}
+
+// ============================================================================
+// 自动封禁
+// ============================================================================
+
+/// 统计 IP 在 since 之后的鉴权失败 (401) 次数
+pub fn count_auth_failures(ip: &str, since: i64) -> Result<u32, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM ip_access_logs
+         WHERE client_ip = ?1 AND timestamp >= ?2 AND status = 401",
+        params![ip, since],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 统计 IP 在 since 之后管理接口 (/api/*) 返回 4xx 的次数
+pub fn count_admin_errors(ip: &str, since: i64) -> Result<u32, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM ip_access_logs
+         WHERE client_ip = ?1 AND timestamp >= ?2 AND blocked = 0
+           AND path LIKE '/api/%' AND status BETWEEN 400 AND 499",
+        params![ip, since],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 计算第 level 次封禁的时长: ban_seconds * multiplier^(level-1)，不超过 max_seconds
+pub fn auto_ban_duration(level: u32, ban_seconds: u64, multiplier: u32, max_seconds: u64) -> i64 {
+    let factor = (multiplier.max(1) as u64).saturating_pow(level.saturating_sub(1));
+    ban_seconds
+        .saturating_mul(factor)
+        .min(max_seconds.max(ban_seconds))
+        .min(i64::MAX as u64) as i64
+}
+
+/// 写入自动封禁 (黑名单条目 + 封禁记录)。IP 已在黑名单中时返回 None
+pub fn create_auto_ban(
+    ip: &str,
+    rule: &str,
+    reason: &str,
+    ban_seconds: u64,
+    multiplier: u32,
+    max_seconds: u64,
+    lookback_seconds: i64,
+) -> Result<Option<AutoBanRecord>, String> {
+    let mut conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    tx.execute(
+        "DELETE FROM ip_blacklist WHERE expires_at IS NOT NULL AND expires_at < ?1",
+        [now],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let exists: i64 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM ip_blacklist WHERE ip_pattern = ?1",
+            [ip],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if exists > 0 {
+        return Ok(None);
     }
 
-    Ok(false)
+    let previous: u32 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM ip_auto_bans WHERE client_ip = ?1 AND created_at >= ?2",
+            params![ip, now - lookback_seconds],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let level = previous + 1;
+    let duration = auto_ban_duration(level, ban_seconds, multiplier, max_seconds);
+    let expires_at = now.saturating_add(duration);
+
+    let blacklist_id = uuid::Uuid::new_v4().to_string();
+    tx.execute(
+        "INSERT INTO ip_blacklist (id, ip_pattern, reason, created_at, expires_at, created_by, hit_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0)",
+        params![
+            blacklist_id,
+            ip,
+            format!("Auto-ban ({}): {}", rule, reason),
+            now,
+            expires_at,
+            AUTO_BAN_CREATED_BY
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let record = AutoBanRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        client_ip: ip.to_string(),
+        rule: rule.to_string(),
+        reason: reason.to_string(),
+        level,
+        ban_seconds: duration,
+        created_at: now,
+        expires_at,
+        blacklist_id,
+        active: true,
+    };
+    tx.execute(
+        "INSERT INTO ip_auto_bans (id, client_ip, rule, reason, level, ban_seconds, created_at, expires_at, blacklist_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            record.id,
+            record.client_ip,
+            record.rule,
+            record.reason,
+            record.level,
+            record.ban_seconds,
+            record.created_at,
+            record.expires_at,
+            record.blacklist_id
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(Some(record))
+}
+
+/// 查询自动封禁记录 (active_only: 仅返回仍生效的封禁)
+pub fn get_auto_bans(active_only: bool, limit: usize) -> Result<Vec<AutoBanRecord>, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.id, a.client_ip, a.rule, a.reason, a.level, a.ban_seconds, a.created_at,
+                    a.expires_at, a.blacklist_id,
+                    b.id IS NOT NULL AND (b.expires_at IS NULL OR b.expires_at > ?1) AS active
+             FROM ip_auto_bans a
+             LEFT JOIN ip_blacklist b ON b.id = a.blacklist_id
+             WHERE ?2 = 0 OR (b.id IS NOT NULL AND (b.expires_at IS NULL OR b.expires_at > ?1))
+             ORDER BY a.created_at DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let records = stmt
+        .query_map(params![now, active_only, limit as i64], |row| {
+            Ok(AutoBanRecord {
+                id: row.get(0)?,
+                client_ip: row.get(1)?,
+                rule: row.get(2)?,
+                reason: row.get(3)?,
+                level: row.get(4)?,
+                ban_seconds: row.get(5)?,
+                created_at: row.get(6)?,
+                expires_at: row.get(7)?,
+                blacklist_id: row.get(8)?,
+                active: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    records
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
 }
 
 /// 清空所有 IP 访问日志
@@ -687,3 +938,51 @@ pub fn get_ip_access_logs_count(
 
     Ok(count)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ip_pattern_matching() {
+        let (net, len) = parse_ip_pattern("2001:db8::/32").unwrap();
+        assert!(ip_in_cidr(ip("2001:db8:1::1"), net, len));
+        assert!(!ip_in_cidr(ip("2001:db9::1"), net, len));
+
+        let (net, len) = parse_ip_pattern("0.0.0.0/0").unwrap();
+        assert!(ip_in_cidr(ip("8.8.8.8"), net, len));
+        assert!(!ip_in_cidr(ip("::1"), net, len));
+
+        // IPv4 映射地址匹配 IPv4 规则
+        assert!(ip_matches_pattern(
+            parse_ip("::ffff:10.0.0.5").unwrap(),
+            "10.0.0.0/8"
+        ));
+        assert!(ip_matches_pattern(ip("10.1.2.3"), "::ffff:10.0.0.0/104"));
+        assert!(ip_matches_pattern(ip("2001:db8::1"), "2001:DB8:0::1"));
+        assert!(!ip_matches_pattern(ip("10.0.0.5"), "legacy.pattern"));
+    }
+
+    #[test]
+    fn test_normalize_ip_pattern() {
+        assert_eq!(normalize_ip_pattern("::FFFF:10.0.0.5").unwrap(), "10.0.0.5");
+        assert_eq!(
+            normalize_ip_pattern(" 2001:DB8::/32 ").unwrap(),
+            "2001:db8::/32"
+        );
+        assert_eq!(normalize_ip_pattern("10.0.0.0/8").unwrap(), "10.0.0.0/8");
+        assert_eq!(
+            normalize_ip_pattern("::ffff:10.0.0.0/104").unwrap(),
+            "10.0.0.0/8"
+        );
+        assert!(normalize_ip_pattern("").is_err());
+        assert!(normalize_ip_pattern("10.0.0.0/33").is_err());
+        assert!(normalize_ip_pattern("2001:db8::/129").is_err());
+        assert!(normalize_ip_pattern("::ffff:10.0.0.0/64").is_err());
+        assert!(normalize_ip_pattern("192.168.1").is_err());
+    }
+}
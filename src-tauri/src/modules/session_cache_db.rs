@@ -0,0 +1,485 @@
+//! Session Cache Database Module
+//! 签名缓存 (SignatureCache) 与粘性会话绑定的 SQLite 持久化，用于跨重启恢复长会话
+//!
+//! 请求路径上的写入 (签名、绑定) 经后台写线程异步落盘: 短时间内的变更合并为一个事务，
+//! 复用同一连接，层容量清理按固定间隔执行而不是每次写入都做。
+
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// 签名缓存层: 工具调用签名 (tool_use_id -> signature)
+pub const LAYER_TOOL: &str = "tool";
+/// 签名缓存层: 签名所属模型家族 (signature -> family)
+pub const LAYER_FAMILY: &str = "family";
+/// 签名缓存层: 会话最新签名 (session_id -> signature)
+pub const LAYER_SESSION: &str = "session";
+
+/// 持久化的签名缓存条目
+#[derive(Debug, Clone)]
+pub struct PersistedSignature {
+    pub key: String,
+    pub value: String,
+    pub message_count: usize,
+    /// 写入时间 (Unix 秒)，用于恢复 TTL
+    pub updated_at: i64,
+}
+
+/// 持久化的会话绑定
+#[derive(Debug, Clone)]
+pub struct PersistedBinding {
+    pub session_id: String,
+    pub account_id: String,
+}
+
+/// 收到第一条写入后继续收集的时间窗口，窗口内的变更合并为一个事务
+const WRITE_BATCH_WINDOW: Duration = Duration::from_millis(200);
+/// 单个事务最多合并的变更数
+const WRITE_BATCH_LIMIT: usize = 512;
+/// 签名层过期 / 超量清理的间隔
+const PRUNE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// 获取会话缓存数据库路径
+pub fn get_session_cache_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("session_cache.db"))
+}
+
+/// 连接数据库
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_session_cache_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化会话缓存数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS signature_cache (
+            layer TEXT NOT NULL,
+            cache_key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            message_count INTEGER NOT NULL DEFAULT 0,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (layer, cache_key)
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_signature_cache_updated ON signature_cache (layer, updated_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_bindings (
+            session_id TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_session_bindings_account ON session_bindings (account_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+// ===== 后台写入 (write-behind) =====
+
+/// 排队等待落盘的变更
+enum WriteOp {
+    /// 写入签名缓存条目 (同层同 key 覆盖)，附带该层的 TTL 与容量供定期清理
+    Signature {
+        layer: String,
+        entry: PersistedSignature,
+        ttl_secs: i64,
+        limit: usize,
+    },
+    /// 写入/刷新会话绑定
+    Binding {
+        session_id: String,
+        account_id: String,
+        updated_at: i64,
+    },
+    DeleteBinding(String),
+    DeleteAccountBindings(String),
+    /// 之前排队的变更全部落盘后回复
+    Flush(Sender<()>),
+}
+
+fn writer() -> &'static Sender<WriteOp> {
+    static WRITER: OnceLock<Sender<WriteOp>> = OnceLock::new();
+    WRITER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        if let Err(e) = std::thread::Builder::new()
+            .name("session-cache-writer".to_string())
+            .spawn(move || run_writer(rx))
+        {
+            tracing::error!("[SessionCache] Failed to start writer thread: {}", e);
+        }
+        tx
+    })
+}
+
+fn enqueue(op: WriteOp) {
+    if writer().send(op).is_err() {
+        tracing::warn!("[SessionCache] Writer thread is not running, dropping write");
+    }
+}
+
+fn run_writer(rx: Receiver<WriteOp>) {
+    let mut conn: Option<Connection> = None;
+    let mut prune_limits: HashMap<String, (i64, usize)> = HashMap::new();
+    let mut last_prune = Instant::now();
+
+    while let Ok(first) = rx.recv() {
+        let mut ops = vec![first];
+        let deadline = Instant::now() + WRITE_BATCH_WINDOW;
+        while ops.len() < WRITE_BATCH_LIMIT && !matches!(ops.last(), Some(WriteOp::Flush(_))) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(op) => ops.push(op),
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        if conn.is_none() {
+            conn = connect_db()
+                .map_err(|e| tracing::warn!("[SessionCache] Failed to open database: {}", e))
+                .ok();
+        }
+        if let Some(db) = conn.as_mut() {
+            if let Err(e) = apply_ops(db, &ops, &mut prune_limits) {
+                tracing::warn!(
+                    "[SessionCache] Failed to persist {} changes: {}",
+                    ops.len(),
+                    e
+                );
+                // 连接可能已失效，下一批重新打开
+                conn = None;
+            } else if last_prune.elapsed() >= PRUNE_INTERVAL {
+                for (layer, (ttl_secs, limit)) in &prune_limits {
+                    if let Err(e) = prune_with(db, layer, *ttl_secs, *limit) {
+                        tracing::warn!("[SessionCache] Failed to prune {} layer: {}", layer, e);
+                    }
+                }
+                last_prune = Instant::now();
+            }
+        }
+
+        for op in ops {
+            if let WriteOp::Flush(done) = op {
+                let _ = done.send(());
+            }
+        }
+    }
+}
+
+fn apply_ops(
+    conn: &mut Connection,
+    ops: &[WriteOp],
+    prune_limits: &mut HashMap<String, (i64, usize)>,
+) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for op in ops {
+        let result = match op {
+            WriteOp::Signature {
+                layer,
+                entry,
+                ttl_secs,
+                limit,
+            } => {
+                prune_limits.insert(layer.clone(), (*ttl_secs, *limit));
+                tx.execute(
+                    "INSERT OR REPLACE INTO signature_cache (layer, cache_key, value, message_count, updated_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        layer,
+                        entry.key,
+                        entry.value,
+                        entry.message_count as i64,
+                        entry.updated_at
+                    ],
+                )
+            }
+            WriteOp::Binding {
+                session_id,
+                account_id,
+                updated_at,
+            } => tx.execute(
+                "INSERT OR REPLACE INTO session_bindings (session_id, account_id, updated_at)
+                 VALUES (?1, ?2, ?3)",
+                params![session_id, account_id, updated_at],
+            ),
+            WriteOp::DeleteBinding(session_id) => tx.execute(
+                "DELETE FROM session_bindings WHERE session_id = ?1",
+                [session_id],
+            ),
+            WriteOp::DeleteAccountBindings(account_id) => tx.execute(
+                "DELETE FROM session_bindings WHERE account_id = ?1",
+                [account_id],
+            ),
+            WriteOp::Flush(_) => Ok(0),
+        };
+        result.map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 等待已排队的变更全部落盘 (管理操作在直接读写数据库前调用，保证顺序)
+pub fn flush_writes() {
+    let (tx, rx) = mpsc::channel();
+    enqueue(WriteOp::Flush(tx));
+    let _ = rx.recv_timeout(Duration::from_secs(10));
+}
+
+// ===== 签名缓存 =====
+
+/// 排队写入签名缓存条目 (同层同 key 覆盖)，该层按 TTL 与容量定期清理
+pub fn queue_signature(layer: &str, entry: PersistedSignature, ttl_secs: i64, limit: usize) {
+    enqueue(WriteOp::Signature {
+        layer: layer.to_string(),
+        entry,
+        ttl_secs,
+        limit,
+    });
+}
+
+/// 加载某一层中未过期的条目 (按写入时间倒序，最多 limit 条)
+pub fn load_signatures(
+    layer: &str,
+    ttl_secs: i64,
+    limit: usize,
+) -> Result<Vec<PersistedSignature>, String> {
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now().timestamp() - ttl_secs;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT cache_key, value, message_count, updated_at FROM signature_cache
+             WHERE layer = ?1 AND updated_at >= ?2
+             ORDER BY updated_at DESC LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![layer, cutoff, limit as i64], |row| {
+            let message_count: i64 = row.get(2)?;
+            Ok(PersistedSignature {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                message_count: message_count.max(0) as usize,
+                updated_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 删除签名缓存条目
+pub fn delete_signature(layer: &str, key: &str) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let affected = conn
+        .execute(
+            "DELETE FROM signature_cache WHERE layer = ?1 AND cache_key = ?2",
+            params![layer, key],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(affected > 0)
+}
+
+/// 清理某一层: 删除过期条目，并只保留最新的 limit 条
+pub fn prune_signatures(layer: &str, ttl_secs: i64, limit: usize) -> Result<usize, String> {
+    let conn = connect_db()?;
+    prune_with(&conn, layer, ttl_secs, limit)
+}
+
+fn prune_with(
+    conn: &Connection,
+    layer: &str,
+    ttl_secs: i64,
+    limit: usize,
+) -> Result<usize, String> {
+    let cutoff = chrono::Utc::now().timestamp() - ttl_secs;
+
+    let expired = conn
+        .execute(
+            "DELETE FROM signature_cache WHERE layer = ?1 AND updated_at < ?2",
+            params![layer, cutoff],
+        )
+        .map_err(|e| e.to_string())?;
+
+    let overflow = conn
+        .execute(
+            "DELETE FROM signature_cache WHERE layer = ?1 AND cache_key NOT IN (
+                SELECT cache_key FROM signature_cache WHERE layer = ?1
+                ORDER BY updated_at DESC LIMIT ?2
+            )",
+            params![layer, limit as i64],
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(expired + overflow)
+}
+
+/// 清空所有签名缓存
+pub fn clear_signatures() -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM signature_cache", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ===== 会话绑定 =====
+
+/// 排队写入/刷新会话绑定
+pub fn queue_binding(session_id: &str, account_id: &str) {
+    enqueue(WriteOp::Binding {
+        session_id: session_id.to_string(),
+        account_id: account_id.to_string(),
+        updated_at: chrono::Utc::now().timestamp(),
+    });
+}
+
+/// 排队删除指定会话的绑定
+pub fn queue_delete_binding(session_id: &str) {
+    enqueue(WriteOp::DeleteBinding(session_id.to_string()));
+}
+
+/// 排队删除绑定到指定账号的所有会话
+pub fn queue_delete_account_bindings(account_id: &str) {
+    enqueue(WriteOp::DeleteAccountBindings(account_id.to_string()));
+}
+
+/// 加载未过期的会话绑定 (最多 limit 条)，并清理过期记录
+pub fn load_bindings(ttl_secs: i64, limit: usize) -> Result<Vec<PersistedBinding>, String> {
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now().timestamp() - ttl_secs;
+
+    conn.execute(
+        "DELETE FROM session_bindings WHERE updated_at < ?1",
+        [cutoff],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT session_id, account_id FROM session_bindings
+             ORDER BY updated_at DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([limit as i64], |row| {
+            Ok(PersistedBinding {
+                session_id: row.get(0)?,
+                account_id: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// 删除指定会话的绑定
+pub fn delete_binding(session_id: &str) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let affected = conn
+        .execute(
+            "DELETE FROM session_bindings WHERE session_id = ?1",
+            [session_id],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(affected > 0)
+}
+
+/// 清空所有会话绑定
+pub fn clear_bindings() -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM session_bindings", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_ttl_and_prune() {
+        let _ = init_db();
+
+        let layer = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let now = chrono::Utc::now().timestamp();
+        for (i, age) in [0i64, 10, 20, 10_000].iter().enumerate() {
+            queue_signature(
+                &layer,
+                PersistedSignature {
+                    key: format!("k{}", i),
+                    value: "sig".to_string(),
+                    message_count: i,
+                    updated_at: now - age,
+                },
+                3600,
+                10,
+            );
+        }
+        flush_writes();
+
+        // TTL 过滤 + 按时间倒序
+        let loaded = load_signatures(&layer, 3600, 10).unwrap();
+        let keys: Vec<&str> = loaded.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["k0", "k1", "k2"]);
+
+        // 过期 1 条 + 超出上限 1 条
+        assert_eq!(prune_signatures(&layer, 3600, 2).unwrap(), 2);
+        assert_eq!(load_signatures(&layer, 3600, 10).unwrap().len(), 2);
+
+        assert!(delete_signature(&layer, "k0").unwrap());
+        assert!(!delete_signature(&layer, "k0").unwrap());
+        let _ = prune_signatures(&layer, 0, 0);
+    }
+
+    #[test]
+    fn test_binding_roundtrip() {
+        let _ = init_db();
+
+        let sid = format!("sid-test-{}", uuid::Uuid::new_v4().simple());
+        let account = format!("acc-{}", uuid::Uuid::new_v4().simple());
+        queue_binding(&sid, &account);
+        flush_writes();
+
+        let loaded = load_bindings(3600, 100_000).unwrap();
+        assert!(loaded
+            .iter()
+            .any(|b| b.session_id == sid && b.account_id == account));
+
+        queue_delete_account_bindings(&account);
+        flush_writes();
+        assert!(!delete_binding(&sid).unwrap());
+    }
+}
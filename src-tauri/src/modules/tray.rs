@@ -3,7 +3,7 @@ use tauri::{
     image::Image,
     menu::{Menu, MenuItem, PredefinedMenuItem},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    Emitter, Listener, Manager,
+    Listener, Manager,
 };
 
 pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
@@ -103,7 +103,10 @@ pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
                     tauri::async_runtime::spawn(async move {
                         if let Ok(Some(account_id)) = modules::get_current_account_id() {
                             // Notify frontend to start
-                            let _ = app_handle.emit("tray://refresh-current", ());
+                            modules::event_bus::publish(modules::event_bus::AppEvent::new(
+                                modules::event_bus::EVENT_TRAY_REFRESH_CURRENT,
+                                (),
+                            ));
 
                             // Execute refresh logic
                             if let Ok(mut account) = modules::load_account(&account_id) {
@@ -152,8 +155,13 @@ pub fn create_tray(app: &tauri::AppHandle) -> tauri::Result<()> {
                                 modules::switch_account(&next_account.id, &integration).await
                             {
                                 // 3. Notify frontend
-                                let _ = app_handle
-                                    .emit("tray://account-switched", next_account.id.clone());
+                                modules::event_bus::publish(
+                                    modules::event_bus::AppEvent::new(
+                                        modules::event_bus::EVENT_TRAY_ACCOUNT_SWITCHED,
+                                        &next_account.id,
+                                    )
+                                    .with_account(Some(next_account.email.clone())),
+                                );
                                 // 4. Update tray
                                 update_tray_menus(&app_handle);
                             }
@@ -4,7 +4,7 @@
 #![allow(dead_code)]
 // 用户令牌存储，部分接口留作后续扩展
 
-use chrono::{FixedOffset, Timelike, Utc};
+use chrono::{Datelike, FixedOffset, Timelike, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -28,6 +28,61 @@ pub struct UserToken {
     pub last_used_at: Option<i64>,
     pub total_requests: i64,
     pub total_tokens_used: i64,
+    // [NEW] 用量限制 (0 = 不限制)
+    #[serde(default)]
+    pub rate_limit_rpm: i64, // 每分钟请求数
+    #[serde(default)]
+    pub daily_token_limit: i64, // 每日 Token 用量 (北京时间自然日)
+    #[serde(default)]
+    pub monthly_token_limit: i64, // 每月 Token 用量 (北京时间自然月)
+    #[serde(default)]
+    pub total_token_limit: i64, // Token 总用量硬上限
+    // [NEW] 模型白名单/黑名单 (支持 * 通配符，空列表表示不限制)
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub denied_models: Vec<String>,
+}
+
+/// 令牌用量限制更新参数 (None 表示不修改)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenLimitsUpdate {
+    pub rate_limit_rpm: Option<i64>,
+    pub daily_token_limit: Option<i64>,
+    pub monthly_token_limit: Option<i64>,
+    pub total_token_limit: Option<i64>,
+    pub allowed_models: Option<Vec<String>>,
+    pub denied_models: Option<Vec<String>>,
+}
+
+/// 令牌超限原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenLimitViolation {
+    /// 请求的模型不在允许范围内 (403)
+    ModelNotAllowed(String),
+    /// 每分钟请求数超限 (429)
+    RateLimited(String),
+    /// Token 配额耗尽 (429)
+    QuotaExceeded(String),
+}
+
+impl TokenLimitViolation {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::ModelNotAllowed(msg) | Self::RateLimited(msg) | Self::QuotaExceeded(msg) => msg,
+        }
+    }
+}
+
+/// 请求涉及的模型 (用于模型白名单/黑名单检查)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RequestedModel<'a> {
+    /// 已从请求中识别出模型
+    Known(&'a str),
+    /// 会调用模型但未能识别 (如超大请求体、无法解析的请求体)
+    Unknown,
+    /// 不调用模型的接口 (模型列表、文件、批处理管理等)
+    NotApplicable,
 }
 
 /// 令牌 IP 绑定结构体
@@ -90,7 +145,13 @@ pub fn init_db() -> Result<(), String> {
             total_requests INTEGER NOT NULL DEFAULT 0,
             total_tokens_used INTEGER NOT NULL DEFAULT 0,
             curfew_start TEXT,
-            curfew_end TEXT
+            curfew_end TEXT,
+            rate_limit_rpm INTEGER NOT NULL DEFAULT 0,
+            daily_token_limit INTEGER NOT NULL DEFAULT 0,
+            monthly_token_limit INTEGER NOT NULL DEFAULT 0,
+            total_token_limit INTEGER NOT NULL DEFAULT 0,
+            allowed_models TEXT,
+            denied_models TEXT
         )",
         [],
     )
@@ -117,6 +178,24 @@ pub fn init_db() -> Result<(), String> {
     );
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_start TEXT", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_end TEXT", []);
+    let _ = conn.execute(
+        "ALTER TABLE user_tokens ADD COLUMN rate_limit_rpm INTEGER DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE user_tokens ADD COLUMN daily_token_limit INTEGER DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE user_tokens ADD COLUMN monthly_token_limit INTEGER DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute(
+        "ALTER TABLE user_tokens ADD COLUMN total_token_limit INTEGER DEFAULT 0",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN allowed_models TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN denied_models TEXT", []);
 
     // 创建 token_ip_bindings 表
     conn.execute(
@@ -158,6 +237,7 @@ pub fn init_db() -> Result<(), String> {
         [],
     );
     let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_request_time ON token_usage_logs(request_time)", []);
+    let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_token_time ON token_usage_logs(token_id, request_time)", []);
 
     // [FIX Issue #1719] 数据清洗：修复旧版本升级导致的 NULL 字段
     // 这些字段在旧版本中可能不存在，ALTER TABLE 添加后默认为 NULL，导致反序列化失败
@@ -236,6 +316,12 @@ pub fn create_token(
         last_used_at: None,
         total_requests: 0,
         total_tokens_used: 0,
+        rate_limit_rpm: 0,
+        daily_token_limit: 0,
+        monthly_token_limit: 0,
+        total_token_limit: 0,
+        allowed_models: Vec::new(),
+        denied_models: Vec::new(),
     };
 
     conn.execute(
@@ -291,6 +377,12 @@ pub fn list_tokens() -> Result<Vec<UserToken>, String> {
                 last_used_at: row.get("last_used_at").unwrap_or(None),
                 total_requests: row.get("total_requests").unwrap_or(0),
                 total_tokens_used: row.get("total_tokens_used").unwrap_or(0),
+                rate_limit_rpm: row.get("rate_limit_rpm").unwrap_or(0),
+                daily_token_limit: row.get("daily_token_limit").unwrap_or(0),
+                monthly_token_limit: row.get("monthly_token_limit").unwrap_or(0),
+                total_token_limit: row.get("total_token_limit").unwrap_or(0),
+                allowed_models: parse_model_patterns(row.get("allowed_models").unwrap_or(None)),
+                denied_models: parse_model_patterns(row.get("denied_models").unwrap_or(None)),
             })
         })
         .map_err(|e| format!("Failed to query tokens: {}", e))?;
@@ -328,6 +420,12 @@ pub fn get_token_by_id(id: &str) -> Result<Option<UserToken>, String> {
                 last_used_at: row.get("last_used_at")?,
                 total_requests: row.get("total_requests")?,
                 total_tokens_used: row.get("total_tokens_used")?,
+                rate_limit_rpm: row.get("rate_limit_rpm").unwrap_or(0),
+                daily_token_limit: row.get("daily_token_limit").unwrap_or(0),
+                monthly_token_limit: row.get("monthly_token_limit").unwrap_or(0),
+                total_token_limit: row.get("total_token_limit").unwrap_or(0),
+                allowed_models: parse_model_patterns(row.get("allowed_models").unwrap_or(None)),
+                denied_models: parse_model_patterns(row.get("denied_models").unwrap_or(None)),
             })
         })
         .optional()
@@ -361,6 +459,12 @@ pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
                 last_used_at: row.get("last_used_at")?,
                 total_requests: row.get("total_requests")?,
                 total_tokens_used: row.get("total_tokens_used")?,
+                rate_limit_rpm: row.get("rate_limit_rpm").unwrap_or(0),
+                daily_token_limit: row.get("daily_token_limit").unwrap_or(0),
+                monthly_token_limit: row.get("monthly_token_limit").unwrap_or(0),
+                total_token_limit: row.get("total_token_limit").unwrap_or(0),
+                allowed_models: parse_model_patterns(row.get("allowed_models").unwrap_or(None)),
+                denied_models: parse_model_patterns(row.get("denied_models").unwrap_or(None)),
             })
         })
         .optional()
@@ -434,6 +538,75 @@ pub fn update_token(
     Ok(())
 }
 
+/// [NEW] 设置固定令牌值 (声明式配置文件)
+pub fn set_token_value(id: &str, token: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+
+    conn.execute(
+        "UPDATE user_tokens SET token = ?1, updated_at = ?2 WHERE id = ?3",
+        params![token, now, id],
+    )
+    .map_err(|e| format!("Failed to update token value: {}", e))?;
+
+    Ok(())
+}
+
+/// 解析存储的模型匹配规则 (JSON 数组)
+fn parse_model_patterns(raw: Option<String>) -> Vec<String> {
+    raw.and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// [NEW] 更新令牌用量限制与模型白名单/黑名单
+pub fn update_token_limits(id: &str, limits: &TokenLimitsUpdate) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+
+    let mut query = "UPDATE user_tokens SET updated_at = ?1".to_string();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(now)];
+
+    let numeric_fields = [
+        ("rate_limit_rpm", limits.rate_limit_rpm),
+        ("daily_token_limit", limits.daily_token_limit),
+        ("monthly_token_limit", limits.monthly_token_limit),
+        ("total_token_limit", limits.total_token_limit),
+    ];
+    for (column, value) in numeric_fields {
+        if let Some(v) = value {
+            params_vec.push(Box::new(v.max(0)));
+            query.push_str(&format!(", {} = ?{}", column, params_vec.len()));
+        }
+    }
+
+    let pattern_fields = [
+        ("allowed_models", &limits.allowed_models),
+        ("denied_models", &limits.denied_models),
+    ];
+    for (column, value) in pattern_fields {
+        if let Some(patterns) = value {
+            let cleaned: Vec<&str> = patterns
+                .iter()
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .collect();
+            let json = serde_json::to_string(&cleaned).map_err(|e| e.to_string())?;
+            params_vec.push(Box::new(json));
+            query.push_str(&format!(", {} = ?{}", column, params_vec.len()));
+        }
+    }
+
+    params_vec.push(Box::new(id.to_string()));
+    query.push_str(&format!(" WHERE id = ?{}", params_vec.len()));
+
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    conn.execute(&query, params_refs.as_slice())
+        .map_err(|e| format!("Failed to update token limits: {}", e))?;
+
+    Ok(())
+}
+
 /// 续期令牌
 pub fn renew_token(id: &str, expires_type: &str) -> Result<(), String> {
     let conn = connect_db()?;
@@ -667,6 +840,122 @@ pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>
     }
 }
 
+/// 检查模型是否命中匹配规则列表
+fn model_matches_any(model: &str, patterns: &[String]) -> bool {
+    patterns
+        .iter()
+        .any(|p| crate::proxy::common::model_mapping::wildcard_match(p, model))
+}
+
+/// 获取北京时间自然日/自然月的起始时间戳 (与宵禁逻辑使用相同时区)
+fn beijing_period_starts() -> (i64, i64) {
+    let beijing_offset = FixedOffset::east_opt(8 * 3600).unwrap();
+    let now_beijing = Utc::now().with_timezone(&beijing_offset);
+    let day_start = now_beijing
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(beijing_offset)
+        .unwrap();
+    let month_start = day_start.with_day(1).unwrap();
+    (day_start.timestamp(), month_start.timestamp())
+}
+
+/// [NEW] 检查令牌的模型白名单/黑名单、请求频率与 Token 配额
+///
+/// 用量数据来自 token_usage_logs，请求完成后才会写入，因此并发中的请求不计入。
+/// 返回 `Ok(None)` 表示未超限。
+pub fn check_token_limits(
+    token_id: &str,
+    model: RequestedModel<'_>,
+) -> Result<Option<TokenLimitViolation>, String> {
+    let token = match get_token_by_id(token_id)? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    // 1. 模型白名单/黑名单 (配置了白名单时，无法识别模型的请求一律拒绝)
+    match model {
+        RequestedModel::Known(model)
+            if model_matches_any(model, &token.denied_models)
+                || (!token.allowed_models.is_empty()
+                    && !model_matches_any(model, &token.allowed_models)) =>
+        {
+            return Ok(Some(TokenLimitViolation::ModelNotAllowed(format!(
+                "Model '{}' is not allowed for this token. Please contact the administrator.",
+                model
+            ))));
+        }
+        RequestedModel::Unknown if !token.allowed_models.is_empty() => {
+            return Ok(Some(TokenLimitViolation::ModelNotAllowed(
+                "Unable to determine the requested model; this token is restricted to specific models."
+                    .to_string(),
+            )));
+        }
+        _ => {}
+    }
+
+    // 2. Token 总量硬上限
+    if token.total_token_limit > 0 && token.total_tokens_used >= token.total_token_limit {
+        return Ok(Some(TokenLimitViolation::QuotaExceeded(format!(
+            "Total token quota exhausted ({}/{}). Please contact the administrator.",
+            token.total_tokens_used, token.total_token_limit
+        ))));
+    }
+
+    if token.rate_limit_rpm <= 0 && token.daily_token_limit <= 0 && token.monthly_token_limit <= 0 {
+        return Ok(None);
+    }
+
+    let conn = connect_db()?;
+
+    // 3. 每分钟请求数
+    if token.rate_limit_rpm > 0 {
+        let since = Utc::now().timestamp() - 60;
+        let recent: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM token_usage_logs WHERE token_id = ?1 AND request_time >= ?2",
+                params![token.id, since],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to count recent requests: {}", e))?;
+        if recent >= token.rate_limit_rpm {
+            return Ok(Some(TokenLimitViolation::RateLimited(format!(
+                "Rate limit reached ({} requests per minute). Please retry later.",
+                token.rate_limit_rpm
+            ))));
+        }
+    }
+
+    // 4. 每日/每月 Token 用量
+    let (day_start, month_start) = beijing_period_starts();
+    let periods = [
+        ("Daily", token.daily_token_limit, day_start),
+        ("Monthly", token.monthly_token_limit, month_start),
+    ];
+    for (label, limit, since) in periods {
+        if limit <= 0 {
+            continue;
+        }
+        let used: i64 = conn
+            .query_row(
+                "SELECT COALESCE(SUM(COALESCE(input_tokens, 0) + COALESCE(output_tokens, 0)), 0)
+                 FROM token_usage_logs WHERE token_id = ?1 AND request_time >= ?2",
+                params![token.id, since],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to sum token usage: {}", e))?;
+        if used >= limit {
+            return Ok(Some(TokenLimitViolation::QuotaExceeded(format!(
+                "{} token quota exhausted ({}/{}). Please retry after the quota resets.",
+                label, used, limit
+            ))));
+        }
+    }
+
+    Ok(None)
+}
+
 /// 获取 IP 关联的用户名 (用于 IP 管理页面)
 /// 返回最近一次使用该 IP 的 Token 所属的用户名
 pub fn get_username_for_ip(ip: &str) -> Result<Option<String>, String> {
@@ -717,4 +1006,79 @@ mod tests {
         assert!(fetched.is_ok());
         assert_eq!(fetched.unwrap().unwrap().username, username);
     }
+
+    #[test]
+    fn test_token_limits() {
+        let _ = init_db();
+
+        let token = create_token(
+            format!("LimitUser_{}", Uuid::new_v4()),
+            "never".to_string(),
+            None,
+            0,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            check_token_limits(&token.id, RequestedModel::Known("gemini-2.5-pro")),
+            Ok(None)
+        );
+
+        update_token_limits(
+            &token.id,
+            &TokenLimitsUpdate {
+                rate_limit_rpm: Some(1),
+                allowed_models: Some(vec!["gemini-*".to_string()]),
+                denied_models: Some(vec!["*-pro".to_string()]),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(matches!(
+            check_token_limits(&token.id, RequestedModel::Known("claude-sonnet-4-5")),
+            Ok(Some(TokenLimitViolation::ModelNotAllowed(_)))
+        ));
+        assert!(matches!(
+            check_token_limits(&token.id, RequestedModel::Known("gemini-2.5-pro")),
+            Ok(Some(TokenLimitViolation::ModelNotAllowed(_)))
+        ));
+        assert_eq!(
+            check_token_limits(&token.id, RequestedModel::Known("gemini-2.5-flash")),
+            Ok(None)
+        );
+        assert!(matches!(
+            check_token_limits(&token.id, RequestedModel::Unknown),
+            Ok(Some(TokenLimitViolation::ModelNotAllowed(_)))
+        ));
+        assert_eq!(
+            check_token_limits(&token.id, RequestedModel::NotApplicable),
+            Ok(None)
+        );
+
+        record_token_usage_and_ip(&token.id, "127.0.0.1", "gemini-2.5-flash", 10, 5, 200, None)
+            .unwrap();
+        assert!(matches!(
+            check_token_limits(&token.id, RequestedModel::Known("gemini-2.5-flash")),
+            Ok(Some(TokenLimitViolation::RateLimited(_)))
+        ));
+
+        update_token_limits(
+            &token.id,
+            &TokenLimitsUpdate {
+                rate_limit_rpm: Some(0),
+                daily_token_limit: Some(15),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(matches!(
+            check_token_limits(&token.id, RequestedModel::Known("gemini-2.5-flash")),
+            Ok(Some(TokenLimitViolation::QuotaExceeded(_)))
+        ));
+
+        let _ = delete_token(&token.id);
+    }
 }
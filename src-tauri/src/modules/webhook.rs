@@ -0,0 +1,439 @@
+//! Webhook Module
+//! 订阅事件总线，将账号 / 配额生命周期事件推送到外部 Webhook (通用 JSON / Slack / Discord 模板)，
+//! 支持 HMAC-SHA256 签名、按事件类型订阅与指数退避重试
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use tokio::sync::broadcast;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::modules::event_bus::{
+    self, AppEvent, EVENT_ACCOUNT_DISABLED, EVENT_ACCOUNT_FORBIDDEN, EVENT_ACCOUNT_INVALID_GRANT,
+    EVENT_ACCOUNT_VALIDATION_BLOCKED, EVENT_QUOTA_PROTECTED, EVENT_SECURITY_AUTO_BAN,
+};
+use crate::proxy::config::{get_webhooks, WebhookConfig, WebhookFormat};
+
+/// 测试推送事件 (仅由测试接口发出，不经过事件总线)
+pub const EVENT_WEBHOOK_TEST: &str = "webhook://test";
+
+/// 未配置 events 时默认订阅的生命周期事件
+pub const DEFAULT_WEBHOOK_EVENTS: &[&str] = &[
+    EVENT_ACCOUNT_DISABLED,
+    EVENT_ACCOUNT_FORBIDDEN,
+    EVENT_ACCOUNT_VALIDATION_BLOCKED,
+    EVENT_ACCOUNT_INVALID_GRANT,
+    EVENT_QUOTA_PROTECTED,
+];
+
+/// 单次退避的上限
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// 一次投递的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DeliveryResult {
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// 判断 Webhook 是否订阅了该事件类型
+pub fn is_subscribed(webhook: &WebhookConfig, event_type: &str) -> bool {
+    let patterns: Vec<&str> = webhook
+        .events
+        .iter()
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .collect();
+    if patterns.is_empty() {
+        return DEFAULT_WEBHOOK_EVENTS.contains(&event_type);
+    }
+    patterns.iter().any(|p| match p.strip_suffix('*') {
+        Some(prefix) => event_type.starts_with(prefix),
+        None => *p == event_type,
+    })
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    // HMAC 接受任意长度的密钥，new_from_slice 不会失败
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// 计算签名头 `X-Webhook-Signature` 的值: `sha256=hex(HMAC(secret, "{timestamp}.{body}"))`
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut message = format!("{}.", timestamp).into_bytes();
+    message.extend_from_slice(body);
+    let digest = hmac_sha256(secret.as_bytes(), &message);
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let mut out: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+        out.push_str("...");
+        out
+    }
+}
+
+/// 事件的标题、颜色 (Discord) 与展示字段
+fn describe(event: &AppEvent) -> (String, u32, Vec<(&'static str, String)>) {
+    let payload = &event.payload;
+    let (title, color) = match event.event_type.as_str() {
+        EVENT_ACCOUNT_DISABLED => ("Account disabled", 0xE74C3C),
+        EVENT_ACCOUNT_FORBIDDEN => ("Account forbidden (403)", 0xE74C3C),
+        EVENT_ACCOUNT_VALIDATION_BLOCKED => ("Account requires verification", 0xF39C12),
+        EVENT_ACCOUNT_INVALID_GRANT => ("Refresh token revoked (invalid_grant)", 0xE74C3C),
+        EVENT_QUOTA_PROTECTED => ("Quota protection triggered", 0xF39C12),
+        EVENT_SECURITY_AUTO_BAN => ("IP auto-banned", 0xE67E22),
+        EVENT_WEBHOOK_TEST => ("Test notification", 0x3498DB),
+        other => (other, 0x95A5A6),
+    };
+
+    let text = |key: &str| {
+        payload
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    let mut fields = Vec::new();
+    if let Some(account) = event.account.clone().or_else(|| text("account_id")) {
+        fields.push(("Account", account));
+    }
+    if let Some(ip) = text("ip") {
+        fields.push(("IP", ip));
+    }
+    if let Some(model) = text("model") {
+        fields.push(("Model", model));
+    }
+    if let (Some(pct), Some(threshold)) = (payload.get("percentage"), payload.get("threshold")) {
+        fields.push(("Remaining", format!("{}% (threshold {}%)", pct, threshold)));
+    }
+    if let Some(until) = payload
+        .get("blocked_until")
+        .and_then(|v| v.as_i64())
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+    {
+        fields.push(("Blocked until", until.to_rfc3339()));
+    }
+    if let Some(reason) = text("reason").or_else(|| text("error")) {
+        fields.push(("Reason", truncate(&reason, 500)));
+    }
+    if let Some(message) = text("message") {
+        fields.push(("Message", message));
+    }
+
+    (title.to_string(), color, fields)
+}
+
+/// 按模板生成请求体
+pub fn build_payload(format: WebhookFormat, event: &AppEvent, delivery_id: &str) -> Value {
+    match format {
+        WebhookFormat::Generic => json!({
+            "id": delivery_id,
+            "type": event.event_type,
+            "timestamp": event.timestamp,
+            "account": event.account,
+            "payload": event.payload,
+        }),
+        WebhookFormat::Slack => {
+            let (title, _, fields) = describe(event);
+            let mut text = format!("*{}*", title);
+            for (name, value) in &fields {
+                text.push_str(&format!("\n• *{}:* {}", name, value));
+            }
+            json!({
+                "text": text,
+                "blocks": [{ "type": "section", "text": { "type": "mrkdwn", "text": text } }]
+            })
+        }
+        WebhookFormat::Discord => {
+            let (title, color, fields) = describe(event);
+            let timestamp = chrono::DateTime::from_timestamp_millis(event.timestamp)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default();
+            json!({
+                "embeds": [{
+                    "title": title,
+                    "color": color,
+                    "fields": fields
+                        .iter()
+                        .map(|(name, value)| json!({ "name": name, "value": truncate(value, 1024), "inline": false }))
+                        .collect::<Vec<_>>(),
+                    "timestamp": timestamp,
+                    "footer": { "text": event.event_type },
+                }]
+            })
+        }
+    }
+}
+
+fn backoff(attempt: u32, retry_after: Option<u64>) -> Duration {
+    let exp = 1u64 << attempt.saturating_sub(1).min(6);
+    Duration::from_secs(retry_after.unwrap_or(exp).min(MAX_BACKOFF_SECS))
+}
+
+/// 投递事件，网络错误 / 429 / 5xx 时按指数退避重试，最多尝试 `max_attempts` 次
+pub async fn deliver(
+    client: &reqwest::Client,
+    webhook: &WebhookConfig,
+    event: &AppEvent,
+    max_attempts: u32,
+) -> DeliveryResult {
+    let delivery_id = uuid::Uuid::new_v4().to_string();
+    let body = build_payload(webhook.format, event, &delivery_id).to_string();
+    let max_attempts = max_attempts.max(1);
+
+    let mut result = DeliveryResult {
+        success: false,
+        status_code: None,
+        attempts: 0,
+        error: None,
+    };
+
+    while result.attempts < max_attempts {
+        result.attempts += 1;
+
+        let timestamp = chrono::Utc::now().timestamp();
+        let mut request = client
+            .post(webhook.url.trim())
+            .header("Content-Type", "application/json")
+            .header("User-Agent", "Antigravity-Tools-Webhook")
+            .header("X-Webhook-Id", &delivery_id)
+            .header("X-Webhook-Event", &event.event_type)
+            .header("X-Webhook-Timestamp", timestamp.to_string());
+        if !webhook.secret.is_empty() {
+            request = request.header(
+                "X-Webhook-Signature",
+                sign(&webhook.secret, timestamp, body.as_bytes()),
+            );
+        }
+        for (name, value) in &webhook.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+
+        let retry_after = match request.body(body.clone()).send().await {
+            Ok(resp) => {
+                let status = resp.status();
+                result.status_code = Some(status.as_u16());
+                if status.is_success() {
+                    result.success = true;
+                    result.error = None;
+                    return result;
+                }
+                let retry_after = resp
+                    .headers()
+                    .get("retry-after")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse::<u64>().ok());
+                let text = resp.text().await.unwrap_or_default();
+                result.error = Some(format!("HTTP {}: {}", status, truncate(&text, 200)));
+                if !(status.as_u16() == 429 || status.is_server_error()) {
+                    return result;
+                }
+                retry_after
+            }
+            Err(e) => {
+                result.status_code = None;
+                result.error = Some(e.to_string());
+                None
+            }
+        };
+
+        if result.attempts < max_attempts {
+            tokio::time::sleep(backoff(result.attempts, retry_after)).await;
+        }
+    }
+
+    result
+}
+
+/// 向指定 Webhook 发送一条测试事件 (不重试，忽略订阅列表)
+pub async fn fire_test(webhook: &WebhookConfig) -> DeliveryResult {
+    let event = AppEvent::new(
+        EVENT_WEBHOOK_TEST,
+        json!({
+            "webhook_id": webhook.id,
+            "message": "This is a test notification from Antigravity Tools."
+        }),
+    );
+    deliver(&crate::utils::http::get_client(), webhook, &event, 1).await
+}
+
+fn dispatch(event: &AppEvent) {
+    for webhook in get_webhooks() {
+        if !webhook.enabled
+            || webhook.url.trim().is_empty()
+            || !is_subscribed(&webhook, &event.event_type)
+        {
+            continue;
+        }
+        let event = event.clone();
+        tokio::spawn(async move {
+            let client = crate::utils::http::get_client();
+            let result = deliver(&client, &webhook, &event, webhook.max_retries + 1).await;
+            if result.success {
+                tracing::debug!(
+                    "[Webhook] Delivered {} to {} ({} attempts)",
+                    event.event_type,
+                    webhook.id,
+                    result.attempts
+                );
+            } else {
+                tracing::warn!(
+                    "[Webhook] Failed to deliver {} to {} after {} attempts: {}",
+                    event.event_type,
+                    webhook.id,
+                    result.attempts,
+                    result.error.unwrap_or_default()
+                );
+            }
+        });
+    }
+}
+
+/// 启动 Webhook 分发器，`cancel` 触发时退出
+pub fn start_dispatcher(cancel: CancellationToken) {
+    let mut rx = event_bus::subscribe();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                received = rx.recv() => match received {
+                    Ok(event) => dispatch(&event),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!("[Webhook] Dispatcher lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    fn webhook(url: &str, format: WebhookFormat, events: &[&str]) -> WebhookConfig {
+        WebhookConfig {
+            id: "ops".to_string(),
+            name: String::new(),
+            enabled: true,
+            url: url.to_string(),
+            format,
+            secret: "s3cret".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            max_retries: 3,
+            headers: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_subscription() {
+        // RFC 4231 test case 2
+        let digest = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let all = webhook("http://x", WebhookFormat::Generic, &[]);
+        assert!(is_subscribed(&all, EVENT_QUOTA_PROTECTED));
+        assert!(!is_subscribed(&all, event_bus::EVENT_PROXY_REQUEST));
+
+        let accounts = webhook("http://x", WebhookFormat::Generic, &["account://*"]);
+        assert!(is_subscribed(&accounts, EVENT_ACCOUNT_FORBIDDEN));
+        assert!(!is_subscribed(&accounts, EVENT_QUOTA_PROTECTED));
+    }
+
+    #[test]
+    fn test_build_payload() {
+        let event = AppEvent::new(
+            EVENT_QUOTA_PROTECTED,
+            json!({ "account_id": "a1", "model": "claude-sonnet-4-5", "percentage": 8, "threshold": 10 }),
+        )
+        .with_account(Some("user@example.com".to_string()));
+
+        let generic = build_payload(WebhookFormat::Generic, &event, "d1");
+        assert_eq!(generic["type"], EVENT_QUOTA_PROTECTED);
+        assert_eq!(generic["payload"]["model"], "claude-sonnet-4-5");
+
+        let slack = build_payload(WebhookFormat::Slack, &event, "d1");
+        let text = slack["text"].as_str().unwrap();
+        assert!(text.starts_with("*Quota protection triggered*"));
+        assert!(text.contains("user@example.com"));
+        assert!(text.contains("8% (threshold 10%)"));
+
+        let discord = build_payload(WebhookFormat::Discord, &event, "d1");
+        let embed = &discord["embeds"][0];
+        assert_eq!(embed["title"], "Quota protection triggered");
+        assert_eq!(embed["fields"][0]["value"], "user@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_retries_and_signs() {
+        let received: Arc<Mutex<Vec<(HeaderMap, String)>>> = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| {
+                let sink = sink.clone();
+                async move {
+                    let mut calls = sink.lock().unwrap();
+                    calls.push((headers, body));
+                    if calls.len() == 1 {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let mut hook = webhook(
+            &format!("http://{}/hook", addr),
+            WebhookFormat::Generic,
+            &[],
+        );
+        hook.headers.insert("X-Team".to_string(), "ops".to_string());
+        let event = AppEvent::new(EVENT_ACCOUNT_DISABLED, json!({ "account_id": "a1" }));
+        let result = deliver(&reqwest::Client::new(), &hook, &event, 3).await;
+
+        assert!(result.success);
+        assert_eq!(result.attempts, 2);
+        assert_eq!(result.status_code, Some(200));
+
+        let calls = received.lock().unwrap();
+        let (headers, body) = &calls[1];
+        let timestamp: i64 = headers["x-webhook-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            headers["x-webhook-signature"].to_str().unwrap(),
+            sign("s3cret", timestamp, body.as_bytes())
+        );
+        assert_eq!(headers["x-webhook-event"], EVENT_ACCOUNT_DISABLED);
+        assert_eq!(headers["x-team"], "ops");
+        // 重试使用相同的投递 ID
+        assert_eq!(calls[0].0["x-webhook-id"], headers["x-webhook-id"]);
+    }
+}
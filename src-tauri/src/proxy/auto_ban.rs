@@ -0,0 +1,298 @@
+//! 自动封禁 (fail2ban 风格)
+//!
+//! - 鉴权失败 (401，含未知用户令牌) 与管理接口 4xx 写入 ip_access_logs，再按规则窗口统计次数
+//! - 请求洪泛在内存中按固定窗口计数 (反代请求仅在开启监控时才落库，无法依赖日志表)
+//! - 封禁写入 IP 黑名单 (created_by = "auto")，同一 IP 再次被封时长按倍数递增
+//! - 白名单、回环地址与可信代理始终豁免
+
+use crate::modules::event_bus::{self, AppEvent, EVENT_SECURITY_AUTO_BAN};
+use crate::modules::security_db::{self, IpAccessLog};
+use crate::proxy::config::{AutoBanConfig, TrustedProxyConfig};
+use dashmap::DashMap;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+
+pub const RULE_AUTH_FAILURES: &str = "auth_failures";
+pub const RULE_REQUEST_FLOOD: &str = "request_flood";
+pub const RULE_ADMIN_ERRORS: &str = "admin_errors";
+
+/// 计算递增封禁时长时回溯的历史范围
+const ESCALATION_LOOKBACK_SECS: i64 = 30 * 24 * 3600;
+/// 洪泛计数表超过该大小时清理过期窗口
+const FLOOD_TABLE_PRUNE_SIZE: usize = 10_000;
+
+/// IP -> (窗口起点, 窗口内请求数)
+static FLOOD_COUNTERS: LazyLock<DashMap<String, (i64, u32)>> = LazyLock::new(DashMap::new);
+
+/// 一次已完成请求的观测数据
+#[derive(Debug, Clone)]
+pub struct RequestObservation {
+    pub client_ip: String,
+    pub method: String,
+    pub path: String,
+    pub user_agent: Option<String>,
+    pub status: u16,
+    pub is_admin: bool,
+}
+
+/// 回环地址与可信代理不参与自动封禁 (避免误封反向代理导致全部请求被拒)
+fn is_exempt(ip: &str, trusted: &TrustedProxyConfig) -> bool {
+    let Some(addr) = ip.parse::<IpAddr>().ok().map(security_db::normalize_ip) else {
+        return true;
+    };
+    addr.is_loopback()
+        || trusted.proxies.iter().any(|pattern| {
+            security_db::parse_ip_pattern(pattern)
+                .is_some_and(|(net, prefix)| security_db::ip_in_cidr(addr, net, prefix))
+        })
+}
+
+/// 洪泛计数，返回窗口内的请求数
+fn count_request(ip: &str, window_seconds: u64, now: i64) -> u32 {
+    let window = window_seconds.max(1) as i64;
+    if FLOOD_COUNTERS.len() > FLOOD_TABLE_PRUNE_SIZE {
+        FLOOD_COUNTERS.retain(|_, (start, _)| now - *start < window);
+    }
+    let mut entry = FLOOD_COUNTERS.entry(ip.to_string()).or_insert((now, 0));
+    let (start, count) = entry.value_mut();
+    if now - *start >= window {
+        *start = now;
+        *count = 0;
+    }
+    *count += 1;
+    *count
+}
+
+/// 记录一次已完成的请求并评估封禁规则
+pub fn observe(obs: RequestObservation, config: &AutoBanConfig, trusted: &TrustedProxyConfig) {
+    if !config.enabled || is_exempt(&obs.client_ip, trusted) {
+        return;
+    }
+    let now = chrono::Utc::now().timestamp();
+
+    let flood = &config.request_flood;
+    if flood.enabled && flood.threshold > 0 {
+        let count = count_request(&obs.client_ip, flood.window_seconds, now);
+        // 仅在恰好达到阈值时触发一次，封禁生效后请求会被 IP 过滤直接拒绝
+        if count == flood.threshold {
+            let ip = obs.client_ip.clone();
+            let reason = format!("{} requests within {}s", count, flood.window_seconds);
+            let config = config.clone();
+            tokio::task::spawn_blocking(move || ban(&ip, RULE_REQUEST_FLOOD, &reason, &config));
+        }
+    }
+
+    let is_auth_failure = obs.status == 401;
+    let is_admin_error = obs.is_admin && (400..500).contains(&obs.status);
+    if !is_auth_failure && !is_admin_error {
+        return;
+    }
+
+    let config = config.clone();
+    tokio::task::spawn_blocking(move || {
+        let log = IpAccessLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            client_ip: obs.client_ip.clone(),
+            timestamp: now,
+            method: Some(obs.method.clone()),
+            path: Some(obs.path.clone()),
+            user_agent: obs.user_agent.clone(),
+            status: Some(obs.status as i32),
+            duration: None,
+            api_key_hash: None,
+            blocked: false,
+            block_reason: None,
+            username: None,
+        };
+        if let Err(e) = security_db::save_ip_access_log(&log) {
+            tracing::error!("[AutoBan] Failed to save access log: {}", e);
+            return;
+        }
+        evaluate(&obs, &config, now);
+    });
+}
+
+/// 基于 ip_access_logs 统计鉴权失败与管理接口错误
+fn evaluate(obs: &RequestObservation, config: &AutoBanConfig, now: i64) {
+    let checks = [
+        (
+            RULE_AUTH_FAILURES,
+            &config.auth_failures,
+            obs.status == 401,
+            security_db::count_auth_failures as fn(&str, i64) -> Result<u32, String>,
+            "authentication failures",
+        ),
+        (
+            RULE_ADMIN_ERRORS,
+            &config.admin_errors,
+            obs.is_admin,
+            security_db::count_admin_errors,
+            "admin API errors",
+        ),
+    ];
+
+    for (rule_name, rule, applies, count_fn, label) in checks {
+        if !applies || !rule.enabled || rule.threshold == 0 {
+            continue;
+        }
+        let since = now - rule.window_seconds as i64;
+        match count_fn(&obs.client_ip, since) {
+            Ok(count) if count >= rule.threshold => {
+                let reason = format!("{} {} within {}s", count, label, rule.window_seconds);
+                ban(&obs.client_ip, rule_name, &reason, config);
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("[AutoBan] Failed to evaluate {}: {}", rule_name, e),
+        }
+    }
+}
+
+fn ban(ip: &str, rule: &str, reason: &str, config: &AutoBanConfig) {
+    // 白名单豁免
+    if let Ok(true) = security_db::is_ip_in_whitelist(ip) {
+        tracing::debug!("[AutoBan] {} is whitelisted, skipping {}", ip, rule);
+        return;
+    }
+
+    match security_db::create_auto_ban(
+        ip,
+        rule,
+        reason,
+        config.ban_seconds,
+        config.ban_multiplier,
+        config.max_ban_seconds,
+        ESCALATION_LOOKBACK_SECS,
+    ) {
+        Ok(Some(record)) => {
+            FLOOD_COUNTERS.remove(ip);
+            tracing::warn!(
+                "[AutoBan] Banned {} for {}s (rule: {}, level {}): {}",
+                ip,
+                record.ban_seconds,
+                rule,
+                record.level,
+                reason
+            );
+            event_bus::publish(AppEvent::new(
+                EVENT_SECURITY_AUTO_BAN,
+                serde_json::json!({
+                    "ip": record.client_ip,
+                    "rule": record.rule,
+                    "reason": record.reason,
+                    "level": record.level,
+                    "ban_seconds": record.ban_seconds,
+                    "blocked_until": record.expires_at,
+                }),
+            ));
+        }
+        // 已在黑名单中 (手动或并发触发)
+        Ok(None) => {}
+        Err(e) => tracing::error!("[AutoBan] Failed to ban {}: {}", ip, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_ban_escalation_and_exemptions() {
+        assert_eq!(security_db::auto_ban_duration(1, 600, 4, 86400), 600);
+        assert_eq!(security_db::auto_ban_duration(2, 600, 4, 86400), 2400);
+        assert_eq!(security_db::auto_ban_duration(3, 600, 4, 86400), 9600);
+        assert_eq!(security_db::auto_ban_duration(9, 600, 4, 86400), 86400);
+        assert_eq!(security_db::auto_ban_duration(60, 600, 4, 86400), 86400);
+
+        let trusted = TrustedProxyConfig {
+            proxies: vec!["172.16.0.0/12".to_string()],
+            trust_cloudflared: true,
+        };
+        assert!(is_exempt("127.0.0.1", &trusted));
+        assert!(is_exempt("::1", &trusted));
+        assert!(is_exempt("172.17.0.2", &trusted));
+        assert!(is_exempt("not-an-ip", &trusted));
+        assert!(!is_exempt("203.0.113.7", &trusted));
+
+        let ip = "192.0.2.250";
+        assert_eq!(count_request(ip, 60, 1_000), 1);
+        assert_eq!(count_request(ip, 60, 1_059), 2);
+        // 窗口结束后重新计数
+        assert_eq!(count_request(ip, 60, 1_060), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_token_counts_as_auth_failure() {
+        use crate::proxy::middleware::{auth_middleware, ip_filter_middleware};
+        use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
+        use axum::extract::ConnectInfo;
+        use std::net::SocketAddr;
+        use std::sync::Arc;
+        use tokio::sync::RwLock;
+        use tower::ServiceExt;
+
+        security_db::init_db().unwrap();
+        crate::modules::user_token_db::init_db().unwrap();
+
+        let mut security_monitor = crate::proxy::config::SecurityMonitorConfig::default();
+        security_monitor.auto_ban.enabled = true;
+        security_monitor.auto_ban.request_flood.enabled = false;
+        security_monitor.auto_ban.auth_failures.threshold = 2;
+        let security = Arc::new(RwLock::new(ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-api".to_string(),
+            admin_password: None,
+            allow_lan_access: true,
+            port: 8045,
+            security_monitor,
+        }));
+        // 与 server.rs 相同的层顺序: ip_filter -> auth -> handler
+        let app = axum::Router::new()
+            .route("/v1/models", axum::routing::get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                security.clone(),
+                auth_middleware,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                security,
+                ip_filter_middleware,
+            ));
+
+        let id = uuid::Uuid::new_v4().as_bytes()[..2].to_vec();
+        let addr: SocketAddr = format!("198.18.{}.{}:40000", id[0], id[1]).parse().unwrap();
+        let ip = addr.ip().to_string();
+        let guess = || {
+            let mut request = axum::http::Request::get("/v1/models")
+                .header(
+                    "Authorization",
+                    format!("Bearer sk-guess-{}", uuid::Uuid::new_v4()),
+                )
+                .body(axum::body::Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(addr));
+            request
+        };
+
+        // 使用不存在的用户令牌撞库，仅靠中间件链路触发封禁
+        for _ in 0..2 {
+            let response = app.clone().oneshot(guess()).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+        }
+
+        let mut entry = None;
+        for _ in 0..50 {
+            entry = security_db::get_blacklist_entry_for_ip(&ip).unwrap();
+            if entry.is_some() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        let entry = entry.expect("unknown token attempts should trigger an auto ban");
+        assert_eq!(entry.created_by, "auto");
+
+        // 封禁后 ip_filter 直接拒绝
+        let response = app.clone().oneshot(guess()).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::FORBIDDEN);
+        let _ = security_db::remove_from_blacklist(&entry.id);
+    }
+}
@@ -0,0 +1,719 @@
+//! 批处理后台执行器 (OpenAI Batch API / Anthropic Message Batches)
+//! 从 SQLite 任务表认领待执行请求，复用各协议 Handler 经 TokenManager 账号池执行；
+//! 遇到限流时并发减半并暂停派发，成功后逐步恢复 (AIMD)，重启后自动续跑未完成的请求；
+//! 文件与任务归属于创建者的 UserToken，执行时按该令牌检查用量限制并记录用量
+
+use std::panic::AssertUnwindSafe;
+use std::sync::OnceLock;
+
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::FutureExt;
+use serde_json::{json, Value};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::modules::batch_db::{
+    self, BatchFile, BatchRecord, BatchRequest, ClaimedRequest, REQ_CANCELLED, REQ_EXPIRED,
+    REQ_FAILED, REQ_SUCCEEDED,
+};
+use crate::modules::user_token_db::{self, RequestedModel, TokenLimitViolation};
+use crate::proxy::server::AppState;
+
+/// OpenAI Batch API 任务
+pub const KIND_OPENAI: &str = "openai";
+
+/// OpenAI 批处理支持的端点
+pub const OPENAI_BATCH_ENDPOINTS: &[&str] =
+    &["/v1/chat/completions", "/v1/completions", "/v1/embeddings"];
+
+/// Anthropic Message Batches 任务 (请求经 Claude Handler 转换后发往 Gemini 上游)
+pub const KIND_ANTHROPIC: &str = "anthropic";
+
+/// Anthropic 批处理的执行端点
+pub const ANTHROPIC_BATCH_ENDPOINT: &str = "/v1/messages";
+
+/// 空闲时轮询任务表的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 单条响应体读取上限
+const RESPONSE_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+fn wake_signal() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+/// 唤醒执行器 (新建 / 取消任务后调用，避免等待下一个轮询周期)
+pub fn wake() {
+    wake_signal().notify_one();
+}
+
+/// 单条请求的执行结果
+enum Outcome {
+    /// 得到最终响应 (含 4xx)
+    Done { status_code: u16, body: Value },
+    /// 账号池限流，放回队列稍后重试
+    RateLimited { retry_after: Option<u64> },
+    /// 上游临时错误 (5xx)，计入重试次数
+    Transient { status_code: u16, body: Value },
+}
+
+/// 自适应并发控制 (AIMD)
+struct Throttle {
+    limit: usize,
+    streak: usize,
+    paused_until: Option<Instant>,
+}
+
+impl Throttle {
+    fn new(max: usize) -> Self {
+        Self {
+            limit: max.max(1),
+            streak: 0,
+            paused_until: None,
+        }
+    }
+
+    /// 当前允许的并发数 (暂停期间为 0)
+    fn capacity(&mut self, max: usize) -> usize {
+        self.limit = self.limit.clamp(1, max.max(1));
+        match self.paused_until {
+            Some(until) if Instant::now() < until => 0,
+            _ => {
+                self.paused_until = None;
+                self.limit
+            }
+        }
+    }
+
+    fn on_success(&mut self, max: usize) {
+        self.streak += 1;
+        if self.streak >= self.limit {
+            self.limit = (self.limit + 1).min(max.max(1));
+            self.streak = 0;
+        }
+    }
+
+    fn on_rate_limited(&mut self, backoff: Duration) {
+        self.limit = (self.limit / 2).max(1);
+        self.streak = 0;
+        let until = Instant::now() + backoff;
+        if self.paused_until.is_none_or(|current| current < until) {
+            self.paused_until = Some(until);
+        }
+    }
+}
+
+async fn db<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 启动执行器，`cancel` 触发时停止派发并退出 (运行中的请求在下次启动时重新排队)
+pub fn spawn(state: AppState, cancel: CancellationToken) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(run(state, cancel))
+}
+
+async fn run(state: AppState, cancel: CancellationToken) {
+    match db(batch_db::reset_running_requests).await {
+        Ok(0) => {}
+        Ok(n) => tracing::info!("[Batch] Resumed {} interrupted requests", n),
+        Err(e) => tracing::warn!("[Batch] Failed to reset interrupted requests: {}", e),
+    }
+
+    let mut throttle = Throttle::new(crate::proxy::config::get_batch_config().max_concurrency);
+    let mut tasks: JoinSet<(ClaimedRequest, Outcome)> = JoinSet::new();
+
+    loop {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let config = crate::proxy::config::get_batch_config();
+
+        if let Err(e) = db(maintain_batches).await {
+            tracing::warn!("[Batch] Failed to update batch status: {}", e);
+        }
+
+        let running = *state.is_running.read().await;
+        let free = throttle
+            .capacity(config.max_concurrency)
+            .saturating_sub(tasks.len());
+        if running && free > 0 {
+            match db(move || batch_db::claim_pending(free)).await {
+                Ok(claimed) => {
+                    for item in claimed {
+                        let state = state.clone();
+                        tasks.spawn(async move {
+                            let outcome = AssertUnwindSafe(execute(&state, &item))
+                                .catch_unwind()
+                                .await
+                                .unwrap_or_else(|_| Outcome::Transient {
+                                    status_code: 500,
+                                    body: error_body("Batch request handler panicked"),
+                                });
+                            (item, outcome)
+                        });
+                    }
+                }
+                Err(e) => tracing::warn!("[Batch] Failed to claim requests: {}", e),
+            }
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+            Some(joined) = tasks.join_next(), if !tasks.is_empty() => {
+                if let Ok((item, outcome)) = joined {
+                    record_outcome(&mut throttle, &config, item.request, outcome).await;
+                }
+            }
+            _ = wake_signal().notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+
+    tasks.abort_all();
+    tracing::info!("[Batch] Worker stopped");
+}
+
+async fn record_outcome(
+    throttle: &mut Throttle,
+    config: &crate::proxy::config::BatchConfig,
+    request: BatchRequest,
+    outcome: Outcome,
+) {
+    let (batch_id, idx) = (request.batch_id.clone(), request.idx);
+    let result = match outcome {
+        Outcome::Done { status_code, body } => {
+            let status = if (200..300).contains(&status_code) {
+                throttle.on_success(config.max_concurrency);
+                REQ_SUCCEEDED
+            } else {
+                REQ_FAILED
+            };
+            db(move || {
+                batch_db::finish_request(
+                    &batch_id,
+                    idx,
+                    status,
+                    Some(status_code),
+                    Some(&body),
+                    None,
+                )
+            })
+            .await
+        }
+        Outcome::RateLimited { retry_after } => {
+            let backoff = retry_after.unwrap_or(config.rate_limit_backoff_secs).max(1);
+            tracing::warn!(
+                "[Batch] Rate limited on {}#{}, backing off {}s",
+                batch_id,
+                idx,
+                backoff
+            );
+            throttle.on_rate_limited(Duration::from_secs(backoff));
+            db(move || batch_db::requeue_request(&batch_id, idx, false)).await
+        }
+        Outcome::Transient { status_code, body } => {
+            if request.attempts + 1 >= config.max_attempts.max(1) {
+                db(move || {
+                    batch_db::finish_request(
+                        &batch_id,
+                        idx,
+                        REQ_FAILED,
+                        Some(status_code),
+                        Some(&body),
+                        None,
+                    )
+                })
+                .await
+            } else {
+                db(move || batch_db::requeue_request(&batch_id, idx, true)).await
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::warn!(
+            "[Batch] Failed to record result for {}#{}: {}",
+            request.batch_id,
+            request.idx,
+            e
+        );
+    }
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": { "message": message, "type": "server_error" } })
+}
+
+/// 创建者令牌的执行前检查结果
+enum OwnerCheck {
+    Allowed,
+    /// 令牌已删除、禁用或过期
+    Revoked(&'static str),
+    Limited(TokenLimitViolation),
+}
+
+fn check_owner(token_id: &str, model: Option<&str>) -> Result<OwnerCheck, String> {
+    let Some(token) = user_token_db::get_token_by_id(token_id)? else {
+        return Ok(OwnerCheck::Revoked(
+            "The token that created this batch no longer exists.",
+        ));
+    };
+    if !token.enabled {
+        return Ok(OwnerCheck::Revoked(
+            "The token that created this batch has been disabled.",
+        ));
+    }
+    if token
+        .expires_at
+        .is_some_and(|t| t < chrono::Utc::now().timestamp())
+    {
+        return Ok(OwnerCheck::Revoked(
+            "The token that created this batch has expired.",
+        ));
+    }
+    let model = model.map_or(RequestedModel::Unknown, RequestedModel::Known);
+    Ok(match user_token_db::check_token_limits(token_id, model)? {
+        Some(violation) => OwnerCheck::Limited(violation),
+        None => OwnerCheck::Allowed,
+    })
+}
+
+/// 以任务创建者的身份执行单条请求: 执行前检查令牌状态与用量限制，完成后计入令牌用量
+async fn execute(state: &AppState, item: &ClaimedRequest) -> Outcome {
+    let model = item
+        .request
+        .body
+        .get("model")
+        .and_then(|m| m.as_str())
+        .map(|m| m.to_string());
+
+    if let Some(token_id) = item.owner_token_id.clone() {
+        let requested = model.clone();
+        match db(move || check_owner(&token_id, requested.as_deref())).await {
+            Ok(OwnerCheck::Allowed) => {}
+            Ok(OwnerCheck::Revoked(message)) => {
+                return Outcome::Done {
+                    status_code: 401,
+                    body: error_body(message),
+                }
+            }
+            // 每分钟请求数超限: 放回队列，与上游限流一样暂停派发
+            Ok(OwnerCheck::Limited(TokenLimitViolation::RateLimited(_))) => {
+                return Outcome::RateLimited { retry_after: None }
+            }
+            Ok(OwnerCheck::Limited(violation)) => {
+                let response = crate::proxy::middleware::monitor::token_limit_response(
+                    &item.endpoint,
+                    &violation,
+                );
+                let (status, _, body) = read_response(response).await;
+                return Outcome::Done {
+                    status_code: status.as_u16(),
+                    body,
+                };
+            }
+            Err(e) => {
+                return Outcome::Transient {
+                    status_code: 500,
+                    body: error_body(&format!("Failed to check token limits: {}", e)),
+                }
+            }
+        }
+    }
+
+    let response = match dispatch(state, item).await {
+        Ok(response) => response,
+        Err(outcome) => return outcome,
+    };
+    let (status, retry_after, body) = read_response(response).await;
+
+    if let Some(token_id) = item.owner_token_id.clone() {
+        let ip = item
+            .owner_ip
+            .clone()
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let (input, output) = body
+            .get("usage")
+            .map(crate::proxy::middleware::monitor::usage_tokens)
+            .unwrap_or_default();
+        let model = model.unwrap_or_else(|| "unknown".to_string());
+        let status_code = status.as_u16();
+        if let Err(e) = db(move || {
+            user_token_db::record_token_usage_and_ip(
+                &token_id,
+                &ip,
+                &model,
+                input.unwrap_or(0) as i32,
+                output.unwrap_or(0) as i32,
+                status_code,
+                None,
+            )
+        })
+        .await
+        {
+            tracing::warn!("[Batch] Failed to record token usage: {}", e);
+        }
+    }
+
+    classify(status, retry_after, body)
+}
+
+/// 通过对应 Handler 执行单条请求
+async fn dispatch(state: &AppState, item: &ClaimedRequest) -> Result<Response, Outcome> {
+    let mut body = item.request.body.clone();
+    let response = match (item.kind.as_str(), item.endpoint.as_str()) {
+        (KIND_OPENAI, "/v1/chat/completions") => {
+            body["stream"] = Value::Bool(false);
+            crate::proxy::handlers::openai::handle_chat_completions(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(body),
+            )
+            .await
+            .into_response()
+        }
+        (KIND_OPENAI, "/v1/completions") => {
+            body["stream"] = Value::Bool(false);
+            crate::proxy::handlers::openai::handle_completions(State(state.clone()), Json(body))
+                .await
+        }
+        (KIND_OPENAI, "/v1/embeddings") => {
+            crate::proxy::handlers::embeddings::handle_embeddings(State(state.clone()), Json(body))
+                .await
+        }
+        (KIND_ANTHROPIC, ANTHROPIC_BATCH_ENDPOINT) => {
+            body["stream"] = Value::Bool(false);
+            crate::proxy::handlers::claude::handle_messages(
+                State(state.clone()),
+                HeaderMap::new(),
+                Json(body),
+            )
+            .await
+        }
+        (kind, endpoint) => {
+            return Err(Outcome::Done {
+                status_code: 400,
+                body: error_body(&format!(
+                    "Unsupported batch endpoint: {} ({})",
+                    endpoint, kind
+                )),
+            })
+        }
+    };
+
+    Ok(response)
+}
+
+/// 读取响应，返回 (状态码, Retry-After 秒数, JSON 响应体)
+async fn read_response(response: Response) -> (StatusCode, Option<u64>, Value) {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+
+    let bytes = axum::body::to_bytes(response.into_body(), RESPONSE_BODY_LIMIT)
+        .await
+        .unwrap_or_default();
+    let body = serde_json::from_slice::<Value>(&bytes)
+        .unwrap_or_else(|_| error_body(String::from_utf8_lossy(&bytes).trim()));
+    (status, retry_after, body)
+}
+
+fn classify(status: StatusCode, retry_after: Option<u64>, body: Value) -> Outcome {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+            Outcome::RateLimited { retry_after }
+        }
+        s if s.as_u16() == 529 => Outcome::RateLimited { retry_after },
+        s if s.is_server_error() => Outcome::Transient {
+            status_code: s.as_u16(),
+            body,
+        },
+        s => Outcome::Done {
+            status_code: s.as_u16(),
+            body,
+        },
+    }
+}
+
+/// 推进任务状态: 过期、取消与完成收尾
+fn maintain_batches() -> Result<(), String> {
+    let now = chrono::Utc::now().timestamp();
+
+    for mut batch in batch_db::list_active_batches()? {
+        let counts = batch_db::request_counts(&batch.id)?;
+        match batch.status.as_str() {
+            "in_progress" => {
+                if batch.expires_at.is_some_and(|t| now >= t) {
+                    batch_db::mark_pending_requests(&batch.id, REQ_EXPIRED)?;
+                    if counts.running == 0 {
+                        finalize_batch(&mut batch, "expired", now)?;
+                    }
+                } else if counts.pending == 0 && counts.running == 0 {
+                    finalize_batch(&mut batch, "completed", now)?;
+                }
+            }
+            "cancelling" => {
+                batch_db::mark_pending_requests(&batch.id, REQ_CANCELLED)?;
+                if counts.running == 0 {
+                    finalize_batch(&mut batch, "cancelled", now)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn finalize_batch(batch: &mut BatchRecord, final_status: &str, now: i64) -> Result<(), String> {
+    batch.finalizing_at = Some(now);
+    if batch.kind == KIND_OPENAI {
+        write_openai_result_files(batch, now)?;
+    }
+
+    batch.status = final_status.to_string();
+    match final_status {
+        "completed" => batch.completed_at = Some(now),
+        "expired" => batch.expired_at = Some(now),
+        "cancelled" => batch.cancelled_at = Some(now),
+        _ => {}
+    }
+    batch_db::update_batch(batch)?;
+
+    tracing::info!("[Batch] {} finished with status {}", batch.id, final_status);
+    Ok(())
+}
+
+/// 单条请求在输出 / 错误文件中的行 (OpenAI Batch 输出格式)，返回 (是否成功, 行内容)
+pub fn openai_result_line(batch_id: &str, request: &BatchRequest) -> (bool, Value) {
+    let id = format!(
+        "batch_req_{}_{}",
+        batch_id.trim_start_matches("batch_"),
+        request.idx
+    );
+    let (response, error) = match (request.status.as_str(), request.status_code) {
+        (_, Some(code)) => (
+            json!({
+                "status_code": code,
+                "request_id": format!("req_{}_{}", batch_id, request.idx),
+                "body": request.response.clone().unwrap_or(Value::Null)
+            }),
+            Value::Null,
+        ),
+        (REQ_EXPIRED, None) => (
+            Value::Null,
+            json!({ "code": "batch_expired", "message": "This request could not be executed before the completion window expired." }),
+        ),
+        (REQ_CANCELLED, None) => (
+            Value::Null,
+            json!({ "code": "batch_cancelled", "message": "This request was not executed because the batch was cancelled." }),
+        ),
+        _ => (
+            Value::Null,
+            request.error.clone().unwrap_or_else(
+                || json!({ "code": "request_failed", "message": "The request could not be executed." }),
+            ),
+        ),
+    };
+
+    let succeeded = request.status == REQ_SUCCEEDED;
+    (
+        succeeded,
+        json!({ "id": id, "custom_id": request.custom_id, "response": response, "error": error }),
+    )
+}
+
+/// 单条请求的 Anthropic 批处理结果行 (`{"custom_id", "result"}`)，未结束的请求返回 None
+pub fn anthropic_result_line(request: &BatchRequest) -> Option<Value> {
+    let result = match request.status.as_str() {
+        REQ_SUCCEEDED => json!({
+            "type": "succeeded",
+            "message": request.response.clone().unwrap_or(Value::Null)
+        }),
+        REQ_FAILED => json!({ "type": "errored", "error": anthropic_error(request) }),
+        REQ_CANCELLED => json!({ "type": "canceled" }),
+        REQ_EXPIRED => json!({ "type": "expired" }),
+        _ => return None,
+    };
+    Some(json!({ "custom_id": request.custom_id, "result": result }))
+}
+
+/// 将失败请求的响应体规范为 Anthropic 错误对象 (`{"type": "error", "error": {...}}`)
+fn anthropic_error(request: &BatchRequest) -> Value {
+    let body = request.response.as_ref().or(request.error.as_ref());
+    if let Some(body) = body.filter(|b| b["type"] == "error" && b["error"].is_object()) {
+        return json!({
+            "type": "error",
+            "error": {
+                "type": body["error"]["type"].as_str().unwrap_or("api_error"),
+                "message": body["error"]["message"].as_str().unwrap_or_default()
+            }
+        });
+    }
+
+    let error_type = match request.status_code {
+        Some(400) | Some(404) | Some(413) | Some(422) => "invalid_request_error",
+        Some(401) => "authentication_error",
+        Some(403) => "permission_error",
+        Some(429) => "rate_limit_error",
+        Some(529) => "overloaded_error",
+        _ => "api_error",
+    };
+    let message = body
+        .and_then(|b| b["error"]["message"].as_str().or(b["message"].as_str()))
+        .unwrap_or("The request could not be executed.");
+    json!({ "type": "error", "error": { "type": error_type, "message": message } })
+}
+
+fn write_openai_result_files(batch: &mut BatchRecord, now: i64) -> Result<(), String> {
+    let mut output = String::new();
+    let mut errors = String::new();
+    for request in batch_db::list_requests(&batch.id)? {
+        let (succeeded, line) = openai_result_line(&batch.id, &request);
+        let target = if succeeded { &mut output } else { &mut errors };
+        target.push_str(&line.to_string());
+        target.push('\n');
+    }
+
+    let save = |suffix: &str, content: &str| -> Result<Option<String>, String> {
+        if content.is_empty() {
+            return Ok(None);
+        }
+        let file = BatchFile {
+            id: format!("file-{}", uuid::Uuid::new_v4().simple()),
+            purpose: "batch_output".to_string(),
+            filename: format!("{}_{}.jsonl", batch.id, suffix),
+            bytes: content.len() as i64,
+            created_at: now,
+            owner_token_id: batch.owner_token_id.clone(),
+        };
+        batch_db::save_file(&file, content.as_bytes())?;
+        Ok(Some(file.id))
+    };
+
+    let output_file_id = save("output", &output)?;
+    let error_file_id = save("error", &errors)?;
+    batch.output_file_id = output_file_id;
+    batch.error_file_id = error_file_id;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_backs_off_and_recovers() {
+        let mut throttle = Throttle::new(8);
+        assert_eq!(throttle.capacity(8), 8);
+
+        throttle.on_rate_limited(Duration::from_secs(60));
+        assert_eq!(throttle.capacity(8), 0);
+        assert_eq!(throttle.limit, 4);
+
+        throttle.paused_until = None;
+        for _ in 0..4 {
+            throttle.on_success(8);
+        }
+        assert_eq!(throttle.capacity(8), 5);
+
+        // 配置下调立即生效
+        assert_eq!(throttle.capacity(2), 2);
+    }
+
+    #[test]
+    fn test_openai_result_line() {
+        let mut request = BatchRequest {
+            batch_id: "batch_abc".to_string(),
+            idx: 3,
+            custom_id: "task-3".to_string(),
+            body: json!({}),
+            status: REQ_SUCCEEDED.to_string(),
+            status_code: Some(200),
+            response: Some(json!({ "object": "chat.completion" })),
+            error: None,
+            attempts: 1,
+        };
+        let (ok, line) = openai_result_line("batch_abc", &request);
+        assert!(ok);
+        assert_eq!(line["id"], "batch_req_abc_3");
+        assert_eq!(line["response"]["status_code"], 200);
+        assert!(line["error"].is_null());
+
+        request.status = REQ_EXPIRED.to_string();
+        request.status_code = None;
+        let (ok, line) = openai_result_line("batch_abc", &request);
+        assert!(!ok);
+        assert!(line["response"].is_null());
+        assert_eq!(line["error"]["code"], "batch_expired");
+    }
+
+    #[test]
+    fn test_anthropic_result_line() {
+        let mut request = BatchRequest {
+            batch_id: "msgbatch_abc".to_string(),
+            idx: 0,
+            custom_id: "req-1".to_string(),
+            body: json!({}),
+            status: REQ_SUCCEEDED.to_string(),
+            status_code: Some(200),
+            response: Some(json!({ "type": "message", "role": "assistant" })),
+            error: None,
+            attempts: 1,
+        };
+        let line = anthropic_result_line(&request).unwrap();
+        assert_eq!(line["custom_id"], "req-1");
+        assert_eq!(line["result"]["type"], "succeeded");
+        assert_eq!(line["result"]["message"]["type"], "message");
+
+        request.status = REQ_FAILED.to_string();
+        request.status_code = Some(400);
+        request.response = Some(json!({
+            "type": "error",
+            "error": { "id": "err_retry_exhausted", "type": "invalid_request_error", "message": "bad" }
+        }));
+        let line = anthropic_result_line(&request).unwrap();
+        assert_eq!(
+            line["result"],
+            json!({
+                "type": "errored",
+                "error": { "type": "error", "error": { "type": "invalid_request_error", "message": "bad" } }
+            })
+        );
+
+        // 非 Anthropic 格式的错误体 (如执行器内部错误) 同样被规范化
+        request.status_code = Some(500);
+        request.response = Some(error_body("Batch request handler panicked"));
+        let line = anthropic_result_line(&request).unwrap();
+        assert_eq!(line["result"]["error"]["error"]["type"], "api_error");
+        assert_eq!(
+            line["result"]["error"]["error"]["message"],
+            "Batch request handler panicked"
+        );
+
+        request.status = REQ_CANCELLED.to_string();
+        assert_eq!(
+            anthropic_result_line(&request).unwrap()["result"],
+            json!({ "type": "canceled" })
+        );
+
+        request.status = batch_db::REQ_PENDING.to_string();
+        assert!(anthropic_result_line(&request).is_none());
+    }
+}
@@ -91,6 +91,19 @@ impl CliApp {
     }
 }
 
+/// 校验同步地址: 这些客户端的 HTTP 栈只接受 TCP 地址，
+/// 反代处于 Unix 套接字独占模式 (exclusive) 时没有可写入的地址，直接说明原因而不是写出不可用的配置
+pub fn validate_sync_url(app: &CliApp, proxy_url: &str) -> Result<(), String> {
+    if crate::proxy::unix_socket::is_socket_url(proxy_url) {
+        return Err(format!(
+            "反代当前处于 Unix 套接字独占模式 (仅监听 {}，未绑定 TCP 端口)，{} 只能连接 HTTP 地址。请在反代设置中关闭套接字独占模式后再同步",
+            proxy_url.trim(),
+            app.as_str()
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CliStatus {
     pub installed: bool,
@@ -325,6 +338,7 @@ pub fn sync_config(
     api_key: &str,
     model: Option<&str>,
 ) -> Result<(), String> {
+    validate_sync_url(app, proxy_url)?;
     let files = app.config_files();
 
     for file in &files {
@@ -650,3 +664,17 @@ pub async fn get_cli_config_content(
     }
     fs::read_to_string(&file.path).map_err(|e| format!("读取配置文件失败: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_sync_url_explains_exclusive_socket_mode() {
+        assert!(validate_sync_url(&CliApp::Claude, "http://127.0.0.1:8045").is_ok());
+
+        let err = validate_sync_url(&CliApp::Codex, "unix:///tmp/antigravity.sock").unwrap_err();
+        assert!(err.contains("独占模式"));
+        assert!(err.contains("/tmp/antigravity.sock"));
+    }
+}
@@ -133,6 +133,11 @@ pub async fn get_all_dynamic_models(
     model_ids.insert("gemini-3-pro-high".to_string());
     model_ids.insert("gemini-3-pro-low".to_string());
 
+    // [NEW] 通用上游提供商声明的模型
+    for m in crate::proxy::providers::registry::list_provider_models() {
+        model_ids.insert(m);
+    }
+
     let mut sorted_ids: Vec<_> = model_ids.into_iter().collect();
     sorted_ids.sort();
     sorted_ids
@@ -147,7 +152,7 @@ pub async fn get_all_dynamic_models(
 /// - `claude-*-sonnet-*` matches `claude-3-5-sonnet-20241022` ✓
 /// - `*-thinking` matches `claude-opus-4-5-thinking` ✓
 /// - `a*b*c` matches `a123b456c` ✓
-fn wildcard_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
     let parts: Vec<&str> = pattern.split('*').collect();
 
     // No wildcard - exact match
@@ -240,6 +245,39 @@ pub fn resolve_model_route(
     result
 }
 
+/// [NEW] 查找模型降级链
+///
+/// 依次以路由后的模型名、原始请求模型名作为 key 查找 (精确匹配优先，其次最具体的通配符)，
+/// 返回按顺序尝试的降级模型列表 (已排除与当前模型相同的项)。
+pub fn resolve_fallback_chain(
+    mapped_model: &str,
+    original_model: &str,
+    fallbacks: &HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    let lookup = |key: &str| -> Option<&Vec<String>> {
+        if let Some(chain) = fallbacks.get(key) {
+            return Some(chain);
+        }
+        fallbacks
+            .iter()
+            .filter(|(pattern, _)| pattern.contains('*') && wildcard_match(pattern, key))
+            .max_by_key(|(pattern, _)| pattern.chars().count() - pattern.matches('*').count())
+            .map(|(_, chain)| chain)
+    };
+
+    let chain = lookup(mapped_model).or_else(|| lookup(original_model));
+    chain
+        .map(|models| {
+            models
+                .iter()
+                .map(|m| m.trim())
+                .filter(|m| !m.is_empty() && *m != mapped_model && *m != original_model)
+                .map(|m| m.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Normalize any physical model name to one of the 3 standard protection IDs.
 /// This ensures quota protection works consistently regardless of API versioning or request variations.
 ///
@@ -388,4 +426,45 @@ mod tests {
         // Multi-wildcard: "a*b*c" (3)
         assert_eq!(resolve_model_route("a-test-b-foo-c", &custom), "multi-wild");
     }
+
+    #[test]
+    fn test_resolve_fallback_chain() {
+        let mut fallbacks = HashMap::new();
+        fallbacks.insert(
+            "claude-opus-4-6-thinking".to_string(),
+            vec![
+                "claude-sonnet-4-5-thinking".to_string(),
+                "gemini-3-pro-high".to_string(),
+            ],
+        );
+        fallbacks.insert(
+            "gemini-3-*".to_string(),
+            vec!["gemini-2.5-flash".to_string()],
+        );
+        fallbacks.insert(
+            "gemini-3-pro-*".to_string(),
+            vec!["gemini-3-flash".to_string()],
+        );
+
+        // 路由后模型精确匹配
+        assert_eq!(
+            resolve_fallback_chain("claude-opus-4-6-thinking", "claude-opus-4-6", &fallbacks),
+            vec!["claude-sonnet-4-5-thinking", "gemini-3-pro-high"]
+        );
+        // 最具体的通配符优先
+        assert_eq!(
+            resolve_fallback_chain("gemini-3-pro-preview", "gemini-3-pro-high", &fallbacks),
+            vec!["gemini-3-flash"]
+        );
+        // 排除自身
+        assert!(
+            resolve_fallback_chain("gemini-3-flash", "gemini-3-flash", &fallbacks)
+                .iter()
+                .all(|m| m != "gemini-3-flash")
+        );
+        // 无配置
+        assert!(
+            resolve_fallback_chain("claude-sonnet-4-5", "claude-sonnet-4-5", &fallbacks).is_empty()
+        );
+    }
 }
@@ -270,6 +270,198 @@ pub fn update_stream_handling_config(config: StreamHandlingConfig) {
     }
 }
 
+// ============================================================================
+// [NEW] 模型降级链配置存储
+// ============================================================================
+static GLOBAL_MODEL_FALLBACKS: OnceLock<RwLock<HashMap<String, Vec<String>>>> = OnceLock::new();
+
+pub fn get_model_fallbacks() -> HashMap<String, Vec<String>> {
+    GLOBAL_MODEL_FALLBACKS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+pub fn update_model_fallbacks(fallbacks: HashMap<String, Vec<String>>) {
+    let count = fallbacks.len();
+    if let Some(lock) = GLOBAL_MODEL_FALLBACKS.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = fallbacks;
+            tracing::info!("[Model-Fallback] Global config updated: {} chains", count);
+        }
+    } else {
+        let _ = GLOBAL_MODEL_FALLBACKS.set(RwLock::new(fallbacks));
+        tracing::info!(
+            "[Model-Fallback] Global config initialized: {} chains",
+            count
+        );
+    }
+}
+
+// ============================================================================
+// [NEW] 通用上游提供商配置存储
+// ============================================================================
+static GLOBAL_UPSTREAM_PROVIDERS: OnceLock<RwLock<Vec<UpstreamProviderConfig>>> = OnceLock::new();
+
+pub fn get_upstream_providers() -> Vec<UpstreamProviderConfig> {
+    GLOBAL_UPSTREAM_PROVIDERS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+pub fn update_upstream_providers(providers: Vec<UpstreamProviderConfig>) {
+    let count = providers.iter().filter(|p| p.enabled).count();
+    if let Some(lock) = GLOBAL_UPSTREAM_PROVIDERS.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = providers;
+            tracing::info!(
+                "[Provider] Global config updated: {} enabled providers",
+                count
+            );
+        }
+    } else {
+        let _ = GLOBAL_UPSTREAM_PROVIDERS.set(RwLock::new(providers));
+        tracing::info!(
+            "[Provider] Global config initialized: {} enabled providers",
+            count
+        );
+    }
+}
+
+// ============================================================================
+// [NEW] 批处理执行器配置存储
+// ============================================================================
+static GLOBAL_BATCH_CONFIG: OnceLock<RwLock<BatchConfig>> = OnceLock::new();
+
+pub fn get_batch_config() -> BatchConfig {
+    GLOBAL_BATCH_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+pub fn update_batch_config(config: BatchConfig) {
+    if let Some(lock) = GLOBAL_BATCH_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Batch] Global config updated: max_concurrency={}",
+                config.max_concurrency
+            );
+        }
+    } else {
+        let _ = GLOBAL_BATCH_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Batch] Global config initialized: max_concurrency={}",
+            config.max_concurrency
+        );
+    }
+}
+
+// ============================================================================
+// [NEW] Webhook 配置存储
+// ============================================================================
+static GLOBAL_WEBHOOKS: OnceLock<RwLock<Vec<WebhookConfig>>> = OnceLock::new();
+
+pub fn get_webhooks() -> Vec<WebhookConfig> {
+    GLOBAL_WEBHOOKS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+pub fn update_webhooks(webhooks: Vec<WebhookConfig>) {
+    let count = webhooks.iter().filter(|w| w.enabled).count();
+    if let Some(lock) = GLOBAL_WEBHOOKS.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = webhooks;
+            tracing::info!(
+                "[Webhook] Global config updated: {} enabled webhooks",
+                count
+            );
+        }
+    } else {
+        let _ = GLOBAL_WEBHOOKS.set(RwLock::new(webhooks));
+        tracing::info!(
+            "[Webhook] Global config initialized: {} enabled webhooks",
+            count
+        );
+    }
+}
+
+// ============================================================================
+// [NEW] 响应缓存配置存储
+// ============================================================================
+static GLOBAL_RESPONSE_CACHE_CONFIG: OnceLock<RwLock<ResponseCacheConfig>> = OnceLock::new();
+
+pub fn get_response_cache_config() -> ResponseCacheConfig {
+    GLOBAL_RESPONSE_CACHE_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+pub fn update_response_cache_config(config: ResponseCacheConfig) {
+    if let Some(lock) = GLOBAL_RESPONSE_CACHE_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[ResponseCache] Global config updated: enabled={}, ttl={}s, max_entries={}",
+                config.enabled,
+                config.ttl_seconds,
+                config.max_entries
+            );
+        }
+    } else {
+        let _ = GLOBAL_RESPONSE_CACHE_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[ResponseCache] Global config initialized: enabled={}, ttl={}s, max_entries={}",
+            config.enabled,
+            config.ttl_seconds,
+            config.max_entries
+        );
+    }
+}
+
+// ============================================================================
+// [NEW] 审计日志配置存储
+// ============================================================================
+static GLOBAL_AUDIT_LOG_CONFIG: OnceLock<RwLock<AuditLogConfig>> = OnceLock::new();
+
+pub fn get_audit_log_config() -> AuditLogConfig {
+    GLOBAL_AUDIT_LOG_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+pub fn update_audit_log_config(config: AuditLogConfig) {
+    if let Some(lock) = GLOBAL_AUDIT_LOG_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Audit] Global config updated: enabled={}, retention_days={}",
+                config.enabled,
+                config.retention_days
+            );
+        }
+    } else {
+        let _ = GLOBAL_AUDIT_LOG_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Audit] Global config initialized: enabled={}, retention_days={}",
+            config.enabled,
+            config.retention_days
+        );
+    }
+}
+
 // ============================================================================
 // 标点规范化配置存储
 // ============================================================================
@@ -523,6 +715,62 @@ impl Default for ZaiConfig {
     }
 }
 
+/// 上游提供商的线路协议
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderProtocol {
+    /// OpenAI Chat Completions (`{base_url}/chat/completions`)
+    #[default]
+    OpenaiChat,
+    /// Anthropic Messages (`{base_url}/v1/messages`)
+    AnthropicMessages,
+}
+
+/// 上游提供商的 API Key 鉴权方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderAuthStyle {
+    /// `Authorization: Bearer <key>` (OpenAI / DeepSeek / vLLM)
+    #[default]
+    Bearer,
+    /// `x-api-key: <key>` (Anthropic)
+    XApiKey,
+    /// `api-key: <key>` (Azure OpenAI)
+    ApiKey,
+    /// 不发送凭证 (本地 Ollama 等)
+    None,
+}
+
+/// 通用上游提供商 (OpenAI / Anthropic 兼容)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamProviderConfig {
+    /// 唯一标识，也可作为模型前缀显式路由 (如 `ollama:llama3.1`)
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub enabled: bool,
+    pub base_url: String,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub auth_style: ProviderAuthStyle,
+    #[serde(default)]
+    pub protocol: ProviderProtocol,
+    /// 调度模式 (语义与 z.ai 相同)
+    #[serde(default)]
+    pub dispatch_mode: ZaiDispatchMode,
+    /// 该提供商接管的模型 (key: 请求模型，支持通配符; value: 上游模型，空或 `*` 表示原样透传)
+    #[serde(default)]
+    pub model_mapping: HashMap<String, String>,
+    /// 附加请求头
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    /// 附加查询参数 (如 Azure 的 `api-version`)
+    #[serde(default)]
+    pub query_params: HashMap<String, String>,
+}
+
 /// 实验性功能配置 (Feature Flags)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExperimentalConfig {
@@ -677,6 +925,217 @@ impl Default for StreamHandlingConfig {
     }
 }
 
+/// 批处理执行器配置 (/v1/batches)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// 并发上限 (遇到限流时自动减半，成功后逐步恢复)
+    #[serde(default = "default_batch_max_concurrency")]
+    pub max_concurrency: usize,
+    /// 遇到限流时暂停派发的秒数
+    #[serde(default = "default_batch_rate_limit_backoff_secs")]
+    pub rate_limit_backoff_secs: u64,
+    /// 上游 5xx 等临时错误的最大尝试次数 (限流不计入)
+    #[serde(default = "default_batch_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_batch_max_concurrency() -> usize {
+    4
+}
+
+fn default_batch_rate_limit_backoff_secs() -> u64 {
+    30
+}
+
+fn default_batch_max_attempts() -> u32 {
+    3
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: default_batch_max_concurrency(),
+            rate_limit_backoff_secs: default_batch_rate_limit_backoff_secs(),
+            max_attempts: default_batch_max_attempts(),
+        }
+    }
+}
+
+/// Webhook 负载模板
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// 原始事件 JSON
+    #[default]
+    Generic,
+    /// Slack Incoming Webhook (`text` + `blocks`)
+    Slack,
+    /// Discord Webhook (`content` + `embeds`)
+    Discord,
+}
+
+/// 出站 Webhook (账号 / 配额生命周期事件推送)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// 唯一标识 (测试推送时引用)
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// HMAC-SHA256 签名密钥，留空则不签名
+    #[serde(default)]
+    pub secret: String,
+    /// 订阅的事件类型，支持前缀通配 (如 "account://*")；留空订阅全部生命周期事件
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// 投递失败 (网络错误 / 429 / 5xx) 时的最大重试次数
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+    /// 额外请求头
+    #[serde(default)]
+    pub headers: std::collections::HashMap<String, String>,
+}
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+/// 管理接口审计日志配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 保留天数，0 表示永久保留
+    #[serde(default = "default_audit_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_audit_retention_days() -> u32 {
+    90
+}
+
+impl Default for AuditLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention_days: default_audit_retention_days(),
+        }
+    }
+}
+
+/// 响应缓存配置 (确定性请求的精确匹配缓存，默认关闭)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存有效期 (秒)
+    #[serde(default = "default_response_cache_ttl")]
+    pub ttl_seconds: u64,
+    /// 最大缓存条目数，超出时淘汰最久未命中的条目
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: u64,
+    /// 单条响应最大字节数，超出则不缓存
+    #[serde(default = "default_response_cache_max_entry_bytes")]
+    pub max_entry_bytes: usize,
+    /// 仅缓存 temperature 为 0 的请求
+    #[serde(default = "default_true")]
+    pub deterministic_only: bool,
+}
+
+fn default_response_cache_ttl() -> u64 {
+    86400
+}
+
+fn default_response_cache_max_entries() -> u64 {
+    10000
+}
+
+fn default_response_cache_max_entry_bytes() -> usize {
+    1024 * 1024
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_seconds: default_response_cache_ttl(),
+            max_entries: default_response_cache_max_entries(),
+            max_entry_bytes: default_response_cache_max_entry_bytes(),
+            deterministic_only: true,
+        }
+    }
+}
+
+/// 监听端口 TLS 配置 (修改后需重启服务生效，证书文件变更会自动重新加载)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM 证书链路径
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// PEM 私钥路径 (PKCS#8 / PKCS#1 / SEC1)
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// 未配置证书路径时，在数据目录下生成并使用自签名证书
+    #[serde(default = "default_true")]
+    pub self_signed: bool,
+    /// 通过 ALPN 协商 HTTP/2 (明文连接支持 h2c prior knowledge)
+    #[serde(default = "default_true")]
+    pub http2: bool,
+    /// 允许本机回环地址使用明文 HTTP (内部预热、cloudflared 隧道与本机 CLI)
+    #[serde(default = "default_true")]
+    pub allow_loopback_plaintext: bool,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            self_signed: true,
+            http2: true,
+            allow_loopback_plaintext: true,
+        }
+    }
+}
+
+/// Unix 域套接字监听配置 (仅 Unix 平台，修改后需重启服务生效)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnixSocketConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 套接字路径 (为空时使用数据目录下的 proxy.sock)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// 套接字文件权限 (八进制，如 "600" 仅允许当前用户访问)
+    #[serde(default = "default_unix_socket_mode")]
+    pub mode: String,
+    /// 仅监听 Unix 套接字，不绑定任何 TCP 端口
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+fn default_unix_socket_mode() -> String {
+    "600".to_string()
+}
+
+impl Default for UnixSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: None,
+            mode: default_unix_socket_mode(),
+            exclusive: false,
+        }
+    }
+}
+
 /// 标点规范化配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PunctuationConfig {
@@ -787,6 +1246,14 @@ pub struct SecurityMonitorConfig {
     /// IP 白名单配置
     #[serde(default)]
     pub whitelist: IpWhitelistConfig,
+
+    /// [NEW] 可信反向代理配置 (客户端 IP 解析)
+    #[serde(default)]
+    pub trusted_proxy: TrustedProxyConfig,
+
+    /// [NEW] 自动封禁配置
+    #[serde(default)]
+    pub auto_ban: AutoBanConfig,
 }
 
 impl Default for SecurityMonitorConfig {
@@ -794,10 +1261,128 @@ impl Default for SecurityMonitorConfig {
         Self {
             blacklist: IpBlacklistConfig::default(),
             whitelist: IpWhitelistConfig::default(),
+            trusted_proxy: TrustedProxyConfig::default(),
+            auto_ban: AutoBanConfig::default(),
+        }
+    }
+}
+
+/// 可信反向代理配置
+/// 仅当 TCP 对端地址属于可信代理时，才解析 Forwarded / X-Forwarded-For / X-Real-IP 请求头
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedProxyConfig {
+    /// 可信代理地址列表 (单个 IP 或 CIDR，支持 IPv6)
+    #[serde(default = "default_trusted_proxies")]
+    pub proxies: Vec<String>,
+
+    /// 内置 cloudflared 隧道运行时，信任其注入的 CF-Connecting-IP
+    #[serde(default = "default_true")]
+    pub trust_cloudflared: bool,
+}
+
+impl Default for TrustedProxyConfig {
+    fn default() -> Self {
+        Self {
+            proxies: default_trusted_proxies(),
+            trust_cloudflared: true,
         }
     }
 }
 
+fn default_trusted_proxies() -> Vec<String> {
+    vec!["127.0.0.0/8".to_string(), "::1".to_string()]
+}
+
+/// 自动封禁触发规则: window_seconds 内达到 threshold 次即封禁
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBanRule {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub threshold: u32,
+    pub window_seconds: u64,
+}
+
+impl AutoBanRule {
+    fn new(threshold: u32, window_seconds: u64) -> Self {
+        Self {
+            enabled: true,
+            threshold,
+            window_seconds,
+        }
+    }
+}
+
+/// 自动封禁配置 (fail2ban 风格)
+/// 封禁写入 IP 黑名单 (created_by = "auto")，时长按 ban_seconds * ban_multiplier^(近期封禁次数) 递增
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBanConfig {
+    /// 是否启用自动封禁 (启用后即使黑名单开关关闭，自动封禁条目同样生效)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 鉴权失败 (401)
+    #[serde(default = "default_auth_failure_rule")]
+    pub auth_failures: AutoBanRule,
+
+    /// 请求洪泛 (任意请求)
+    #[serde(default = "default_request_flood_rule")]
+    pub request_flood: AutoBanRule,
+
+    /// 管理接口 4xx
+    #[serde(default = "default_admin_error_rule")]
+    pub admin_errors: AutoBanRule,
+
+    /// 首次封禁时长 (秒)
+    #[serde(default = "default_ban_seconds")]
+    pub ban_seconds: u64,
+
+    /// 再次封禁的时长倍数
+    #[serde(default = "default_ban_multiplier")]
+    pub ban_multiplier: u32,
+
+    /// 封禁时长上限 (秒)
+    #[serde(default = "default_max_ban_seconds")]
+    pub max_ban_seconds: u64,
+}
+
+impl Default for AutoBanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auth_failures: default_auth_failure_rule(),
+            request_flood: default_request_flood_rule(),
+            admin_errors: default_admin_error_rule(),
+            ban_seconds: default_ban_seconds(),
+            ban_multiplier: default_ban_multiplier(),
+            max_ban_seconds: default_max_ban_seconds(),
+        }
+    }
+}
+
+fn default_auth_failure_rule() -> AutoBanRule {
+    AutoBanRule::new(10, 300)
+}
+
+fn default_request_flood_rule() -> AutoBanRule {
+    AutoBanRule::new(600, 60)
+}
+
+fn default_admin_error_rule() -> AutoBanRule {
+    AutoBanRule::new(30, 300)
+}
+
+fn default_ban_seconds() -> u64 {
+    600
+}
+
+fn default_ban_multiplier() -> u32 {
+    4
+}
+
+fn default_max_ban_seconds() -> u64 {
+    7 * 24 * 3600
+}
+
 /// 反代服务配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyConfig {
@@ -931,6 +1516,39 @@ pub struct ProxyConfig {
     /// 上游端点代理配置
     #[serde(default)]
     pub endpoint_proxy: EndpointProxyConfig,
+
+    /// [NEW] 模型降级链 (key: 模型名，支持通配符; value: 按顺序尝试的降级模型)
+    /// 当某模型所有账号配额耗尽或被限流时，依次尝试链中的下一个模型
+    #[serde(default)]
+    pub model_fallbacks: std::collections::HashMap<String, Vec<String>>,
+
+    /// [NEW] 通用上游提供商 (DeepSeek / Ollama / vLLM / Azure OpenAI 等)
+    #[serde(default)]
+    pub providers: Vec<UpstreamProviderConfig>,
+
+    /// [NEW] 批处理执行器配置
+    #[serde(default)]
+    pub batch: BatchConfig,
+
+    /// [NEW] 出站 Webhook (账号 / 配额生命周期事件)
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+
+    /// [NEW] 管理接口审计日志
+    #[serde(default)]
+    pub audit_log: AuditLogConfig,
+
+    /// [NEW] 响应缓存 (确定性请求)
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+
+    /// [NEW] 监听端口 TLS / HTTP/2
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// [NEW] Unix 域套接字监听
+    #[serde(default)]
+    pub unix_socket: UnixSocketConfig,
 }
 
 /// 上游代理配置
@@ -975,6 +1593,14 @@ impl Default for ProxyConfig {
             image_thinking_mode: None,
             claude_thinking_mapping: true,
             endpoint_proxy: EndpointProxyConfig::default(),
+            model_fallbacks: std::collections::HashMap::new(),
+            providers: Vec::new(),
+            batch: BatchConfig::default(),
+            webhooks: Vec::new(),
+            audit_log: AuditLogConfig::default(),
+            response_cache: ResponseCacheConfig::default(),
+            tls: TlsConfig::default(),
+            unix_socket: UnixSocketConfig::default(),
         }
     }
 }
@@ -100,7 +100,7 @@ pub async fn handle_audio_transcription(
     let (access_token, project_id, email, account_id, _wait_ms) = token_manager
         .get_token("text", false, None, &model)
         .await
-        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e))?;
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
 
     info!("使用账号: {}", email);
 
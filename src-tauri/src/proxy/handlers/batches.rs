@@ -0,0 +1,614 @@
+// OpenAI Files & Batch API Handler
+// /v1/files (上传 / 查询 / 下载 / 删除) 与 /v1/batches (创建 / 查询 / 列表 / 取消)
+use axum::{
+    extract::{Extension, Json, Multipart, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::modules::batch_db::{self, BatchFile, BatchRecord, RequestCounts};
+use crate::proxy::batch_worker::{self, KIND_OPENAI, OPENAI_BATCH_ENDPOINTS};
+use crate::proxy::middleware::auth::{owner_of, Caller};
+
+/// 单个批处理任务的最大请求数 (与 OpenAI 限制一致)
+const MAX_REQUESTS_PER_BATCH: usize = 50_000;
+
+/// 目前仅支持 24 小时的完成窗口
+const COMPLETION_WINDOW: &str = "24h";
+const COMPLETION_WINDOW_SECS: i64 = 24 * 3600;
+
+/// 构造 OpenAI 风格的错误响应
+fn error_response(status: StatusCode, message: &str, param: Option<&str>, code: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": "invalid_request_error",
+                "param": param,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn internal_error(e: String) -> Response {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, &e, None, "server_error")
+}
+
+async fn db<T, F>(f: F) -> Result<T, Response>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => Err(internal_error(e)),
+        Err(e) => Err(internal_error(e.to_string())),
+    }
+}
+
+fn file_to_json(file: &BatchFile) -> Value {
+    json!({
+        "id": file.id,
+        "object": "file",
+        "bytes": file.bytes,
+        "created_at": file.created_at,
+        "filename": file.filename,
+        "purpose": file.purpose,
+    })
+}
+
+fn file_not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        &format!("No such File object: {}", id),
+        Some("id"),
+        "file_not_found",
+    )
+}
+
+fn batch_to_json(batch: &BatchRecord, counts: &RequestCounts) -> Value {
+    json!({
+        "id": batch.id,
+        "object": "batch",
+        "endpoint": batch.endpoint,
+        "errors": batch.errors,
+        "input_file_id": batch.input_file_id,
+        "completion_window": batch.completion_window,
+        "status": batch.status,
+        "output_file_id": batch.output_file_id,
+        "error_file_id": batch.error_file_id,
+        "created_at": batch.created_at,
+        "in_progress_at": batch.in_progress_at,
+        "expires_at": batch.expires_at,
+        "finalizing_at": batch.finalizing_at,
+        "completed_at": batch.completed_at,
+        "failed_at": batch.failed_at,
+        "expired_at": batch.expired_at,
+        "cancelling_at": batch.cancelling_at,
+        "cancelled_at": batch.cancelled_at,
+        "request_counts": {
+            "total": counts.total,
+            "completed": counts.succeeded,
+            "failed": counts.failed + counts.expired,
+        },
+        "metadata": batch.metadata,
+    })
+}
+
+fn batch_not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        &format!("No batch found with id '{}'.", id),
+        Some("batch_id"),
+        "batch_not_found",
+    )
+}
+
+// ===== Files =====
+
+/// POST /v1/files (multipart: file + purpose)
+pub async fn handle_upload_file(caller: Caller, mut multipart: Multipart) -> Response {
+    let mut content: Option<Vec<u8>> = None;
+    let mut filename = "upload.jsonl".to_string();
+    let mut purpose: Option<String> = None;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Invalid multipart body: {}", e),
+                    None,
+                    "invalid_request",
+                )
+            }
+        };
+
+        match field.name().unwrap_or("") {
+            "file" => {
+                if let Some(name) = field.file_name() {
+                    filename = name.to_string();
+                }
+                match field.bytes().await {
+                    Ok(bytes) => content = Some(bytes.to_vec()),
+                    Err(e) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            &format!("Failed to read file: {}", e),
+                            Some("file"),
+                            "invalid_file",
+                        )
+                    }
+                }
+            }
+            "purpose" => purpose = field.text().await.ok(),
+            _ => {}
+        }
+    }
+
+    let Some(content) = content else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Missing required parameter: 'file'.",
+            Some("file"),
+            "missing_required_parameter",
+        );
+    };
+    let Some(purpose) = purpose.filter(|p| !p.trim().is_empty()) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Missing required parameter: 'purpose'.",
+            Some("purpose"),
+            "missing_required_parameter",
+        );
+    };
+
+    let file = BatchFile {
+        id: format!("file-{}", uuid::Uuid::new_v4().simple()),
+        purpose: purpose.trim().to_string(),
+        filename,
+        bytes: content.len() as i64,
+        created_at: chrono::Utc::now().timestamp(),
+        owner_token_id: owner_of(&caller),
+    };
+
+    let record = file.clone();
+    if let Err(resp) = db(move || batch_db::save_file(&record, &content)).await {
+        return resp;
+    }
+    tracing::info!(
+        "[Batch] File uploaded: {} ({} bytes, purpose={})",
+        file.id,
+        file.bytes,
+        file.purpose
+    );
+    Json(file_to_json(&file)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListFilesQuery {
+    purpose: Option<String>,
+    limit: Option<usize>,
+}
+
+/// GET /v1/files
+pub async fn handle_list_files(caller: Caller, Query(query): Query<ListFilesQuery>) -> Response {
+    let limit = query.limit.unwrap_or(10_000).clamp(1, 10_000);
+    let owner = owner_of(&caller);
+    match db(move || batch_db::list_files(owner.as_deref(), query.purpose.as_deref(), limit)).await
+    {
+        Ok(files) => Json(json!({
+            "object": "list",
+            "data": files.iter().map(file_to_json).collect::<Vec<_>>(),
+            "has_more": false
+        }))
+        .into_response(),
+        Err(resp) => resp,
+    }
+}
+
+/// GET /v1/files/:file_id
+pub async fn handle_get_file(caller: Caller, Path(id): Path<String>) -> Response {
+    let lookup_id = id.clone();
+    let owner = owner_of(&caller);
+    match db(move || batch_db::get_file(&lookup_id, owner.as_deref())).await {
+        Ok(Some(file)) => Json(file_to_json(&file)).into_response(),
+        Ok(None) => file_not_found(&id),
+        Err(resp) => resp,
+    }
+}
+
+/// GET /v1/files/:file_id/content
+pub async fn handle_get_file_content(caller: Caller, Path(id): Path<String>) -> Response {
+    let lookup_id = id.clone();
+    let owner = owner_of(&caller);
+    match db(move || batch_db::get_file_content(&lookup_id, owner.as_deref())).await {
+        Ok(Some(content)) => {
+            ([(header::CONTENT_TYPE, "application/jsonl")], content).into_response()
+        }
+        Ok(None) => file_not_found(&id),
+        Err(resp) => resp,
+    }
+}
+
+/// DELETE /v1/files/:file_id
+pub async fn handle_delete_file(caller: Caller, Path(id): Path<String>) -> Response {
+    let lookup_id = id.clone();
+    let owner = owner_of(&caller);
+    match db(move || batch_db::delete_file(&lookup_id, owner.as_deref())).await {
+        Ok(true) => Json(json!({ "id": id, "object": "file", "deleted": true })).into_response(),
+        Ok(false) => file_not_found(&id),
+        Err(resp) => resp,
+    }
+}
+
+// ===== Batches =====
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBatchRequest {
+    input_file_id: String,
+    endpoint: String,
+    completion_window: String,
+    #[serde(default)]
+    metadata: Option<Value>,
+}
+
+/// 输入文件中的一条校验错误
+fn line_error(code: &str, message: String, line: usize) -> Value {
+    json!({ "code": code, "message": message, "param": null, "line": line })
+}
+
+/// 解析并校验 JSONL 输入文件，返回 (custom_id, body) 列表或错误列表
+pub fn parse_openai_input(
+    content: &str,
+    endpoint: &str,
+) -> Result<Vec<(String, Value)>, Vec<Value>> {
+    let mut requests = Vec::new();
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+
+    for (i, raw) in content.lines().enumerate() {
+        let line_no = i + 1;
+        let raw = raw.trim();
+        if raw.is_empty() {
+            continue;
+        }
+
+        let item: Value = match serde_json::from_str(raw) {
+            Ok(v) => v,
+            Err(e) => {
+                errors.push(line_error(
+                    "invalid_json_line",
+                    format!("Line is not valid JSON: {}", e),
+                    line_no,
+                ));
+                continue;
+            }
+        };
+
+        let Some(custom_id) = item
+            .get("custom_id")
+            .and_then(|c| c.as_str())
+            .filter(|c| !c.is_empty())
+        else {
+            errors.push(line_error(
+                "missing_required_parameter",
+                "Missing required parameter: 'custom_id'.".to_string(),
+                line_no,
+            ));
+            continue;
+        };
+        if !seen.insert(custom_id.to_string()) {
+            errors.push(line_error(
+                "duplicate_custom_id",
+                format!("The custom_id '{}' is not unique.", custom_id),
+                line_no,
+            ));
+            continue;
+        }
+
+        let method = item
+            .get("method")
+            .and_then(|m| m.as_str())
+            .unwrap_or("POST");
+        if !method.eq_ignore_ascii_case("POST") {
+            errors.push(line_error(
+                "invalid_method",
+                format!("Only POST is supported, got '{}'.", method),
+                line_no,
+            ));
+            continue;
+        }
+
+        let url = item.get("url").and_then(|u| u.as_str()).unwrap_or("");
+        if url != endpoint {
+            errors.push(line_error(
+                "mismatched_endpoint",
+                format!(
+                    "The url '{}' does not match the batch endpoint '{}'.",
+                    url, endpoint
+                ),
+                line_no,
+            ));
+            continue;
+        }
+
+        match item.get("body") {
+            Some(body @ Value::Object(_)) => requests.push((custom_id.to_string(), body.clone())),
+            _ => errors.push(line_error(
+                "invalid_request",
+                "The 'body' field must be a JSON object.".to_string(),
+                line_no,
+            )),
+        }
+    }
+
+    if requests.is_empty() && errors.is_empty() {
+        errors.push(line_error(
+            "empty_file",
+            "The input file contains no requests.".to_string(),
+            0,
+        ));
+    }
+    if requests.len() > MAX_REQUESTS_PER_BATCH {
+        errors.push(line_error(
+            "too_many_requests",
+            format!(
+                "The input file contains {} requests; the limit is {}.",
+                requests.len(),
+                MAX_REQUESTS_PER_BATCH
+            ),
+            0,
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(requests)
+    } else {
+        Err(errors)
+    }
+}
+
+/// POST /v1/batches
+pub async fn handle_create_batch(caller: Caller, Json(req): Json<CreateBatchRequest>) -> Response {
+    if !OPENAI_BATCH_ENDPOINTS.contains(&req.endpoint.as_str()) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Invalid endpoint '{}'. Supported: {}",
+                req.endpoint,
+                OPENAI_BATCH_ENDPOINTS.join(", ")
+            ),
+            Some("endpoint"),
+            "invalid_value",
+        );
+    }
+    if req.completion_window != COMPLETION_WINDOW {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Only completion_window '24h' is supported.",
+            Some("completion_window"),
+            "invalid_value",
+        );
+    }
+
+    let file_id = req.input_file_id.clone();
+    let owner = owner_of(&caller);
+    let (file, content) = match db(move || {
+        Ok((
+            batch_db::get_file(&file_id, owner.as_deref())?,
+            batch_db::get_file_content(&file_id, owner.as_deref())?,
+        ))
+    })
+    .await
+    {
+        Ok((Some(file), Some(content))) => (file, content),
+        Ok(_) => return file_not_found(&req.input_file_id),
+        Err(resp) => return resp,
+    };
+    if file.purpose != "batch" {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "File {} has purpose '{}', expected 'batch'.",
+                file.id, file.purpose
+            ),
+            Some("input_file_id"),
+            "invalid_value",
+        );
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let mut batch = BatchRecord {
+        id: format!("batch_{}", uuid::Uuid::new_v4().simple()),
+        kind: KIND_OPENAI.to_string(),
+        endpoint: req.endpoint.clone(),
+        input_file_id: file.id.clone(),
+        completion_window: req.completion_window.clone(),
+        metadata: req.metadata.clone(),
+        created_at: now,
+        expires_at: Some(now + COMPLETION_WINDOW_SECS),
+        owner_token_id: owner_of(&caller),
+        owner_ip: caller
+            .as_ref()
+            .map(|Extension(identity)| identity.client_ip.clone()),
+        ..Default::default()
+    };
+
+    let requests = match parse_openai_input(&String::from_utf8_lossy(&content), &req.endpoint) {
+        Ok(requests) => {
+            batch.status = "in_progress".to_string();
+            batch.in_progress_at = Some(now);
+            requests
+        }
+        Err(errors) => {
+            // 与 OpenAI 一致: 校验失败时任务仍被创建，状态为 failed
+            batch.status = "failed".to_string();
+            batch.failed_at = Some(now);
+            batch.errors = Some(json!({ "object": "list", "data": errors }));
+            Vec::new()
+        }
+    };
+
+    let record = batch.clone();
+    let count = requests.len();
+    if let Err(resp) = db(move || batch_db::create_batch(&record, &requests)).await {
+        return resp;
+    }
+    tracing::info!(
+        "[Batch] Created {} ({} requests, endpoint={}, status={})",
+        batch.id,
+        count,
+        batch.endpoint,
+        batch.status
+    );
+    batch_worker::wake();
+
+    let counts = RequestCounts {
+        total: count as i64,
+        pending: count as i64,
+        ..Default::default()
+    };
+    Json(batch_to_json(&batch, &counts)).into_response()
+}
+
+async fn load_batch(
+    id: String,
+    owner: Option<String>,
+) -> Result<Option<(BatchRecord, RequestCounts)>, Response> {
+    db(move || match batch_db::get_batch(&id, owner.as_deref())? {
+        Some(batch) if batch.kind == KIND_OPENAI => {
+            let counts = batch_db::request_counts(&batch.id)?;
+            Ok(Some((batch, counts)))
+        }
+        _ => Ok(None),
+    })
+    .await
+}
+
+/// GET /v1/batches/:batch_id
+pub async fn handle_get_batch(caller: Caller, Path(id): Path<String>) -> Response {
+    match load_batch(id.clone(), owner_of(&caller)).await {
+        Ok(Some((batch, counts))) => Json(batch_to_json(&batch, &counts)).into_response(),
+        Ok(None) => batch_not_found(&id),
+        Err(resp) => resp,
+    }
+}
+
+/// POST /v1/batches/:batch_id/cancel
+pub async fn handle_cancel_batch(caller: Caller, Path(id): Path<String>) -> Response {
+    let (mut batch, counts) = match load_batch(id.clone(), owner_of(&caller)).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return batch_not_found(&id),
+        Err(resp) => return resp,
+    };
+
+    if batch.status != "in_progress" {
+        if batch.status == "cancelling" || batch.status == "cancelled" {
+            return Json(batch_to_json(&batch, &counts)).into_response();
+        }
+        return error_response(
+            StatusCode::CONFLICT,
+            &format!("Cannot cancel a batch with status '{}'.", batch.status),
+            None,
+            "batch_not_cancellable",
+        );
+    }
+
+    batch.status = "cancelling".to_string();
+    batch.cancelling_at = Some(chrono::Utc::now().timestamp());
+    let record = batch.clone();
+    if let Err(resp) = db(move || batch_db::update_batch(&record)).await {
+        return resp;
+    }
+    batch_worker::wake();
+
+    Json(batch_to_json(&batch, &counts)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListBatchesQuery {
+    after: Option<String>,
+    limit: Option<usize>,
+}
+
+/// GET /v1/batches
+pub async fn handle_list_batches(
+    caller: Caller,
+    Query(query): Query<ListBatchesQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let owner = owner_of(&caller);
+    let result = db(move || {
+        // 多取一条用于判断 has_more
+        let mut batches = batch_db::list_batches(
+            KIND_OPENAI,
+            owner.as_deref(),
+            query.after.as_deref(),
+            limit + 1,
+        )?;
+        let has_more = batches.len() > limit;
+        batches.truncate(limit);
+        let data = batches
+            .iter()
+            .map(|b| Ok(batch_to_json(b, &batch_db::request_counts(&b.id)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((data, has_more))
+    })
+    .await;
+
+    match result {
+        Ok((data, has_more)) => Json(json!({
+            "object": "list",
+            "first_id": data.first().map(|b| b["id"].clone()),
+            "last_id": data.last().map(|b| b["id"].clone()),
+            "has_more": has_more,
+            "data": data,
+        }))
+        .into_response(),
+        Err(resp) => resp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_openai_input() {
+        let content = r#"
+{"custom_id": "a", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gemini-2.5-flash", "messages": []}}
+{"custom_id": "b", "method": "POST", "url": "/v1/chat/completions", "body": {"model": "gemini-2.5-flash", "messages": []}}
+"#;
+        let parsed = parse_openai_input(content, "/v1/chat/completions").unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].0, "b");
+
+        let bad = r#"{"custom_id": "a", "url": "/v1/chat/completions", "body": {}}
+not json
+{"custom_id": "a", "url": "/v1/chat/completions", "body": {}}
+{"custom_id": "c", "url": "/v1/embeddings", "body": {}}"#;
+        let errors = parse_openai_input(bad, "/v1/chat/completions").unwrap_err();
+        let codes: Vec<&str> = errors.iter().map(|e| e["code"].as_str().unwrap()).collect();
+        assert_eq!(
+            codes,
+            vec![
+                "invalid_json_line",
+                "duplicate_custom_id",
+                "mismatched_endpoint"
+            ]
+        );
+        assert_eq!(errors[0]["line"], 2);
+
+        let empty = parse_openai_input("\n\n", "/v1/chat/completions").unwrap_err();
+        assert_eq!(empty[0]["code"], "empty_file");
+    }
+}
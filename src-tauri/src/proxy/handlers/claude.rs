@@ -105,7 +105,8 @@ The structure MUST be as follows:
 // ===== 统一退避策略模块 =====
 // 移除本地重复定义，使用 common 中的统一实现
 use super::common::{
-    apply_retry_strategy, determine_retry_strategy, should_rotate_account, RetryStrategy,
+    apply_retry_strategy, determine_retry_strategy, get_token_with_fallback, should_rotate_account,
+    with_fallback_header, RetryStrategy,
 };
 
 // ===== 退避策略模块结束 =====
@@ -299,6 +300,31 @@ pub async fn handle_messages(
         .await;
     }
 
+    // [NEW] 通用上游提供商 (OpenAI / Anthropic 兼容)
+    if let Some(route) =
+        crate::proxy::providers::registry::select_provider_route(&state, &request.model, "claude")
+            .await
+    {
+        let new_body = match serde_json::to_value(&request) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::error!("Failed to serialize request for provider: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+
+        return crate::proxy::providers::generic::forward(
+            &state,
+            &route,
+            crate::proxy::providers::generic::ClientProtocol::Anthropic,
+            &new_body,
+            request.stream,
+            &request.model,
+            &headers,
+        )
+        .await;
+    }
+
     // Google Flow 继续使用 request 对象
     // (后续代码不需要再次 filter_invalid_thinking_blocks)
 
@@ -461,7 +487,7 @@ pub async fn handle_messages(
                 .collect()
         });
 
-        let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        let mut config = crate::proxy::mappers::common_utils::resolve_request_config(
             &request_for_body.model,
             &mapped_model,
             &tools_val,
@@ -477,37 +503,55 @@ pub async fn handle_messages(
         let session_id = Some(session_id_str.as_str());
 
         let force_rotate_token = attempt > 0;
-        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-            .get_token(
+        let ((access_token, project_id, email, account_id, _wait_ms), fallback_model) =
+            match get_token_with_fallback(
+                &token_manager,
+                &state.custom_mapping,
                 &config.request_type,
                 force_rotate_token,
                 session_id,
+                &request_for_body.model,
+                &mapped_model,
                 &config.final_model,
             )
             .await
-        {
-            Ok(t) => t,
-            Err(e) => {
-                let safe_message = if e.contains("invalid_grant") {
-                    "OAuth refresh failed (invalid_grant): refresh_token likely revoked/expired; reauthorize account(s) to restore service.".to_string()
-                } else {
-                    e
-                };
-                let headers = [("X-Mapped-Model", mapped_model.as_str())];
-                return (
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    headers,
-                    Json(json!({
-                        "type": "error",
-                        "error": {
-                            "type": "overloaded_error",
-                            "message": format!("No available accounts: {}", safe_message)
-                        }
-                    })),
-                )
-                    .into_response();
-            }
-        };
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    let safe_message = if e.contains("invalid_grant") {
+                        "OAuth refresh failed (invalid_grant): refresh_token likely revoked/expired; reauthorize account(s) to restore service.".to_string()
+                    } else {
+                        e
+                    };
+                    let headers = [("X-Mapped-Model", mapped_model.as_str())];
+                    return (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        headers,
+                        Json(json!({
+                            "type": "error",
+                            "error": {
+                                "type": "overloaded_error",
+                                "message": format!("No available accounts: {}", safe_message)
+                            }
+                        })),
+                    )
+                        .into_response();
+                }
+            };
+
+        // [NEW] 模型降级：原模型账号耗尽，切换到降级链中的模型并重新解析请求配置
+        if let Some(fallback) = &fallback_model {
+            mapped_model = fallback.clone();
+            last_mapped_model = Some(mapped_model.clone());
+            config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &request_for_body.model,
+                &mapped_model,
+                &tools_val,
+                request.size.as_deref(),
+                request.quality.as_deref(),
+                None,
+            );
+        }
 
         last_email = Some(email.clone());
         info!("✓ Using account: {} (type: {})", email, config.request_type);
@@ -944,6 +988,11 @@ pub async fn handle_messages(
                     Some(raw_estimated), // [FIX] Pass estimated tokens for calibrator learning
                     current_message_count, // [NEW v4.0.0] Pass message count for rewind detection
                     client_adapter.clone(), // [NEW] Pass client adapter
+                    request_with_mapped
+                        .stop_sequences
+                        .clone()
+                        .unwrap_or_default(),
+                    request_with_mapped.single_tool_use(),
                 );
 
                 let mut first_data_chunk = None;
@@ -1028,20 +1077,23 @@ pub async fn handle_messages(
                         // 判断客户端期望的格式
                         if client_wants_stream {
                             // 客户端本就要 Stream，直接返回 SSE
-                            return Response::builder()
-                                .status(StatusCode::OK)
-                                .header(header::CONTENT_TYPE, "text/event-stream")
-                                .header(header::CACHE_CONTROL, "no-cache")
-                                .header(header::CONNECTION, "keep-alive")
-                                .header("X-Accel-Buffering", "no")
-                                .header("X-Account-Email", &email)
-                                .header("X-Mapped-Model", &request_with_mapped.model)
-                                .header(
-                                    "X-Context-Purified",
-                                    if is_purified { "true" } else { "false" },
-                                )
-                                .body(Body::from_stream(combined_stream))
-                                .unwrap();
+                            return with_fallback_header(
+                                Response::builder()
+                                    .status(StatusCode::OK)
+                                    .header(header::CONTENT_TYPE, "text/event-stream")
+                                    .header(header::CACHE_CONTROL, "no-cache")
+                                    .header(header::CONNECTION, "keep-alive")
+                                    .header("X-Accel-Buffering", "no")
+                                    .header("X-Account-Email", &email)
+                                    .header("X-Mapped-Model", &request_with_mapped.model)
+                                    .header(
+                                        "X-Context-Purified",
+                                        if is_purified { "true" } else { "false" },
+                                    )
+                                    .body(Body::from_stream(combined_stream))
+                                    .unwrap(),
+                                fallback_model.as_deref(),
+                            );
                         } else {
                             // 客户端要非 Stream，需要收集完整响应并转换为 JSON
                             use crate::proxy::mappers::claude::collect_stream_to_json;
@@ -1052,19 +1104,22 @@ pub async fn handle_messages(
                                         "[{}] ✓ Stream collected and converted to JSON",
                                         trace_id
                                     );
-                                    return Response::builder()
-                                        .status(StatusCode::OK)
-                                        .header(header::CONTENT_TYPE, "application/json")
-                                        .header("X-Account-Email", &email)
-                                        .header("X-Mapped-Model", &request_with_mapped.model)
-                                        .header(
-                                            "X-Context-Purified",
-                                            if is_purified { "true" } else { "false" },
-                                        )
-                                        .body(Body::from(
-                                            serde_json::to_string(&full_response).unwrap(),
-                                        ))
-                                        .unwrap();
+                                    return with_fallback_header(
+                                        Response::builder()
+                                            .status(StatusCode::OK)
+                                            .header(header::CONTENT_TYPE, "application/json")
+                                            .header("X-Account-Email", &email)
+                                            .header("X-Mapped-Model", &request_with_mapped.model)
+                                            .header(
+                                                "X-Context-Purified",
+                                                if is_purified { "true" } else { "false" },
+                                            )
+                                            .body(Body::from(
+                                                serde_json::to_string(&full_response).unwrap(),
+                                            ))
+                                            .unwrap(),
+                                        fallback_model.as_deref(),
+                                    );
                                 }
                                 Err(e) => {
                                     return (
@@ -1138,7 +1193,7 @@ pub async fn handle_messages(
                 // [FIX #765] Pass session_id and model_name for signature caching
                 let s_id_owned = session_id.map(|s| s.to_string());
                 // 转换
-                let claude_response = match transform_response(
+                let mut claude_response = match transform_response(
                     &gemini_response,
                     scaling_enabled,
                     context_limit,
@@ -1156,6 +1211,19 @@ pub async fn handle_messages(
                     }
                 };
 
+                // [NEW] 客户端 stop_sequences 命中检测与截断
+                if let Some(stop_sequences) = &request_with_mapped.stop_sequences {
+                    crate::proxy::mappers::claude::response::apply_stop_sequences(
+                        &mut claude_response,
+                        stop_sequences,
+                    );
+                }
+                if request_with_mapped.single_tool_use() {
+                    crate::proxy::mappers::claude::response::keep_first_tool_use(
+                        &mut claude_response,
+                    );
+                }
+
                 // [Optimization] 记录闭环日志：消耗情况
                 let cache_info = if let Some(cached) = claude_response.usage.cache_read_input_tokens
                 {
@@ -1173,15 +1241,18 @@ pub async fn handle_messages(
                     cache_info
                 );
 
-                return (
-                    StatusCode::OK,
-                    [
-                        ("X-Account-Email", email.as_str()),
-                        ("X-Mapped-Model", request_with_mapped.model.as_str()),
-                    ],
-                    Json(claude_response),
-                )
-                    .into_response();
+                return with_fallback_header(
+                    (
+                        StatusCode::OK,
+                        [
+                            ("X-Account-Email", email.as_str()),
+                            ("X-Mapped-Model", request_with_mapped.model.as_str()),
+                        ],
+                        Json(claude_response),
+                    )
+                        .into_response(),
+                    fallback_model.as_deref(),
+                );
             }
         }
 
@@ -1562,11 +1633,83 @@ pub async fn handle_count_tokens(
         .await;
     }
 
-    Json(json!({
-        "input_tokens": 0,
-        "output_tokens": 0
-    }))
-    .into_response()
+    let mut request: ClaudeRequest = match serde_json::from_value(body) {
+        Ok(r) => r,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "type": "error",
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": format!("Invalid request body: {}", e)
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    // Thinking budget 属于输出预留，不计入输入 token
+    let mut estimate_request = request.clone();
+    estimate_request.thinking = None;
+    let raw_estimate = ContextManager::estimate_token_usage(&estimate_request);
+
+    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        &request.model,
+        &*state.custom_mapping.read().await,
+    );
+    let tools_val: Option<Vec<Value>> = request.tools.as_ref().map(|list| {
+        list.iter()
+            .map(|t| serde_json::to_value(t).unwrap_or(json!({})))
+            .collect()
+    });
+    let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        &request.model,
+        &mapped_model,
+        &tools_val,
+        None,
+        None,
+        None,
+    );
+
+    // 复用 messages 的转换逻辑，保证计数对象与真实发送的 Gemini 请求一致
+    request.model = mapped_model.clone();
+    let inner_request = match transform_claude_request_in(&request, "", false) {
+        Ok(mut b) => b.get_mut("request").map(Value::take).unwrap_or(Value::Null),
+        Err(e) => {
+            debug!(
+                "[CountTokens] Transform failed, using estimation only: {}",
+                e
+            );
+            Value::Null
+        }
+    };
+
+    let result = if inner_request.is_null() {
+        super::common::TokenCountResult {
+            tokens: get_calibrator().calibrate(raw_estimate),
+            estimated: true,
+        }
+    } else {
+        super::common::count_input_tokens(
+            &state,
+            &config.request_type,
+            &config.final_model,
+            &inner_request,
+            raw_estimate,
+        )
+        .await
+    };
+
+    (
+        [("X-Mapped-Model", mapped_model.as_str())],
+        Json(json!({
+            "input_tokens": result.tokens,
+            "estimated": result.estimated
+        })),
+    )
+        .into_response()
 }
 
 // 移除已失效的简单单元测试，后续将补全完整的集成测试
@@ -1994,6 +2137,8 @@ async fn try_compress_with_summary(
         output_config: None,
         size: None,
         quality: None,
+        tool_choice: None,
+        stop_sequences: None,
     };
 
     debug!(
@@ -2067,5 +2212,7 @@ async fn try_compress_with_summary(
         output_config: original_request.output_config.clone(),
         size: original_request.size.clone(),
         quality: original_request.quality.clone(),
+        tool_choice: original_request.tool_choice.clone(),
+        stop_sequences: original_request.stop_sequences.clone(),
     })
 }
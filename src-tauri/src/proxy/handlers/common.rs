@@ -1,4 +1,5 @@
 use crate::proxy::server::AppState;
+use crate::proxy::TokenError;
 use axum::{
     extract::State,
     http::StatusCode,
@@ -153,6 +154,197 @@ pub fn should_rotate_account(status_code: u16) -> bool {
     }
 }
 
+// ===== 模型降级链 (Model Fallback) =====
+
+/// 发生模型降级时附加的响应头，值为实际使用的降级模型
+pub const FALLBACK_MODEL_HEADER: &str = "x-antigravity-fallback-model";
+
+/// 为降级模型重新计算配额分组
+///
+/// 联网意图来自原始请求 (`-online` 后缀 / 联网工具)，降级后保持不变；
+/// 其余情况按降级模型本身重新解析 (如降级到图像模型时应使用 `image_gen` 分组)。
+fn fallback_quota_group(quota_group: &str, original_model: &str, fallback_model: &str) -> String {
+    if quota_group == "web_search" {
+        return quota_group.to_string();
+    }
+    crate::proxy::mappers::common_utils::resolve_request_config(
+        original_model,
+        fallback_model,
+        &None,
+        None,
+        None,
+        None,
+    )
+    .request_type
+}
+
+/// 获取账号 Token，当前模型所有账号耗尽时沿 `model_fallbacks` 降级链依次尝试
+///
+/// 返回 Token 元组及实际使用的降级模型 (已经过 `resolve_model_route` 路由)；
+/// 未发生降级时第二项为 `None`。所有候选均失败时返回原始错误。
+#[allow(clippy::too_many_arguments)]
+pub async fn get_token_with_fallback(
+    token_manager: &crate::proxy::TokenManager,
+    custom_mapping: &tokio::sync::RwLock<std::collections::HashMap<String, String>>,
+    quota_group: &str,
+    force_rotate: bool,
+    session_id: Option<&str>,
+    original_model: &str,
+    mapped_model: &str,
+    target_model: &str,
+) -> Result<((String, String, String, String, u64), Option<String>), String> {
+    let primary_err = match token_manager
+        .get_token(quota_group, force_rotate, session_id, target_model)
+        .await
+    {
+        Ok(token) => return Ok((token, None)),
+        Err(TokenError::ModelExhausted(e)) => e,
+        Err(e) => return Err(e.into()),
+    };
+
+    let chain = crate::proxy::common::model_mapping::resolve_fallback_chain(
+        mapped_model,
+        original_model,
+        &crate::proxy::config::get_model_fallbacks(),
+    );
+    if chain.is_empty() {
+        return Err(primary_err);
+    }
+
+    let custom_mapping = custom_mapping.read().await.clone();
+    for candidate in chain {
+        let fallback_model =
+            crate::proxy::common::model_mapping::resolve_model_route(&candidate, &custom_mapping);
+        if fallback_model == mapped_model {
+            continue;
+        }
+        let fallback_group = fallback_quota_group(quota_group, original_model, &fallback_model);
+        match token_manager
+            .get_token(&fallback_group, force_rotate, session_id, &fallback_model)
+            .await
+        {
+            Ok(token) => {
+                info!(
+                    "[Model-Fallback] {} exhausted ({}), falling back to {}",
+                    mapped_model, primary_err, fallback_model
+                );
+                return Ok((token, Some(fallback_model)));
+            }
+            Err(e) => {
+                debug!(
+                    "[Model-Fallback] Candidate {} unavailable: {}",
+                    fallback_model, e
+                );
+            }
+        }
+    }
+
+    Err(primary_err)
+}
+
+/// 发生模型降级时为响应附加 `x-antigravity-fallback-model` 头
+pub fn with_fallback_header(mut response: Response, fallback_model: Option<&str>) -> Response {
+    if let Some(v) = fallback_model.and_then(|m| axum::http::HeaderValue::from_str(m).ok()) {
+        response.headers_mut().insert(FALLBACK_MODEL_HEADER, v);
+    }
+    response
+}
+
+// ===== Token 计数 (count_tokens / countTokens) =====
+
+/// Token 计数结果
+#[derive(Debug, Clone, Copy)]
+pub struct TokenCountResult {
+    pub tokens: u32,
+    /// true 表示上游不可用，数值来自校准后的本地估算
+    pub estimated: bool,
+}
+
+/// 将 Gemini 请求体折叠为 countTokens 可接受的 contents 数组
+///
+/// v1internal countTokens 只接受 contents，因此把 systemInstruction 与工具声明
+/// 作为前置 user 内容一并计入，使结果与真实请求的输入 token 数保持一致。
+fn build_count_tokens_contents(inner_request: &Value) -> Vec<Value> {
+    let mut contents = Vec::new();
+
+    let mut preamble_parts: Vec<Value> = inner_request
+        .get("systemInstruction")
+        .and_then(|s| s.get("parts"))
+        .and_then(|p| p.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if let Some(tools) = inner_request.get("tools") {
+        if let Ok(tools_str) = serde_json::to_string(tools) {
+            preamble_parts.push(json!({ "text": tools_str }));
+        }
+    }
+    if !preamble_parts.is_empty() {
+        contents.push(json!({ "role": "user", "parts": preamble_parts }));
+    }
+
+    if let Some(arr) = inner_request.get("contents").and_then(|c| c.as_array()) {
+        contents.extend(arr.iter().cloned());
+    }
+    contents
+}
+
+/// 统计输入 token 数：优先调用上游 countTokens，失败时回退到校准后的估算值
+///
+/// `raw_estimate` 为 ContextManager 的原始估算值；上游成功时会用于校准器学习。
+pub async fn count_input_tokens(
+    state: &AppState,
+    request_type: &str,
+    mapped_model: &str,
+    inner_request: &Value,
+    raw_estimate: u32,
+) -> TokenCountResult {
+    let calibrator = crate::proxy::mappers::estimation_calibrator::get_calibrator();
+
+    if state.token_manager.len() > 0 {
+        match state
+            .token_manager
+            .get_token(request_type, false, None, mapped_model)
+            .await
+        {
+            Ok((access_token, _project_id, email, account_id, _wait_ms)) => {
+                let contents = build_count_tokens_contents(inner_request);
+                match state
+                    .upstream
+                    .count_tokens(&access_token, mapped_model, contents, Some(&account_id))
+                    .await
+                {
+                    Ok(tokens) => {
+                        calibrator.record(raw_estimate, tokens);
+                        return TokenCountResult {
+                            tokens,
+                            estimated: false,
+                        };
+                    }
+                    Err(e) => {
+                        debug!(
+                            "[CountTokens] Upstream count failed for {} ({}): {}, falling back to estimation",
+                            mapped_model,
+                            crate::proxy::upstream::client::mask_email(&email),
+                            e
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                debug!(
+                    "[CountTokens] No account available for {}: {}, falling back to estimation",
+                    mapped_model, e
+                );
+            }
+        }
+    }
+
+    TokenCountResult {
+        tokens: calibrator.calibrate(raw_estimate),
+        estimated: true,
+    }
+}
+
 /// Detects model capabilities and configuration
 /// POST /v1/models/detect
 pub async fn handle_detect_model(
@@ -0,0 +1,413 @@
+// Embeddings Handler
+// POST /v1/embeddings (OpenAI) 以及 /v1beta/models/:model:embedContent / :batchEmbedContents (Gemini 原生)
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use base64::Engine as _;
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+use super::common::{apply_retry_strategy, determine_retry_strategy};
+use crate::proxy::mappers::context_manager::ContextManager;
+use crate::proxy::server::AppState;
+
+const MAX_RETRY_ATTEMPTS: usize = 3;
+
+/// OpenAI 向量模型名未配置映射时使用的默认 Gemini 向量模型
+const DEFAULT_EMBEDDING_MODEL: &str = "gemini-embedding-001";
+
+/// 上游批量向量化结果
+struct EmbedOutcome {
+    /// Gemini ContentEmbedding 对象数组 ({"values": [...]})
+    embeddings: Vec<Value>,
+    email: String,
+    prompt_tokens: u32,
+}
+
+/// 解析向量模型路由：OpenAI 模型名 (text-embedding-*) 未被自定义映射时回退到默认 Gemini 向量模型
+async fn resolve_embedding_model(state: &AppState, requested_model: &str) -> String {
+    let mapped = crate::proxy::common::model_mapping::resolve_model_route(
+        requested_model,
+        &*state.custom_mapping.read().await,
+    );
+    if mapped.starts_with("text-embedding-") {
+        DEFAULT_EMBEDDING_MODEL.to_string()
+    } else {
+        mapped
+    }
+}
+
+/// 将 OpenAI `input` 解析为文本列表 (仅支持字符串或字符串数组)
+fn openai_input_to_texts(input: Option<&Value>) -> Result<Vec<String>, String> {
+    match input {
+        Some(Value::String(s)) => Ok(vec![s.clone()]),
+        Some(Value::Array(arr)) if !arr.is_empty() => arr
+            .iter()
+            .map(|v| {
+                v.as_str().map(|s| s.to_string()).ok_or_else(|| {
+                    "Token array input is not supported, please send text".to_string()
+                })
+            })
+            .collect(),
+        _ => Err("'input' must be a non-empty string or array of strings".to_string()),
+    }
+}
+
+/// 指定 dimensions 时上游返回的截断向量未归一化，这里做 L2 归一化以对齐 OpenAI 语义
+fn normalize_vector(values: &mut [f64]) {
+    let norm = values.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        values.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+/// 按 encoding_format 输出向量：base64 为 little-endian f32 字节序列
+fn encode_embedding(values: &[f64], base64: bool) -> Value {
+    if base64 {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| (*v as f32).to_le_bytes())
+            .collect();
+        json!(base64::engine::general_purpose::STANDARD.encode(bytes))
+    } else {
+        json!(values)
+    }
+}
+
+/// 调用上游 batchEmbedContents，复用账号轮换与重试策略
+async fn execute_batch_embed(
+    state: &AppState,
+    requested_model: &str,
+    mapped_model: &str,
+    mut requests: Vec<Value>,
+) -> Result<EmbedOutcome, Response> {
+    // 与其他处理器一致，由模型路由解析配额分组
+    let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        requested_model,
+        mapped_model,
+        &None,
+        None,
+        None,
+        None,
+    );
+
+    for req in requests.iter_mut() {
+        req["model"] = json!(format!("models/{}", mapped_model));
+    }
+
+    // 上游不返回 token 用量，使用校准后的本地估算
+    let contents: Vec<Value> = requests
+        .iter()
+        .filter_map(|r| r.get("content").cloned())
+        .collect();
+    let raw_estimate =
+        ContextManager::estimate_gemini_token_usage(&json!({ "contents": contents }));
+    let prompt_tokens =
+        crate::proxy::mappers::estimation_calibrator::get_calibrator().calibrate(raw_estimate);
+
+    let token_manager = state.token_manager.clone();
+    let max_attempts = MAX_RETRY_ATTEMPTS
+        .min(token_manager.len().saturating_add(1))
+        .max(2);
+    let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
+    let mut last_error = String::new();
+
+    for attempt in 0..max_attempts {
+        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
+            .get_token(&config.request_type, attempt > 0, None, mapped_model)
+            .await
+        {
+            Ok(t) => t,
+            Err(e) => {
+                return Err((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [("X-Mapped-Model", mapped_model.to_string())],
+                    format!("Token error: {}", e),
+                )
+                    .into_response());
+            }
+        };
+        info!("✓ Using account: {} (type: embedding)", email);
+
+        let body = json!({
+            "project": project_id,
+            "requestId": format!("embed-{}", uuid::Uuid::new_v4()),
+            "model": mapped_model,
+            "request": { "requests": requests.clone() },
+        });
+
+        let response = match state
+            .upstream
+            .call_v1_internal(
+                "batchEmbedContents",
+                &access_token,
+                body,
+                None,
+                Some(account_id.as_str()),
+            )
+            .await
+        {
+            Ok(r) => r.response,
+            Err(e) => {
+                debug!(
+                    "[Embeddings] Request failed on attempt {}/{}: {}",
+                    attempt + 1,
+                    max_attempts,
+                    e
+                );
+                last_error = e;
+                continue;
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            token_manager.mark_account_success(&email);
+            let json: Value = response.json().await.map_err(|e| {
+                (
+                    StatusCode::BAD_GATEWAY,
+                    [("X-Mapped-Model", mapped_model.to_string())],
+                    format!("Parse error: {}", e),
+                )
+                    .into_response()
+            })?;
+            // v1internal 可能包裹一层 response
+            let data = json.get("response").unwrap_or(&json);
+            let embeddings = data
+                .get("embeddings")
+                .and_then(|e| e.as_array())
+                .cloned()
+                .unwrap_or_default();
+
+            let (usage_email, usage_model) = (email.clone(), mapped_model.to_string());
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = crate::modules::token_stats::record_usage(
+                    &usage_email,
+                    &usage_model,
+                    prompt_tokens,
+                    0,
+                ) {
+                    debug!("Failed to record embedding token stats: {}", e);
+                }
+            });
+
+            return Ok(EmbedOutcome {
+                embeddings,
+                email,
+                prompt_tokens,
+            });
+        }
+
+        let status_code = status.as_u16();
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| format!("HTTP {}", status_code));
+        last_error = format!("HTTP {}: {}", status_code, error_text);
+        tracing::error!(
+            "[Embeddings-Upstream] Error Response {}: {}",
+            status_code,
+            error_text
+        );
+
+        if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 {
+            token_manager
+                .mark_rate_limited_async(
+                    &email,
+                    status_code,
+                    retry_after.as_deref(),
+                    &error_text,
+                    Some(mapped_model),
+                )
+                .await;
+        }
+
+        let strategy = determine_retry_strategy(status_code, &error_text, false);
+        if !apply_retry_strategy(strategy, attempt, max_attempts, status_code, &trace_id).await {
+            return Err((
+                status,
+                [
+                    ("X-Account-Email", email),
+                    ("X-Mapped-Model", mapped_model.to_string()),
+                ],
+                error_text,
+            )
+                .into_response());
+        }
+    }
+
+    Err((
+        StatusCode::TOO_MANY_REQUESTS,
+        [("X-Mapped-Model", mapped_model.to_string())],
+        format!("All accounts exhausted. Last error: {}", last_error),
+    )
+        .into_response())
+}
+
+/// POST /v1/embeddings
+pub async fn handle_embeddings(State(state): State<AppState>, Json(body): Json<Value>) -> Response {
+    let requested_model = body
+        .get("model")
+        .and_then(|v| v.as_str())
+        .unwrap_or(DEFAULT_EMBEDDING_MODEL)
+        .to_string();
+    let texts = match openai_input_to_texts(body.get("input")) {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let dimensions = body.get("dimensions").and_then(|v| v.as_u64());
+    let use_base64 = body.get("encoding_format").and_then(|v| v.as_str()) == Some("base64");
+
+    let mapped_model = resolve_embedding_model(&state, &requested_model).await;
+    debug!(
+        "[Embeddings] {} inputs, model {} -> {}",
+        texts.len(),
+        requested_model,
+        mapped_model
+    );
+
+    let requests: Vec<Value> = texts
+        .iter()
+        .map(|text| {
+            let mut req = json!({ "content": { "parts": [{ "text": text }] } });
+            if let Some(dim) = dimensions {
+                req["outputDimensionality"] = json!(dim);
+            }
+            req
+        })
+        .collect();
+
+    let outcome = match execute_batch_embed(&state, &requested_model, &mapped_model, requests).await
+    {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+
+    let data: Vec<Value> = outcome
+        .embeddings
+        .iter()
+        .enumerate()
+        .map(|(index, emb)| {
+            let mut values: Vec<f64> = emb
+                .get("values")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().filter_map(|x| x.as_f64()).collect())
+                .unwrap_or_default();
+            if dimensions.is_some() {
+                normalize_vector(&mut values);
+            }
+            json!({
+                "object": "embedding",
+                "index": index,
+                "embedding": encode_embedding(&values, use_base64),
+            })
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        [
+            ("X-Account-Email", outcome.email.as_str()),
+            ("X-Mapped-Model", mapped_model.as_str()),
+        ],
+        Json(json!({
+            "object": "list",
+            "data": data,
+            "model": requested_model,
+            "usage": {
+                "prompt_tokens": outcome.prompt_tokens,
+                "total_tokens": outcome.prompt_tokens,
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// Gemini 原生 embedContent / batchEmbedContents
+///
+/// 由 `gemini::handle_generate` 根据 `model:method` 中的 method 分发进来。
+pub async fn handle_gemini_embed(
+    state: AppState,
+    model_name: String,
+    method: &str,
+    body: Value,
+) -> Response {
+    let mapped_model = resolve_embedding_model(&state, &model_name).await;
+
+    let requests = if method == "embedContent" {
+        vec![body]
+    } else {
+        match body.get("requests").and_then(|r| r.as_array()) {
+            Some(arr) if !arr.is_empty() => arr.clone(),
+            _ => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    "'requests' must be a non-empty array",
+                )
+                    .into_response()
+            }
+        }
+    };
+
+    let outcome = match execute_batch_embed(&state, &model_name, &mapped_model, requests).await {
+        Ok(o) => o,
+        Err(resp) => return resp,
+    };
+
+    let payload = if method == "embedContent" {
+        json!({ "embedding": outcome.embeddings.into_iter().next().unwrap_or(json!({ "values": [] })) })
+    } else {
+        json!({ "embeddings": outcome.embeddings })
+    };
+
+    (
+        StatusCode::OK,
+        [
+            ("X-Account-Email", outcome.email.as_str()),
+            ("X-Mapped-Model", mapped_model.as_str()),
+        ],
+        Json(payload),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_input_to_texts() {
+        assert_eq!(
+            openai_input_to_texts(Some(&json!("hello"))).unwrap(),
+            vec!["hello"]
+        );
+        assert_eq!(
+            openai_input_to_texts(Some(&json!(["a", "b"]))).unwrap(),
+            vec!["a", "b"]
+        );
+        assert!(openai_input_to_texts(Some(&json!([1, 2, 3]))).is_err());
+        assert!(openai_input_to_texts(Some(&json!([]))).is_err());
+        assert!(openai_input_to_texts(None).is_err());
+    }
+
+    #[test]
+    fn test_encode_embedding_base64() {
+        let encoded = encode_embedding(&[1.0, -2.0], true);
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded.as_str().unwrap())
+            .unwrap();
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(f32::from_le_bytes(bytes[4..8].try_into().unwrap()), -2.0);
+
+        let mut values = vec![3.0, 4.0];
+        normalize_vector(&mut values);
+        assert_eq!(values, vec![0.6, 0.8]);
+    }
+}
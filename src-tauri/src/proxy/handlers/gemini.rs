@@ -11,7 +11,8 @@ use tracing::{debug, error, info};
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS;
 use crate::proxy::debug_logger;
 use crate::proxy::handlers::common::{
-    apply_retry_strategy, determine_retry_strategy, should_rotate_account,
+    apply_retry_strategy, determine_retry_strategy, get_token_with_fallback, should_rotate_account,
+    with_fallback_header,
 };
 use crate::proxy::mappers::gemini::{unwrap_response, wrap_request};
 use crate::proxy::server::AppState;
@@ -52,6 +53,11 @@ pub async fn handle_generate(
         debug!("[{}] Client Adapter detected", trace_id);
     }
 
+    // [NEW] 向量化请求交由 embeddings 处理器
+    if method == "embedContent" || method == "batchEmbedContents" {
+        return Ok(super::embeddings::handle_gemini_embed(state, model_name, &method, body).await);
+    }
+
     // 1. 验证方法
     if method != "generateContent" && method != "streamGenerateContent" {
         return Err((
@@ -59,6 +65,23 @@ pub async fn handle_generate(
             format!("Unsupported method: {}", method),
         ));
     }
+
+    // [NEW] 通用上游提供商 (OpenAI / Anthropic 兼容)
+    if let Some(route) =
+        crate::proxy::providers::registry::select_provider_route(&state, &model_name, "gemini")
+            .await
+    {
+        return Ok(crate::proxy::providers::generic::forward(
+            &state,
+            &route,
+            crate::proxy::providers::generic::ClientProtocol::Gemini,
+            &body,
+            method == "streamGenerateContent",
+            &model_name,
+            &headers,
+        )
+        .await);
+    }
     if debug_logger::is_enabled(&debug_cfg) {
         let original_payload = json!({
             "kind": "original_request",
@@ -98,7 +121,7 @@ pub async fn handle_generate(
 
     for attempt in 0..max_attempts {
         // 3. 模型路由解析
-        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
             &model_name,
             &*state.custom_mapping.read().await,
         );
@@ -119,7 +142,7 @@ pub async fn handle_generate(
                 flattened
             });
 
-        let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        let mut config = crate::proxy::mappers::common_utils::resolve_request_config(
             &model_name,
             &mapped_model,
             &tools_val,
@@ -133,23 +156,40 @@ pub async fn handle_generate(
         let session_id = SessionManager::extract_gemini_session_id(&body, &model_name);
 
         // 关键：在重试尝试 (attempt > 0) 时强制轮换账号
-        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-            .get_token(
+        let ((access_token, project_id, email, account_id, _wait_ms), fallback_model) =
+            match get_token_with_fallback(
+                &token_manager,
+                &state.custom_mapping,
                 &config.request_type,
                 attempt > 0,
                 Some(&session_id),
+                &model_name,
+                &mapped_model,
                 &config.final_model,
             )
             .await
-        {
-            Ok(t) => t,
-            Err(e) => {
-                return Err((
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    format!("Token error: {}", e),
-                ));
-            }
-        };
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    return Err((
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        format!("Token error: {}", e),
+                    ));
+                }
+            };
+
+        // [NEW] 模型降级：原模型账号耗尽，切换到降级链中的模型
+        if let Some(fallback) = &fallback_model {
+            mapped_model = fallback.clone();
+            config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &model_name,
+                &mapped_model,
+                &tools_val,
+                None,
+                None,
+                Some(&body),
+            );
+        }
 
         last_email = Some(email.clone());
         info!("✓ Using account: {} (type: {})", email, config.request_type);
@@ -415,16 +455,19 @@ pub async fn handle_generate(
 
                 if client_wants_stream {
                     let body = Body::from_stream(stream);
-                    return Ok(Response::builder()
-                        .header("Content-Type", "text/event-stream")
-                        .header("Cache-Control", "no-cache")
-                        .header("Connection", "keep-alive")
-                        .header("X-Accel-Buffering", "no")
-                        .header("X-Account-Email", &email)
-                        .header("X-Mapped-Model", &mapped_model)
-                        .body(body)
-                        .unwrap()
-                        .into_response());
+                    return Ok(with_fallback_header(
+                        Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .header("Cache-Control", "no-cache")
+                            .header("Connection", "keep-alive")
+                            .header("X-Accel-Buffering", "no")
+                            .header("X-Account-Email", &email)
+                            .header("X-Mapped-Model", &mapped_model)
+                            .body(body)
+                            .unwrap()
+                            .into_response(),
+                        fallback_model.as_deref(),
+                    ));
                 } else {
                     // Collect to JSON
                     use crate::proxy::mappers::gemini::collector::collect_stream_to_json;
@@ -435,15 +478,18 @@ pub async fn handle_generate(
                                 session_id
                             );
                             let unwrapped = unwrap_response(&gemini_resp);
-                            return Ok((
-                                StatusCode::OK,
-                                [
-                                    ("X-Account-Email", email.as_str()),
-                                    ("X-Mapped-Model", mapped_model.as_str()),
-                                ],
-                                Json(unwrapped),
-                            )
-                                .into_response());
+                            return Ok(with_fallback_header(
+                                (
+                                    StatusCode::OK,
+                                    [
+                                        ("X-Account-Email", email.as_str()),
+                                        ("X-Mapped-Model", mapped_model.as_str()),
+                                    ],
+                                    Json(unwrapped),
+                                )
+                                    .into_response(),
+                                fallback_model.as_deref(),
+                            ));
                         }
                         Err(e) => {
                             error!("Stream collection error: {}", e);
@@ -501,15 +547,18 @@ pub async fn handle_generate(
             }
 
             let unwrapped = unwrap_response(&gemini_resp);
-            return Ok((
-                StatusCode::OK,
-                [
-                    ("X-Account-Email", email.as_str()),
-                    ("X-Mapped-Model", mapped_model.as_str()),
-                ],
-                Json(unwrapped),
-            )
-                .into_response());
+            return Ok(with_fallback_header(
+                (
+                    StatusCode::OK,
+                    [
+                        ("X-Account-Email", email.as_str()),
+                        ("X-Mapped-Model", mapped_model.as_str()),
+                    ],
+                    Json(unwrapped),
+                )
+                    .into_response(),
+                fallback_model.as_deref(),
+            ));
         }
 
         // 处理错误并重试
@@ -656,20 +705,47 @@ pub async fn handle_get_model(Path(model_name): Path<String>) -> impl IntoRespon
 
 pub async fn handle_count_tokens(
     State(state): State<AppState>,
-    Path(_model_name): Path<String>,
-    Json(_body): Json<Value>,
+    Path(model_name): Path<String>,
+    Json(body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let model_group = "gemini";
-    let (_access_token, _project_id, _, _, _wait_ms) = state
-        .token_manager
-        .get_token(model_group, false, None, "gemini")
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                format!("Token error: {}", e),
-            )
-        })?;
-
-    Ok(Json(json!({"totalTokens": 0})))
+    // 官方 countTokens 同时支持 {contents} 与 {generateContentRequest: {...}} 两种形式
+    let mut inner_request = body
+        .get("generateContentRequest")
+        .cloned()
+        .unwrap_or(body);
+    crate::proxy::mappers::common_utils::deep_clean_undefined(&mut inner_request);
+
+    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        &model_name,
+        &*state.custom_mapping.read().await,
+    );
+    let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        &model_name,
+        &mapped_model,
+        &None,
+        None,
+        None,
+        None,
+    );
+
+    let raw_estimate =
+        crate::proxy::mappers::context_manager::ContextManager::estimate_gemini_token_usage(
+            &inner_request,
+        );
+    let result = crate::proxy::handlers::common::count_input_tokens(
+        &state,
+        &config.request_type,
+        &config.final_model,
+        &inner_request,
+        raw_estimate,
+    )
+    .await;
+
+    Ok((
+        [("X-Mapped-Model", mapped_model)],
+        Json(json!({
+            "totalTokens": result.tokens,
+            "estimated": result.estimated
+        })),
+    ))
 }
@@ -0,0 +1,510 @@
+// Anthropic Message Batches API Handler
+// /v1/messages/batches (创建 / 查询 / 列表 / 取消 / 删除 / 结果下载)，请求由批处理执行器经 Claude Handler 异步执行
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::modules::batch_db::{self, BatchRecord, RequestCounts};
+use crate::proxy::batch_worker::{self, ANTHROPIC_BATCH_ENDPOINT, KIND_ANTHROPIC};
+use crate::proxy::middleware::auth::{owner_of, Caller};
+
+/// 单个批处理任务的最大请求数 (与 Anthropic 限制一致)
+const MAX_REQUESTS_PER_BATCH: usize = 100_000;
+
+/// 任务在创建 24 小时后过期
+const EXPIRES_AFTER_SECS: i64 = 24 * 3600;
+
+/// 构造 Anthropic 风格的错误响应
+fn error_response(status: StatusCode, error_type: &str, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "type": "error",
+            "error": {
+                "type": error_type,
+                "message": message
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn invalid_request(message: &str) -> Response {
+    error_response(StatusCode::BAD_REQUEST, "invalid_request_error", message)
+}
+
+fn not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "not_found_error",
+        &format!("Message batch not found: {}", id),
+    )
+}
+
+async fn db<T, F>(f: F) -> Result<T, Response>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    let internal = |e: String| error_response(StatusCode::INTERNAL_SERVER_ERROR, "api_error", &e);
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(v)) => Ok(v),
+        Ok(Err(e)) => Err(internal(e)),
+        Err(e) => Err(internal(e.to_string())),
+    }
+}
+
+fn rfc3339(ts: Option<i64>) -> Value {
+    ts.and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|t| Value::String(t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)))
+        .unwrap_or(Value::Null)
+}
+
+/// 内部任务状态 -> Anthropic processing_status
+fn processing_status(batch: &BatchRecord) -> &'static str {
+    match batch.status.as_str() {
+        "in_progress" | "finalizing" => "in_progress",
+        "cancelling" => "canceling",
+        _ => "ended",
+    }
+}
+
+/// SDK 直接请求 results_url，因此需返回基于当前请求地址的绝对 URL
+fn results_url(headers: &HeaderMap, id: &str) -> String {
+    let header_value = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let scheme = header_value("x-forwarded-proto").unwrap_or_else(|| "http".to_string());
+    let host = header_value("x-forwarded-host")
+        .or_else(|| header_value("host"))
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    format!("{}://{}/v1/messages/batches/{}/results", scheme, host, id)
+}
+
+fn batch_to_json(batch: &BatchRecord, counts: &RequestCounts, headers: &HeaderMap) -> Value {
+    let status = processing_status(batch);
+    let ended_at = batch
+        .completed_at
+        .or(batch.cancelled_at)
+        .or(batch.expired_at)
+        .or(batch.failed_at);
+    json!({
+        "id": batch.id,
+        "type": "message_batch",
+        "processing_status": status,
+        "request_counts": {
+            "processing": counts.pending + counts.running,
+            "succeeded": counts.succeeded,
+            "errored": counts.failed,
+            "canceled": counts.cancelled,
+            "expired": counts.expired,
+        },
+        "ended_at": rfc3339(ended_at),
+        "created_at": rfc3339(Some(batch.created_at)),
+        "expires_at": rfc3339(batch.expires_at),
+        "archived_at": null,
+        "cancel_initiated_at": rfc3339(batch.cancelling_at),
+        "results_url": if status == "ended" {
+            Value::String(results_url(headers, &batch.id))
+        } else {
+            Value::Null
+        },
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchItem {
+    custom_id: String,
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateMessageBatchRequest {
+    requests: Vec<BatchItem>,
+}
+
+fn is_valid_custom_id(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 校验创建请求，返回 (custom_id, params) 列表
+fn parse_requests(body: Value) -> Result<Vec<(String, Value)>, String> {
+    let req: CreateMessageBatchRequest =
+        serde_json::from_value(body).map_err(|e| format!("Invalid request body: {}", e))?;
+
+    if req.requests.is_empty() {
+        return Err("requests: must contain at least one request".to_string());
+    }
+    if req.requests.len() > MAX_REQUESTS_PER_BATCH {
+        return Err(format!(
+            "requests: a batch may contain at most {} requests",
+            MAX_REQUESTS_PER_BATCH
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    let mut requests = Vec::with_capacity(req.requests.len());
+    for (i, item) in req.requests.into_iter().enumerate() {
+        if !is_valid_custom_id(&item.custom_id) {
+            return Err(format!(
+                "requests.{}.custom_id: must be 1-64 characters of letters, digits, '_' or '-'",
+                i
+            ));
+        }
+        if !seen.insert(item.custom_id.clone()) {
+            return Err(format!(
+                "requests.{}.custom_id: duplicate custom_id '{}'",
+                i, item.custom_id
+            ));
+        }
+        let Some(params) = item.params.as_object() else {
+            return Err(format!("requests.{}.params: must be an object", i));
+        };
+        for field in ["model", "max_tokens", "messages"] {
+            if !params.contains_key(field) {
+                return Err(format!("requests.{}.params.{}: Field required", i, field));
+            }
+        }
+        if params.get("stream").and_then(|v| v.as_bool()) == Some(true) {
+            return Err(format!(
+                "requests.{}.params.stream: streaming is not supported in batches",
+                i
+            ));
+        }
+        requests.push((item.custom_id, item.params));
+    }
+
+    Ok(requests)
+}
+
+/// POST /v1/messages/batches
+pub async fn handle_create_message_batch(
+    caller: Caller,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    let requests = match parse_requests(body) {
+        Ok(requests) => requests,
+        Err(message) => return invalid_request(&message),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let batch = BatchRecord {
+        id: format!("msgbatch_{}", uuid::Uuid::new_v4().simple()),
+        kind: KIND_ANTHROPIC.to_string(),
+        endpoint: ANTHROPIC_BATCH_ENDPOINT.to_string(),
+        completion_window: "24h".to_string(),
+        status: "in_progress".to_string(),
+        created_at: now,
+        in_progress_at: Some(now),
+        expires_at: Some(now + EXPIRES_AFTER_SECS),
+        owner_token_id: owner_of(&caller),
+        owner_ip: caller
+            .as_ref()
+            .map(|Extension(identity)| identity.client_ip.clone()),
+        ..Default::default()
+    };
+
+    let record = batch.clone();
+    let count = requests.len();
+    if let Err(resp) = db(move || batch_db::create_batch(&record, &requests)).await {
+        return resp;
+    }
+    tracing::info!(
+        "[Batch] Created message batch {} ({} requests)",
+        batch.id,
+        count
+    );
+    batch_worker::wake();
+
+    let counts = RequestCounts {
+        total: count as i64,
+        pending: count as i64,
+        ..Default::default()
+    };
+    Json(batch_to_json(&batch, &counts, &headers)).into_response()
+}
+
+async fn load_batch(
+    id: String,
+    owner: Option<String>,
+) -> Result<Option<(BatchRecord, RequestCounts)>, Response> {
+    db(move || match batch_db::get_batch(&id, owner.as_deref())? {
+        Some(batch) if batch.kind == KIND_ANTHROPIC => {
+            let counts = batch_db::request_counts(&batch.id)?;
+            Ok(Some((batch, counts)))
+        }
+        _ => Ok(None),
+    })
+    .await
+}
+
+/// GET /v1/messages/batches/:batch_id
+pub async fn handle_get_message_batch(
+    caller: Caller,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    match load_batch(id.clone(), owner_of(&caller)).await {
+        Ok(Some((batch, counts))) => Json(batch_to_json(&batch, &counts, &headers)).into_response(),
+        Ok(None) => not_found(&id),
+        Err(resp) => resp,
+    }
+}
+
+/// POST /v1/messages/batches/:batch_id/cancel
+pub async fn handle_cancel_message_batch(
+    caller: Caller,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let (mut batch, counts) = match load_batch(id.clone(), owner_of(&caller)).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return not_found(&id),
+        Err(resp) => return resp,
+    };
+
+    // 与 Anthropic 一致: 对已结束或取消中的任务重复取消直接返回当前状态
+    if batch.status == "in_progress" {
+        batch.status = "cancelling".to_string();
+        batch.cancelling_at = Some(chrono::Utc::now().timestamp());
+        let record = batch.clone();
+        if let Err(resp) = db(move || batch_db::update_batch(&record)).await {
+            return resp;
+        }
+        batch_worker::wake();
+    }
+
+    Json(batch_to_json(&batch, &counts, &headers)).into_response()
+}
+
+/// DELETE /v1/messages/batches/:batch_id (仅限已结束的任务)
+pub async fn handle_delete_message_batch(caller: Caller, Path(id): Path<String>) -> Response {
+    let batch = match load_batch(id.clone(), owner_of(&caller)).await {
+        Ok(Some((batch, _))) => batch,
+        Ok(None) => return not_found(&id),
+        Err(resp) => return resp,
+    };
+    if processing_status(&batch) != "ended" {
+        return invalid_request(&format!(
+            "Message batch {} cannot be deleted while it is still processing; cancel it first.",
+            id
+        ));
+    }
+
+    let batch_id = batch.id.clone();
+    match db(move || batch_db::delete_batch(&batch_id)).await {
+        Ok(_) => Json(json!({ "id": batch.id, "type": "message_batch_deleted" })).into_response(),
+        Err(resp) => resp,
+    }
+}
+
+/// GET /v1/messages/batches/:batch_id/results (JSONL)
+pub async fn handle_message_batch_results(caller: Caller, Path(id): Path<String>) -> Response {
+    let batch = match load_batch(id.clone(), owner_of(&caller)).await {
+        Ok(Some((batch, _))) => batch,
+        Ok(None) => return not_found(&id),
+        Err(resp) => return resp,
+    };
+    if processing_status(&batch) != "ended" {
+        return invalid_request(&format!(
+            "Results for message batch {} are not available until processing has ended.",
+            id
+        ));
+    }
+
+    let batch_id = batch.id.clone();
+    let requests = match db(move || batch_db::list_requests(&batch_id)).await {
+        Ok(requests) => requests,
+        Err(resp) => return resp,
+    };
+
+    let mut body = String::new();
+    for line in requests
+        .iter()
+        .filter_map(batch_worker::anthropic_result_line)
+    {
+        body.push_str(&line.to_string());
+        body.push('\n');
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/x-jsonl")],
+        body,
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListMessageBatchesQuery {
+    before_id: Option<String>,
+    after_id: Option<String>,
+    limit: Option<usize>,
+}
+
+/// GET /v1/messages/batches (按创建时间倒序)
+pub async fn handle_list_message_batches(
+    caller: Caller,
+    headers: HeaderMap,
+    Query(query): Query<ListMessageBatchesQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(20).clamp(1, 1000);
+    let owner = owner_of(&caller);
+    let result = db(move || {
+        // 多取一条用于判断 has_more
+        let (batches, has_more) = match query.before_id.as_deref() {
+            Some(before) => {
+                let mut batches = batch_db::list_batches_before(
+                    KIND_ANTHROPIC,
+                    owner.as_deref(),
+                    before,
+                    limit + 1,
+                )?;
+                let has_more = batches.len() > limit;
+                if has_more {
+                    batches.remove(0);
+                }
+                (batches, has_more)
+            }
+            None => {
+                let mut batches = batch_db::list_batches(
+                    KIND_ANTHROPIC,
+                    owner.as_deref(),
+                    query.after_id.as_deref(),
+                    limit + 1,
+                )?;
+                let has_more = batches.len() > limit;
+                batches.truncate(limit);
+                (batches, has_more)
+            }
+        };
+        let counts = batches
+            .iter()
+            .map(|b| batch_db::request_counts(&b.id))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((batches, counts, has_more))
+    })
+    .await;
+
+    let (batches, counts, has_more) = match result {
+        Ok(found) => found,
+        Err(resp) => return resp,
+    };
+
+    let data: Vec<Value> = batches
+        .iter()
+        .zip(counts.iter())
+        .map(|(b, c)| batch_to_json(b, c, &headers))
+        .collect();
+    Json(json!({
+        "data": data,
+        "has_more": has_more,
+        "first_id": batches.first().map(|b| b.id.clone()),
+        "last_id": batches.last().map(|b| b.id.clone()),
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        let params = json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 1024,
+            "messages": [{ "role": "user", "content": "Hello" }]
+        });
+        let parsed = parse_requests(json!({
+            "requests": [
+                { "custom_id": "req-1", "params": params },
+                { "custom_id": "req_2", "params": params }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].0, "req_2");
+
+        let dup = parse_requests(json!({
+            "requests": [
+                { "custom_id": "a", "params": params },
+                { "custom_id": "a", "params": params }
+            ]
+        }));
+        assert!(dup.unwrap_err().contains("duplicate"));
+
+        let mut streaming = params.clone();
+        streaming["stream"] = json!(true);
+        assert!(parse_requests(json!({
+            "requests": [{ "custom_id": "a", "params": streaming }]
+        }))
+        .is_err());
+
+        assert!(parse_requests(json!({
+            "requests": [{ "custom_id": "bad id!", "params": params }]
+        }))
+        .is_err());
+        assert!(parse_requests(json!({
+            "requests": [{ "custom_id": "a", "params": { "model": "x" } }]
+        }))
+        .unwrap_err()
+        .contains("max_tokens"));
+        assert!(parse_requests(json!({ "requests": [] })).is_err());
+    }
+
+    #[test]
+    fn test_batch_to_json() {
+        let mut batch = BatchRecord {
+            id: "msgbatch_abc".to_string(),
+            kind: KIND_ANTHROPIC.to_string(),
+            status: "in_progress".to_string(),
+            created_at: 1_724_000_000,
+            expires_at: Some(1_724_086_400),
+            ..Default::default()
+        };
+        let counts = RequestCounts {
+            total: 4,
+            pending: 1,
+            running: 1,
+            succeeded: 1,
+            failed: 1,
+            ..Default::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::HOST, "127.0.0.1:8045".parse().unwrap());
+
+        let value = batch_to_json(&batch, &counts, &headers);
+        assert_eq!(value["type"], "message_batch");
+        assert_eq!(value["processing_status"], "in_progress");
+        assert_eq!(value["request_counts"]["processing"], 2);
+        assert_eq!(value["request_counts"]["errored"], 1);
+        assert_eq!(value["created_at"], "2024-08-18T16:53:20Z");
+        assert!(value["results_url"].is_null());
+
+        batch.status = "cancelled".to_string();
+        batch.cancelling_at = Some(1_724_000_100);
+        batch.cancelled_at = Some(1_724_000_200);
+        let value = batch_to_json(&batch, &counts, &headers);
+        assert_eq!(value["processing_status"], "ended");
+        assert_eq!(value["ended_at"], "2024-08-18T16:56:40Z");
+        assert_eq!(
+            value["results_url"],
+            "http://127.0.0.1:8045/v1/messages/batches/msgbatch_abc/results"
+        );
+    }
+}
@@ -2,9 +2,13 @@
 // 核心端点处理器模块
 
 pub mod audio; // 音频转录处理器
+pub mod batches; // OpenAI Files & Batch API
 pub mod claude;
 pub mod common;
+pub mod embeddings; // 向量化处理器
 pub mod gemini;
 pub mod mcp;
+pub mod message_batches; // Anthropic Message Batches API
 pub mod openai;
+pub mod responses; // OpenAI Responses API
 pub mod warmup; // 预热处理器
@@ -10,6 +10,7 @@ use tracing::{debug, error, info}; // Import Engine trait for encode method
 use crate::proxy::mappers::openai::models::{
     OpenAIContent, OpenAIContentBlock, OpenAIResponse, ToolCall,
 };
+use crate::proxy::mappers::openai::responses::{input_items_to_messages, normalize_input_items};
 use crate::proxy::mappers::openai::{
     transform_openai_request, transform_openai_response, OpenAIRequest,
 };
@@ -24,7 +25,8 @@ use crate::proxy::upstream::client::mask_email;
 
 const MAX_RETRY_ATTEMPTS: usize = 3;
 use super::common::{
-    apply_retry_strategy, determine_retry_strategy, should_rotate_account, RetryStrategy,
+    apply_retry_strategy, determine_retry_strategy, get_token_with_fallback, should_rotate_account,
+    with_fallback_header, RetryStrategy,
 };
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS; // [NEW] Adapter Registry
 use crate::proxy::session_manager::SessionManager;
@@ -231,6 +233,25 @@ pub async fn handle_chat_completions(
         }
     }
 
+    // [NEW] 通用上游提供商 (OpenAI / Anthropic 兼容)
+    if let Some(model) = body.get("model").and_then(|m| m.as_str()).map(str::to_string) {
+        if let Some(route) =
+            crate::proxy::providers::registry::select_provider_route(&state, &model, "gemini").await
+        {
+            let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(false);
+            return Ok(crate::proxy::providers::generic::forward(
+                &state,
+                &route,
+                crate::proxy::providers::generic::ClientProtocol::OpenAI,
+                &body,
+                stream,
+                &model,
+                &headers,
+            )
+            .await);
+        }
+    }
+
     let mut openai_req: OpenAIRequest = serde_json::from_value(body)
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
 
@@ -307,10 +328,12 @@ pub async fn handle_chat_completions(
     let mut last_email: Option<String> = None;
 
     // 2. 模型路由解析 (移到循环外以支持在所有路径返回 X-Mapped-Model)
-    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+    let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
         &openai_req.model,
         &*state.custom_mapping.read().await,
     );
+    // [NEW] 模型降级链命中时实际使用的模型
+    let mut fallback_model: Option<String> = None;
 
     for attempt in 0..max_attempts {
         // 将 OpenAI 工具转为 Value 数组以便探测联网
@@ -318,7 +341,7 @@ pub async fn handle_chat_completions(
             .tools
             .as_ref()
             .map(|list| list.iter().cloned().collect());
-        let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        let mut config = crate::proxy::mappers::common_utils::resolve_request_config(
             &openai_req.model,
             &mapped_model,
             &tools_val,
@@ -332,27 +355,45 @@ pub async fn handle_chat_completions(
 
         // 4. 获取 Token (使用准确的 request_type)
         // 关键：在重试尝试 (attempt > 0) 时强制轮换账号
-        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-            .get_token(
+        let ((access_token, project_id, email, account_id, _wait_ms), fallback) =
+            match get_token_with_fallback(
+                &token_manager,
+                &state.custom_mapping,
                 &config.request_type,
                 attempt > 0,
                 Some(&session_id),
+                &openai_req.model,
+                &mapped_model,
                 &mapped_model,
             )
             .await
-        {
-            Ok(t) => t,
-            Err(e) => {
-                // [FIX] Attach headers to error response for logging visibility
-                let headers = [("X-Mapped-Model", mapped_model.as_str())];
-                return Ok((
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    headers,
-                    format!("Token error: {}", e),
-                )
-                    .into_response());
-            }
-        };
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    // [FIX] Attach headers to error response for logging visibility
+                    let headers = [("X-Mapped-Model", mapped_model.as_str())];
+                    return Ok((
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        headers,
+                        format!("Token error: {}", e),
+                    )
+                        .into_response());
+                }
+            };
+
+        // [NEW] 模型降级：原模型账号耗尽，后续请求转换与重试均使用降级模型
+        if let Some(fallback) = fallback {
+            mapped_model = fallback;
+            fallback_model = Some(mapped_model.clone());
+            config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &openai_req.model,
+                &mapped_model,
+                &tools_val,
+                None,
+                None,
+                None,
+            );
+        }
 
         last_email = Some(email.clone());
         info!("✓ Using account: {} (type: {})", email, config.request_type);
@@ -602,16 +643,19 @@ pub async fn handle_chat_completions(
                 if client_wants_stream {
                     // 客户端请求流式，返回 SSE
                     let body = Body::from_stream(combined_stream);
-                    return Ok(Response::builder()
-                        .header("Content-Type", "text/event-stream")
-                        .header("Cache-Control", "no-cache")
-                        .header("Connection", "keep-alive")
-                        .header("X-Accel-Buffering", "no")
-                        .header("X-Account-Email", &email)
-                        .header("X-Mapped-Model", &mapped_model)
-                        .body(body)
-                        .unwrap()
-                        .into_response());
+                    return Ok(with_fallback_header(
+                        Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .header("Cache-Control", "no-cache")
+                            .header("Connection", "keep-alive")
+                            .header("X-Accel-Buffering", "no")
+                            .header("X-Account-Email", &email)
+                            .header("X-Mapped-Model", &mapped_model)
+                            .body(body)
+                            .unwrap()
+                            .into_response(),
+                        fallback_model.as_deref(),
+                    ));
                 } else {
                     // 客户端请求非流式，但内部强制转为流式
                     // 收集流数据并聚合为 JSON
@@ -625,15 +669,18 @@ pub async fn handle_chat_completions(
                                 &punctuation_config,
                             );
 
-                            return Ok((
-                                StatusCode::OK,
-                                [
-                                    ("X-Account-Email", email.as_str()),
-                                    ("X-Mapped-Model", mapped_model.as_str()),
-                                ],
-                                Json(full_response),
-                            )
-                                .into_response());
+                            return Ok(with_fallback_header(
+                                (
+                                    StatusCode::OK,
+                                    [
+                                        ("X-Account-Email", email.as_str()),
+                                        ("X-Mapped-Model", mapped_model.as_str()),
+                                    ],
+                                    Json(full_response),
+                                )
+                                    .into_response(),
+                                fallback_model.as_deref(),
+                            ));
                         }
                         Err(e) => {
                             error!("[{}] Stream collection error: {}", trace_id, e);
@@ -660,23 +707,29 @@ pub async fn handle_chat_completions(
             maybe_apply_punctuation_to_openai_response(&mut openai_response, &punctuation_config);
 
             if fake_prefix_stream_mode {
-                return Ok(build_fake_stream_from_openai_response(
-                    &openai_response,
-                    &requested_model,
-                    &mapped_model,
-                    &email,
+                return Ok(with_fallback_header(
+                    build_fake_stream_from_openai_response(
+                        &openai_response,
+                        &requested_model,
+                        &mapped_model,
+                        &email,
+                    ),
+                    fallback_model.as_deref(),
                 ));
             }
 
-            return Ok((
-                StatusCode::OK,
-                [
-                    ("X-Account-Email", email.as_str()),
-                    ("X-Mapped-Model", mapped_model.as_str()),
-                ],
-                Json(openai_response),
-            )
-                .into_response());
+            return Ok(with_fallback_header(
+                (
+                    StatusCode::OK,
+                    [
+                        ("X-Account-Email", email.as_str()),
+                        ("X-Mapped-Model", mapped_model.as_str()),
+                    ],
+                    Json(openai_response),
+                )
+                    .into_response(),
+                fallback_model.as_deref(),
+            ));
         }
 
         // 处理特定错误并重试
@@ -936,7 +989,7 @@ pub async fn handle_completions(
     Json(mut body): Json<Value>,
 ) -> Response {
     debug!(
-        "Received /v1/completions or /v1/responses payload: {:?}",
+        "Received /v1/completions payload: {:?}",
         body
     );
 
@@ -944,225 +997,9 @@ pub async fn handle_completions(
 
     // 1. Convert Payload to Messages (Shared Chat Format)
     if is_codex_style {
-        let instructions = body
-            .get("instructions")
-            .and_then(|v| v.as_str())
-            .unwrap_or_default();
-        let input_items = body.get("input").and_then(|v| v.as_array());
-
-        let mut messages = Vec::new();
-
-        // System Instructions
-        if !instructions.is_empty() {
-            messages.push(json!({ "role": "system", "content": instructions }));
-        }
-
-        let mut call_id_to_name = std::collections::HashMap::new();
-
-        // Pass 1: Build Call ID to Name Map
-        if let Some(items) = input_items {
-            for item in items {
-                let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                match item_type {
-                    "function_call" | "local_shell_call" | "web_search_call" => {
-                        let call_id = item
-                            .get("call_id")
-                            .and_then(|v| v.as_str())
-                            .or_else(|| item.get("id").and_then(|v| v.as_str()))
-                            .unwrap_or("unknown");
-
-                        let name = if item_type == "local_shell_call" {
-                            "shell"
-                        } else if item_type == "web_search_call" {
-                            "google_search"
-                        } else {
-                            item.get("name")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("unknown")
-                        };
-
-                        call_id_to_name.insert(call_id.to_string(), name.to_string());
-                        tracing::debug!("Mapped call_id {} to name {}", call_id, name);
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        // Pass 2: Map Input Items to Messages
-        if let Some(items) = input_items {
-            for item in items {
-                let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
-                match item_type {
-                    "message" => {
-                        let role = item.get("role").and_then(|v| v.as_str()).unwrap_or("user");
-                        let content = item.get("content").and_then(|v| v.as_array());
-                        let mut text_parts = Vec::new();
-                        let mut image_parts: Vec<Value> = Vec::new();
-
-                        if let Some(parts) = content {
-                            for part in parts {
-                                // 处理文本块
-                                if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
-                                    text_parts.push(text.to_string());
-                                }
-                                // [NEW] 处理图像块 (Codex input_image 格式)
-                                else if part.get("type").and_then(|v| v.as_str())
-                                    == Some("input_image")
-                                {
-                                    if let Some(image_url) =
-                                        part.get("image_url").and_then(|v| v.as_str())
-                                    {
-                                        image_parts.push(json!({
-                                            "type": "image_url",
-                                            "image_url": { "url": image_url }
-                                        }));
-                                        debug!("[Codex] Found input_image: {}", image_url);
-                                    }
-                                }
-                                // [NEW] 兼容标准 OpenAI image_url 格式
-                                else if part.get("type").and_then(|v| v.as_str())
-                                    == Some("image_url")
-                                {
-                                    if let Some(url_obj) = part.get("image_url") {
-                                        image_parts.push(json!({
-                                            "type": "image_url",
-                                            "image_url": url_obj.clone()
-                                        }));
-                                    }
-                                }
-                            }
-                        }
-
-                        // 构造消息内容：如果有图像则使用数组格式
-                        if image_parts.is_empty() {
-                            messages.push(json!({
-                                "role": role,
-                                "content": text_parts.join("\n")
-                            }));
-                        } else {
-                            let mut content_blocks: Vec<Value> = Vec::new();
-                            if !text_parts.is_empty() {
-                                content_blocks.push(json!({
-                                    "type": "text",
-                                    "text": text_parts.join("\n")
-                                }));
-                            }
-                            content_blocks.extend(image_parts);
-                            messages.push(json!({
-                                "role": role,
-                                "content": content_blocks
-                            }));
-                        }
-                    }
-                    "function_call" | "local_shell_call" | "web_search_call" => {
-                        let mut name = item
-                            .get("name")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("unknown");
-                        let mut args_str = item
-                            .get("arguments")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("{}")
-                            .to_string();
-                        let call_id = item
-                            .get("call_id")
-                            .and_then(|v| v.as_str())
-                            .or_else(|| item.get("id").and_then(|v| v.as_str()))
-                            .unwrap_or("unknown");
-
-                        // Handle native shell calls
-                        if item_type == "local_shell_call" {
-                            name = "shell";
-                            if let Some(action) = item.get("action") {
-                                if let Some(exec) = action.get("exec") {
-                                    // Map to ShellCommandToolCallParams (string command) or ShellToolCallParams (array command)
-                                    // Most LLMs prefer a single string for shell
-                                    let mut args_obj = serde_json::Map::new();
-                                    if let Some(cmd) = exec.get("command") {
-                                        // CRITICAL FIX: The 'shell' tool schema defines 'command' as an ARRAY of strings.
-                                        // We MUST pass it as an array, not a joined string, otherwise Gemini rejects with 400 INVALID_ARGUMENT.
-                                        let cmd_val = if cmd.is_string() {
-                                            json!([cmd]) // Wrap in array
-                                        } else {
-                                            cmd.clone() // Assume already array
-                                        };
-                                        args_obj.insert("command".to_string(), cmd_val);
-                                    }
-                                    if let Some(wd) =
-                                        exec.get("working_directory").or(exec.get("workdir"))
-                                    {
-                                        args_obj.insert("workdir".to_string(), wd.clone());
-                                    }
-                                    args_str = serde_json::to_string(&args_obj)
-                                        .unwrap_or("{}".to_string());
-                                }
-                            }
-                        } else if item_type == "web_search_call" {
-                            name = "google_search";
-                            if let Some(action) = item.get("action") {
-                                let mut args_obj = serde_json::Map::new();
-                                if let Some(q) = action.get("query") {
-                                    args_obj.insert("query".to_string(), q.clone());
-                                }
-                                args_str =
-                                    serde_json::to_string(&args_obj).unwrap_or("{}".to_string());
-                            }
-                        }
-
-                        messages.push(json!({
-                            "role": "assistant",
-                            "tool_calls": [
-                                {
-                                    "id": call_id,
-                                    "type": "function",
-                                    "function": {
-                                        "name": name,
-                                        "arguments": args_str
-                                    }
-                                }
-                            ]
-                        }));
-                    }
-                    "function_call_output" | "custom_tool_call_output" => {
-                        let call_id = item
-                            .get("call_id")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("unknown");
-                        let output = item.get("output");
-                        let output_str = if let Some(o) = output {
-                            if o.is_string() {
-                                o.as_str().unwrap().to_string()
-                            } else if let Some(content) = o.get("content").and_then(|v| v.as_str())
-                            {
-                                content.to_string()
-                            } else {
-                                o.to_string()
-                            }
-                        } else {
-                            "".to_string()
-                        };
-
-                        let name = call_id_to_name.get(call_id).cloned().unwrap_or_else(|| {
-                            // Fallback: if unknown and we see function_call_output, it's likely "shell" in this context
-                            tracing::warn!(
-                                "Unknown tool name for call_id {}, defaulting to 'shell'",
-                                call_id
-                            );
-                            "shell".to_string()
-                        });
-
-                        messages.push(json!({
-                            "role": "tool",
-                            "tool_call_id": call_id,
-                            "name": name,
-                            "content": output_str
-                        }));
-                    }
-                    _ => {}
-                }
-            }
-        }
+        let instructions = body.get("instructions").and_then(|v| v.as_str());
+        let input_items = normalize_input_items(body.get("input"));
+        let messages = input_items_to_messages(instructions, &input_items);
 
         if let Some(obj) = body.as_object_mut() {
             obj.insert("messages".to_string(), json!(messages));
@@ -1320,10 +1157,11 @@ pub async fn handle_completions(
     let mut last_email: Option<String> = None;
 
     // 2. 模型路由解析 (移到循环外以支持在所有路径返回 X-Mapped-Model)
-    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+    let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
         &openai_req.model,
         &*state.custom_mapping.read().await,
     );
+    let mut fallback_model: Option<String> = None;
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
 
     for attempt in 0..max_attempts {
@@ -1333,7 +1171,7 @@ pub async fn handle_completions(
             .tools
             .as_ref()
             .map(|list| list.iter().cloned().collect());
-        let config = crate::proxy::mappers::common_utils::resolve_request_config(
+        let mut config = crate::proxy::mappers::common_utils::resolve_request_config(
             &openai_req.model,
             &mapped_model,
             &tools_val,
@@ -1350,25 +1188,43 @@ pub async fn handle_completions(
         // 重试时强制轮换，除非只是简单的网络抖动但 Claude 逻辑里 attempt > 0 总是 force_rotate
         let force_rotate = attempt > 0;
 
-        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-            .get_token(
+        let ((access_token, project_id, email, account_id, _wait_ms), fallback) =
+            match get_token_with_fallback(
+                &token_manager,
+                &state.custom_mapping,
                 &config.request_type,
                 force_rotate,
                 session_id,
+                &openai_req.model,
+                &mapped_model,
                 &mapped_model,
             )
             .await
-        {
-            Ok(t) => t,
-            Err(e) => {
-                return (
-                    StatusCode::SERVICE_UNAVAILABLE,
-                    [("X-Mapped-Model", mapped_model)],
-                    format!("Token error: {}", e),
-                )
-                    .into_response()
-            }
-        };
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    return (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        [("X-Mapped-Model", mapped_model)],
+                        format!("Token error: {}", e),
+                    )
+                        .into_response()
+                }
+            };
+
+        // [NEW] 模型降级
+        if let Some(fallback) = fallback {
+            mapped_model = fallback;
+            fallback_model = Some(mapped_model.clone());
+            config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &openai_req.model,
+                &mapped_model,
+                &tools_val,
+                None,
+                None,
+                None,
+            );
+        }
 
         last_email = Some(email.clone());
 
@@ -1514,15 +1370,18 @@ pub async fn handle_completions(
                     })
                     .chain(openai_stream);
 
-                    return Response::builder()
-                        .header("Content-Type", "text/event-stream")
-                        .header("Cache-Control", "no-cache")
-                        .header("Connection", "keep-alive")
-                        .header("X-Account-Email", &email)
-                        .header("X-Mapped-Model", &mapped_model)
-                        .body(Body::from_stream(combined_stream))
-                        .unwrap()
-                        .into_response();
+                    return with_fallback_header(
+                        Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .header("Cache-Control", "no-cache")
+                            .header("Connection", "keep-alive")
+                            .header("X-Account-Email", &email)
+                            .header("X-Mapped-Model", &mapped_model)
+                            .body(Body::from_stream(combined_stream))
+                            .unwrap()
+                            .into_response(),
+                        fallback_model.as_deref(),
+                    );
                 } else {
                     // Forced Stream Internal -> Convert to Legacy JSON
                     // Use CHAT SSE Stream (so Collector can parse it)
@@ -1621,15 +1480,18 @@ pub async fn handle_completions(
                                 "usage": chat_resp.usage
                             });
 
-                            return (
-                                StatusCode::OK,
-                                [
-                                    ("X-Account-Email", email.as_str()),
-                                    ("X-Mapped-Model", mapped_model.as_str()),
-                                ],
-                                Json(legacy_resp),
-                            )
-                                .into_response();
+                            return with_fallback_header(
+                                (
+                                    StatusCode::OK,
+                                    [
+                                        ("X-Account-Email", email.as_str()),
+                                        ("X-Mapped-Model", mapped_model.as_str()),
+                                    ],
+                                    Json(legacy_resp),
+                                )
+                                    .into_response(),
+                                fallback_model.as_deref(),
+                            );
                         }
                         Err(e) => {
                             return (
@@ -1679,15 +1541,18 @@ pub async fn handle_completions(
                 "usage": chat_resp.usage
             });
 
-            return (
-                StatusCode::OK,
-                [
-                    ("X-Account-Email", email.as_str()),
-                    ("X-Mapped-Model", mapped_model.as_str()),
-                ],
-                Json(legacy_resp),
-            )
-                .into_response();
+            return with_fallback_header(
+                (
+                    StatusCode::OK,
+                    [
+                        ("X-Account-Email", email.as_str()),
+                        ("X-Mapped-Model", mapped_model.as_str()),
+                    ],
+                    Json(legacy_resp),
+                )
+                    .into_response(),
+                fallback_model.as_deref(),
+            );
         }
 
         // Handle errors and retry
@@ -0,0 +1,459 @@
+// OpenAI Responses API Handler
+// POST /v1/responses, GET /v1/responses/:id, DELETE /v1/responses/:id
+use axum::{
+    body::Body,
+    extract::{Json, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+use super::common::{
+    apply_retry_strategy, determine_retry_strategy, get_token_with_fallback, with_fallback_header,
+};
+use crate::modules::responses_db::{self, StoredResponse};
+use crate::proxy::mappers::openai::responses::{
+    build_chat_request, build_response_skeleton, collect_response, create_responses_sse_stream,
+    input_items_to_messages, new_response_id, normalize_input_items, ResponseCompleteHook,
+};
+use crate::proxy::mappers::openai::{transform_openai_request, OpenAIRequest};
+use crate::proxy::middleware::auth::{owner_of, Caller};
+use crate::proxy::server::AppState;
+use crate::proxy::session_manager::SessionManager;
+
+const MAX_RETRY_ATTEMPTS: usize = 3;
+
+/// 构造 OpenAI 风格的错误响应
+fn error_response(status: StatusCode, message: &str, param: Option<&str>, code: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": "invalid_request_error",
+                "param": param,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        &format!("Response with id '{}' not found.", id),
+        None,
+        "response_not_found",
+    )
+}
+
+/// 创建持久化回调：store=false 时返回 None
+fn build_store_hook(
+    store: bool,
+    model: String,
+    previous_response_id: Option<String>,
+    input_items: Vec<Value>,
+    owner_token_id: Option<String>,
+) -> Option<ResponseCompleteHook> {
+    if !store {
+        return None;
+    }
+    Some(Box::new(move |response: Value| {
+        let record = StoredResponse {
+            id: response["id"].as_str().unwrap_or_default().to_string(),
+            created_at: response["created_at"].as_i64().unwrap_or_default(),
+            model,
+            previous_response_id,
+            input_items: Value::Array(input_items),
+            output_items: response["output"].clone(),
+            response,
+            owner_token_id,
+        };
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = responses_db::save_response(&record) {
+                tracing::warn!("[Responses] Failed to store response {}: {}", record.id, e);
+            }
+        });
+    }))
+}
+
+/// POST /v1/responses
+pub async fn handle_create_response(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(body): Json<Value>,
+) -> Response {
+    let owner = owner_of(&caller);
+    debug!("Received /v1/responses payload: {:?}", body);
+
+    let store = body.get("store").and_then(|v| v.as_bool()).unwrap_or(true);
+    let client_wants_stream = body
+        .get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let previous_response_id = body
+        .get("previous_response_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    // 1. 还原对话历史 (previous_response_id 链)
+    let new_items = normalize_input_items(body.get("input"));
+    let mut all_items = Vec::new();
+    if let Some(prev_id) = previous_response_id.clone() {
+        let lookup_id = prev_id.clone();
+        let lookup_owner = owner.clone();
+        let history = tokio::task::spawn_blocking(move || {
+            responses_db::load_conversation_items(&lookup_id, lookup_owner.as_deref())
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        match history {
+            Ok(Some(items)) => all_items = items,
+            Ok(None) => {
+                return error_response(
+                    StatusCode::NOT_FOUND,
+                    &format!("Previous response with id '{}' not found.", prev_id),
+                    Some("previous_response_id"),
+                    "previous_response_not_found",
+                );
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to load previous response: {}", e),
+                )
+                    .into_response();
+            }
+        }
+    }
+    all_items.extend(new_items.iter().cloned());
+
+    // 2. 转换为 Chat 请求 (instructions 不会从历史继承，与官方语义一致)
+    let messages = input_items_to_messages(
+        body.get("instructions").and_then(|v| v.as_str()),
+        &all_items,
+    );
+    let mut openai_req: OpenAIRequest =
+        match serde_json::from_value(build_chat_request(&body, messages)) {
+            Ok(req) => req,
+            Err(e) => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Invalid request: {}", e),
+                    None,
+                    "invalid_request",
+                );
+            }
+        };
+
+    // Safety: Inject empty message if needed
+    if openai_req.messages.is_empty() {
+        openai_req
+            .messages
+            .push(crate::proxy::mappers::openai::OpenAIMessage {
+                role: "user".to_string(),
+                content: Some(crate::proxy::mappers::openai::OpenAIContent::String(
+                    " ".to_string(),
+                )),
+                reasoning_content: None,
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+            });
+    }
+
+    let token_manager = state.token_manager.clone();
+    let pool_size = token_manager.len();
+    let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size.saturating_add(1)).max(2);
+
+    let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        &openai_req.model,
+        &*state.custom_mapping.read().await,
+    );
+    let mut fallback_model: Option<String> = None;
+    let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
+
+    let response_id = new_response_id();
+    let created_at = chrono::Utc::now().timestamp();
+
+    let mut last_error = String::new();
+    let mut last_email: Option<String> = None;
+
+    for attempt in 0..max_attempts {
+        let tools_val: Option<Vec<Value>> = openai_req.tools.clone();
+        let mut config = crate::proxy::mappers::common_utils::resolve_request_config(
+            &openai_req.model,
+            &mapped_model,
+            &tools_val,
+            None,
+            None,
+            None,
+        );
+
+        let session_id_str = SessionManager::extract_openai_session_id(&openai_req);
+        let force_rotate = attempt > 0;
+
+        let ((access_token, project_id, email, account_id, _wait_ms), fallback) =
+            match get_token_with_fallback(
+                &token_manager,
+                &state.custom_mapping,
+                &config.request_type,
+                force_rotate,
+                Some(session_id_str.as_str()),
+                &openai_req.model,
+                &mapped_model,
+                &mapped_model,
+            )
+            .await
+            {
+                Ok(t) => t,
+                Err(e) => {
+                    return (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        [("X-Mapped-Model", mapped_model)],
+                        format!("Token error: {}", e),
+                    )
+                        .into_response()
+                }
+            };
+        // [NEW] 模型降级
+        if let Some(fallback) = fallback {
+            mapped_model = fallback;
+            fallback_model = Some(mapped_model.clone());
+            config = crate::proxy::mappers::common_utils::resolve_request_config(
+                &openai_req.model,
+                &mapped_model,
+                &tools_val,
+                None,
+                None,
+                None,
+            );
+        }
+        last_email = Some(email.clone());
+        info!("✓ Using account: {} (type: {})", email, config.request_type);
+
+        let (gemini_body, session_id, message_count) =
+            transform_openai_request(&openai_req, &project_id, &mapped_model);
+
+        // Responses 事件流需要增量数据，上游统一使用流式接口
+        let call_result = match state
+            .upstream
+            .call_v1_internal(
+                "streamGenerateContent",
+                &access_token,
+                gemini_body,
+                Some("alt=sse"),
+                Some(account_id.as_str()),
+            )
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                last_error = e.clone();
+                debug!(
+                    "[Responses] Request failed on attempt {}/{}: {}",
+                    attempt + 1,
+                    max_attempts,
+                    e
+                );
+                continue;
+            }
+        };
+
+        let response = call_result.response;
+        let status = response.status();
+        if status.is_success() {
+            token_manager.mark_account_success(&email);
+
+            let skeleton = build_response_skeleton(&body, &response_id, created_at);
+            let hook = build_store_hook(
+                store,
+                openai_req.model.clone(),
+                previous_response_id.clone(),
+                new_items.clone(),
+                owner.clone(),
+            );
+            let mut events = create_responses_sse_stream(
+                Box::pin(response.bytes_stream()),
+                skeleton,
+                session_id,
+                message_count,
+                hook,
+            );
+
+            // Peek: 首个有效事件到达前的错误/空流/超时均换号重试
+            let mut first_data_chunk = None;
+            loop {
+                match tokio::time::timeout(std::time::Duration::from_secs(60), events.next()).await
+                {
+                    Ok(Some(Ok(bytes))) => {
+                        if bytes.is_empty() || bytes.starts_with(b":") {
+                            continue;
+                        }
+                        first_data_chunk = Some(bytes);
+                        break;
+                    }
+                    Ok(Some(Err(e))) => {
+                        last_error = format!("Stream error during peek: {}", e);
+                        break;
+                    }
+                    Ok(None) => {
+                        last_error = "Empty response stream".to_string();
+                        break;
+                    }
+                    Err(_) => {
+                        last_error = "Timeout waiting for first data".to_string();
+                        break;
+                    }
+                }
+            }
+            let Some(first_data_chunk) = first_data_chunk else {
+                continue;
+            };
+
+            let combined_stream =
+                futures::stream::once(async move { Ok::<Bytes, String>(first_data_chunk) })
+                    .chain(events);
+
+            if client_wants_stream {
+                return with_fallback_header(
+                    Response::builder()
+                        .header("Content-Type", "text/event-stream")
+                        .header("Cache-Control", "no-cache")
+                        .header("Connection", "keep-alive")
+                        .header("X-Account-Email", &email)
+                        .header("X-Mapped-Model", &mapped_model)
+                        .body(Body::from_stream(combined_stream))
+                        .unwrap()
+                        .into_response(),
+                    fallback_model.as_deref(),
+                );
+            }
+
+            return match collect_response(Box::pin(combined_stream)).await {
+                Ok(resp) => with_fallback_header(
+                    (
+                        StatusCode::OK,
+                        [
+                            ("X-Account-Email", email.as_str()),
+                            ("X-Mapped-Model", mapped_model.as_str()),
+                        ],
+                        Json(resp),
+                    )
+                        .into_response(),
+                    fallback_model.as_deref(),
+                ),
+                Err(e) => (
+                    StatusCode::BAD_GATEWAY,
+                    [("X-Mapped-Model", mapped_model.as_str())],
+                    format!("Stream collection error: {}", e),
+                )
+                    .into_response(),
+            };
+        }
+
+        // Handle errors and retry
+        let status_code = status.as_u16();
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| format!("HTTP {}", status_code));
+        last_error = format!("HTTP {}: {}", status_code, error_text);
+
+        tracing::error!(
+            "[Responses-Upstream] Error Response {}: {}",
+            status_code,
+            error_text
+        );
+
+        if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 {
+            token_manager
+                .mark_rate_limited_async(
+                    &email,
+                    status_code,
+                    retry_after.as_deref(),
+                    &error_text,
+                    Some(&mapped_model),
+                )
+                .await;
+        }
+
+        let strategy = determine_retry_strategy(status_code, &error_text, false);
+        if apply_retry_strategy(strategy, attempt, max_attempts, status_code, &trace_id).await {
+            continue;
+        }
+        return (
+            status,
+            [
+                ("X-Account-Email", email.as_str()),
+                ("X-Mapped-Model", mapped_model.as_str()),
+            ],
+            error_text,
+        )
+            .into_response();
+    }
+
+    // 所有尝试均失败
+    if let Some(email) = last_email {
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [("X-Account-Email", email), ("X-Mapped-Model", mapped_model)],
+            format!("All accounts exhausted. Last error: {}", last_error),
+        )
+            .into_response()
+    } else {
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            [("X-Mapped-Model", mapped_model)],
+            format!("All accounts exhausted. Last error: {}", last_error),
+        )
+            .into_response()
+    }
+}
+
+/// GET /v1/responses/:id
+pub async fn handle_get_response(caller: Caller, Path(id): Path<String>) -> Response {
+    let lookup_id = id.clone();
+    let owner = owner_of(&caller);
+    match tokio::task::spawn_blocking(move || {
+        responses_db::get_response(&lookup_id, owner.as_deref())
+    })
+    .await
+    {
+        Ok(Ok(Some(record))) => Json(record.response).into_response(),
+        Ok(Ok(None)) => not_found(&id),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// DELETE /v1/responses/:id
+pub async fn handle_delete_response(caller: Caller, Path(id): Path<String>) -> Response {
+    let lookup_id = id.clone();
+    let owner = owner_of(&caller);
+    match tokio::task::spawn_blocking(move || {
+        responses_db::delete_response(&lookup_id, owner.as_deref())
+    })
+    .await
+    {
+        Ok(Ok(true)) => Json(json!({
+            "id": id,
+            "object": "response.deleted",
+            "deleted": true
+        }))
+        .into_response(),
+        Ok(Ok(false)) => not_found(&id),
+        Ok(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
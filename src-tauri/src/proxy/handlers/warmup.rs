@@ -131,6 +131,8 @@ pub async fn handle_warmup(
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         match crate::proxy::mappers::claude::transform_claude_request_in(
@@ -255,6 +257,7 @@ pub async fn handle_warmup(
                 output_tokens: Some(0),
                 protocol: Some("warmup".to_string()),
                 username: None,
+                cache_hit: false,
             };
             state.monitor.log_request(log).await;
 
@@ -323,6 +326,7 @@ pub async fn handle_warmup(
                 output_tokens: None,
                 protocol: Some("warmup".to_string()),
                 username: None,
+                cache_hit: false,
             };
             state.monitor.log_request(log).await;
 
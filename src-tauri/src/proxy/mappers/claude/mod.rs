@@ -36,6 +36,8 @@ pub fn create_claude_sse_stream(
     estimated_prompt_tokens: Option<u32>, // [FIX] Estimated tokens for calibrator learning
     message_count: usize,                 // [NEW v4.0.0] Message count for rewind detection
     client_adapter: Option<std::sync::Arc<dyn ClientAdapter>>, // [NEW] Adapter reference
+    stop_sequences: Vec<String>,          // [NEW] Client stop_sequences for stop_reason reporting
+    single_tool_use: bool,                // [NEW] tool_choice.disable_parallel_tool_use
 ) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
     use async_stream::stream;
     use bytes::BytesMut;
@@ -49,9 +51,11 @@ pub fn create_claude_sse_stream(
         state.context_limit = context_limit;
         state.estimated_prompt_tokens = estimated_prompt_tokens; // [FIX] Pass estimated tokens
         state.set_client_adapter(client_adapter); // [NEW] Set adapter
+        state.stop_sequences = stop_sequences; // [NEW] Set client stop sequences
+        state.single_tool_use = single_tool_use;
         let mut buffer = BytesMut::new();
 
-        loop {
+        'upstream: loop {
             // [NEW] 60秒心跳保活: 延长超时时间以增加网络抖动容错
             let next_chunk = tokio::time::timeout(
                 std::time::Duration::from_secs(60),
@@ -76,6 +80,13 @@ pub fn create_claude_sse_stream(
                                             yield Ok(sse_chunk);
                                         }
                                     }
+
+                                    // [NEW] 命中客户端停止序列后已发送 message_stop，不再读取上游并释放连接
+                                    if state.stop_sequence_hit.is_some() && state.message_stop_sent {
+                                        tracing::debug!("[{}] Stop sequence hit, closing upstream stream", trace_id);
+                                        buffer.clear();
+                                        break 'upstream;
+                                    }
                                 }
                             }
                         }
@@ -93,6 +104,8 @@ pub fn create_claude_sse_stream(
             }
         }
 
+        drop(gemini_stream);
+
         // [FIX #1732] Mandatory Flush remaining buffer on stream termination
         // Prevents hangs when the last SSE chunk doesn't end with a newline (network fragmentation)
         if !buffer.is_empty() {
@@ -264,12 +277,15 @@ fn process_sse_line(
     }
     */
 
-    // 检查是否结束
+    // 检查是否结束 ([NEW] 命中客户端停止序列时无需等待上游结束)
+    let stop_sequence_finish =
+        (state.stop_sequence_hit.is_some() && !state.message_stop_sent).then_some("STOP");
     if let Some(finish_reason) = raw_json
         .get("candidates")
         .and_then(|c| c.get(0))
         .and_then(|cand| cand.get("finishReason"))
         .and_then(|f| f.as_str())
+        .or(stop_sequence_finish)
     {
         let usage = raw_json
             .get("usageMetadata")
@@ -514,8 +530,10 @@ mod tests {
             false,
             1_000,
             None,
-            1,    // message_count
-            None, // client_adapter
+            1,          // message_count
+            None,       // client_adapter
+            Vec::new(), // stop_sequences
+            false,      // single_tool_use
         );
 
         // 3. 收集输出
@@ -538,4 +556,51 @@ mod tests {
         assert!(output.contains("\"usage\":"));
         assert!(output.contains("\"output_tokens\":100")); // Should contain the recovery usage
     }
+
+    #[tokio::test]
+    async fn test_stop_sequence_hit_closes_upstream_stream() {
+        use futures::StreamExt;
+
+        // 命中停止序列后上游仍在生成 (此处永不结束)，转换后的流必须立即收尾
+        let mock_stream = async_stream::stream! {
+            for text in ["Thought: search\nObs", "ervation: result", " more text"] {
+                let chunk = serde_json::json!({
+                    "candidates": [{ "content": { "parts": [{ "text": text }] } }],
+                    "modelVersion": "gemini-2.5-flash",
+                    "responseId": "msg_stop"
+                });
+                yield Ok(bytes::Bytes::from(format!("data: {}\n\n", chunk)));
+            }
+            futures::future::pending::<()>().await;
+        };
+
+        let claude_stream = create_claude_sse_stream(
+            Box::pin(mock_stream),
+            "trace_test".to_string(),
+            "test@example.com".to_string(),
+            None,
+            false,
+            1_000,
+            None,
+            1,                                   // message_count
+            None,                                // client_adapter
+            vec!["\nObservation:".to_string()], // stop_sequences
+            false,                               // single_tool_use
+        );
+
+        let chunks: Vec<_> =
+            tokio::time::timeout(std::time::Duration::from_secs(5), claude_stream.collect())
+                .await
+                .expect("stream should finish once the stop sequence is hit");
+        let output: String = chunks
+            .into_iter()
+            .filter_map(|r| r.ok())
+            .map(|b| String::from_utf8(b.to_vec()).unwrap())
+            .collect();
+
+        assert!(output.contains("Thought: search"));
+        assert!(!output.contains("result"));
+        assert!(output.contains(r#""stop_reason":"stop_sequence""#));
+        assert_eq!(output.matches("event: message_stop").count(), 1);
+    }
 }
@@ -29,6 +29,12 @@ pub struct ClaudeRequest {
     /// Output configuration for effort level (Claude API v2.0.67+)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub output_config: Option<OutputConfig>,
+    /// [NEW] 工具选择策略: auto / any / tool / none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    /// [NEW] 自定义停止序列
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_sequences: Option<Vec<String>>,
     // [NEW] Image generation parameters (for Anthropic protocol compatibility)
     #[serde(default)]
     pub size: Option<String>,
@@ -45,6 +51,27 @@ pub struct ThinkingConfig {
     pub budget_tokens: Option<u32>,
 }
 
+/// Tool Choice 配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolChoice {
+    #[serde(rename = "type")]
+    pub choice_type: String, // "auto" | "any" | "tool" | "none"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_parallel_tool_use: Option<bool>,
+}
+
+impl ClaudeRequest {
+    /// 是否要求单次最多返回一个工具调用 (tool_choice.disable_parallel_tool_use)
+    pub fn single_tool_use(&self) -> bool {
+        self.tool_choice
+            .as_ref()
+            .and_then(|choice| choice.disable_parallel_tool_use)
+            .unwrap_or(false)
+    }
+}
+
 /// System Prompt
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -214,12 +241,24 @@ pub struct Metadata {
 }
 
 /// Output Configuration (Claude API v2.0.67+)
-/// Controls effort level for model reasoning
+/// Controls effort level for model reasoning and structured output format
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputConfig {
     /// Effort level: "high", "medium", "low"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<String>,
+    /// [NEW] Structured output format: {"type": "json_schema", "schema": {...}}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+}
+
+/// Structured Output Format (output_config.format)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputFormat {
+    #[serde(rename = "type")]
+    pub format_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
 }
 
 /// Claude API 响应
@@ -593,12 +593,7 @@ pub fn transform_claude_request_in(
 
     if let Some(tools_val) = tools {
         inner_request["tools"] = tools_val;
-        // 显式设置工具配置模式为 VALIDATED
-        inner_request["toolConfig"] = json!({
-            "functionCallingConfig": {
-                "mode": "VALIDATED"
-            }
-        });
+        inner_request["toolConfig"] = build_tool_config(claude_req.tool_choice.as_ref());
     }
 
     // Inject googleSearch tool if needed (and not already done by build_tools)
@@ -634,6 +629,7 @@ pub fn transform_claude_request_in(
                 }
 
                 gen_obj.remove("responseMimeType");
+                gen_obj.remove("responseSchema");
                 gen_obj.remove("responseModalities");
                 gen_obj.insert("imageConfig".to_string(), image_config);
             }
@@ -1816,6 +1812,16 @@ fn build_generation_config(
                 config["effortLevel"]
             );
         }
+
+        // [NEW] Structured outputs: output_config.format (json_schema) -> responseSchema
+        if let Some(format) = &output_config.format {
+            if format.format_type == "json_schema" {
+                if let Some(schema) = &format.schema {
+                    crate::proxy::mappers::common_utils::apply_response_schema(&mut config, schema);
+                    tracing::debug!("[Generation-Config] Structured output schema applied");
+                }
+            }
+        }
     }
 
     // web_search 强制 candidateCount=1
@@ -1860,11 +1866,51 @@ fn build_generation_config(
     //   2. 将其作为 stopSequence 会导致模型输出被意外截断 (如解释 SSE 协议时)
     //   3. Gemini 流的真正结束由 finishReason 字段控制,无需依赖 stopSequence
     //   4. SSE 层面的 "data: [DONE]" 已在 mod.rs 中单独处理
-    config["stopSequences"] = json!(["<|user|>", "<|end_of_turn|>", "\n\nHuman:"]);
+    // [NEW] 客户端 stop_sequences 优先下发上游，再补充默认序列 (Gemini 最多支持 5 个)
+    //       被客户端序列包含的默认序列不下发，避免上游在客户端序列之前截断导致无法回报命中的序列
+    let user_sequences = claude_req.stop_sequences.as_deref().unwrap_or_default();
+    let default_sequences = DEFAULT_STOP_SEQUENCES
+        .iter()
+        .filter(|seq| !user_sequences.iter().any(|user| user.contains(*seq)))
+        .map(|seq| seq.to_string());
+    let mut stop_sequences: Vec<String> = Vec::new();
+    for seq in user_sequences.iter().cloned().chain(default_sequences) {
+        if stop_sequences.len() >= MAX_STOP_SEQUENCES {
+            break;
+        }
+        if !seq.is_empty() && !stop_sequences.contains(&seq) {
+            stop_sequences.push(seq);
+        }
+    }
+    config["stopSequences"] = json!(stop_sequences);
 
     config
 }
 
+/// 全局默认停止序列
+const DEFAULT_STOP_SEQUENCES: [&str; 3] = ["<|user|>", "<|end_of_turn|>", "\n\nHuman:"];
+
+/// Gemini generationConfig.stopSequences 数量上限
+const MAX_STOP_SEQUENCES: usize = 5;
+
+/// [NEW] 将 Claude tool_choice 映射为 Gemini functionCallingConfig
+fn build_tool_config(tool_choice: Option<&ToolChoice>) -> Value {
+    let calling_config = match tool_choice {
+        Some(choice) => match choice.choice_type.as_str() {
+            "any" => json!({ "mode": "ANY" }),
+            "none" => json!({ "mode": "NONE" }),
+            "tool" => match &choice.name {
+                Some(name) => json!({ "mode": "ANY", "allowedFunctionNames": [name] }),
+                None => json!({ "mode": "ANY" }),
+            },
+            _ => json!({ "mode": "VALIDATED" }),
+        },
+        // 显式设置工具配置模式为 VALIDATED
+        None => json!({ "mode": "VALIDATED" }),
+    };
+    json!({ "functionCallingConfig": calling_config })
+}
+
 /// Recursively remove 'thought' and 'thoughtSignature' fields
 /// Used when downgrading thinking (e.g. during 400 retry)
 pub fn clean_thinking_fields_recursive(val: &mut Value) {
@@ -1994,6 +2040,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2004,6 +2052,51 @@ mod tests {
         assert!(body["requestId"].as_str().unwrap().starts_with("agent-"));
     }
 
+    #[test]
+    fn test_tool_choice_and_stop_sequences() {
+        let req: ClaudeRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [{ "role": "user", "content": "What's the weather?" }],
+            "tools": [{
+                "name": "get_weather",
+                "description": "Get weather",
+                "input_schema": { "type": "object", "properties": {} }
+            }],
+            "tool_choice": { "type": "tool", "name": "get_weather" },
+            "stop_sequences": ["\nObservation:", "<|user|>\n"]
+        }))
+        .unwrap();
+
+        let body = transform_claude_request_in(&req, "test-project", false).unwrap();
+        let calling_config = &body["request"]["toolConfig"]["functionCallingConfig"];
+        assert_eq!(calling_config["mode"], "ANY");
+        assert_eq!(
+            calling_config["allowedFunctionNames"],
+            json!(["get_weather"])
+        );
+        // 客户端序列优先下发，被其包含的默认序列不再下发
+        assert_eq!(
+            body["request"]["generationConfig"]["stopSequences"],
+            json!([
+                "\nObservation:",
+                "<|user|>\n",
+                "<|end_of_turn|>",
+                "\n\nHuman:"
+            ])
+        );
+
+        let none_config = build_tool_config(Some(&ToolChoice {
+            choice_type: "none".to_string(),
+            name: None,
+            disable_parallel_tool_use: None,
+        }));
+        assert_eq!(none_config["functionCallingConfig"]["mode"], "NONE");
+        assert_eq!(
+            build_tool_config(None)["functionCallingConfig"]["mode"],
+            "VALIDATED"
+        );
+    }
+
     #[test]
     fn test_clean_json_schema() {
         let mut schema = json!({
@@ -2091,6 +2184,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2161,6 +2256,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2234,6 +2331,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2284,6 +2383,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2339,6 +2440,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2387,6 +2490,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-project", false);
@@ -2578,6 +2683,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         let result = transform_claude_request_in(&req, "test-v", false).unwrap();
@@ -2614,6 +2721,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         // Should cap at 24576
@@ -2644,6 +2753,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         // Should cap
@@ -2680,6 +2791,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         // Transform
@@ -2720,6 +2833,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         // Transform
@@ -2757,6 +2872,8 @@ mod tests {
             output_config: None,
             size: Some("1024x1024".to_string()),
             quality: Some("hd".to_string()),
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         // 3. Transform request
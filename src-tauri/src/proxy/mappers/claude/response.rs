@@ -2,7 +2,7 @@
 // 对应 NonStreamingProcessor
 
 use super::models::*;
-use super::utils::to_claude_usage;
+use super::utils::{find_stop_sequence, to_claude_usage};
 use serde_json::json;
 
 /// Known parameter remappings for Gemini → Claude compatibility
@@ -552,6 +552,76 @@ pub fn transform_response(
     Ok(processor.process(gemini_response, scaling_enabled, context_limit))
 }
 
+/// [NEW] 回报客户端 stop_sequences 命中并截断非流式响应
+///
+/// 客户端序列已下发上游; 上游以 STOP 结束时先比对末尾文本，
+/// 再全文扫描作为兜底 (上游未按序列截断时丢弃其后的内容块)。
+/// 命中后回填 `stop_reason: "stop_sequence"` 与命中的序列。
+pub fn apply_stop_sequences(response: &mut ClaudeResponse, stop_sequences: &[String]) {
+    if stop_sequences.is_empty() {
+        return;
+    }
+
+    let tail_hit = if response.stop_reason == "end_turn" {
+        response
+            .content
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, block)| match block {
+                ContentBlock::Text { text } => Some((index, text)),
+                _ => None,
+            })
+            .filter(|(index, _)| *index + 1 == response.content.len())
+            .and_then(|(index, text)| {
+                stop_sequences
+                    .iter()
+                    .filter(|seq| !seq.is_empty())
+                    .find(|seq| text.ends_with(seq.as_str()))
+                    .map(|seq| (index, text.len() - seq.len(), seq.clone()))
+            })
+    } else {
+        None
+    };
+
+    let hit = tail_hit.or_else(|| {
+        response
+            .content
+            .iter()
+            .enumerate()
+            .find_map(|(index, block)| match block {
+                ContentBlock::Text { text } => {
+                    find_stop_sequence(text, stop_sequences).map(|(pos, seq)| (index, pos, seq))
+                }
+                _ => None,
+            })
+    });
+
+    if let Some((index, pos, seq)) = hit {
+        response.content.truncate(index + 1);
+        if let Some(ContentBlock::Text { text }) = response.content.last_mut() {
+            text.truncate(pos);
+        }
+        if !response
+            .content
+            .iter()
+            .any(|b| matches!(b, ContentBlock::ToolUse { .. }))
+        {
+            response.stop_reason = "stop_sequence".to_string();
+            response.stop_sequence = Some(seq);
+        }
+    }
+}
+
+/// [NEW] tool_choice.disable_parallel_tool_use: Gemini 没有对应参数，仅保留第一个工具调用
+pub fn keep_first_tool_use(response: &mut ClaudeResponse) {
+    let mut seen_tool_use = false;
+    response.content.retain(|block| match block {
+        ContentBlock::ToolUse { .. } => !std::mem::replace(&mut seen_tool_use, true),
+        _ => true,
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -674,4 +744,123 @@ mod tests {
             _ => panic!("Expected Text block"),
         }
     }
+
+    #[test]
+    fn test_upstream_truncated_response_is_not_a_stop_sequence_hit() {
+        // 上游已在序列处截断 (序列被剥离, finishReason 仍为 STOP)，与自然结束无法区分
+        let gemini_resp = GeminiResponse {
+            candidates: Some(vec![Candidate {
+                content: Some(GeminiContent {
+                    role: "model".to_string(),
+                    parts: vec![GeminiPart {
+                        text: Some("Thought: I need to search".to_string()),
+                        thought: None,
+                        thought_signature: None,
+                        function_call: None,
+                        function_response: None,
+                        inline_data: None,
+                    }],
+                }),
+                finish_reason: Some("STOP".to_string()),
+                index: Some(0),
+                grounding_metadata: None,
+            }]),
+            usage_metadata: None,
+            model_version: Some("gemini-2.5-flash".to_string()),
+            response_id: Some("resp_456".to_string()),
+        };
+
+        let mut claude_resp =
+            transform_response(&gemini_resp, false, 1_000_000, None, String::new(), 1).unwrap();
+        apply_stop_sequences(&mut claude_resp, &["\nObservation:".to_string()]);
+
+        // 末尾与全文均未匹配到客户端序列，按自然结束回报
+        assert_eq!(claude_resp.stop_reason, "end_turn");
+        assert_eq!(claude_resp.stop_sequence, None);
+        match &claude_resp.content[0] {
+            ContentBlock::Text { text } => assert_eq!(text, "Thought: I need to search"),
+            _ => panic!("Expected Text block"),
+        }
+    }
+
+    #[test]
+    fn test_keep_first_tool_use() {
+        let tool_use = |id: &str| ContentBlock::ToolUse {
+            id: id.to_string(),
+            name: "get_weather".to_string(),
+            input: json!({}),
+            signature: None,
+            cache_control: None,
+        };
+        let mut response = ClaudeResponse {
+            id: "msg_2".to_string(),
+            type_: "message".to_string(),
+            role: "assistant".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            content: vec![
+                ContentBlock::Text {
+                    text: "Checking both cities".to_string(),
+                },
+                tool_use("call_1"),
+                tool_use("call_2"),
+            ],
+            stop_reason: "tool_use".to_string(),
+            stop_sequence: None,
+            usage: Usage {
+                input_tokens: 1,
+                output_tokens: 1,
+                cache_read_input_tokens: None,
+                cache_creation_input_tokens: None,
+                server_tool_use: None,
+            },
+        };
+
+        keep_first_tool_use(&mut response);
+        assert_eq!(response.content.len(), 2);
+        assert!(matches!(&response.content[1], ContentBlock::ToolUse { id, .. } if id == "call_1"));
+    }
+
+    #[test]
+    fn test_apply_stop_sequences() {
+        let mut response = ClaudeResponse {
+            id: "msg_1".to_string(),
+            type_: "message".to_string(),
+            role: "assistant".to_string(),
+            model: "gemini-2.5-flash".to_string(),
+            content: vec![ContentBlock::Text {
+                text: "Answer: 42\nHuman: next".to_string(),
+            }],
+            stop_reason: "end_turn".to_string(),
+            stop_sequence: None,
+            usage: Usage {
+                input_tokens: 1,
+                output_tokens: 1,
+                cache_read_input_tokens: None,
+                cache_creation_input_tokens: None,
+                server_tool_use: None,
+            },
+        };
+
+        apply_stop_sequences(&mut response, &["\nHuman:".to_string()]);
+        assert_eq!(response.stop_reason, "stop_sequence");
+        assert_eq!(response.stop_sequence.as_deref(), Some("\nHuman:"));
+        match &response.content[0] {
+            ContentBlock::Text { text } => assert_eq!(text, "Answer: 42"),
+            _ => panic!("Expected Text block"),
+        }
+
+        // 上游以 STOP 结束且末尾保留了序列
+        response.content = vec![ContentBlock::Text {
+            text: "Thought: search\nObservation:".to_string(),
+        }];
+        response.stop_reason = "end_turn".to_string();
+        response.stop_sequence = None;
+        apply_stop_sequences(&mut response, &["\nObservation:".to_string()]);
+        assert_eq!(response.stop_reason, "stop_sequence");
+        assert_eq!(response.stop_sequence.as_deref(), Some("\nObservation:"));
+        match &response.content[0] {
+            ContentBlock::Text { text } => assert_eq!(text, "Thought: search"),
+            _ => panic!("Expected Text block"),
+        }
+    }
 }
@@ -2,7 +2,7 @@
 // 对应 StreamingState + PartProcessor
 
 use super::models::*;
-use super::utils::to_claude_usage;
+use super::utils::{find_stop_sequence, partial_stop_sequence_len, to_claude_usage};
 use crate::proxy::mappers::estimation_calibrator::get_calibrator;
 // use crate::proxy::mappers::signature_store::store_thought_signature; // Deprecated
 use crate::proxy::common::client_adapter::{ClientAdapter, SignatureBufferStrategy}; // [NEW]
@@ -232,6 +232,12 @@ pub struct StreamingState {
     pub has_content: bool,
     pub message_count: usize, // [NEW v4.0.0] Message count for rewind detection
     pub client_adapter: Option<std::sync::Arc<dyn ClientAdapter>>, // [FIX] Remove Box, use Arc<dyn> directly
+    // [NEW] 客户端 stop_sequences: 暂缓输出可能构成序列前缀的尾部文本
+    pub stop_sequences: Vec<String>,
+    stop_holdback: String,
+    pub stop_sequence_hit: Option<String>,
+    // [NEW] tool_choice.disable_parallel_tool_use: 仅输出第一个工具调用
+    pub single_tool_use: bool,
 }
 
 impl StreamingState {
@@ -260,14 +266,58 @@ impl StreamingState {
             has_content: false,
             message_count: 0,
             client_adapter: None,
+            stop_sequences: Vec::new(),
+            stop_holdback: String::new(),
+            stop_sequence_hit: None,
+            single_tool_use: false,
         }
     }
 
+    /// [NEW] 按 stop_sequences 过滤文本，返回可以立即输出的部分
+    ///
+    /// 命中后记录序列并丢弃其后的所有文本；未命中时保留可能是序列前缀的尾部，等待后续分片。
+    pub fn filter_stop_sequences(&mut self, text: &str) -> String {
+        if self.stop_sequences.is_empty() {
+            return text.to_string();
+        }
+        if self.stop_sequence_hit.is_some() {
+            return String::new();
+        }
+
+        let mut combined = std::mem::take(&mut self.stop_holdback);
+        combined.push_str(text);
+
+        if let Some((pos, seq)) = find_stop_sequence(&combined, &self.stop_sequences) {
+            self.stop_sequence_hit = Some(seq);
+            self.has_content = true;
+            combined.truncate(pos);
+            return combined;
+        }
+
+        let keep = partial_stop_sequence_len(&combined, &self.stop_sequences);
+        self.stop_holdback = combined.split_off(combined.len() - keep);
+        combined
+    }
+
     // [NEW] Set client adapter
     pub fn set_client_adapter(&mut self, adapter: Option<std::sync::Arc<dyn ClientAdapter>>) {
         self.client_adapter = adapter;
     }
 
+    /// [NEW] 输出暂缓的尾部文本 (后续内容已证明其不构成停止序列)
+    pub fn flush_stop_holdback(&mut self) -> Vec<Bytes> {
+        if self.stop_holdback.is_empty() {
+            return vec![];
+        }
+        let text = std::mem::take(&mut self.stop_holdback);
+        let mut chunks = Vec::new();
+        if self.block_type != BlockType::Text {
+            chunks.extend(self.start_block(BlockType::Text, json!({ "type": "text", "text": "" })));
+        }
+        chunks.push(self.emit_delta("text_delta", json!({ "text": text })));
+        chunks
+    }
+
     /// 发送 SSE 事件
     pub fn emit(&self, event_type: &str, data: serde_json::Value) -> Bytes {
         let sse = format!(
@@ -404,6 +454,9 @@ impl StreamingState {
     ) -> Vec<Bytes> {
         let mut chunks = Vec::new();
 
+        // [NEW] 输出未命中停止序列而暂缓的尾部文本
+        chunks.extend(self.flush_stop_holdback());
+
         // 关闭最后一个块
         chunks.extend(self.end_block());
 
@@ -475,6 +528,8 @@ impl StreamingState {
         // 确定 stop_reason
         let stop_reason = if self.used_tool {
             "tool_use"
+        } else if self.stop_sequence_hit.is_some() {
+            "stop_sequence"
         } else if finish_reason == Some("MAX_TOKENS") {
             "max_tokens"
         } else {
@@ -512,7 +567,10 @@ impl StreamingState {
             "message_delta",
             json!({
                 "type": "message_delta",
-                "delta": { "stop_reason": stop_reason, "stop_sequence": null },
+                "delta": {
+                    "stop_reason": stop_reason,
+                    "stop_sequence": if stop_reason == "stop_sequence" { self.stop_sequence_hit.clone() } else { None },
+                },
                 "usage": usage
             }),
         ));
@@ -645,6 +703,10 @@ impl<'a> PartProcessor<'a> {
     /// 处理单个 part
     pub fn process(&mut self, part: &GeminiPart) -> Vec<Bytes> {
         let mut chunks = Vec::new();
+        // [NEW] 已命中客户端停止序列，忽略后续所有内容
+        if self.state.stop_sequence_hit.is_some() {
+            return chunks;
+        }
         // [FIX #545] Decode Base64 signature if present (Gemini sends Base64, Claude expects Raw)
         let signature = part.thought_signature.as_ref().map(|sig| {
             // Try to decode as base64
@@ -669,6 +731,11 @@ impl<'a> PartProcessor<'a> {
 
         // 1. FunctionCall 处理
         if let Some(fc) = &part.function_call {
+            // [NEW] 禁用并行工具调用时丢弃后续的 functionCall
+            if self.state.single_tool_use && self.state.used_tool {
+                return chunks;
+            }
+            chunks.extend(self.state.flush_stop_holdback());
             // 先处理 trailingSignature (B4/C3 场景)
             if self.state.has_trailing_signature() {
                 chunks.extend(self.state.end_block());
@@ -865,6 +932,7 @@ impl<'a> PartProcessor<'a> {
             // 这种情况下, 我们只需确签被缓存在状态中。
             self.state.store_signature(signature);
 
+            let text = self.state.filter_stop_sequences(text);
             chunks.extend(
                 self.state
                     .start_block(BlockType::Text, json!({ "type": "text", "text": "" })),
@@ -946,6 +1014,11 @@ impl<'a> PartProcessor<'a> {
             return vec![];
         }
 
+        let text = self.state.filter_stop_sequences(text);
+        if text.is_empty() {
+            return chunks;
+        }
+
         if self.state.current_block_type() != BlockType::Text {
             chunks.extend(
                 self.state
@@ -1070,6 +1143,70 @@ mod tests {
         assert!(s.contains("\"foo\":\"bar\""));
     }
 
+    #[test]
+    fn test_stop_sequence_across_chunks() {
+        let mut state = StreamingState::new();
+        state.stop_sequences = vec!["\nObservation:".to_string()];
+
+        let mut output = String::new();
+        for text in ["Thought: search\nObs", "ervation: result", "more text"] {
+            let part = GeminiPart {
+                text: Some(text.to_string()),
+                function_call: None,
+                inline_data: None,
+                thought: None,
+                thought_signature: None,
+                function_response: None,
+            };
+            let mut processor = PartProcessor::new(&mut state);
+            for chunk in processor.process(&part) {
+                output.push_str(std::str::from_utf8(&chunk).unwrap());
+            }
+        }
+        for chunk in state.emit_finish(Some("STOP"), None) {
+            output.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        assert!(output.contains(r#""text":"Thought: search""#));
+        assert!(!output.contains("result"));
+        assert!(!output.contains("more text"));
+        assert!(output.contains(r#""stop_reason":"stop_sequence""#));
+        assert!(output.contains(r#""stop_sequence":"\nObservation:""#));
+    }
+
+    #[test]
+    fn test_single_tool_use_drops_parallel_calls() {
+        let mut state = StreamingState::new();
+        state.single_tool_use = true;
+
+        let mut output = String::new();
+        for (name, id) in [("first_tool", "call_1"), ("second_tool", "call_2")] {
+            let part = GeminiPart {
+                text: None,
+                function_call: Some(FunctionCall {
+                    name: name.to_string(),
+                    args: Some(json!({})),
+                    id: Some(id.to_string()),
+                }),
+                inline_data: None,
+                thought: None,
+                thought_signature: None,
+                function_response: None,
+            };
+            let mut processor = PartProcessor::new(&mut state);
+            for chunk in processor.process(&part) {
+                output.push_str(std::str::from_utf8(&chunk).unwrap());
+            }
+        }
+        for chunk in state.emit_finish(Some("STOP"), None) {
+            output.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+
+        assert!(output.contains(r#""name":"first_tool""#));
+        assert!(!output.contains("second_tool"));
+        assert!(output.contains(r#""stop_reason":"tool_use""#));
+    }
+
     #[test]
     fn test_process_function_call_deltas() {
         let mut state = StreamingState::new();
@@ -105,13 +105,47 @@ pub fn to_claude_usage(
     }
 }
 
-/// 提取 thoughtSignature
-// 已移除未使用的 extract_thought_signature 函数
+/// [NEW] 查找文本中最早出现的停止序列，返回 (字节位置, 命中的序列)
+pub fn find_stop_sequence(text: &str, stop_sequences: &[String]) -> Option<(usize, String)> {
+    stop_sequences
+        .iter()
+        .filter(|seq| !seq.is_empty())
+        .filter_map(|seq| text.find(seq.as_str()).map(|idx| (idx, seq.clone())))
+        .min_by_key(|(idx, _)| *idx)
+}
+
+/// [NEW] 流式场景下需暂缓输出的尾部长度: 文本末尾可能是某个停止序列的前缀
+pub fn partial_stop_sequence_len(text: &str, stop_sequences: &[String]) -> usize {
+    stop_sequences
+        .iter()
+        .flat_map(|seq| {
+            seq.char_indices()
+                .skip(1)
+                .map(|(i, _)| &seq[..i])
+                .filter(|prefix| text.ends_with(prefix))
+                .map(|prefix| prefix.len())
+        })
+        .max()
+        .unwrap_or(0)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_stop_sequence_helpers() {
+        let seqs = vec!["STOP".to_string(), "\nObservation:".to_string()];
+        assert_eq!(
+            find_stop_sequence("foo\nObservation: x STOP", &seqs),
+            Some((3, "\nObservation:".to_string()))
+        );
+        assert_eq!(find_stop_sequence("nothing here", &seqs), None);
+        assert_eq!(partial_stop_sequence_len("hello\nObs", &seqs), 4);
+        assert_eq!(partial_stop_sequence_len("hello ST", &seqs), 2);
+        assert_eq!(partial_stop_sequence_len("hello", &seqs), 0);
+    }
+
     #[test]
     fn test_to_claude_usage() {
         use super::super::models::UsageMetadata;
@@ -333,6 +333,17 @@ pub fn inject_google_search_tool(body: &mut Value) {
     }
 }
 
+/// [NEW] 结构化输出：清洗 JSON Schema 后写入 generationConfig.responseSchema
+///
+/// Gemini 会按 responseSchema 做约束解码，保证输出结构；不支持的关键字
+/// ($ref、additionalProperties 等) 由 clean_json_schema 展开或剔除。
+pub fn apply_response_schema(gen_config: &mut Value, schema: &Value) {
+    let mut schema = schema.clone();
+    crate::proxy::common::json_schema::clean_json_schema(&mut schema);
+    gen_config["responseMimeType"] = json!("application/json");
+    gen_config["responseSchema"] = schema;
+}
+
 /// 深度迭代清理客户端发送的 [undefined] 脏字符串，防止 Gemini 接口校验失败
 pub fn deep_clean_undefined(value: &mut Value) {
     match value {
@@ -4,6 +4,7 @@
 //! to prevent "Prompt is too long" errors and avoid invalid signatures.
 
 use super::claude::models::{ClaudeRequest, ContentBlock, Message, MessageContent, SystemPrompt};
+use serde_json::Value;
 use tracing::{debug, info};
 
 /// Helper to estimate tokens from text with multi-language awareness
@@ -36,6 +37,34 @@ fn estimate_tokens_from_str(s: &str) -> u32 {
     ((ascii_tokens + unicode_tokens) as f32 * 1.15).ceil() as u32
 }
 
+/// Estimate tokens for a Gemini `parts` array
+fn estimate_gemini_parts(parts: Option<&Value>) -> u32 {
+    let Some(parts) = parts.and_then(|p| p.as_array()) else {
+        return 0;
+    };
+
+    let mut total = 0;
+    for part in parts {
+        if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+            total += estimate_tokens_from_str(text);
+        } else if let Some(call) = part.get("functionCall") {
+            total += 20; // Function call overhead
+            if let Ok(json_str) = serde_json::to_string(call) {
+                total += estimate_tokens_from_str(&json_str);
+            }
+        } else if let Some(resp) = part.get("functionResponse") {
+            total += 10; // Result overhead
+            if let Ok(json_str) = serde_json::to_string(resp) {
+                total += estimate_tokens_from_str(&json_str);
+            }
+        } else if part.get("inlineData").is_some() || part.get("fileData").is_some() {
+            // Images / media are billed at a roughly fixed rate upstream
+            total += 258;
+        }
+    }
+    total
+}
+
 /// Strategy for context purification
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PurificationStrategy {
@@ -198,6 +227,34 @@ impl ContextManager {
         total
     }
 
+    /// Estimate token usage for a Gemini-native request body
+    ///
+    /// Counterpart of `estimate_token_usage` for `contents` / `systemInstruction` / `tools`
+    /// payloads (used when upstream countTokens is unavailable).
+    pub fn estimate_gemini_token_usage(body: &Value) -> u32 {
+        let mut total = 0;
+
+        if let Some(sys) = body.get("systemInstruction") {
+            total += estimate_gemini_parts(sys.get("parts"));
+        }
+
+        if let Some(contents) = body.get("contents").and_then(|c| c.as_array()) {
+            for content in contents {
+                // Message overhead
+                total += 4;
+                total += estimate_gemini_parts(content.get("parts"));
+            }
+        }
+
+        if let Some(tools) = body.get("tools") {
+            if let Ok(json_str) = serde_json::to_string(tools) {
+                total += estimate_tokens_from_str(&json_str);
+            }
+        }
+
+        total
+    }
+
     // ===== [Layer 2] Thinking Content Compression + Signature Preservation =====
     // Borrowed from learn-claude-code's "append-only log" principle
     // This layer compresses thinking text but PRESERVES signatures
@@ -459,6 +516,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         }
     }
 
@@ -475,6 +534,25 @@ mod tests {
         assert!(tokens < 50);
     }
 
+    #[test]
+    fn test_estimate_gemini_tokens() {
+        let body = serde_json::json!({
+            "systemInstruction": {"parts": [{"text": "You are helpful"}]},
+            "contents": [
+                {"role": "user", "parts": [{"text": "Hello World"}]},
+                {"role": "model", "parts": [{"functionCall": {"name": "ls", "args": {}}}]}
+            ]
+        });
+
+        let tokens = ContextManager::estimate_gemini_token_usage(&body);
+        assert!(tokens > 30);
+        assert!(tokens < 100);
+        assert_eq!(
+            ContextManager::estimate_gemini_token_usage(&serde_json::json!({})),
+            0
+        );
+    }
+
     #[test]
     fn test_purify_history_soft() {
         // Construct history of 6 messages (indices 0-5)
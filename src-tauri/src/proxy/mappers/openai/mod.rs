@@ -4,6 +4,7 @@
 pub mod collector; // [NEW]
 pub mod models;
 pub mod request;
+pub mod responses;
 pub mod response;
 pub mod streaming;
 pub mod thinking_recovery;
@@ -535,9 +535,36 @@ pub fn transform_openai_request(
         }
     }
 
+    // 非严格模式的 json_schema 只能作为提示注入系统指令
+    let mut schema_hint: Option<String> = None;
     if let Some(fmt) = &request.response_format {
-        if fmt.r#type == "json_object" {
-            gen_config["responseMimeType"] = json!("application/json");
+        match fmt.r#type.as_str() {
+            "json_object" => {
+                gen_config["responseMimeType"] = json!("application/json");
+            }
+            // [NEW] 结构化输出：strict=true 时映射为 responseSchema 约束解码
+            "json_schema" => {
+                let spec = fmt.json_schema.as_ref();
+                match spec.and_then(|s| s.schema.as_ref()) {
+                    Some(schema) if spec.and_then(|s| s.strict).unwrap_or(false) => {
+                        crate::proxy::mappers::common_utils::apply_response_schema(
+                            &mut gen_config,
+                            schema,
+                        );
+                    }
+                    Some(schema) => {
+                        gen_config["responseMimeType"] = json!("application/json");
+                        schema_hint = Some(format!(
+                            "Respond with a JSON object that conforms to this JSON Schema:\n{}",
+                            schema
+                        ));
+                    }
+                    None => {
+                        gen_config["responseMimeType"] = json!("application/json");
+                    }
+                }
+            }
+            _ => {}
         }
     }
 
@@ -683,6 +710,11 @@ pub fn transform_openai_request(
         parts.push(json!({"text": inst}));
     }
 
+    // 4. [NEW] 非严格 json_schema 的结构提示
+    if let Some(hint) = schema_hint {
+        parts.push(json!({"text": hint}));
+    }
+
     inner_request["systemInstruction"] = json!({
         "role": "user",
         "parts": parts
@@ -701,6 +733,7 @@ pub fn transform_openai_request(
                 // [REMOVED] thinkingConfig 拦截已删除，允许图像生成时输出思维链
                 // gen_obj.remove("thinkingConfig");
                 gen_obj.remove("responseMimeType");
+                gen_obj.remove("responseSchema");
                 gen_obj.remove("responseModalities");
                 gen_obj.insert("imageConfig".to_string(), image_config);
             }
@@ -1225,4 +1258,37 @@ mod tests {
         // 4. Reset global mode
         crate::proxy::config::update_image_thinking_mode(Some("enabled".to_string()));
     }
+
+    #[test]
+    fn test_json_schema_response_format() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"],
+            "additionalProperties": false
+        });
+        let mut req: OpenAIRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.5-flash",
+            "messages": [{ "role": "user", "content": "extract" }],
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "person", "schema": schema, "strict": true }
+            }
+        }))
+        .unwrap();
+
+        // strict: 约束解码
+        let (result, _, _) = transform_openai_request(&req, "test-proj", "gemini-2.5-flash");
+        let gen_config = &result["request"]["generationConfig"];
+        assert_eq!(gen_config["responseMimeType"], "application/json");
+        assert_eq!(gen_config["responseSchema"]["required"][0], "name");
+        assert!(gen_config["responseSchema"].get("additionalProperties").is_none());
+
+        // 非 strict: 仅 JSON 模式 + 系统提示
+        req.response_format.as_mut().unwrap().json_schema.as_mut().unwrap().strict = None;
+        let (result, _, _) = transform_openai_request(&req, "test-proj", "gemini-2.5-flash");
+        assert!(result["request"]["generationConfig"].get("responseSchema").is_none());
+        let sys_text = result["request"]["systemInstruction"].to_string();
+        assert!(sys_text.contains("JSON Schema"));
+    }
 }
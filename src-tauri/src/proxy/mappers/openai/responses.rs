@@ -0,0 +1,773 @@
+// OpenAI Responses API 协议转换
+// 负责 Responses input items → Chat messages 以及 Gemini SSE → response.* 事件流
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::pin::Pin;
+use tracing::debug;
+use uuid::Uuid;
+
+use super::streaming::{normalize_shell_args, store_thought_signature};
+
+/// 响应完成时的回调 (用于持久化最终 Response 对象)
+pub type ResponseCompleteHook = Box<dyn FnOnce(Value) + Send>;
+
+/// 生成 Response ID (resp_ 前缀，与 OpenAI 格式一致)
+pub fn new_response_id() -> String {
+    format!("resp_{}", Uuid::new_v4().simple())
+}
+
+/// 将 `input` 字段标准化为 item 数组
+///
+/// - 字符串 → 单条 user message
+/// - 仅含 role 的简写消息 (EasyInputMessage) → 补全 `type: "message"`
+pub fn normalize_input_items(input: Option<&Value>) -> Vec<Value> {
+    match input {
+        Some(Value::String(s)) => vec![json!({
+            "type": "message",
+            "role": "user",
+            "content": [{ "type": "input_text", "text": s }]
+        })],
+        Some(Value::Array(arr)) => arr
+            .iter()
+            .map(|item| {
+                let mut item = item.clone();
+                if item.get("type").is_none() && item.get("role").is_some() {
+                    item["type"] = json!("message");
+                }
+                item
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 将 Responses input items 转换为 Chat Completions messages
+///
+/// 支持 message / function_call / local_shell_call / web_search_call / function_call_output。
+pub fn input_items_to_messages(instructions: Option<&str>, items: &[Value]) -> Vec<Value> {
+    let mut messages = Vec::new();
+
+    // System Instructions
+    if let Some(instructions) = instructions.filter(|s| !s.is_empty()) {
+        messages.push(json!({ "role": "system", "content": instructions }));
+    }
+
+    let mut call_id_to_name = HashMap::new();
+
+    // Pass 1: Build Call ID to Name Map
+    for item in items {
+        let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        match item_type {
+            "function_call" | "local_shell_call" | "web_search_call" => {
+                let call_id = item
+                    .get("call_id")
+                    .and_then(|v| v.as_str())
+                    .or_else(|| item.get("id").and_then(|v| v.as_str()))
+                    .unwrap_or("unknown");
+
+                let name = if item_type == "local_shell_call" {
+                    "shell"
+                } else if item_type == "web_search_call" {
+                    "google_search"
+                } else {
+                    item.get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("unknown")
+                };
+
+                call_id_to_name.insert(call_id.to_string(), name.to_string());
+                debug!("Mapped call_id {} to name {}", call_id, name);
+            }
+            _ => {}
+        }
+    }
+
+    // Pass 2: Map Input Items to Messages
+    for item in items {
+        let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        match item_type {
+            "message" => {
+                let role = item.get("role").and_then(|v| v.as_str()).unwrap_or("user");
+                let mut text_parts = Vec::new();
+                let mut image_parts: Vec<Value> = Vec::new();
+
+                // 简写消息的 content 可以直接是字符串
+                if let Some(text) = item.get("content").and_then(|v| v.as_str()) {
+                    text_parts.push(text.to_string());
+                }
+
+                if let Some(parts) = item.get("content").and_then(|v| v.as_array()) {
+                    for part in parts {
+                        // 处理文本块 (input_text / output_text)
+                        if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                            text_parts.push(text.to_string());
+                        }
+                        // [NEW] 处理图像块 (Codex input_image 格式)
+                        else if part.get("type").and_then(|v| v.as_str()) == Some("input_image") {
+                            if let Some(image_url) = part.get("image_url").and_then(|v| v.as_str())
+                            {
+                                image_parts.push(json!({
+                                    "type": "image_url",
+                                    "image_url": { "url": image_url }
+                                }));
+                                debug!("[Codex] Found input_image: {}", image_url);
+                            }
+                        }
+                        // [NEW] 兼容标准 OpenAI image_url 格式
+                        else if part.get("type").and_then(|v| v.as_str()) == Some("image_url") {
+                            if let Some(url_obj) = part.get("image_url") {
+                                image_parts.push(json!({
+                                    "type": "image_url",
+                                    "image_url": url_obj.clone()
+                                }));
+                            }
+                        }
+                    }
+                }
+
+                // 构造消息内容：如果有图像则使用数组格式
+                if image_parts.is_empty() {
+                    messages.push(json!({
+                        "role": role,
+                        "content": text_parts.join("\n")
+                    }));
+                } else {
+                    let mut content_blocks: Vec<Value> = Vec::new();
+                    if !text_parts.is_empty() {
+                        content_blocks.push(json!({
+                            "type": "text",
+                            "text": text_parts.join("\n")
+                        }));
+                    }
+                    content_blocks.extend(image_parts);
+                    messages.push(json!({
+                        "role": role,
+                        "content": content_blocks
+                    }));
+                }
+            }
+            "function_call" | "local_shell_call" | "web_search_call" => {
+                let mut name = item
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown");
+                let mut args_str = item
+                    .get("arguments")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("{}")
+                    .to_string();
+                let call_id = item
+                    .get("call_id")
+                    .and_then(|v| v.as_str())
+                    .or_else(|| item.get("id").and_then(|v| v.as_str()))
+                    .unwrap_or("unknown");
+
+                // Handle native shell calls
+                if item_type == "local_shell_call" {
+                    name = "shell";
+                    if let Some(exec) = item.get("action").and_then(|a| a.get("exec")) {
+                        let mut args_obj = serde_json::Map::new();
+                        if let Some(cmd) = exec.get("command") {
+                            // CRITICAL FIX: The 'shell' tool schema defines 'command' as an ARRAY of strings.
+                            // We MUST pass it as an array, not a joined string, otherwise Gemini rejects with 400 INVALID_ARGUMENT.
+                            let cmd_val = if cmd.is_string() {
+                                json!([cmd]) // Wrap in array
+                            } else {
+                                cmd.clone() // Assume already array
+                            };
+                            args_obj.insert("command".to_string(), cmd_val);
+                        }
+                        if let Some(wd) = exec.get("working_directory").or(exec.get("workdir")) {
+                            args_obj.insert("workdir".to_string(), wd.clone());
+                        }
+                        args_str = serde_json::to_string(&args_obj).unwrap_or("{}".to_string());
+                    }
+                } else if item_type == "web_search_call" {
+                    name = "google_search";
+                    if let Some(action) = item.get("action") {
+                        let mut args_obj = serde_json::Map::new();
+                        if let Some(q) = action.get("query") {
+                            args_obj.insert("query".to_string(), q.clone());
+                        }
+                        args_str = serde_json::to_string(&args_obj).unwrap_or("{}".to_string());
+                    }
+                }
+
+                messages.push(json!({
+                    "role": "assistant",
+                    "tool_calls": [
+                        {
+                            "id": call_id,
+                            "type": "function",
+                            "function": {
+                                "name": name,
+                                "arguments": args_str
+                            }
+                        }
+                    ]
+                }));
+            }
+            "function_call_output" | "custom_tool_call_output" => {
+                let call_id = item
+                    .get("call_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown");
+                let output_str = match item.get("output") {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(o) => o
+                        .get("content")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| o.to_string()),
+                    None => String::new(),
+                };
+
+                let name = call_id_to_name.get(call_id).cloned().unwrap_or_else(|| {
+                    // Fallback: if unknown and we see function_call_output, it's likely "shell" in this context
+                    tracing::warn!(
+                        "Unknown tool name for call_id {}, defaulting to 'shell'",
+                        call_id
+                    );
+                    "shell".to_string()
+                });
+
+                messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": call_id,
+                    "name": name,
+                    "content": output_str
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    messages
+}
+
+/// 将 Responses 请求体转换为 Chat Completions 请求体 (供 transform_openai_request 复用)
+pub fn build_chat_request(body: &Value, messages: Vec<Value>) -> Value {
+    let mut chat = json!({
+        "model": body.get("model").cloned().unwrap_or(json!("")),
+        "messages": messages,
+        "stream": body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false),
+    });
+
+    for key in ["temperature", "top_p", "tools", "parallel_tool_calls"] {
+        if let Some(v) = body.get(key).filter(|v| !v.is_null()) {
+            chat[key] = v.clone();
+        }
+    }
+    if let Some(max) = body.get("max_output_tokens").filter(|v| !v.is_null()) {
+        chat["max_tokens"] = max.clone();
+    }
+
+    // text.format (Responses) → response_format (Chat)
+    if let Some(format) = body.get("text").and_then(|t| t.get("format")) {
+        match format.get("type").and_then(|v| v.as_str()) {
+            Some("json_schema") => {
+                chat["response_format"] = json!({
+                    "type": "json_schema",
+                    "json_schema": {
+                        "name": format.get("name"),
+                        "schema": format.get("schema"),
+                        "strict": format.get("strict"),
+                    }
+                });
+            }
+            Some("json_object") => chat["response_format"] = json!({ "type": "json_object" }),
+            _ => {}
+        }
+    }
+
+    // Responses 格式 {"type":"function","name":"x"} → Chat 格式 {"type":"function","function":{"name":"x"}}
+    if let Some(tool_choice) = body.get("tool_choice").filter(|v| !v.is_null()) {
+        chat["tool_choice"] = match tool_choice.get("name").and_then(|v| v.as_str()) {
+            Some(name) if tool_choice.get("function").is_none() => {
+                json!({ "type": "function", "function": { "name": name } })
+            }
+            _ => tool_choice.clone(),
+        };
+    }
+
+    chat
+}
+
+/// 构造初始 Response 对象 (status = in_progress)
+pub fn build_response_skeleton(body: &Value, response_id: &str, created_at: i64) -> Value {
+    let field = |key: &str| body.get(key).cloned().unwrap_or(Value::Null);
+    json!({
+        "id": response_id,
+        "object": "response",
+        "created_at": created_at,
+        "status": "in_progress",
+        "error": null,
+        "incomplete_details": null,
+        "instructions": field("instructions"),
+        "max_output_tokens": field("max_output_tokens"),
+        "model": field("model"),
+        "output": [],
+        "parallel_tool_calls": body.get("parallel_tool_calls").cloned().unwrap_or(json!(true)),
+        "previous_response_id": field("previous_response_id"),
+        "store": body.get("store").and_then(|v| v.as_bool()).unwrap_or(true),
+        "temperature": field("temperature"),
+        "text": body.get("text").cloned().unwrap_or(json!({ "format": { "type": "text" } })),
+        "tool_choice": body.get("tool_choice").cloned().unwrap_or(json!("auto")),
+        "tools": body.get("tools").cloned().unwrap_or(json!([])),
+        "top_p": field("top_p"),
+        "usage": null,
+        "metadata": body.get("metadata").cloned().unwrap_or(json!({})),
+    })
+}
+
+/// Gemini usageMetadata → Responses usage
+fn map_usage(u: &Value) -> Value {
+    let get = |key: &str| u.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    let input_tokens = get("promptTokenCount");
+    let reasoning_tokens = get("thoughtsTokenCount");
+    let output_tokens = get("candidatesTokenCount") + reasoning_tokens;
+    json!({
+        "input_tokens": input_tokens,
+        "input_tokens_details": { "cached_tokens": get("cachedContentTokenCount") },
+        "output_tokens": output_tokens,
+        "output_tokens_details": { "reasoning_tokens": reasoning_tokens },
+        "total_tokens": input_tokens + output_tokens,
+    })
+}
+
+/// 正在输出的 assistant 文本消息
+struct OpenTextItem {
+    id: String,
+    output_index: usize,
+    text: String,
+}
+
+/// response.* 事件写入器，维护 sequence_number 与 output items 状态
+struct ResponseEventWriter {
+    response: Value,
+    output: Vec<Value>,
+    sequence_number: u64,
+    text_item: Option<OpenTextItem>,
+}
+
+impl ResponseEventWriter {
+    fn new(skeleton: Value) -> Self {
+        Self {
+            response: skeleton,
+            output: Vec::new(),
+            sequence_number: 0,
+            text_item: None,
+        }
+    }
+
+    fn emit(&mut self, event_type: &str, mut payload: Value) -> Bytes {
+        payload["type"] = json!(event_type);
+        payload["sequence_number"] = json!(self.sequence_number);
+        self.sequence_number += 1;
+        Bytes::from(format!(
+            "event: {}\ndata: {}\n\n",
+            event_type,
+            serde_json::to_string(&payload).unwrap_or_default()
+        ))
+    }
+
+    fn start(&mut self) -> Vec<Bytes> {
+        let response = self.response.clone();
+        vec![
+            self.emit("response.created", json!({ "response": response })),
+            self.emit("response.in_progress", json!({ "response": response })),
+        ]
+    }
+
+    fn push_text(&mut self, text: &str) -> Vec<Bytes> {
+        let mut events = Vec::new();
+        if self.text_item.is_none() {
+            let item = OpenTextItem {
+                id: format!("msg_{}", Uuid::new_v4().simple()),
+                output_index: self.output.len(),
+                text: String::new(),
+            };
+            // 占位，close_text 时替换为最终内容
+            self.output.push(Value::Null);
+            events.push(self.emit(
+                "response.output_item.added",
+                json!({
+                    "output_index": item.output_index,
+                    "item": {
+                        "id": &item.id,
+                        "type": "message",
+                        "status": "in_progress",
+                        "role": "assistant",
+                        "content": []
+                    }
+                }),
+            ));
+            events.push(self.emit(
+                "response.content_part.added",
+                json!({
+                    "item_id": &item.id,
+                    "output_index": item.output_index,
+                    "content_index": 0,
+                    "part": { "type": "output_text", "text": "", "annotations": [] }
+                }),
+            ));
+            self.text_item = Some(item);
+        }
+
+        let (item_id, output_index) = {
+            let item = self.text_item.as_mut().unwrap();
+            item.text.push_str(text);
+            (item.id.clone(), item.output_index)
+        };
+        events.push(self.emit(
+            "response.output_text.delta",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "content_index": 0,
+                "delta": text
+            }),
+        ));
+        events
+    }
+
+    fn close_text(&mut self) -> Vec<Bytes> {
+        let Some(item) = self.text_item.take() else {
+            return Vec::new();
+        };
+        let part = json!({ "type": "output_text", "text": &item.text, "annotations": [] });
+        let message = json!({
+            "id": &item.id,
+            "type": "message",
+            "status": "completed",
+            "role": "assistant",
+            "content": [part.clone()]
+        });
+        self.output[item.output_index] = message.clone();
+
+        vec![
+            self.emit(
+                "response.output_text.done",
+                json!({
+                    "item_id": &item.id,
+                    "output_index": item.output_index,
+                    "content_index": 0,
+                    "text": &item.text
+                }),
+            ),
+            self.emit(
+                "response.content_part.done",
+                json!({
+                    "item_id": &item.id,
+                    "output_index": item.output_index,
+                    "content_index": 0,
+                    "part": part
+                }),
+            ),
+            self.emit(
+                "response.output_item.done",
+                json!({ "output_index": item.output_index, "item": message }),
+            ),
+        ]
+    }
+
+    fn push_function_call(&mut self, call_id: &str, name: &str, arguments: &str) -> Vec<Bytes> {
+        let mut events = self.close_text();
+        let item_id = format!("fc_{}", Uuid::new_v4().simple());
+        let output_index = self.output.len();
+        let item = json!({
+            "id": &item_id,
+            "type": "function_call",
+            "status": "completed",
+            "call_id": call_id,
+            "name": name,
+            "arguments": arguments
+        });
+        self.output.push(item.clone());
+
+        events.push(self.emit(
+            "response.output_item.added",
+            json!({
+                "output_index": output_index,
+                "item": {
+                    "id": &item_id,
+                    "type": "function_call",
+                    "status": "in_progress",
+                    "call_id": call_id,
+                    "name": name,
+                    "arguments": ""
+                }
+            }),
+        ));
+        events.push(self.emit(
+            "response.function_call_arguments.delta",
+            json!({ "item_id": &item_id, "output_index": output_index, "delta": arguments }),
+        ));
+        events.push(self.emit(
+            "response.function_call_arguments.done",
+            json!({ "item_id": &item_id, "output_index": output_index, "arguments": arguments }),
+        ));
+        events.push(self.emit(
+            "response.output_item.done",
+            json!({ "output_index": output_index, "item": item }),
+        ));
+        events
+    }
+
+    /// 结束响应，返回剩余事件与最终 Response 对象
+    fn finish(
+        &mut self,
+        usage: Option<&Value>,
+        finish_reason: Option<&str>,
+    ) -> (Vec<Bytes>, Value) {
+        let mut events = self.close_text();
+        let incomplete = finish_reason == Some("MAX_TOKENS");
+
+        let mut response = self.response.clone();
+        response["status"] = json!(if incomplete {
+            "incomplete"
+        } else {
+            "completed"
+        });
+        if incomplete {
+            response["incomplete_details"] = json!({ "reason": "max_output_tokens" });
+        }
+        response["output"] = json!(self.output.clone());
+        response["usage"] = usage.map(map_usage).unwrap_or(Value::Null);
+
+        let event_type = if incomplete {
+            "response.incomplete"
+        } else {
+            "response.completed"
+        };
+        events.push(self.emit(event_type, json!({ "response": response.clone() })));
+        (events, response)
+    }
+
+    fn fail(&mut self, message: &str) -> Bytes {
+        let mut response = self.response.clone();
+        response["status"] = json!("failed");
+        response["error"] = json!({ "code": "server_error", "message": message });
+        response["output"] = json!(self.output.clone());
+        self.emit("response.failed", json!({ "response": response }))
+    }
+}
+
+/// 将 Gemini SSE 流转换为 Responses API 事件流
+///
+/// `response.created` 在收到首个上游数据时才发出；若上游在此之前即出错或为空，
+/// 流会返回 Err / 直接结束，便于调用方换号重试。
+pub fn create_responses_sse_stream(
+    mut gemini_stream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
+    skeleton: Value,
+    session_id: String,
+    message_count: usize,
+    on_complete: Option<ResponseCompleteHook>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
+    let mut buffer = BytesMut::new();
+
+    let stream = async_stream::stream! {
+        let mut writer = ResponseEventWriter::new(skeleton);
+        let mut started = false;
+        let mut emitted_tool_calls = std::collections::HashSet::new();
+        let mut usage: Option<Value> = None;
+        let mut finish_reason: Option<String> = None;
+        let mut on_complete = on_complete;
+
+        let mut heartbeat_interval = tokio::time::interval(std::time::Duration::from_secs(15));
+        heartbeat_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                item = gemini_stream.next() => {
+                    match item {
+                        Some(Ok(bytes)) => {
+                            buffer.extend_from_slice(&bytes);
+                            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                                let line_raw = buffer.split_to(pos + 1);
+                                let Ok(line_str) = std::str::from_utf8(&line_raw) else { continue };
+                                let line = line_str.trim();
+                                if !line.starts_with("data: ") { continue; }
+                                let json_part = line.trim_start_matches("data: ").trim();
+                                if json_part == "[DONE]" { continue; }
+                                let Ok(mut json) = serde_json::from_str::<Value>(json_part) else { continue };
+                                let actual_data = if let Some(inner) = json.get_mut("response").map(|v| v.take()) { inner } else { json };
+
+                                if !started {
+                                    started = true;
+                                    for ev in writer.start() { yield Ok::<Bytes, String>(ev); }
+                                }
+
+                                if let Some(u) = actual_data.get("usageMetadata") {
+                                    usage = Some(u.clone());
+                                }
+
+                                let Some(candidate) = actual_data.get("candidates").and_then(|c| c.get(0)) else { continue };
+                                if let Some(reason) = candidate.get("finishReason").and_then(|f| f.as_str()) {
+                                    finish_reason = Some(reason.to_string());
+                                }
+                                let Some(parts) = candidate.get("content").and_then(|c| c.get("parts")).and_then(|p| p.as_array()) else { continue };
+
+                                for part in parts {
+                                    if let Some(sig) = part.get("thoughtSignature").or(part.get("thought_signature")).and_then(|s| s.as_str()) {
+                                        store_thought_signature(sig, &session_id, message_count);
+                                    }
+                                    let is_thought_part = part.get("thought").and_then(|v| v.as_bool()).unwrap_or(false);
+                                    if is_thought_part { continue; }
+
+                                    if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                                        if !text.is_empty() {
+                                            for ev in writer.push_text(text) { yield Ok::<Bytes, String>(ev); }
+                                        }
+                                    }
+                                    if let Some(img) = part.get("inlineData") {
+                                        let mime_type = img.get("mimeType").and_then(|v| v.as_str()).unwrap_or("image/png");
+                                        let data = img.get("data").and_then(|v| v.as_str()).unwrap_or("");
+                                        if !data.is_empty() {
+                                            let md = format!("![image](data:{};base64,{})", mime_type, data);
+                                            for ev in writer.push_text(&md) { yield Ok::<Bytes, String>(ev); }
+                                        }
+                                    }
+                                    if let Some(func_call) = part.get("functionCall") {
+                                        let call_key = serde_json::to_string(func_call).unwrap_or_default();
+                                        if !emitted_tool_calls.insert(call_key.clone()) { continue; }
+
+                                        let name = func_call.get("name").and_then(|v| v.as_str()).unwrap_or("unknown");
+                                        let mut args = func_call.get("args").cloned().unwrap_or(json!({}));
+                                        normalize_shell_args(name, &mut args);
+                                        let args_str = serde_json::to_string(&args).unwrap_or_default();
+
+                                        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                                        use std::hash::{Hash, Hasher};
+                                        call_key.hash(&mut hasher);
+                                        let call_id = format!("call_{:x}", hasher.finish());
+
+                                        for ev in writer.push_function_call(&call_id, name, &args_str) { yield Ok::<Bytes, String>(ev); }
+                                    }
+                                }
+                            }
+                        }
+                        Some(Err(e)) => {
+                            if started {
+                                yield Ok::<Bytes, String>(writer.fail(&format!("Upstream stream error: {}", e)));
+                            } else {
+                                yield Err(format!("Upstream stream error: {}", e));
+                            }
+                            return;
+                        }
+                        None => break,
+                    }
+                }
+                _ = heartbeat_interval.tick() => { yield Ok::<Bytes, String>(Bytes::from(": ping\n\n")); }
+            }
+        }
+
+        if !started {
+            return;
+        }
+
+        // 先触发回调再发出终止事件：非流式收集在 response.completed 处即停止拉取
+        let (events, response) = writer.finish(usage.as_ref(), finish_reason.as_deref());
+        if let Some(hook) = on_complete.take() {
+            hook(response);
+        }
+        for ev in events { yield Ok::<Bytes, String>(ev); }
+    };
+
+    Box::pin(stream)
+}
+
+/// 收集 Responses 事件流，返回最终的 Response 对象 (用于非流式请求)
+pub async fn collect_response(
+    mut stream: Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>>,
+) -> Result<Value, String> {
+    let mut buffer = BytesMut::new();
+    while let Some(chunk) = stream.next().await {
+        buffer.extend_from_slice(&chunk?);
+        while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line_raw = buffer.split_to(pos + 1);
+            let Ok(line) = std::str::from_utf8(&line_raw) else {
+                continue;
+            };
+            let Some(data) = line.trim().strip_prefix("data: ") else {
+                continue;
+            };
+            let Ok(event) = serde_json::from_str::<Value>(data) else {
+                continue;
+            };
+            match event.get("type").and_then(|t| t.as_str()) {
+                Some("response.completed") | Some("response.incomplete") => {
+                    return Ok(event["response"].clone());
+                }
+                Some("response.failed") => {
+                    let message = event["response"]["error"]["message"]
+                        .as_str()
+                        .unwrap_or("Response failed")
+                        .to_string();
+                    return Err(message);
+                }
+                _ => {}
+            }
+        }
+    }
+    Err("Stream ended without response.completed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_items_to_messages_tool_loop() {
+        let items = normalize_input_items(Some(&json!([
+            { "role": "user", "content": "list files" },
+            { "type": "function_call", "call_id": "call_1", "name": "shell", "arguments": "{\"command\":[\"ls\"]}" },
+            { "type": "function_call_output", "call_id": "call_1", "output": "a.txt" }
+        ])));
+        let messages = input_items_to_messages(Some("be brief"), &items);
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[1]["content"], "list files");
+        assert_eq!(messages[2]["tool_calls"][0]["function"]["name"], "shell");
+        assert_eq!(messages[3]["role"], "tool");
+        assert_eq!(messages[3]["name"], "shell");
+        assert_eq!(messages[3]["content"], "a.txt");
+    }
+
+    #[tokio::test]
+    async fn test_responses_stream_events() {
+        let chunks = vec![
+            Ok::<Bytes, reqwest::Error>(Bytes::from(
+                "data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hel\"}]}}]}}\n\n",
+            )),
+            Ok(Bytes::from(
+                "data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"lo\"},{\"functionCall\":{\"name\":\"get_weather\",\"args\":{\"city\":\"Paris\"}}}]},\"finishReason\":\"STOP\"}],\"usageMetadata\":{\"promptTokenCount\":10,\"candidatesTokenCount\":5}}}\n\n",
+            )),
+        ];
+        let skeleton =
+            build_response_skeleton(&json!({ "model": "gemini-2.5-flash" }), "resp_test", 0);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let stream = create_responses_sse_stream(
+            Box::pin(futures::stream::iter(chunks)),
+            skeleton,
+            "sid".to_string(),
+            1,
+            Some(Box::new(move |resp| {
+                let _ = tx.send(resp);
+            })),
+        );
+
+        let response = collect_response(stream).await.unwrap();
+        assert_eq!(response["status"], "completed");
+        assert_eq!(response["output"][0]["type"], "message");
+        assert_eq!(response["output"][0]["content"][0]["text"], "Hello");
+        assert_eq!(response["output"][1]["type"], "function_call");
+        assert_eq!(response["output"][1]["name"], "get_weather");
+        assert_eq!(response["output"][1]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(response["usage"]["total_tokens"], 15);
+        assert_eq!(rx.recv().unwrap()["id"], "resp_test");
+    }
+}
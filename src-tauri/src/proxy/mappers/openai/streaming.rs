@@ -61,6 +61,24 @@ fn extract_usage_metadata(u: &Value) -> Option<super::models::OpenAIUsage> {
     })
 }
 
+/// [FIX #1575] 标准化 shell 工具参数名称
+/// Gemini 可能使用 cmd/code/script 等替代参数名，统一为 command
+pub fn normalize_shell_args(name: &str, args: &mut Value) {
+    if name == "shell" || name == "bash" || name == "local_shell" {
+        if let Some(obj) = args.as_object_mut() {
+            if !obj.contains_key("command") {
+                for alt_key in &["cmd", "code", "script", "shell_command"] {
+                    if let Some(val) = obj.remove(*alt_key) {
+                        obj.insert("command".to_string(), val);
+                        debug!("[OpenAI-Stream] Normalized shell arg '{}' -> 'command'", alt_key);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
 pub fn create_openai_sse_stream(
     mut gemini_stream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
     model: String,
@@ -129,21 +147,7 @@ pub fn create_openai_sse_stream(
                                                                     let name = func_call.get("name").and_then(|v| v.as_str()).unwrap_or("unknown");
                                                                     let mut args = func_call.get("args").unwrap_or(&json!({})).clone();
 
-                                                                    // [FIX #1575] 标准化 shell 工具参数名称
-                                                                    // Gemini 可能使用 cmd/code/script 等替代参数名，统一为 command
-                                                                    if name == "shell" || name == "bash" || name == "local_shell" {
-                                                                        if let Some(obj) = args.as_object_mut() {
-                                                                            if !obj.contains_key("command") {
-                                                                                for alt_key in &["cmd", "code", "script", "shell_command"] {
-                                                                                    if let Some(val) = obj.remove(*alt_key) {
-                                                                                        obj.insert("command".to_string(), val);
-                                                                                        debug!("[OpenAI-Stream] Normalized shell arg '{}' -> 'command'", alt_key);
-                                                                                        break;
-                                                                                    }
-                                                                                }
-                                                                            }
-                                                                        }
-                                                                    }
+                                                                    normalize_shell_args(name, &mut args);
 
                                                                     let args_str = serde_json::to_string(&args).unwrap_or_default();
                                                                     let mut hasher = std::collections::hash_map::DefaultHasher::new();
@@ -0,0 +1,488 @@
+// Prometheus 指标导出
+// 请求计数/延迟直方图在 ProxyMonitor::log_request 中累积，其余指标在抓取时从各组件快照生成
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+
+use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::server::AppState;
+use crate::proxy::upstream::client::mask_email;
+
+/// 请求延迟直方图分桶 (秒)
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// 请求维度标签
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct RequestLabels {
+    protocol: String,
+    model: String,
+    account: String,
+    status: u16,
+    user: String,
+}
+
+/// 单个标签组合下的累计数据
+#[derive(Debug, Clone, Default)]
+struct RequestSeries {
+    count: u64,
+    duration_sum_secs: f64,
+    /// 非累计分桶计数，渲染时再累加
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+/// 进程级请求指标
+pub struct ProxyMetrics {
+    requests: Mutex<HashMap<RequestLabels, RequestSeries>>,
+}
+
+impl ProxyMetrics {
+    fn new() -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Global singleton instance
+    pub fn global() -> &'static ProxyMetrics {
+        static INSTANCE: OnceLock<ProxyMetrics> = OnceLock::new();
+        INSTANCE.get_or_init(ProxyMetrics::new)
+    }
+
+    /// 记录一次已完成的请求
+    pub fn record_request(&self, log: &ProxyRequestLog) {
+        let labels = RequestLabels {
+            protocol: log.protocol.clone().unwrap_or_else(|| "other".to_string()),
+            model: log
+                .mapped_model
+                .clone()
+                .or_else(|| log.model.clone())
+                .unwrap_or_else(|| "unknown".to_string()),
+            account: log
+                .account_email
+                .as_deref()
+                .map(mask_email)
+                .unwrap_or_default(),
+            status: log.status,
+            user: log.username.clone().unwrap_or_default(),
+        };
+        let duration_secs = log.duration as f64 / 1000.0;
+
+        if let Ok(mut requests) = self.requests.lock() {
+            let series = requests.entry(labels).or_default();
+            series.count += 1;
+            series.duration_sum_secs += duration_secs;
+            if let Some(idx) = LATENCY_BUCKETS.iter().position(|le| duration_secs <= *le) {
+                series.buckets[idx] += 1;
+            }
+            series.input_tokens += log.input_tokens.unwrap_or(0) as u64;
+            series.output_tokens += log.output_tokens.unwrap_or(0) as u64;
+        }
+    }
+
+    fn snapshot(&self) -> Vec<(RequestLabels, RequestSeries)> {
+        let mut entries: Vec<_> = self
+            .requests
+            .lock()
+            .map(|r| r.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            .unwrap_or_default();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+}
+
+/// Prometheus 文本格式输出
+struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn new() -> Self {
+        Self { out: String::new() }
+    }
+
+    fn header(&mut self, name: &str, metric_type: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, metric_type);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let rendered: Vec<String> = labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", rendered.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn bool_value(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// 渲染请求相关指标 (计数、延迟直方图、Token 用量)
+fn render_request_metrics(w: &mut MetricsWriter, entries: &[(RequestLabels, RequestSeries)]) {
+    w.header(
+        "antigravity_requests_total",
+        "counter",
+        "Total proxied requests.",
+    );
+    for (labels, series) in entries {
+        let status = labels.status.to_string();
+        w.sample(
+            "antigravity_requests_total",
+            &[
+                ("protocol", &labels.protocol),
+                ("model", &labels.model),
+                ("account", &labels.account),
+                ("status", &status),
+                ("user", &labels.user),
+            ],
+            series.count as f64,
+        );
+    }
+
+    w.header(
+        "antigravity_request_duration_seconds",
+        "histogram",
+        "Proxied request latency in seconds.",
+    );
+    for (labels, series) in entries {
+        let status = labels.status.to_string();
+        let base = [
+            ("protocol", labels.protocol.as_str()),
+            ("model", labels.model.as_str()),
+            ("account", labels.account.as_str()),
+            ("status", status.as_str()),
+            ("user", labels.user.as_str()),
+        ];
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(series.buckets.iter()) {
+            cumulative += count;
+            let le = le.to_string();
+            let mut labels = base.to_vec();
+            labels.push(("le", &le));
+            w.sample(
+                "antigravity_request_duration_seconds_bucket",
+                &labels,
+                cumulative as f64,
+            );
+        }
+        let mut labels = base.to_vec();
+        labels.push(("le", "+Inf"));
+        w.sample(
+            "antigravity_request_duration_seconds_bucket",
+            &labels,
+            series.count as f64,
+        );
+        w.sample(
+            "antigravity_request_duration_seconds_sum",
+            &base,
+            series.duration_sum_secs,
+        );
+        w.sample(
+            "antigravity_request_duration_seconds_count",
+            &base,
+            series.count as f64,
+        );
+    }
+
+    // Token 用量不区分状态码
+    let mut tokens: BTreeMap<(&str, &str, &str, &str), (u64, u64)> = BTreeMap::new();
+    for (labels, series) in entries {
+        let entry = tokens
+            .entry((
+                labels.protocol.as_str(),
+                labels.model.as_str(),
+                labels.account.as_str(),
+                labels.user.as_str(),
+            ))
+            .or_default();
+        entry.0 += series.input_tokens;
+        entry.1 += series.output_tokens;
+    }
+    for (name, help, pick_output) in [
+        (
+            "antigravity_input_tokens_total",
+            "Total input tokens consumed.",
+            false,
+        ),
+        (
+            "antigravity_output_tokens_total",
+            "Total output tokens generated.",
+            true,
+        ),
+    ] {
+        w.header(name, "counter", help);
+        for ((protocol, model, account, user), (input, output)) in &tokens {
+            let value = if pick_output { *output } else { *input };
+            w.sample(
+                name,
+                &[
+                    ("protocol", protocol),
+                    ("model", model),
+                    ("account", account),
+                    ("user", user),
+                ],
+                value as f64,
+            );
+        }
+    }
+}
+
+/// 生成完整的 Prometheus 指标文本
+pub async fn render(state: &AppState) -> String {
+    let mut w = MetricsWriter::new();
+
+    render_request_metrics(&mut w, &ProxyMetrics::global().snapshot());
+
+    // 1. 账号可用性、配额与限流锁定
+    let mut accounts = state.token_manager.account_metrics();
+    accounts.sort_by(|a, b| a.email.cmp(&b.email));
+
+    w.header(
+        "antigravity_account_available",
+        "gauge",
+        "Whether the account can currently serve requests (1 = available).",
+    );
+    for account in &accounts {
+        let masked = mask_email(&account.email);
+        w.sample(
+            "antigravity_account_available",
+            &[("account", &masked)],
+            bool_value(account.available),
+        );
+    }
+
+    w.header(
+        "antigravity_account_health_score",
+        "gauge",
+        "Account health score between 0 and 1.",
+    );
+    for account in &accounts {
+        let masked = mask_email(&account.email);
+        w.sample(
+            "antigravity_account_health_score",
+            &[("account", &masked)],
+            account.health_score as f64,
+        );
+    }
+
+    w.header(
+        "antigravity_account_quota_percent",
+        "gauge",
+        "Remaining quota percentage per account and model.",
+    );
+    for account in &accounts {
+        let masked = mask_email(&account.email);
+        if let Some(remaining) = account.remaining_quota {
+            w.sample(
+                "antigravity_account_quota_percent",
+                &[("account", &masked), ("model", "")],
+                remaining as f64,
+            );
+        }
+        let quotas: BTreeMap<_, _> = account.model_quotas.iter().collect();
+        for (model, pct) in quotas {
+            w.sample(
+                "antigravity_account_quota_percent",
+                &[("account", &masked), ("model", model)],
+                *pct as f64,
+            );
+        }
+    }
+
+    w.header(
+        "antigravity_rate_limit_lockout_seconds",
+        "gauge",
+        "Remaining lockout seconds per account and model (empty model = account-wide).",
+    );
+    for account in &accounts {
+        let masked = mask_email(&account.email);
+        for (model, secs) in &account.lockouts {
+            w.sample(
+                "antigravity_rate_limit_lockout_seconds",
+                &[
+                    ("account", &masked),
+                    ("model", model.as_deref().unwrap_or("")),
+                ],
+                *secs as f64,
+            );
+        }
+    }
+
+    // 2. 代理池健康状况
+    let pool = state.proxy_pool_state.read().await.clone();
+    w.header(
+        "antigravity_proxy_pool_healthy",
+        "gauge",
+        "Proxy pool entry health (1 = healthy).",
+    );
+    for proxy in &pool.proxies {
+        w.sample(
+            "antigravity_proxy_pool_healthy",
+            &[("proxy", &proxy.id), ("name", &proxy.name)],
+            bool_value(proxy.enabled && proxy.is_healthy),
+        );
+    }
+    w.header(
+        "antigravity_proxy_pool_latency_seconds",
+        "gauge",
+        "Last measured proxy latency in seconds.",
+    );
+    for proxy in &pool.proxies {
+        if let Some(latency) = proxy.latency {
+            w.sample(
+                "antigravity_proxy_pool_latency_seconds",
+                &[("proxy", &proxy.id), ("name", &proxy.name)],
+                latency as f64 / 1000.0,
+            );
+        }
+    }
+
+    // 3. 缓存命中率
+    let sig = crate::proxy::SignatureCache::global().stats();
+    let schema = crate::proxy::common::schema_cache::get_cache_stats();
+    w.header(
+        "antigravity_cache_hits_total",
+        "counter",
+        "Cache lookups that hit.",
+    );
+    w.sample(
+        "antigravity_cache_hits_total",
+        &[("cache", "signature")],
+        sig.hits as f64,
+    );
+    w.sample(
+        "antigravity_cache_hits_total",
+        &[("cache", "schema")],
+        schema.cache_hits as f64,
+    );
+    w.header(
+        "antigravity_cache_misses_total",
+        "counter",
+        "Cache lookups that missed.",
+    );
+    w.sample(
+        "antigravity_cache_misses_total",
+        &[("cache", "signature")],
+        sig.misses as f64,
+    );
+    w.sample(
+        "antigravity_cache_misses_total",
+        &[("cache", "schema")],
+        schema.cache_misses as f64,
+    );
+    w.header(
+        "antigravity_cache_hit_ratio",
+        "gauge",
+        "Cache hit ratio since process start.",
+    );
+    let sig_total = sig.hits + sig.misses;
+    let sig_ratio = if sig_total == 0 {
+        0.0
+    } else {
+        sig.hits as f64 / sig_total as f64
+    };
+    w.sample(
+        "antigravity_cache_hit_ratio",
+        &[("cache", "signature")],
+        sig_ratio,
+    );
+    w.sample(
+        "antigravity_cache_hit_ratio",
+        &[("cache", "schema")],
+        schema.hit_rate(),
+    );
+    w.header(
+        "antigravity_signature_cache_entries",
+        "gauge",
+        "Signature cache entries per layer.",
+    );
+    for (layer, count) in [
+        ("tool", sig.tool_entries),
+        ("family", sig.family_entries),
+        ("session", sig.session_entries),
+    ] {
+        w.sample(
+            "antigravity_signature_cache_entries",
+            &[("layer", layer)],
+            count as f64,
+        );
+    }
+
+    w.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(status: u16, duration: u64) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "1".to_string(),
+            timestamp: 0,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status,
+            duration,
+            model: Some("claude-sonnet-4-5".to_string()),
+            mapped_model: Some("claude-sonnet-4-5-thinking".to_string()),
+            account_email: Some("someone@example.com".to_string()),
+            client_ip: None,
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: Some(100),
+            output_tokens: Some(20),
+            protocol: Some("anthropic".to_string()),
+            username: Some("alice".to_string()),
+            cache_hit: false,
+        }
+    }
+
+    #[test]
+    fn test_render_request_metrics() {
+        let metrics = ProxyMetrics::new();
+        metrics.record_request(&log(200, 300));
+        metrics.record_request(&log(200, 3000));
+
+        let mut w = MetricsWriter::new();
+        render_request_metrics(&mut w, &metrics.snapshot());
+        let out = w.out;
+
+        let labels = r#"protocol="anthropic",model="claude-sonnet-4-5-thinking",account="som***@ex***",status="200",user="alice""#;
+        assert!(out.contains(&format!("antigravity_requests_total{{{}}} 2", labels)));
+        assert!(out.contains(&format!(
+            "antigravity_request_duration_seconds_bucket{{{},le=\"0.5\"}} 1",
+            labels
+        )));
+        assert!(out.contains(&format!(
+            "antigravity_request_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+            labels
+        )));
+        assert!(out.contains("antigravity_input_tokens_total{protocol=\"anthropic\",model=\"claude-sonnet-4-5-thinking\",account=\"som***@ex***\",user=\"alice\"} 200"));
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
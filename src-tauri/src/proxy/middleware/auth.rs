@@ -6,11 +6,98 @@ use axum::{
     middleware::Next,
     response::Response,
 };
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::RwLock;
 
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
 
+/// [NEW] 内部端点 (/internal/*) 调用需携带的进程级密钥请求头
+pub const INTERNAL_SECRET_HEADER: &str = "x-antigravity-internal-secret";
+
+static INTERNAL_SECRET: OnceLock<String> = OnceLock::new();
+
+/// 获取进程级内部调用密钥 (首次调用时随机生成，仅存在于内存中)
+pub fn internal_secret() -> &'static str {
+    INTERNAL_SECRET.get_or_init(|| {
+        use rand::RngCore;
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    })
+}
+
+/// 常量时间比较，避免通过响应耗时猜测密钥
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 校验内部端点请求：必须来自本机回环地址 (TCP 对端，不信任转发头) 且携带正确的进程密钥
+fn verify_internal_request(request: &Request) -> Result<(), &'static str> {
+    let peer_ip = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map(|info| info.0.ip());
+    match peer_ip {
+        Some(ip) if ip.is_loopback() => {}
+        Some(_) => return Err("Internal endpoint is loopback-only"),
+        None => return Err("Unable to determine peer address"),
+    }
+
+    let provided = request
+        .headers()
+        .get(INTERNAL_SECRET_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("");
+    if constant_time_eq(provided.as_bytes(), internal_secret().as_bytes()) {
+        Ok(())
+    } else {
+        Err("Missing or invalid internal secret")
+    }
+}
+
+/// 记录被拒绝的内部端点访问 (写入 IP 访问日志，安全监控页面可见)
+fn record_rejected_internal_request(request: &Request, reason: &str) {
+    let client_ip = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map(|info| info.0.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    tracing::warn!(
+        "[Security] Rejected internal endpoint request from {}: {} {} ({})",
+        client_ip,
+        request.method(),
+        request.uri().path(),
+        reason
+    );
+
+    let log = crate::modules::security_db::IpAccessLog {
+        id: uuid::Uuid::new_v4().to_string(),
+        client_ip,
+        timestamp: chrono::Utc::now().timestamp(),
+        method: Some(request.method().to_string()),
+        path: Some(crate::proxy::middleware::audit::redact_uri(request.uri())),
+        user_agent: request
+            .headers()
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string()),
+        status: Some(403),
+        duration: Some(0),
+        api_key_hash: None,
+        blocked: true,
+        block_reason: Some(format!("Internal endpoint: {}", reason)),
+        username: None,
+    };
+    tokio::spawn(async move {
+        if let Err(e) = crate::modules::security_db::save_ip_access_log(&log) {
+            tracing::error!(
+                "[Security] Failed to save rejected internal access log: {}",
+                e
+            );
+        }
+    });
+}
+
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
 pub async fn auth_middleware(
     state: State<Arc<RwLock<ProxySecurityConfig>>>,
@@ -53,6 +140,17 @@ async fn auth_middleware_internal(
         return Ok(next.run(request).await);
     }
 
+    // [NEW] 内部端点 (/internal/*) 不走 API Key 鉴权，在任何 auth_mode 下都要求回环地址 + 进程密钥
+    if is_internal_endpoint {
+        return match verify_internal_request(&request) {
+            Ok(()) => Ok(next.run(request).await),
+            Err(reason) => {
+                record_rejected_internal_request(&request, reason);
+                Err(StatusCode::FORBIDDEN)
+            }
+        };
+    }
+
     let security = security.read().await.clone();
     let effective_mode = security.effective_auth_mode();
 
@@ -79,10 +177,16 @@ async fn auth_middleware_internal(
                 if let Ok(Some(user_token)) =
                     crate::modules::user_token_db::get_token_by_value(token)
                 {
+                    let client_ip = crate::proxy::middleware::client_ip::resolve_client_ip(
+                        &request,
+                        &security.security_monitor.trusted_proxy,
+                    )
+                    .unwrap_or_else(|| "127.0.0.1".to_string());
                     let identity = UserTokenIdentity {
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        client_ip,
                     };
                     // 注入 identity 到请求
                     let (mut parts, body) = request.into_parts();
@@ -98,12 +202,6 @@ async fn auth_middleware_internal(
         if matches!(effective_mode, ProxyAuthMode::AllExceptHealth) && is_health_check {
             return Ok(next.run(request).await);
         }
-
-        // 内部端点 (/internal/*) 豁免鉴权 - 用于 warmup 等内部功能
-        if is_internal_endpoint {
-            tracing::debug!("Internal endpoint bypassed auth: {}", path);
-            return Ok(next.run(request).await);
-        }
     } else {
         // 管理接口 (/api/*)
         // 1. 如果全局鉴权关闭，则管理接口也放行 (除非是强制局域网模式)
@@ -117,6 +215,17 @@ async fn auth_middleware_internal(
         }
     }
 
+    // [NEW] 事件流 (/api/events) 允许通过 ?token= 传递凭证，因为浏览器的 EventSource / WebSocket 无法设置请求头
+    let query_token = if force_strict && (path == "/events" || path == "/api/events") {
+        request.uri().query().and_then(|q| {
+            url::form_urlencoded::parse(q.as_bytes())
+                .find(|(k, _)| k == "token")
+                .map(|(_, v)| v.into_owned())
+        })
+    } else {
+        None
+    };
+
     // 从 header 中提取 API key
     let api_key = request
         .headers()
@@ -134,7 +243,8 @@ async fn auth_middleware_internal(
                 .headers()
                 .get("x-goog-api-key")
                 .and_then(|h| h.to_str().ok())
-        });
+        })
+        .or(query_token.as_deref());
 
     if security.api_key.is_empty()
         && (security.admin_password.is_none()
@@ -169,20 +279,30 @@ async fn auth_middleware_internal(
         // 尝试验证 UserToken
         let token = api_key.unwrap();
 
-        // 提取 IP (复用逻辑)
-        let client_ip = request
-            .headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-            .or_else(|| {
-                request
-                    .headers()
-                    .get("x-real-ip")
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string())
-            })
-            .unwrap_or_else(|| "127.0.0.1".to_string()); // Default fallback
+        // 提取 IP (仅信任可信代理的转发头，防止伪造 IP 绕过 max_ips 绑定)
+        let client_ip = crate::proxy::middleware::client_ip::resolve_client_ip(
+            &request,
+            &security.security_monitor.trusted_proxy,
+        )
+        .unwrap_or_else(|| "127.0.0.1".to_string()); // Default fallback
+
+        // [FIX] 未知令牌属于凭据错误，返回 401 以计入自动封禁的鉴权失败规则
+        //       (过期、IP 上限、宵禁等策略拒绝仍返回 403)
+        match crate::modules::user_token_db::get_token_by_value(token) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                tracing::warn!("UserToken rejected: unknown token");
+                return Ok(token_error_response(
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid token. Please check your API key.",
+                    "invalid_api_key",
+                ));
+            }
+            Err(e) => {
+                tracing::error!("UserToken lookup error: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
 
         // 验证 Token
         match crate::modules::user_token_db::validate_token(token, &client_ip) {
@@ -195,6 +315,7 @@ async fn auth_middleware_internal(
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        client_ip,
                     };
 
                     // [FIX] 将身份信息注入到请求 extensions 中，而不是响应
@@ -217,21 +338,11 @@ async fn auth_middleware_internal(
             Ok((false, reason)) => {
                 let reason_str = reason.unwrap_or_else(|| "Access denied".to_string());
                 tracing::warn!("UserToken rejected: {}", reason_str);
-                let body = serde_json::json!({
-                    "error": {
-                        "message": reason_str,
-                        "type": "token_rejected",
-                        "code": "token_rejected"
-                    }
-                });
-                let response = axum::response::Response::builder()
-                    .status(StatusCode::FORBIDDEN)
-                    .header("Content-Type", "application/json")
-                    .body(axum::body::Body::from(
-                        serde_json::to_string(&body).unwrap(),
-                    ))
-                    .unwrap();
-                Ok(response)
+                Ok(token_error_response(
+                    StatusCode::FORBIDDEN,
+                    &reason_str,
+                    "token_rejected",
+                ))
             }
             Err(e) => {
                 tracing::error!("UserToken validation error: {}", e);
@@ -243,6 +354,56 @@ async fn auth_middleware_internal(
     }
 }
 
+/// 用户令牌被拒绝时的 JSON 错误响应
+fn token_error_response(status: StatusCode, message: &str, code: &str) -> Response {
+    let body = serde_json::json!({
+        "error": {
+            "message": message,
+            "type": code,
+            "code": code
+        }
+    });
+    axum::response::Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(axum::body::Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// [NEW] 识别管理请求的鉴权主体 (供审计日志使用)，判定规则与 admin_auth_middleware 一致
+pub fn admin_principal(
+    security: &ProxySecurityConfig,
+    headers: &axum::http::HeaderMap,
+    query: Option<&str>,
+) -> &'static str {
+    if matches!(security.effective_auth_mode(), ProxyAuthMode::Off) {
+        return "anonymous";
+    }
+
+    let query_token = query.and_then(|q| {
+        url::form_urlencoded::parse(q.as_bytes())
+            .find(|(k, _)| k == "token")
+            .map(|(_, v)| v.into_owned())
+    });
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .or_else(|| headers.get("x-goog-api-key").and_then(|h| h.to_str().ok()))
+        .or(query_token.as_deref());
+    let Some(provided) = provided else {
+        return "unauthenticated";
+    };
+
+    match security.admin_password.as_deref() {
+        Some(pwd) if !pwd.is_empty() && provided == pwd => "admin_password",
+        Some(pwd) if !pwd.is_empty() => "unauthenticated",
+        _ if !security.api_key.is_empty() && provided == security.api_key => "api_key",
+        _ => "unauthenticated",
+    }
+}
+
 /// 用户令牌身份信息 (传递给 Monitor 使用)
 #[derive(Clone, Debug)]
 pub struct UserTokenIdentity {
@@ -250,6 +411,18 @@ pub struct UserTokenIdentity {
     #[allow(dead_code)] // 保留原始 token 便于审计/调试
     pub token: String,
     pub username: String,
+    /// 通过鉴权时的客户端 IP (批处理等异步任务按创建者 IP 记录用量)
+    pub client_ip: String,
+}
+
+/// Handler 侧的调用方身份 (由本中间件注入，未使用 UserToken 时为 None)
+pub type Caller = Option<axum::extract::Extension<UserTokenIdentity>>;
+
+/// 调用方的 UserToken ID，作为其创建的资源 (批处理文件 / 任务、存储的 Response) 的归属
+///
+/// 所有读取都按归属过滤；未使用 UserToken 的调用方 (全局 API Key / 免鉴权) 只能访问无归属的记录。
+pub fn owner_of(caller: &Caller) -> Option<String> {
+    caller.as_ref().map(|identity| identity.token_id.clone())
 }
 
 #[cfg(test)]
@@ -279,6 +452,73 @@ mod tests {
         // 我们在 auth_middleware_internal 基础上做了逻辑校验即可
     }
 
+    #[test]
+    fn test_verify_internal_request() {
+        let build = |peer: &str, secret: Option<&str>| {
+            let mut builder = Request::builder().uri("/internal/warmup");
+            if let Some(secret) = secret {
+                builder = builder.header(INTERNAL_SECRET_HEADER, secret);
+            }
+            let mut req = builder.body(axum::body::Body::empty()).unwrap();
+            req.extensions_mut()
+                .insert(axum::extract::ConnectInfo::<std::net::SocketAddr>(
+                    peer.parse().unwrap(),
+                ));
+            req
+        };
+
+        assert!(verify_internal_request(&build("127.0.0.1:5000", Some(internal_secret()))).is_ok());
+        assert!(verify_internal_request(&build("[::1]:5000", Some(internal_secret()))).is_ok());
+        // 错误或缺失密钥
+        assert!(verify_internal_request(&build("127.0.0.1:5000", Some("guess"))).is_err());
+        assert!(verify_internal_request(&build("127.0.0.1:5000", None)).is_err());
+        // 局域网来源即使携带正确密钥也拒绝
+        assert!(
+            verify_internal_request(&build("192.168.1.20:5000", Some(internal_secret()))).is_err()
+        );
+    }
+
+    #[test]
+    fn test_admin_principal() {
+        let mut security = ProxySecurityConfig {
+            auth_mode: ProxyAuthMode::Strict,
+            api_key: "sk-api".to_string(),
+            admin_password: Some("admin123".to_string()),
+            allow_lan_access: true,
+            port: 8045,
+            security_monitor: crate::proxy::config::SecurityMonitorConfig::default(),
+        };
+        let mut headers = axum::http::HeaderMap::new();
+        assert_eq!(
+            admin_principal(&security, &headers, None),
+            "unauthenticated"
+        );
+
+        headers.insert(header::AUTHORIZATION, "Bearer admin123".parse().unwrap());
+        assert_eq!(admin_principal(&security, &headers, None), "admin_password");
+
+        // 设置了管理密码时 api_key 不能访问管理接口
+        headers.insert(header::AUTHORIZATION, "Bearer sk-api".parse().unwrap());
+        assert_eq!(
+            admin_principal(&security, &headers, None),
+            "unauthenticated"
+        );
+
+        security.admin_password = None;
+        assert_eq!(admin_principal(&security, &headers, None), "api_key");
+        assert_eq!(
+            admin_principal(
+                &security,
+                &axum::http::HeaderMap::new(),
+                Some("token=sk-api")
+            ),
+            "api_key"
+        );
+
+        security.auth_mode = ProxyAuthMode::Off;
+        assert_eq!(admin_principal(&security, &headers, None), "anonymous");
+    }
+
     #[test]
     fn test_auth_placeholder() {
         assert!(true);
@@ -0,0 +1,176 @@
+// 客户端 IP 解析 (IP 过滤、鉴权、监控与审计共用)
+//
+// 只有当 TCP 对端属于可信代理时才解析转发头，否则直接使用对端地址，防止伪造
+// X-Forwarded-For 绕过黑白名单或令牌 IP 绑定。
+
+use crate::modules::security_db::{ip_in_cidr, normalize_ip, parse_ip_pattern};
+use crate::proxy::config::TrustedProxyConfig;
+use axum::{extract::Request, http::HeaderMap};
+use std::net::{IpAddr, SocketAddr};
+
+/// 从请求中解析客户端 IP
+pub fn resolve_client_ip(request: &Request, config: &TrustedProxyConfig) -> Option<String> {
+    let peer = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    resolve(
+        peer,
+        request.headers(),
+        config,
+        crate::modules::cloudflared::is_tunnel_active(),
+    )
+    .map(|ip| ip.to_string())
+}
+
+fn resolve(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    config: &TrustedProxyConfig,
+    tunnel_active: bool,
+) -> Option<IpAddr> {
+    let peer = normalize_ip(peer?);
+    let trusted: Vec<(IpAddr, u8)> = config
+        .proxies
+        .iter()
+        .filter_map(|p| parse_ip_pattern(p))
+        .collect();
+    let is_trusted = |ip: IpAddr| trusted.iter().any(|&(net, len)| ip_in_cidr(ip, net, len));
+
+    if !is_trusted(peer) {
+        return Some(peer);
+    }
+
+    // cloudflared 隧道从本机回连，CF-Connecting-IP 由 Cloudflare 边缘写入
+    if config.trust_cloudflared && tunnel_active && peer.is_loopback() {
+        if let Some(ip) = header_values(headers, "cf-connecting-ip")
+            .next()
+            .and_then(parse_node)
+        {
+            return Some(ip);
+        }
+    }
+
+    // 优先使用 RFC 7239 Forwarded，其次 X-Forwarded-For
+    let mut chain: Vec<&str> = header_values(headers, "forwarded")
+        .flat_map(|v| v.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("for").then_some(value)
+            })
+        })
+        .collect();
+    if chain.is_empty() {
+        chain = header_values(headers, "x-forwarded-for")
+            .flat_map(|v| v.split(','))
+            .collect();
+    }
+
+    if chain.is_empty() {
+        return Some(
+            header_values(headers, "x-real-ip")
+                .next()
+                .and_then(parse_node)
+                .unwrap_or(peer),
+        );
+    }
+
+    // 从右向左跳过可信代理，第一个不可信地址即为客户端
+    let mut client = peer;
+    for node in chain.iter().rev() {
+        match parse_node(node) {
+            Some(ip) => {
+                client = ip;
+                if !is_trusted(ip) {
+                    break;
+                }
+            }
+            // unknown / 混淆标识符: 无法继续向前追溯
+            None => break,
+        }
+    }
+    Some(client)
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> impl Iterator<Item = &'a str> {
+    headers.get_all(name).iter().filter_map(|v| v.to_str().ok())
+}
+
+/// 解析转发链中的节点: `1.2.3.4`、`1.2.3.4:5678`、`"[2001:db8::1]:443"`、`2001:db8::1`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok().map(normalize_ip);
+    }
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(normalize_ip(ip));
+    }
+    let (host, port) = node.rsplit_once(':')?;
+    port.parse::<u16>().ok()?;
+    host.parse::<std::net::Ipv4Addr>().ok().map(IpAddr::V4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarding_headers() {
+        let config = TrustedProxyConfig::default();
+        let h = headers(&[
+            ("x-forwarded-for", "10.0.0.1"),
+            ("x-real-ip", "10.0.0.2"),
+            ("cf-connecting-ip", "10.0.0.3"),
+        ]);
+        let resolved = resolve(Some(ip("192.168.1.50")), &h, &config, true);
+        assert_eq!(resolved, Some(ip("192.168.1.50")));
+        assert_eq!(resolve(None, &h, &config, true), None);
+    }
+
+    #[test]
+    fn test_trusted_chain_resolution() {
+        let config = TrustedProxyConfig {
+            proxies: vec!["127.0.0.1".to_string(), "172.16.0.0/12".to_string()],
+            trust_cloudflared: true,
+        };
+        let peer = Some(ip("::ffff:127.0.0.1"));
+
+        // 客户端伪造的最左侧地址被忽略，取最右侧的不可信地址
+        let h = headers(&[("x-forwarded-for", "6.6.6.6, 203.0.113.9, 172.17.0.2")]);
+        assert_eq!(resolve(peer, &h, &config, false), Some(ip("203.0.113.9")));
+
+        // Forwarded 优先于 X-Forwarded-For，支持带端口的 IPv6
+        let h = headers(&[
+            (
+                "forwarded",
+                "for=\"[2001:db8::7]:4711\";proto=https, for=172.17.0.2",
+            ),
+            ("x-forwarded-for", "6.6.6.6"),
+        ]);
+        assert_eq!(resolve(peer, &h, &config, false), Some(ip("2001:db8::7")));
+
+        // CF-Connecting-IP 仅在隧道运行时生效
+        let h = headers(&[
+            ("cf-connecting-ip", "198.51.100.4"),
+            ("x-real-ip", "198.51.100.5"),
+        ]);
+        assert_eq!(resolve(peer, &h, &config, true), Some(ip("198.51.100.4")));
+        assert_eq!(resolve(peer, &h, &config, false), Some(ip("198.51.100.5")));
+
+        let h = headers(&[("x-forwarded-for", "unknown, 203.0.113.1:8080")]);
+        assert_eq!(resolve(peer, &h, &config, false), Some(ip("203.0.113.1")));
+    }
+}
@@ -1,27 +1,52 @@
 use crate::modules::security_db;
-use crate::proxy::server::AppState;
+use crate::proxy::auto_ban::{self, RequestObservation};
+use crate::proxy::middleware::client_ip::resolve_client_ip;
+use crate::proxy::ProxySecurityConfig;
 use axum::{
-    extract::{Request, State},
+    extract::{OriginalUri, Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 /// IP 黑白名单过滤中间件
 pub async fn ip_filter_middleware(
-    State(state): State<AppState>,
+    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
     request: Request,
     next: Next,
 ) -> Response {
-    // 提取客户端 IP
-    let client_ip = extract_client_ip(&request);
+    ip_filter_internal(security, request, next, false).await
+}
+
+/// [NEW] 管理接口 IP 过滤: 仅执行黑名单 (含自动封禁)，不应用白名单模式，避免锁死管理端
+pub async fn admin_ip_filter_middleware(
+    State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    ip_filter_internal(security, request, next, true).await
+}
+
+async fn ip_filter_internal(
+    security: Arc<RwLock<ProxySecurityConfig>>,
+    request: Request,
+    next: Next,
+    is_admin: bool,
+) -> Response {
+    // 提取客户端 IP (仅信任可信代理的转发头)
+    let client_ip = {
+        let security_config = security.read().await;
+        resolve_client_ip(&request, &security_config.security_monitor.trusted_proxy)
+    };
 
     if let Some(ip) = &client_ip {
         // 读取安全配置
-        let security_config = state.security.read().await;
+        let security_config = security.read().await;
 
         // 1. 检查白名单 (如果启用白名单模式,只允许白名单 IP)
-        if security_config.security_monitor.whitelist.enabled {
+        if security_config.security_monitor.whitelist.enabled && !is_admin {
             match security_db::is_ip_in_whitelist(ip) {
                 Ok(true) => {
                     // 在白名单中,直接放行
@@ -62,10 +87,15 @@ pub async fn ip_filter_middleware(
             }
         }
 
-        // 2. 检查黑名单
-        if security_config.security_monitor.blacklist.enabled {
+        // 2. 检查黑名单 (自动封禁启用时，自动封禁条目不受黑名单开关影响)
+        let blacklist_enabled = security_config.security_monitor.blacklist.enabled;
+        let auto_ban_enabled = security_config.security_monitor.auto_ban.enabled;
+        if blacklist_enabled || auto_ban_enabled {
             match security_db::get_blacklist_entry_for_ip(ip) {
-                Ok(Some(entry)) => {
+                Ok(Some(entry))
+                    if blacklist_enabled
+                        || entry.created_by == security_db::AUTO_BAN_CREATED_BY =>
+                {
                     tracing::warn!("[IP Filter] IP {} is in blacklist, blocking", ip);
 
                     // 构建详细的封禁消息
@@ -108,7 +138,7 @@ pub async fn ip_filter_middleware(
                         client_ip: ip.clone(),
                         timestamp: chrono::Utc::now().timestamp(),
                         method: Some(request.method().to_string()),
-                        path: Some(request.uri().to_string()),
+                        path: Some(crate::proxy::middleware::audit::redact_uri(request.uri())),
                         user_agent: request
                             .headers()
                             .get("user-agent")
@@ -130,7 +160,7 @@ pub async fn ip_filter_middleware(
 
                     return create_blocked_response(ip, &detailed_message);
                 }
-                Ok(None) => {
+                Ok(_) => {
                     // 不在黑名单中,放行
                     tracing::debug!("[IP Filter] IP {} not in blacklist, allowing", ip);
                 }
@@ -143,34 +173,42 @@ pub async fn ip_filter_middleware(
         tracing::warn!("[IP Filter] Unable to extract client IP from request");
     }
 
-    // 放行请求
-    next.run(request).await
-}
+    let Some(client_ip) = client_ip else {
+        return next.run(request).await;
+    };
 
-/// 从请求中提取客户端 IP
-fn extract_client_ip(request: &Request) -> Option<String> {
-    // 1. 优先从 X-Forwarded-For 提取 (取第一个 IP)
-    request
+    // [NEW] 记录请求结果，供自动封禁规则评估
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let method = request.method().to_string();
+    let user_agent = request
         .headers()
-        .get("x-forwarded-for")
+        .get("user-agent")
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            // 2. 备选从 X-Real-IP 提取
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        })
-        .or_else(|| {
-            // 3. 最后尝试从 ConnectInfo 获取 (TCP 连接 IP)
-            // 这可以解决本地开发/测试时没有代理头导致 IP 获取失败的问题
-            request
-                .extensions()
-                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-                .map(|info| info.0.ip().to_string())
-        })
+        .map(|s| s.to_string());
+
+    // 放行请求
+    let response = next.run(request).await;
+
+    let security_config = security.read().await;
+    auto_ban::observe(
+        RequestObservation {
+            client_ip,
+            method,
+            path,
+            user_agent,
+            status: response.status().as_u16(),
+            is_admin,
+        },
+        &security_config.security_monitor.auto_ban,
+        &security_config.security_monitor.trusted_proxy,
+    );
+    drop(security_config);
+
+    response
 }
 
 /// 创建被封禁的响应
@@ -1,15 +1,18 @@
 // Middleware 模块 - Axum 中间件
 
+pub mod audit;
 pub mod auth;
+pub mod client_ip;
 pub mod cors;
 pub mod ip_filter;
 pub mod logging;
 pub mod monitor;
+pub mod response_cache;
 
 pub mod service_status;
 
 pub use auth::{admin_auth_middleware, auth_middleware};
 pub use cors::cors_layer;
-pub use ip_filter::ip_filter_middleware;
+pub use ip_filter::{admin_ip_filter_middleware, ip_filter_middleware};
 pub use monitor::monitor_middleware;
 pub use service_status::service_status_middleware;
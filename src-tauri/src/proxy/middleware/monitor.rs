@@ -1,19 +1,72 @@
+use crate::modules::user_token_db::{RequestedModel, TokenLimitViolation};
 use crate::proxy::middleware::auth::UserTokenIdentity;
 use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::replay::ReplayCapture;
 use crate::proxy::server::AppState;
 use axum::{
     body::Body,
     extract::{Request, State},
+    http::StatusCode,
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+    Json,
 };
 use futures::StreamExt;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::time::Instant;
 
 const MAX_REQUEST_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB
 const MAX_RESPONSE_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB for image responses
 
+/// 会调用模型的接口 (POST)，其余接口 (模型列表、文件、批处理管理等) 不做模型白名单检查
+const MODEL_ROUTES: &[&str] = &[
+    "/v1/chat/completions",
+    "/v1/completions",
+    "/v1/responses",
+    "/v1/images/generations",
+    "/v1/images/edits",
+    "/v1/embeddings",
+    "/v1/audio/transcriptions",
+    "/v1/messages",
+    "/v1/messages/count_tokens",
+    "/v1/models/detect",
+];
+
+fn invokes_model(method: &str, path: &str) -> bool {
+    method == "POST" && (MODEL_ROUTES.contains(&path) || path.starts_with("/v1beta/models/"))
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// 从 multipart/form-data 请求体中提取 `model` 字段 (音频转写、图片编辑等接口)
+fn multipart_model(content_type: &str, body: &[u8]) -> Option<String> {
+    let boundary = content_type
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))
+        .map(|b| b.trim_matches('"'))
+        .filter(|b| !b.is_empty())?;
+    let delimiter = format!("--{}", boundary);
+
+    let mut rest = body;
+    while let Some(start) = find_bytes(rest, delimiter.as_bytes()) {
+        rest = &rest[start + delimiter.len()..];
+        let part = &rest[..find_bytes(rest, delimiter.as_bytes()).unwrap_or(rest.len())];
+        let Some(sep) = find_bytes(part, b"\r\n\r\n") else {
+            continue;
+        };
+        let headers = String::from_utf8_lossy(&part[..sep]).to_ascii_lowercase();
+        if headers.contains("; name=\"model\"") {
+            return std::str::from_utf8(&part[sep + 4..])
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty());
+        }
+    }
+    None
+}
+
 /// Helper function to record User Token usage
 fn record_user_token_usage(
     user_token_identity: &Option<UserTokenIdentity>,
@@ -33,6 +86,74 @@ fn record_user_token_usage(
     }
 }
 
+/// 从响应的 usage / usageMetadata 对象提取 (输入, 输出) Token 数 (兼容 OpenAI / Anthropic / Gemini)
+pub(crate) fn usage_tokens(usage: &Value) -> (Option<u32>, Option<u32>) {
+    let input = usage
+        .get("prompt_tokens")
+        .or(usage.get("input_tokens"))
+        .or(usage.get("promptTokenCount"))
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32);
+    let output = usage
+        .get("completion_tokens")
+        .or(usage.get("output_tokens"))
+        .or(usage.get("candidatesTokenCount"))
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32);
+
+    if input.is_none() && output.is_none() {
+        let total = usage
+            .get("total_tokens")
+            .or(usage.get("totalTokenCount"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32);
+        return (None, total);
+    }
+    (input, output)
+}
+
+/// 按请求协议构造 UserToken 超限错误 (Anthropic / Gemini / OpenAI 格式)
+pub(crate) fn token_limit_response(uri: &str, violation: &TokenLimitViolation) -> Response {
+    let message = violation.message();
+    let (status, anthropic_type, openai_type, gemini_status) = match violation {
+        TokenLimitViolation::ModelNotAllowed(_) => (
+            StatusCode::FORBIDDEN,
+            "permission_error",
+            "model_not_allowed",
+            "PERMISSION_DENIED",
+        ),
+        TokenLimitViolation::RateLimited(_) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limit_error",
+            "rate_limit_exceeded",
+            "RESOURCE_EXHAUSTED",
+        ),
+        TokenLimitViolation::QuotaExceeded(_) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limit_error",
+            "insufficient_quota",
+            "RESOURCE_EXHAUSTED",
+        ),
+    };
+
+    let body = if uri.contains("/v1/messages") {
+        json!({
+            "type": "error",
+            "error": { "type": anthropic_type, "message": message }
+        })
+    } else if uri.contains("/v1beta/models") {
+        json!({
+            "error": { "code": status.as_u16(), "message": message, "status": gemini_status }
+        })
+    } else {
+        json!({
+            "error": { "message": message, "type": openai_type, "code": openai_type }
+        })
+    };
+
+    (status, Json(body)).into_response()
+}
+
 pub async fn monitor_middleware(
     State(state): State<AppState>,
     request: Request,
@@ -42,6 +163,7 @@ pub async fn monitor_middleware(
 
     let method = request.method().to_string();
     let uri = request.uri().to_string();
+    let path = request.uri().path().to_string();
 
     if uri.contains("event_logging") || uri.contains("/api/") || uri.starts_with("/internal/") {
         return next.run(request).await;
@@ -49,21 +171,15 @@ pub async fn monitor_middleware(
 
     let start = Instant::now();
 
-    // Extract client IP from headers (X-Forwarded-For or X-Real-IP)
-    // IMPORTANT: Extract from Request headers, not Response headers (since we want the client's IP)
-    // Note: We need to do this BEFORE consuming the request body if possible, or extract it from the original request
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        });
+    // Resolve client IP (forwarding headers are only honored from trusted proxies)
+    // Note: We need to do this BEFORE consuming the request body
+    let client_ip = {
+        let security = state.security.read().await;
+        crate::proxy::middleware::client_ip::resolve_client_ip(
+            &request,
+            &security.security_monitor.trusted_proxy,
+        )
+    };
 
     let user_agent = request
         .headers()
@@ -85,6 +201,8 @@ pub async fn monitor_middleware(
     // [FIX] 从请求 extensions 提取 UserTokenIdentity (由 Auth 中间件注入)
     // 必须在处理 request body 之前提取，因为 into_parts() 后需要保留这个值
     let user_token_identity = request.extensions().get::<UserTokenIdentity>().cloned();
+    // [NEW] 请求重放时回填日志，用于新旧响应对比
+    let replay_capture = request.extensions().get::<ReplayCapture>().cloned();
 
     let request = if method == "POST" {
         let (parts, body) = request.into_parts();
@@ -97,6 +215,15 @@ pub async fn monitor_middleware(
                             .map(|s| s.to_string())
                     });
                 }
+                // [FIX] multipart 请求 (音频转写、图片编辑) 的模型在表单字段中
+                if model.is_none() {
+                    model = parts
+                        .headers
+                        .get(axum::http::header::CONTENT_TYPE)
+                        .and_then(|v| v.to_str().ok())
+                        .filter(|ct| ct.starts_with("multipart/form-data"))
+                        .and_then(|ct| multipart_model(ct, &bytes));
+                }
                 request_body_str = if let Ok(s) = std::str::from_utf8(&bytes) {
                     Some(s.to_string())
                 } else {
@@ -114,6 +241,34 @@ pub async fn monitor_middleware(
         request
     };
 
+    // [NEW] UserToken 用量限制检查 (模型白名单/黑名单、RPM、Token 配额)
+    if let Some(identity) = &user_token_identity {
+        let token_id = identity.token_id.clone();
+        let requested_model = model.clone();
+        let model_required = invokes_model(&method, &path);
+        let check = tokio::task::spawn_blocking(move || {
+            let requested = match requested_model.as_deref() {
+                Some(model) => RequestedModel::Known(model),
+                None if model_required => RequestedModel::Unknown,
+                None => RequestedModel::NotApplicable,
+            };
+            crate::modules::user_token_db::check_token_limits(&token_id, requested)
+        })
+        .await;
+        match check {
+            Ok(Ok(Some(violation))) => {
+                tracing::warn!(
+                    "UserToken limit reached for {}: {}",
+                    identity.username,
+                    violation.message()
+                );
+                return token_limit_response(&uri, &violation);
+            }
+            Ok(Err(e)) => tracing::error!("UserToken limit check error: {}", e),
+            _ => {}
+        }
+    }
+
     let response = next.run(request).await;
 
     // user_token_identity 已在上面从请求 extensions 中提取
@@ -142,6 +297,12 @@ pub async fn monitor_middleware(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
+    // [NEW] 响应缓存命中标记
+    let cache_hit = response
+        .headers()
+        .get(crate::proxy::middleware::response_cache::CACHE_STATUS_HEADER)
+        .is_some_and(|v| v == "HIT");
+
     // Determine protocol from URL path
     let protocol = if uri.contains("/v1/messages") {
         Some("anthropic".to_string())
@@ -179,6 +340,7 @@ pub async fn monitor_middleware(
         output_tokens: None,
         protocol,
         username,
+        cache_hit,
     };
 
     if content_type.contains("text/event-stream") {
@@ -508,6 +670,9 @@ pub async fn monitor_middleware(
             // Record User Token Usage
             record_user_token_usage(&user_token_identity, &log, user_agent.clone());
 
+            if let Some(capture) = &replay_capture {
+                capture.record(&log);
+            }
             monitor.log_request(log).await;
         });
 
@@ -523,26 +688,7 @@ pub async fn monitor_middleware(
                     if let Ok(json) = serde_json::from_str::<Value>(&s) {
                         // 支持 OpenAI "usage" 或 Gemini "usageMetadata"
                         if let Some(usage) = json.get("usage").or(json.get("usageMetadata")) {
-                            log.input_tokens = usage
-                                .get("prompt_tokens")
-                                .or(usage.get("input_tokens"))
-                                .or(usage.get("promptTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            log.output_tokens = usage
-                                .get("completion_tokens")
-                                .or(usage.get("output_tokens"))
-                                .or(usage.get("candidatesTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-
-                            if log.input_tokens.is_none() && log.output_tokens.is_none() {
-                                log.output_tokens = usage
-                                    .get("total_tokens")
-                                    .or(usage.get("totalTokenCount"))
-                                    .and_then(|v| v.as_u64())
-                                    .map(|v| v as u32);
-                            }
+                            (log.input_tokens, log.output_tokens) = usage_tokens(usage);
                         }
                     }
                     log.response_body = Some(s.to_string());
@@ -557,6 +703,9 @@ pub async fn monitor_middleware(
                 // Record User Token Usage
                 record_user_token_usage(&user_token_identity, &log, user_agent.clone());
 
+                if let Some(capture) = &replay_capture {
+                    capture.record(&log);
+                }
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::from(bytes))
             }
@@ -566,6 +715,9 @@ pub async fn monitor_middleware(
                 // Record User Token Usage (even if too large)
                 record_user_token_usage(&user_token_identity, &log, user_agent.clone());
 
+                if let Some(capture) = &replay_capture {
+                    capture.record(&log);
+                }
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::empty())
             }
@@ -576,7 +728,36 @@ pub async fn monitor_middleware(
         // Record User Token Usage
         record_user_token_usage(&user_token_identity, &log, user_agent);
 
+        if let Some(capture) = &replay_capture {
+            capture.record(&log);
+        }
         monitor.log_request(log).await;
         response
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_from_multipart_body() {
+        let body = b"--XyZ\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.wav\"\r\nContent-Type: audio/wav\r\n\r\nRIFF....\r\n--XyZ\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n--XyZ--\r\n";
+        assert_eq!(
+            multipart_model("multipart/form-data; boundary=XyZ", body),
+            Some("whisper-1".to_string())
+        );
+        assert_eq!(
+            multipart_model("multipart/form-data; boundary=\"other\"", body),
+            None
+        );
+
+        assert!(invokes_model("POST", "/v1/audio/transcriptions"));
+        assert!(invokes_model(
+            "POST",
+            "/v1beta/models/gemini-2.5-flash:generateContent"
+        ));
+        assert!(!invokes_model("POST", "/v1/files"));
+        assert!(!invokes_model("GET", "/v1/models"));
+    }
+}
@@ -0,0 +1,400 @@
+// 响应缓存中间件
+// 对确定性请求 (temperature = 0) 按 协议 + 映射模型 + 规范化请求体 的哈希精确匹配缓存，
+// 适用于 /v1/chat/completions、/v1/messages 与 Gemini generateContent / streamGenerateContent。
+// 客户端可通过 `Cache-Control: no-cache|no-store` 或 `X-Response-Cache: bypass` 跳过缓存。
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures::StreamExt;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::modules::response_cache_db::{self, CachedResponse};
+use crate::proxy::config::ResponseCacheConfig;
+use crate::proxy::server::AppState;
+
+/// 缓存命中标记响应头 (HIT / MISS)，监控中间件据此标记日志
+pub const CACHE_STATUS_HEADER: &str = "X-Cache";
+const BYPASS_HEADER: &str = "x-response-cache";
+
+const MAX_REQUEST_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB
+
+/// 不影响生成结果的字段，不参与缓存键计算
+const NON_SEMANTIC_KEYS: &[&str] = &["model", "stream", "stream_options", "user", "metadata"];
+
+/// 仅对生成类接口启用缓存 (在读取请求体之前快速过滤)
+fn is_cacheable_path(path: &str) -> bool {
+    path == "/v1/chat/completions"
+        || path == "/v1/messages"
+        || (path.starts_with("/v1beta/models/")
+            && (path.ends_with(":generateContent") || path.ends_with(":streamGenerateContent")))
+}
+
+/// 请求所属协议、原始模型名与是否流式
+fn detect_protocol(path: &str, body: &Value) -> Option<(&'static str, String, bool)> {
+    let body_model = || body.get("model").and_then(|m| m.as_str()).map(String::from);
+    let body_stream = body
+        .get("stream")
+        .and_then(|s| s.as_bool())
+        .unwrap_or(false);
+    match path {
+        "/v1/chat/completions" => Some(("openai", body_model()?, body_stream)),
+        "/v1/messages" => Some(("anthropic", body_model()?, body_stream)),
+        _ => {
+            let (model, method) = path.strip_prefix("/v1beta/models/")?.split_once(':')?;
+            match method {
+                "generateContent" => Some(("gemini", model.to_string(), false)),
+                "streamGenerateContent" => Some(("gemini", model.to_string(), true)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// 是否为确定性请求 (temperature 显式为 0)
+fn is_deterministic(protocol: &str, body: &Value) -> bool {
+    let temperature = if protocol == "gemini" {
+        body.get("generationConfig")
+            .and_then(|c| c.get("temperature"))
+    } else {
+        body.get("temperature")
+    };
+    temperature.and_then(|t| t.as_f64()) == Some(0.0)
+}
+
+/// 递归排序对象键并统一整数值浮点数，保证等价请求序列化结果一致
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Object(
+                keys.into_iter()
+                    .map(|k| (k.clone(), canonicalize(&map[k])))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        // 0 与 0.0 视为同一参数
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < 9.0e15 => Value::from(f as i64),
+            _ => value.clone(),
+        },
+        other => other.clone(),
+    }
+}
+
+/// 计算缓存键: sha256(协议 / 映射模型 / 是否流式 / 规范化请求体)
+pub fn cache_key(protocol: &str, mapped_model: &str, stream: bool, body: &Value) -> String {
+    let mut normalized = body.clone();
+    if let Some(map) = normalized.as_object_mut() {
+        for key in NON_SEMANTIC_KEYS {
+            map.remove(*key);
+        }
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(protocol.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(mapped_model.as_bytes());
+    hasher.update(if stream { "\nstream\n" } else { "\nblock\n" });
+    hasher.update(canonicalize(&normalized).to_string().as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn is_bypassed(request: &Request) -> bool {
+    let headers = request.headers();
+    let cache_control = headers
+        .get(header::CACHE_CONTROL)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let bypass = headers
+        .get(BYPASS_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    cache_control.contains("no-cache")
+        || cache_control.contains("no-store")
+        || matches!(bypass.as_str(), "bypass" | "off" | "false" | "0")
+}
+
+/// 流式响应中是否包含错误事件 (此类响应不缓存)
+fn sse_has_error(text: &str) -> bool {
+    text.lines().any(|line| {
+        if line.starts_with("event: error") {
+            return true;
+        }
+        line.strip_prefix("data: ")
+            .and_then(|data| serde_json::from_str::<Value>(data.trim()).ok())
+            .is_some_and(|json| {
+                json.get("error").is_some()
+                    || json.get("type").and_then(|t| t.as_str()) == Some("error")
+            })
+    })
+}
+
+/// 构造缓存命中的响应，流式请求按 SSE 事件逐条回放
+fn cached_response(entry: CachedResponse) -> Response {
+    let body = if entry.stream {
+        let events: Vec<Result<Bytes, std::io::Error>> = match String::from_utf8(entry.body) {
+            Ok(text) => text
+                .split_inclusive("\n\n")
+                .map(|event| Ok(Bytes::from(event.to_string())))
+                .collect(),
+            Err(e) => vec![Ok(Bytes::from(e.into_bytes()))],
+        };
+        Body::from_stream(futures::stream::iter(events))
+    } else {
+        Body::from(entry.body)
+    };
+
+    let mut response = Response::new(body);
+    let headers = response.headers_mut();
+    if let Ok(v) = HeaderValue::from_str(&entry.content_type) {
+        headers.insert(header::CONTENT_TYPE, v);
+    }
+    if let Ok(v) = HeaderValue::from_str(&entry.model) {
+        headers.insert("X-Mapped-Model", v);
+    }
+    headers.insert(CACHE_STATUS_HEADER, HeaderValue::from_static("HIT"));
+    response
+}
+
+fn store(entry: CachedResponse, config: &ResponseCacheConfig) {
+    let (ttl, max_entries) = (config.ttl_seconds, config.max_entries);
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = response_cache_db::put_entry(&entry, ttl, max_entries) {
+            tracing::warn!("[ResponseCache] Failed to store entry: {}", e);
+        }
+    });
+}
+
+pub async fn response_cache_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = crate::proxy::config::get_response_cache_config();
+    if !config.enabled
+        || request.method() != Method::POST
+        || !is_cacheable_path(request.uri().path())
+        || is_bypassed(&request)
+    {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_REQUEST_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => return (StatusCode::BAD_REQUEST, "Failed to read request body").into_response(),
+    };
+
+    let key = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|json| {
+            let (protocol, model, stream) = detect_protocol(parts.uri.path(), &json)?;
+            if config.deterministic_only && !is_deterministic(protocol, &json) {
+                return None;
+            }
+            Some((protocol, model, stream, json))
+        });
+    let request = Request::from_parts(parts, Body::from(bytes));
+    let Some((protocol, model, stream, json)) = key else {
+        return next.run(request).await;
+    };
+
+    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        &model,
+        &*state.custom_mapping.read().await,
+    );
+    let key = cache_key(protocol, &mapped_model, stream, &json);
+
+    let lookup_key = key.clone();
+    match tokio::task::spawn_blocking(move || response_cache_db::get_entry(&lookup_key)).await {
+        Ok(Ok(Some(entry))) => {
+            tracing::info!(
+                "[ResponseCache] Hit {} {} (stream: {})",
+                protocol,
+                mapped_model,
+                stream
+            );
+            return cached_response(entry);
+        }
+        Ok(Err(e)) => tracing::warn!("[ResponseCache] Lookup failed: {}", e),
+        _ => {}
+    }
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(CACHE_STATUS_HEADER, HeaderValue::from_static("MISS"));
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let entry = CachedResponse {
+        key,
+        protocol: protocol.to_string(),
+        model: mapped_model,
+        stream,
+        content_type: content_type.clone(),
+        body: Vec::new(),
+    };
+
+    if stream && content_type.contains("text/event-stream") {
+        // 边转发边收集，流正常结束且无错误事件时写入缓存
+        let (parts, body) = response.into_parts();
+        let mut upstream = body.into_data_stream();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+        tokio::spawn(async move {
+            let mut entry = entry;
+            let mut complete = true;
+            while let Some(chunk) = upstream.next().await {
+                match chunk {
+                    Ok(chunk) => {
+                        if complete && entry.body.len() + chunk.len() <= config.max_entry_bytes {
+                            entry.body.extend_from_slice(&chunk);
+                        } else {
+                            complete = false;
+                        }
+                        if tx.send(Ok::<_, axum::Error>(chunk)).await.is_err() {
+                            // 客户端已断开，响应不完整
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                }
+            }
+            let cacheable =
+                complete && std::str::from_utf8(&entry.body).is_ok_and(|text| !sse_has_error(text));
+            if cacheable {
+                store(entry, &config);
+            }
+        });
+        Response::from_parts(
+            parts,
+            Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+        )
+    } else if !stream && content_type.contains("application/json") {
+        let (parts, body) = response.into_parts();
+        match axum::body::to_bytes(body, MAX_REQUEST_BODY_SIZE).await {
+            Ok(bytes) => {
+                if bytes.len() <= config.max_entry_bytes {
+                    store(
+                        CachedResponse {
+                            body: bytes.to_vec(),
+                            ..entry
+                        },
+                        &config,
+                    );
+                }
+                Response::from_parts(parts, Body::from(bytes))
+            }
+            Err(_) => Response::from_parts(parts, Body::empty()),
+        }
+    } else {
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cache_key_normalization() {
+        let a = json!({
+            "model": "gpt-4o",
+            "temperature": 0,
+            "messages": [{ "role": "user", "content": "explain this lint" }],
+            "stream": false,
+            "user": "ci-1"
+        });
+        let b = json!({
+            "user": "ci-2",
+            "messages": [{ "content": "explain this lint", "role": "user" }],
+            "temperature": 0.0,
+            "model": "gpt-4o-alias"
+        });
+        // 字段顺序、model 别名 (映射后一致) 与 user 不影响缓存键
+        assert_eq!(
+            cache_key("openai", "gemini-2.5-flash", false, &a),
+            cache_key("openai", "gemini-2.5-flash", false, &b)
+        );
+        // 映射模型、流式与生成参数参与缓存键
+        assert_ne!(
+            cache_key("openai", "gemini-2.5-flash", false, &a),
+            cache_key("openai", "gemini-2.5-pro", false, &a)
+        );
+        assert_ne!(
+            cache_key("openai", "gemini-2.5-flash", false, &a),
+            cache_key("openai", "gemini-2.5-flash", true, &a)
+        );
+        let mut c = a.clone();
+        c["max_tokens"] = json!(16);
+        assert_ne!(
+            cache_key("openai", "gemini-2.5-flash", false, &a),
+            cache_key("openai", "gemini-2.5-flash", false, &c)
+        );
+
+        assert!(is_deterministic("openai", &a));
+        assert!(!is_deterministic(
+            "anthropic",
+            &json!({ "temperature": 0.7 })
+        ));
+        assert!(!is_deterministic("anthropic", &json!({})));
+        assert!(is_deterministic(
+            "gemini",
+            &json!({ "generationConfig": { "temperature": 0 } })
+        ));
+    }
+
+    #[test]
+    fn test_detect_protocol_and_sse_errors() {
+        let body = json!({ "model": "claude-sonnet-4-5", "stream": true });
+        assert_eq!(
+            detect_protocol("/v1/messages", &body),
+            Some(("anthropic", "claude-sonnet-4-5".to_string(), true))
+        );
+        assert_eq!(
+            detect_protocol(
+                "/v1beta/models/gemini-2.5-flash:streamGenerateContent",
+                &json!({})
+            ),
+            Some(("gemini", "gemini-2.5-flash".to_string(), true))
+        );
+        assert_eq!(detect_protocol("/v1/messages/count_tokens", &body), None);
+        assert_eq!(
+            detect_protocol("/v1beta/models/gemini-2.5-flash:countTokens", &body),
+            None
+        );
+
+        assert!(!sse_has_error(
+            "event: message_start\ndata: {\"type\":\"message_start\"}\n\n"
+        ));
+        assert!(sse_has_error(
+            "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\"}}\n\n"
+        ));
+        assert!(sse_has_error(
+            "data: {\"error\":{\"message\":\"quota\"}}\n\n"
+        ));
+    }
+}
@@ -9,21 +9,27 @@ pub mod token_manager;
 
 // 新架构模块
 pub mod audio; // 音频处理模块
+pub mod auto_ban; // 自动封禁 (fail2ban 风格)
+pub mod batch_worker; // 批处理后台执行器 (/v1/batches)
 pub mod cli_sync; // CLI 配置同步 (v3.3.35)
 pub mod common; // 公共工具
 pub mod debug_logger;
 pub mod droid_sync; // Droid (Factory CLI) 配置同步
 pub mod handlers; // API 端点处理器
 pub mod mappers; // 协议转换器
+pub mod metrics; // Prometheus 指标导出
 pub mod middleware; // Axum 中间件
 pub mod monitor; // 监控
 pub mod opencode_sync; // OpenCode 配置同步
 pub mod providers; // Extra upstream providers (z.ai, etc.)
 pub mod proxy_pool; // 代理池管理器
 pub mod rate_limit; // 限流跟踪
+pub mod replay; // 请求重放 (监控日志)
 pub mod session_manager; // 会话指纹管理
 pub mod signature_cache; // Signature Cache (v3.3.16)
 pub mod sticky_config; // 粘性调度配置
+pub mod tls; // 监听端口 TLS 终止
+pub mod unix_socket; // Unix 域套接字监听
 pub mod upstream; // 上游客户端
 pub mod zai_vision_mcp; // Built-in Vision MCP server state
 pub mod zai_vision_tools; // Built-in Vision MCP tools (z.ai vision API) // 调试日志
@@ -36,6 +42,12 @@ pub use config::update_stream_handling_config;
 pub use config::update_thinking_budget_config;
 pub use config::update_claude_thinking_mapping_enabled;
 pub use config::update_endpoint_proxy_config;
+pub use config::update_model_fallbacks;
+pub use config::update_upstream_providers;
+pub use config::update_batch_config;
+pub use config::update_webhooks;
+pub use config::update_audit_log_config;
+pub use config::update_response_cache_config;
 pub use config::ProxyAuthMode;
 pub use config::ProxyConfig;
 pub use config::ProxyPoolConfig;
@@ -45,7 +57,7 @@ pub use config::ZaiDispatchMode;
 pub use security::ProxySecurityConfig;
 pub use server::AxumServer;
 pub use signature_cache::SignatureCache;
-pub use token_manager::TokenManager;
+pub use token_manager::{TokenError, TokenManager};
 
 #[cfg(test)]
 pub mod tests;
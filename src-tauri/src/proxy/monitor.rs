@@ -1,7 +1,7 @@
+use crate::modules::event_bus::{self, AppEvent, EVENT_PROXY_REQUEST};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::Emitter;
 use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +23,8 @@ pub struct ProxyRequestLog {
     pub output_tokens: Option<u32>,
     pub protocol: Option<String>, // 协议类型: "openai", "anthropic", "gemini"
     pub username: Option<String>, // User token username
+    #[serde(default)]
+    pub cache_hit: bool, // [NEW] 是否由响应缓存直接返回
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -37,11 +39,10 @@ pub struct ProxyMonitor {
     pub stats: RwLock<ProxyStats>,
     pub max_logs: usize,
     pub enabled: AtomicBool,
-    app_handle: Option<tauri::AppHandle>,
 }
 
 impl ProxyMonitor {
-    pub fn new(max_logs: usize, app_handle: Option<tauri::AppHandle>) -> Self {
+    pub fn new(max_logs: usize) -> Self {
         // Initialize DB
         if let Err(e) = crate::modules::proxy_db::init_db() {
             tracing::error!("Failed to initialize proxy DB: {}", e);
@@ -66,7 +67,6 @@ impl ProxyMonitor {
             stats: RwLock::new(ProxyStats::default()),
             max_logs,
             enabled: AtomicBool::new(false), // Default to disabled
-            app_handle,
         }
     }
 
@@ -93,6 +93,9 @@ impl ProxyMonitor {
             });
         }
 
+        // Prometheus 指标不受日志开关影响
+        crate::proxy::metrics::ProxyMetrics::global().record_request(&log);
+
         if !self.is_enabled() {
             return;
         }
@@ -165,7 +168,8 @@ impl ProxyMonitor {
         });
 
         // Emit event (send summary only, without body to reduce memory)
-        if let Some(app) = &self.app_handle {
+        // [NEW] 经事件总线发布，Tauri 前端与 /api/events 均可订阅 (headless 模式同样生效)
+        {
             let log_summary = ProxyRequestLog {
                 id: log.id.clone(),
                 timestamp: log.timestamp,
@@ -184,8 +188,12 @@ impl ProxyMonitor {
                 output_tokens: log.output_tokens,
                 protocol: log.protocol.clone(),
                 username: log.username.clone(),
+                cache_hit: log.cache_hit,
             };
-            let _ = app.emit("proxy://request", &log_summary);
+            let event = AppEvent::new(EVENT_PROXY_REQUEST, &log_summary)
+                .with_account(log_summary.account_email.clone())
+                .with_user_token(log_summary.username.clone());
+            event_bus::publish(event);
         }
     }
 
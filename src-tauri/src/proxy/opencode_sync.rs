@@ -615,6 +615,11 @@ pub fn sync_opencode_config(
     sync_accounts: bool,
     models_to_sync: Option<Vec<String>>,
 ) -> Result<(), String> {
+    crate::proxy::cli_sync::validate_sync_url(
+        &crate::proxy::cli_sync::CliApp::OpenCode,
+        proxy_url,
+    )?;
+
     let Some((config_path, _ag_config_path, ag_accounts_path)) = get_config_paths() else {
         return Err("Failed to get OpenCode config directory".to_string());
     };
@@ -0,0 +1,948 @@
+//! 通用上游提供商的协议转换
+//!
+//! 跨协议转发时统一以非流式请求上游，再将完整响应转换为客户端协议
+//! (需要流式时由 `*_response_to_sse` 合成事件流)。
+
+use serde_json::{json, Map, Value};
+
+/// Anthropic 要求必须提供 max_tokens
+const DEFAULT_MAX_TOKENS: u64 = 4096;
+
+fn text_of_blocks(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|b| {
+                if b.get("type").and_then(|t| t.as_str()) == Some("text") {
+                    b.get("text").and_then(|t| t.as_str())
+                } else {
+                    b.as_str()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn parse_arguments(arguments: Option<&Value>) -> Value {
+    match arguments {
+        Some(Value::String(s)) => serde_json::from_str(s).unwrap_or_else(|_| json!({})),
+        Some(v @ Value::Object(_)) => v.clone(),
+        _ => json!({}),
+    }
+}
+
+fn copy_field(from: &Value, from_key: &str, to: &mut Map<String, Value>, to_key: &str) {
+    if let Some(v) = from.get(from_key).filter(|v| !v.is_null()) {
+        to.insert(to_key.to_string(), v.clone());
+    }
+}
+
+/// Gemini 的 schema 类型为大写 (OBJECT / STRING)，OpenAI 要求小写
+fn lowercase_schema_types(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            if let Some(Value::String(t)) = map.get_mut("type") {
+                *t = t.to_lowercase();
+            }
+            for v in map.values_mut() {
+                lowercase_schema_types(v);
+            }
+        }
+        Value::Array(arr) => arr.iter_mut().for_each(lowercase_schema_types),
+        _ => {}
+    }
+}
+
+// ===== 请求转换 =====
+
+/// Anthropic Messages -> OpenAI Chat Completions
+pub fn claude_to_openai_request(req: &Value, model: &str) -> Value {
+    let mut messages = Vec::new();
+
+    if let Some(system) = req.get("system") {
+        let text = text_of_blocks(system);
+        if !text.is_empty() {
+            messages.push(json!({ "role": "system", "content": text }));
+        }
+    }
+
+    for msg in req
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+        let content = msg.get("content").cloned().unwrap_or(Value::Null);
+        let Value::Array(blocks) = content else {
+            messages.push(json!({ "role": role, "content": text_of_blocks(&content) }));
+            continue;
+        };
+
+        let mut parts = Vec::new();
+        let mut tool_calls = Vec::new();
+        for block in &blocks {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("text") => parts.push(json!({
+                    "type": "text",
+                    "text": block.get("text").cloned().unwrap_or_default()
+                })),
+                Some("image") => {
+                    let source = block.get("source").cloned().unwrap_or_default();
+                    let url = match source.get("type").and_then(|t| t.as_str()) {
+                        Some("base64") => format!(
+                            "data:{};base64,{}",
+                            source
+                                .get("media_type")
+                                .and_then(|m| m.as_str())
+                                .unwrap_or("image/png"),
+                            source.get("data").and_then(|d| d.as_str()).unwrap_or("")
+                        ),
+                        _ => source
+                            .get("url")
+                            .and_then(|u| u.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                    };
+                    parts.push(json!({ "type": "image_url", "image_url": { "url": url } }));
+                }
+                Some("tool_use") => tool_calls.push(json!({
+                    "id": block.get("id").cloned().unwrap_or_default(),
+                    "type": "function",
+                    "function": {
+                        "name": block.get("name").cloned().unwrap_or_default(),
+                        "arguments": block.get("input").cloned().unwrap_or_else(|| json!({})).to_string()
+                    }
+                })),
+                Some("tool_result") => messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": block.get("tool_use_id").cloned().unwrap_or_default(),
+                    "content": text_of_blocks(block.get("content").unwrap_or(&Value::Null))
+                })),
+                // thinking / redacted_thinking 等块无法跨协议传递
+                _ => {}
+            }
+        }
+
+        if role == "assistant" {
+            let text = parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("");
+            if text.is_empty() && tool_calls.is_empty() {
+                continue;
+            }
+            let mut out = json!({ "role": "assistant", "content": text });
+            if !tool_calls.is_empty() {
+                out["tool_calls"] = Value::Array(tool_calls);
+            }
+            messages.push(out);
+        } else if !parts.is_empty() {
+            let only_text = parts.iter().all(|p| p["type"] == "text");
+            let content = if only_text {
+                Value::String(text_of_blocks(&Value::Array(parts)))
+            } else {
+                Value::Array(parts)
+            };
+            messages.push(json!({ "role": role, "content": content }));
+        }
+    }
+
+    let mut out = Map::new();
+    out.insert("model".into(), Value::String(model.to_string()));
+    out.insert("messages".into(), Value::Array(messages));
+    out.insert("stream".into(), Value::Bool(false));
+    copy_field(req, "max_tokens", &mut out, "max_tokens");
+    copy_field(req, "temperature", &mut out, "temperature");
+    copy_field(req, "top_p", &mut out, "top_p");
+    copy_field(req, "stop_sequences", &mut out, "stop");
+
+    let tools: Vec<Value> = req
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+        // 服务端工具 (web_search 等) 没有 input_schema，无法转发
+        .filter(|t| t.get("input_schema").is_some())
+        .map(|t| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": t.get("name").cloned().unwrap_or_default(),
+                    "description": t.get("description").cloned().unwrap_or_default(),
+                    "parameters": t.get("input_schema").cloned().unwrap_or_default()
+                }
+            })
+        })
+        .collect();
+    if !tools.is_empty() {
+        out.insert("tools".into(), Value::Array(tools));
+        if let Some(choice) = req.get("tool_choice") {
+            let mapped = match choice.get("type").and_then(|t| t.as_str()) {
+                Some("any") => json!("required"),
+                Some("none") => json!("none"),
+                Some("tool") => json!({
+                    "type": "function",
+                    "function": { "name": choice.get("name").cloned().unwrap_or_default() }
+                }),
+                _ => json!("auto"),
+            };
+            out.insert("tool_choice".into(), mapped);
+        }
+    }
+
+    Value::Object(out)
+}
+
+/// OpenAI Chat Completions -> Anthropic Messages
+pub fn openai_to_claude_request(req: &Value, model: &str) -> Value {
+    let mut system = Vec::new();
+    let mut messages: Vec<Value> = Vec::new();
+
+    let mut push = |role: &str, blocks: Vec<Value>| {
+        if blocks.is_empty() {
+            return;
+        }
+        // Anthropic 要求 user/assistant 交替出现，合并连续的同角色消息
+        if let Some(last) = messages.last_mut() {
+            if last["role"] == role {
+                if let Some(arr) = last["content"].as_array_mut() {
+                    arr.extend(blocks);
+                    return;
+                }
+            }
+        }
+        messages.push(json!({ "role": role, "content": blocks }));
+    };
+
+    for msg in req
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+        let content = msg.get("content").unwrap_or(&Value::Null);
+        match role {
+            "system" | "developer" => system.push(text_of_blocks(content)),
+            "tool" => push(
+                "user",
+                vec![json!({
+                    "type": "tool_result",
+                    "tool_use_id": msg.get("tool_call_id").cloned().unwrap_or_default(),
+                    "content": text_of_blocks(content)
+                })],
+            ),
+            _ => {
+                let mut blocks = Vec::new();
+                match content {
+                    Value::String(s) if !s.is_empty() => {
+                        blocks.push(json!({ "type": "text", "text": s }))
+                    }
+                    Value::Array(parts) => {
+                        for part in parts {
+                            match part.get("type").and_then(|t| t.as_str()) {
+                                Some("text") => blocks.push(json!({
+                                    "type": "text",
+                                    "text": part.get("text").cloned().unwrap_or_default()
+                                })),
+                                Some("image_url") => {
+                                    let url = part
+                                        .pointer("/image_url/url")
+                                        .and_then(|u| u.as_str())
+                                        .unwrap_or("");
+                                    let source = match url
+                                        .strip_prefix("data:")
+                                        .and_then(|rest| rest.split_once(";base64,"))
+                                    {
+                                        Some((media_type, data)) => json!({
+                                            "type": "base64",
+                                            "media_type": media_type,
+                                            "data": data
+                                        }),
+                                        None => json!({ "type": "url", "url": url }),
+                                    };
+                                    blocks.push(json!({ "type": "image", "source": source }));
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if role == "assistant" {
+                    for call in msg
+                        .get("tool_calls")
+                        .and_then(|t| t.as_array())
+                        .into_iter()
+                        .flatten()
+                    {
+                        blocks.push(json!({
+                            "type": "tool_use",
+                            "id": call.get("id").cloned().unwrap_or_default(),
+                            "name": call.pointer("/function/name").cloned().unwrap_or_default(),
+                            "input": parse_arguments(call.pointer("/function/arguments"))
+                        }));
+                    }
+                    push("assistant", blocks);
+                } else {
+                    push("user", blocks);
+                }
+            }
+        }
+    }
+
+    let mut out = Map::new();
+    out.insert("model".into(), Value::String(model.to_string()));
+    out.insert("messages".into(), Value::Array(messages));
+    out.insert("stream".into(), Value::Bool(false));
+    let system: Vec<String> = system.into_iter().filter(|s| !s.is_empty()).collect();
+    if !system.is_empty() {
+        out.insert("system".into(), Value::String(system.join("\n\n")));
+    }
+    let max_tokens = req
+        .get("max_tokens")
+        .or_else(|| req.get("max_completion_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_MAX_TOKENS);
+    out.insert("max_tokens".into(), json!(max_tokens));
+    copy_field(req, "temperature", &mut out, "temperature");
+    copy_field(req, "top_p", &mut out, "top_p");
+    match req.get("stop") {
+        Some(Value::String(s)) => {
+            out.insert("stop_sequences".into(), json!([s]));
+        }
+        Some(v @ Value::Array(_)) => {
+            out.insert("stop_sequences".into(), v.clone());
+        }
+        _ => {}
+    }
+
+    let tools: Vec<Value> = req
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.get("function"))
+        .map(|f| {
+            json!({
+                "name": f.get("name").cloned().unwrap_or_default(),
+                "description": f.get("description").cloned().unwrap_or_default(),
+                "input_schema": f.get("parameters").cloned().unwrap_or_else(|| json!({ "type": "object" }))
+            })
+        })
+        .collect();
+    if !tools.is_empty() {
+        out.insert("tools".into(), Value::Array(tools));
+        if let Some(choice) = req.get("tool_choice") {
+            let mapped = match choice {
+                Value::String(s) if s == "required" => json!({ "type": "any" }),
+                Value::String(s) if s == "none" => json!({ "type": "none" }),
+                Value::Object(_) => json!({
+                    "type": "tool",
+                    "name": choice.pointer("/function/name").cloned().unwrap_or_default()
+                }),
+                _ => json!({ "type": "auto" }),
+            };
+            out.insert("tool_choice".into(), mapped);
+        }
+    }
+
+    Value::Object(out)
+}
+
+/// Gemini generateContent -> OpenAI Chat Completions
+pub fn gemini_to_openai_request(req: &Value, model: &str) -> Value {
+    let mut messages = Vec::new();
+
+    if let Some(system) = req
+        .get("systemInstruction")
+        .or_else(|| req.get("system_instruction"))
+    {
+        let text = system
+            .get("parts")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.is_empty() {
+            messages.push(json!({ "role": "system", "content": text }));
+        }
+    }
+
+    // Gemini 的 functionCall 没有 ID，按名称顺序生成并与 functionResponse 配对
+    let mut call_counter = 0usize;
+    let mut pending_calls: Vec<(String, String)> = Vec::new();
+
+    for content in req
+        .get("contents")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let role = match content.get("role").and_then(|r| r.as_str()) {
+            Some("model") => "assistant",
+            _ => "user",
+        };
+        let mut parts_out = Vec::new();
+        let mut tool_calls = Vec::new();
+
+        for part in content
+            .get("parts")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+        {
+            if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                if part.get("thought").and_then(|t| t.as_bool()) != Some(true) {
+                    parts_out.push(json!({ "type": "text", "text": text }));
+                }
+            } else if let Some(inline) = part.get("inlineData").or_else(|| part.get("inline_data"))
+            {
+                let mime = inline
+                    .get("mimeType")
+                    .or_else(|| inline.get("mime_type"))
+                    .and_then(|m| m.as_str())
+                    .unwrap_or("image/png");
+                let data = inline.get("data").and_then(|d| d.as_str()).unwrap_or("");
+                parts_out.push(json!({
+                    "type": "image_url",
+                    "image_url": { "url": format!("data:{};base64,{}", mime, data) }
+                }));
+            } else if let Some(call) = part.get("functionCall") {
+                let name = call
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let id = format!("call_{}_{}", name, call_counter);
+                call_counter += 1;
+                pending_calls.push((name.clone(), id.clone()));
+                tool_calls.push(json!({
+                    "id": id,
+                    "type": "function",
+                    "function": {
+                        "name": name,
+                        "arguments": call.get("args").cloned().unwrap_or_else(|| json!({})).to_string()
+                    }
+                }));
+            } else if let Some(resp) = part.get("functionResponse") {
+                let name = resp.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                let id = match pending_calls.iter().position(|(n, _)| n == name) {
+                    Some(idx) => pending_calls.remove(idx).1,
+                    None => format!("call_{}", name),
+                };
+                messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": id,
+                    "content": resp.get("response").cloned().unwrap_or_default().to_string()
+                }));
+            }
+        }
+
+        if role == "assistant" {
+            let text = parts_out
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("");
+            if text.is_empty() && tool_calls.is_empty() {
+                continue;
+            }
+            let mut out = json!({ "role": "assistant", "content": text });
+            if !tool_calls.is_empty() {
+                out["tool_calls"] = Value::Array(tool_calls);
+            }
+            messages.push(out);
+        } else if !parts_out.is_empty() {
+            let only_text = parts_out.iter().all(|p| p["type"] == "text");
+            let content = if only_text {
+                Value::String(text_of_blocks(&Value::Array(parts_out)))
+            } else {
+                Value::Array(parts_out)
+            };
+            messages.push(json!({ "role": "user", "content": content }));
+        }
+    }
+
+    let mut out = Map::new();
+    out.insert("model".into(), Value::String(model.to_string()));
+    out.insert("messages".into(), Value::Array(messages));
+    out.insert("stream".into(), Value::Bool(false));
+    if let Some(cfg) = req.get("generationConfig") {
+        copy_field(cfg, "maxOutputTokens", &mut out, "max_tokens");
+        copy_field(cfg, "temperature", &mut out, "temperature");
+        copy_field(cfg, "topP", &mut out, "top_p");
+        copy_field(cfg, "stopSequences", &mut out, "stop");
+    }
+
+    let tools: Vec<Value> = req
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.get("functionDeclarations").and_then(|d| d.as_array()))
+        .flatten()
+        .map(|decl| {
+            let mut parameters = decl
+                .get("parameters")
+                .cloned()
+                .unwrap_or_else(|| json!({ "type": "object" }));
+            lowercase_schema_types(&mut parameters);
+            json!({
+                "type": "function",
+                "function": {
+                    "name": decl.get("name").cloned().unwrap_or_default(),
+                    "description": decl.get("description").cloned().unwrap_or_default(),
+                    "parameters": parameters
+                }
+            })
+        })
+        .collect();
+    if !tools.is_empty() {
+        out.insert("tools".into(), Value::Array(tools));
+    }
+
+    Value::Object(out)
+}
+
+// ===== 响应转换 =====
+
+/// OpenAI Chat Completions 响应 -> Anthropic Messages 响应
+pub fn openai_to_claude_response(resp: &Value, model: &str) -> Value {
+    let message = resp
+        .pointer("/choices/0/message")
+        .cloned()
+        .unwrap_or_default();
+    let mut content = Vec::new();
+
+    if let Some(text) = message.get("content").map(text_of_blocks) {
+        if !text.is_empty() {
+            content.push(json!({ "type": "text", "text": text }));
+        }
+    }
+    for call in message
+        .get("tool_calls")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+    {
+        content.push(json!({
+            "type": "tool_use",
+            "id": call.get("id").cloned().unwrap_or_default(),
+            "name": call.pointer("/function/name").cloned().unwrap_or_default(),
+            "input": parse_arguments(call.pointer("/function/arguments"))
+        }));
+    }
+
+    let stop_reason = match resp
+        .pointer("/choices/0/finish_reason")
+        .and_then(|f| f.as_str())
+    {
+        Some("length") => "max_tokens",
+        Some("tool_calls") | Some("function_call") => "tool_use",
+        _ => "end_turn",
+    };
+
+    json!({
+        "id": format!("msg_{}", resp.get("id").and_then(|i| i.as_str()).unwrap_or("provider")),
+        "type": "message",
+        "role": "assistant",
+        "model": model,
+        "content": content,
+        "stop_reason": stop_reason,
+        "stop_sequence": null,
+        "usage": {
+            "input_tokens": resp.pointer("/usage/prompt_tokens").cloned().unwrap_or(json!(0)),
+            "output_tokens": resp.pointer("/usage/completion_tokens").cloned().unwrap_or(json!(0))
+        }
+    })
+}
+
+/// Anthropic Messages 响应 -> OpenAI Chat Completions 响应
+pub fn claude_to_openai_response(resp: &Value, model: &str) -> Value {
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    let mut tool_calls = Vec::new();
+
+    for block in resp
+        .get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        match block.get("type").and_then(|t| t.as_str()) {
+            Some("text") => text.push_str(block.get("text").and_then(|t| t.as_str()).unwrap_or("")),
+            Some("thinking") => reasoning.push_str(
+                block
+                    .get("thinking")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or(""),
+            ),
+            Some("tool_use") => tool_calls.push(json!({
+                "id": block.get("id").cloned().unwrap_or_default(),
+                "type": "function",
+                "function": {
+                    "name": block.get("name").cloned().unwrap_or_default(),
+                    "arguments": block.get("input").cloned().unwrap_or_else(|| json!({})).to_string()
+                }
+            })),
+            _ => {}
+        }
+    }
+
+    let finish_reason = match resp.get("stop_reason").and_then(|s| s.as_str()) {
+        Some("max_tokens") => "length",
+        Some("tool_use") => "tool_calls",
+        _ => "stop",
+    };
+
+    let mut message = json!({ "role": "assistant", "content": text });
+    if !reasoning.is_empty() {
+        message["reasoning_content"] = Value::String(reasoning);
+    }
+    if !tool_calls.is_empty() {
+        message["tool_calls"] = Value::Array(tool_calls);
+    }
+
+    let input = resp
+        .pointer("/usage/input_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let output = resp
+        .pointer("/usage/output_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    json!({
+        "id": format!("chatcmpl-{}", resp.get("id").and_then(|i| i.as_str()).unwrap_or("provider")),
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{ "index": 0, "message": message, "finish_reason": finish_reason }],
+        "usage": {
+            "prompt_tokens": input,
+            "completion_tokens": output,
+            "total_tokens": input + output
+        }
+    })
+}
+
+/// OpenAI Chat Completions 响应 -> Gemini generateContent 响应
+pub fn openai_to_gemini_response(resp: &Value, model: &str) -> Value {
+    let message = resp
+        .pointer("/choices/0/message")
+        .cloned()
+        .unwrap_or_default();
+    let mut parts = Vec::new();
+
+    if let Some(text) = message.get("content").map(text_of_blocks) {
+        if !text.is_empty() {
+            parts.push(json!({ "text": text }));
+        }
+    }
+    for call in message
+        .get("tool_calls")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+    {
+        parts.push(json!({
+            "functionCall": {
+                "name": call.pointer("/function/name").cloned().unwrap_or_default(),
+                "args": parse_arguments(call.pointer("/function/arguments"))
+            }
+        }));
+    }
+
+    let finish_reason = match resp
+        .pointer("/choices/0/finish_reason")
+        .and_then(|f| f.as_str())
+    {
+        Some("length") => "MAX_TOKENS",
+        Some("content_filter") => "SAFETY",
+        _ => "STOP",
+    };
+
+    let prompt = resp
+        .pointer("/usage/prompt_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    let completion = resp
+        .pointer("/usage/completion_tokens")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    json!({
+        "candidates": [{
+            "content": { "role": "model", "parts": parts },
+            "finishReason": finish_reason,
+            "index": 0
+        }],
+        "usageMetadata": {
+            "promptTokenCount": prompt,
+            "candidatesTokenCount": completion,
+            "totalTokenCount": prompt + completion
+        },
+        "modelVersion": model
+    })
+}
+
+// ===== 流式合成 =====
+
+/// 将完整的 Anthropic 响应合成为 SSE 事件序列
+pub fn claude_response_to_sse(resp: &Value) -> String {
+    let mut events = Vec::new();
+    let mut push = |event: &str, data: Value| {
+        events.push(format!("event: {}\ndata: {}\n\n", event, data));
+    };
+
+    let mut start = resp.clone();
+    start["content"] = json!([]);
+    start["stop_reason"] = Value::Null;
+    push(
+        "message_start",
+        json!({ "type": "message_start", "message": start }),
+    );
+
+    for (index, block) in resp
+        .get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .enumerate()
+    {
+        match block.get("type").and_then(|t| t.as_str()) {
+            Some("tool_use") => {
+                let mut head = block.clone();
+                head["input"] = json!({});
+                push(
+                    "content_block_start",
+                    json!({ "type": "content_block_start", "index": index, "content_block": head }),
+                );
+                push(
+                    "content_block_delta",
+                    json!({
+                        "type": "content_block_delta",
+                        "index": index,
+                        "delta": {
+                            "type": "input_json_delta",
+                            "partial_json": block.get("input").cloned().unwrap_or_else(|| json!({})).to_string()
+                        }
+                    }),
+                );
+            }
+            _ => {
+                push(
+                    "content_block_start",
+                    json!({
+                        "type": "content_block_start",
+                        "index": index,
+                        "content_block": { "type": "text", "text": "" }
+                    }),
+                );
+                push(
+                    "content_block_delta",
+                    json!({
+                        "type": "content_block_delta",
+                        "index": index,
+                        "delta": { "type": "text_delta", "text": block.get("text").cloned().unwrap_or_default() }
+                    }),
+                );
+            }
+        }
+        push(
+            "content_block_stop",
+            json!({ "type": "content_block_stop", "index": index }),
+        );
+    }
+
+    push(
+        "message_delta",
+        json!({
+            "type": "message_delta",
+            "delta": { "stop_reason": resp.get("stop_reason").cloned().unwrap_or(json!("end_turn")), "stop_sequence": null },
+            "usage": { "output_tokens": resp.pointer("/usage/output_tokens").cloned().unwrap_or(json!(0)) }
+        }),
+    );
+    push("message_stop", json!({ "type": "message_stop" }));
+
+    events.join("")
+}
+
+/// 将完整的 OpenAI 响应合成为 chat.completion.chunk 事件序列
+pub fn openai_response_to_sse(resp: &Value) -> String {
+    let message = resp
+        .pointer("/choices/0/message")
+        .cloned()
+        .unwrap_or_default();
+    let mut delta = json!({ "role": "assistant" });
+    if let Some(content) = message.get("content").filter(|c| !c.is_null()) {
+        delta["content"] = content.clone();
+    }
+    if let Some(reasoning) = message.get("reasoning_content") {
+        delta["reasoning_content"] = reasoning.clone();
+    }
+    if let Some(Value::Array(calls)) = message.get("tool_calls") {
+        let calls: Vec<Value> = calls
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let mut c = c.clone();
+                c["index"] = json!(i);
+                c
+            })
+            .collect();
+        delta["tool_calls"] = Value::Array(calls);
+    }
+
+    let chunk = |delta: Value, finish: Value, usage: Option<Value>| {
+        let mut c = json!({
+            "id": resp.get("id").cloned().unwrap_or_default(),
+            "object": "chat.completion.chunk",
+            "created": resp.get("created").cloned().unwrap_or_default(),
+            "model": resp.get("model").cloned().unwrap_or_default(),
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish }]
+        });
+        if let Some(u) = usage {
+            c["usage"] = u;
+        }
+        format!("data: {}\n\n", c)
+    };
+
+    let finish = resp
+        .pointer("/choices/0/finish_reason")
+        .cloned()
+        .unwrap_or(json!("stop"));
+    format!(
+        "{}{}data: [DONE]\n\n",
+        chunk(delta, Value::Null, None),
+        chunk(json!({}), finish, resp.get("usage").cloned())
+    )
+}
+
+/// 将完整的 Gemini 响应合成为单个 SSE 事件
+pub fn gemini_response_to_sse(resp: &Value) -> String {
+    format!("data: {}\n\n", resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claude_openai_request_roundtrip() {
+        let claude = json!({
+            "model": "claude-sonnet-4-5",
+            "system": [{ "type": "text", "text": "be brief" }],
+            "max_tokens": 256,
+            "messages": [
+                { "role": "user", "content": "weather?" },
+                { "role": "assistant", "content": [
+                    { "type": "thinking", "thinking": "hmm", "signature": "sig" },
+                    { "type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": { "city": "Paris" } }
+                ]},
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "toolu_1", "content": "sunny" }
+                ]}
+            ],
+            "tools": [{ "name": "get_weather", "input_schema": { "type": "object" } }],
+            "tool_choice": { "type": "any" }
+        });
+
+        let openai = claude_to_openai_request(&claude, "llama3.1");
+        assert_eq!(openai["model"], "llama3.1");
+        assert_eq!(openai["messages"][0]["role"], "system");
+        assert_eq!(
+            openai["messages"][2]["tool_calls"][0]["function"]["name"],
+            "get_weather"
+        );
+        assert_eq!(openai["messages"][3]["role"], "tool");
+        assert_eq!(openai["messages"][3]["tool_call_id"], "toolu_1");
+        assert_eq!(openai["tool_choice"], "required");
+
+        let back = openai_to_claude_request(&openai, "claude-sonnet-4-5");
+        assert_eq!(back["system"], "be brief");
+        assert_eq!(back["max_tokens"], 256);
+        assert_eq!(back["messages"][1]["content"][0]["type"], "tool_use");
+        assert_eq!(back["messages"][1]["content"][0]["input"]["city"], "Paris");
+        assert_eq!(back["messages"][2]["content"][0]["type"], "tool_result");
+        assert_eq!(back["tool_choice"]["type"], "any");
+    }
+
+    #[test]
+    fn test_gemini_to_openai_request_pairs_function_calls() {
+        let gemini = json!({
+            "systemInstruction": { "parts": [{ "text": "sys" }] },
+            "contents": [
+                { "role": "user", "parts": [{ "text": "hi" }] },
+                { "role": "model", "parts": [{ "functionCall": { "name": "lookup", "args": { "q": 1 } } }] },
+                { "role": "user", "parts": [{ "functionResponse": { "name": "lookup", "response": { "ok": true } } }] }
+            ],
+            "tools": [{ "functionDeclarations": [{ "name": "lookup", "parameters": { "type": "OBJECT" } }] }],
+            "generationConfig": { "maxOutputTokens": 64 }
+        });
+
+        let openai = gemini_to_openai_request(&gemini, "qwen2.5");
+        let messages = openai["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        let call_id = messages[2]["tool_calls"][0]["id"].clone();
+        assert_eq!(messages[3]["tool_call_id"], call_id);
+        assert_eq!(
+            openai["tools"][0]["function"]["parameters"]["type"],
+            "object"
+        );
+        assert_eq!(openai["max_tokens"], 64);
+    }
+
+    #[test]
+    fn test_response_conversion_and_sse() {
+        let openai = json!({
+            "id": "abc",
+            "created": 1,
+            "model": "llama3.1",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "Hello",
+                    "tool_calls": [{ "id": "call_1", "type": "function", "function": { "name": "f", "arguments": "{\"a\":1}" } }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 2, "total_tokens": 5 }
+        });
+
+        let claude = openai_to_claude_response(&openai, "claude-sonnet-4-5");
+        assert_eq!(claude["stop_reason"], "tool_use");
+        assert_eq!(claude["content"][1]["input"]["a"], 1);
+        assert_eq!(claude["usage"]["output_tokens"], 2);
+
+        let sse = claude_response_to_sse(&claude);
+        assert!(sse.starts_with("event: message_start"));
+        assert!(sse.contains("input_json_delta"));
+        assert!(sse.ends_with("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+
+        let back = claude_to_openai_response(&claude, "llama3.1");
+        assert_eq!(back["choices"][0]["finish_reason"], "tool_calls");
+        assert_eq!(back["usage"]["total_tokens"], 5);
+
+        let gemini = openai_to_gemini_response(&openai, "llama3.1");
+        assert_eq!(
+            gemini["candidates"][0]["content"]["parts"][0]["text"],
+            "Hello"
+        );
+        assert_eq!(
+            gemini["candidates"][0]["content"]["parts"][1]["functionCall"]["args"]["a"],
+            1
+        );
+
+        assert!(openai_response_to_sse(&openai).ends_with("data: [DONE]\n\n"));
+    }
+}
@@ -0,0 +1,543 @@
+//! 通用上游提供商转发
+//! 同协议时直接透传 (含流式)；跨协议时以非流式请求上游，转换响应后按需合成 SSE
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+use super::compat;
+use super::registry::ProviderRoute;
+use super::zai_anthropic::{
+    build_client, copy_passthrough_headers, deep_remove_cache_control, join_base_url,
+};
+use crate::proxy::config::{ProviderAuthStyle, ProviderProtocol, UpstreamProviderConfig};
+use crate::proxy::server::AppState;
+
+/// 客户端使用的入口协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientProtocol {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+impl ClientProtocol {
+    fn matches(self, protocol: ProviderProtocol) -> bool {
+        matches!(
+            (self, protocol),
+            (ClientProtocol::OpenAI, ProviderProtocol::OpenaiChat)
+                | (
+                    ClientProtocol::Anthropic,
+                    ProviderProtocol::AnthropicMessages
+                )
+        )
+    }
+}
+
+/// 按客户端协议格式化错误响应
+fn error_response(client: ClientProtocol, status: StatusCode, message: &str) -> Response {
+    let body = match client {
+        ClientProtocol::Anthropic => json!({
+            "type": "error",
+            "error": { "type": "api_error", "message": message }
+        }),
+        ClientProtocol::OpenAI => json!({
+            "error": { "message": message, "type": "upstream_error", "code": status.as_u16() }
+        }),
+        ClientProtocol::Gemini => json!({
+            "error": { "code": status.as_u16(), "message": message, "status": "UNAVAILABLE" }
+        }),
+    };
+    (status, axum::Json(body)).into_response()
+}
+
+fn upstream_url(provider: &UpstreamProviderConfig) -> Result<String, String> {
+    let path = match provider.protocol {
+        ProviderProtocol::OpenaiChat => "/chat/completions",
+        ProviderProtocol::AnthropicMessages => "/v1/messages",
+    };
+    let mut url = join_base_url(&provider.base_url, path)?;
+    if !provider.query_params.is_empty() {
+        let mut parsed = url::Url::parse(&url).map_err(|e| format!("Invalid base_url: {}", e))?;
+        {
+            let mut pairs = parsed.query_pairs_mut();
+            for (k, v) in &provider.query_params {
+                pairs.append_pair(k, v);
+            }
+        }
+        url = parsed.to_string();
+    }
+    Ok(url)
+}
+
+fn build_headers(provider: &UpstreamProviderConfig, incoming: &HeaderMap) -> HeaderMap {
+    let mut headers = copy_passthrough_headers(incoming);
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    // 跨协议时客户端的 accept 可能是 text/event-stream，统一由上游决定
+    headers.remove(header::ACCEPT);
+    // 压缩响应无法在转换前解析
+    headers.remove(header::ACCEPT_ENCODING);
+
+    if provider.protocol == ProviderProtocol::AnthropicMessages {
+        headers
+            .entry("anthropic-version")
+            .or_insert(HeaderValue::from_static("2023-06-01"));
+    } else {
+        headers.remove("anthropic-version");
+    }
+
+    let key = provider.api_key.trim();
+    if !key.is_empty() {
+        let (name, value) = match provider.auth_style {
+            ProviderAuthStyle::Bearer => (header::AUTHORIZATION, format!("Bearer {}", key)),
+            ProviderAuthStyle::XApiKey => (HeaderName::from_static("x-api-key"), key.to_string()),
+            ProviderAuthStyle::ApiKey => (HeaderName::from_static("api-key"), key.to_string()),
+            ProviderAuthStyle::None => return apply_extra_headers(headers, provider),
+        };
+        if let Ok(v) = HeaderValue::from_str(&value) {
+            headers.insert(name, v);
+        }
+    }
+
+    apply_extra_headers(headers, provider)
+}
+
+fn apply_extra_headers(mut headers: HeaderMap, provider: &UpstreamProviderConfig) -> HeaderMap {
+    for (k, v) in &provider.extra_headers {
+        match (
+            HeaderName::from_bytes(k.as_bytes()),
+            HeaderValue::from_str(v),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!("[Provider] {} has invalid extra header: {}", provider.id, k),
+        }
+    }
+    headers
+}
+
+/// 将客户端请求体转换为上游协议的请求体
+fn convert_request(
+    client: ClientProtocol,
+    protocol: ProviderProtocol,
+    body: &Value,
+    model: &str,
+) -> Value {
+    match (client, protocol) {
+        (ClientProtocol::Anthropic, ProviderProtocol::OpenaiChat) => {
+            compat::claude_to_openai_request(body, model)
+        }
+        (ClientProtocol::OpenAI, ProviderProtocol::AnthropicMessages) => {
+            compat::openai_to_claude_request(body, model)
+        }
+        (ClientProtocol::Gemini, ProviderProtocol::OpenaiChat) => {
+            compat::gemini_to_openai_request(body, model)
+        }
+        (ClientProtocol::Gemini, ProviderProtocol::AnthropicMessages) => {
+            // Gemini -> OpenAI -> Anthropic
+            let openai = compat::gemini_to_openai_request(body, model);
+            compat::openai_to_claude_request(&openai, model)
+        }
+        // 同协议: 仅替换模型名
+        _ => {
+            let mut body = body.clone();
+            body["model"] = Value::String(model.to_string());
+            body
+        }
+    }
+}
+
+/// 将上游非流式响应转换为客户端协议的响应体
+fn convert_response(
+    client: ClientProtocol,
+    protocol: ProviderProtocol,
+    resp: &Value,
+    model: &str,
+) -> Value {
+    match (client, protocol) {
+        (ClientProtocol::Anthropic, ProviderProtocol::OpenaiChat) => {
+            compat::openai_to_claude_response(resp, model)
+        }
+        (ClientProtocol::OpenAI, ProviderProtocol::AnthropicMessages) => {
+            compat::claude_to_openai_response(resp, model)
+        }
+        (ClientProtocol::Gemini, ProviderProtocol::OpenaiChat) => {
+            compat::openai_to_gemini_response(resp, model)
+        }
+        (ClientProtocol::Gemini, ProviderProtocol::AnthropicMessages) => {
+            let openai = compat::claude_to_openai_response(resp, model);
+            compat::openai_to_gemini_response(&openai, model)
+        }
+        _ => resp.clone(),
+    }
+}
+
+fn synthesize_sse(client: ClientProtocol, resp: &Value) -> String {
+    match client {
+        ClientProtocol::Anthropic => compat::claude_response_to_sse(resp),
+        ClientProtocol::OpenAI => compat::openai_response_to_sse(resp),
+        ClientProtocol::Gemini => compat::gemini_response_to_sse(resp),
+    }
+}
+
+/// 转发请求到通用提供商
+///
+/// `body` 为客户端协议的请求体，`stream` 为客户端是否请求流式响应，
+/// `client_model` 为客户端请求的模型名 (用于响应中的 model 字段)
+pub async fn forward(
+    state: &AppState,
+    route: &ProviderRoute,
+    client: ClientProtocol,
+    body: &Value,
+    stream: bool,
+    client_model: &str,
+    incoming_headers: &HeaderMap,
+) -> Response {
+    let timeout_secs = state.request_timeout.max(5);
+    let upstream_proxy = state.upstream_proxy.read().await.clone();
+    let http = match build_client(Some(upstream_proxy), timeout_secs) {
+        Ok(c) => c,
+        Err(e) => return error_response(client, StatusCode::INTERNAL_SERVER_ERROR, &e),
+    };
+
+    forward_with_client(
+        &http,
+        route,
+        client,
+        body,
+        stream,
+        client_model,
+        incoming_headers,
+    )
+    .await
+}
+
+async fn forward_with_client(
+    http: &reqwest::Client,
+    route: &ProviderRoute,
+    client: ClientProtocol,
+    body: &Value,
+    stream: bool,
+    client_model: &str,
+    incoming_headers: &HeaderMap,
+) -> Response {
+    let provider = &route.provider;
+    let same_protocol = client.matches(provider.protocol);
+
+    let url = match upstream_url(provider) {
+        Ok(u) => u,
+        Err(e) => return error_response(client, StatusCode::BAD_REQUEST, &e),
+    };
+
+    let mut upstream_body = convert_request(client, provider.protocol, body, &route.upstream_model);
+    // 跨协议时只支持非流式上游，由本地合成 SSE
+    let upstream_stream = same_protocol && stream;
+    upstream_body["stream"] = Value::Bool(upstream_stream);
+    if !upstream_stream {
+        if let Some(obj) = upstream_body.as_object_mut() {
+            obj.remove("stream_options");
+        }
+    }
+    deep_remove_cache_control(&mut upstream_body);
+
+    tracing::debug!(
+        "[Provider] Forwarding {:?} request to {} ({}): {}",
+        client,
+        provider.id,
+        route.upstream_model,
+        url
+    );
+
+    let resp = match http
+        .post(&url)
+        .headers(build_headers(provider, incoming_headers))
+        .body(serde_json::to_vec(&upstream_body).unwrap_or_default())
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return error_response(
+                client,
+                StatusCode::BAD_GATEWAY,
+                &format!("Upstream provider {} request failed: {}", provider.id, e),
+            )
+        }
+    };
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    let provider_header = HeaderValue::from_str(&provider.id).ok();
+    let model_header = HeaderValue::from_str(&route.upstream_model).ok();
+    let with_route_headers = |mut response: Response| {
+        if let Some(v) = provider_header.clone() {
+            response.headers_mut().insert("X-Upstream-Provider", v);
+        }
+        if let Some(v) = model_header.clone() {
+            response.headers_mut().insert("X-Mapped-Model", v);
+        }
+        response
+    };
+
+    if same_protocol {
+        let mut out = Response::builder().status(status);
+        if let Some(ct) = resp.headers().get(header::CONTENT_TYPE) {
+            out = out.header(header::CONTENT_TYPE, ct.clone());
+        }
+        let body_stream = resp.bytes_stream().map(|chunk| match chunk {
+            Ok(b) => Ok::<Bytes, std::io::Error>(b),
+            Err(e) => Ok(Bytes::from(format!("Upstream stream error: {}", e))),
+        });
+        return with_route_headers(
+            out.body(Body::from_stream(body_stream))
+                .unwrap_or_else(|_| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to build response",
+                    )
+                        .into_response()
+                }),
+        );
+    }
+
+    let text = match resp.text().await {
+        Ok(t) => t,
+        Err(e) => {
+            return error_response(
+                client,
+                StatusCode::BAD_GATEWAY,
+                &format!("Failed to read upstream response: {}", e),
+            )
+        }
+    };
+
+    if !status.is_success() {
+        let message = serde_json::from_str::<Value>(&text)
+            .ok()
+            .and_then(|v| {
+                v.pointer("/error/message")
+                    .and_then(|m| m.as_str())
+                    .map(str::to_string)
+            })
+            .unwrap_or(text);
+        return with_route_headers(error_response(client, status, &message));
+    }
+
+    let upstream_json: Value = match serde_json::from_str(&text) {
+        Ok(v) => v,
+        Err(e) => {
+            return error_response(
+                client,
+                StatusCode::BAD_GATEWAY,
+                &format!("Invalid upstream response: {}", e),
+            )
+        }
+    };
+
+    let converted = convert_response(client, provider.protocol, &upstream_json, client_model);
+    let response = if stream {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::from(synthesize_sse(client, &converted)))
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+    } else {
+        (StatusCode::OK, axum::Json(converted)).into_response()
+    };
+
+    with_route_headers(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::ZaiDispatchMode;
+    use std::collections::HashMap;
+
+    /// 本地 Ollama 替身: 只实现 /v1/chat/completions
+    async fn spawn_mock_ollama() -> String {
+        use axum::{routing::post, Json, Router};
+
+        async fn chat(headers: HeaderMap, Json(body): Json<Value>) -> Json<Value> {
+            let last = body["messages"]
+                .as_array()
+                .and_then(|m| m.last())
+                .and_then(|m| m["content"].as_str())
+                .unwrap_or("")
+                .to_string();
+            Json(json!({
+                "id": "ollama-1",
+                "object": "chat.completion",
+                "created": 1,
+                "model": body["model"],
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": format!(
+                            "echo:{}|auth:{}|stream:{}",
+                            last,
+                            headers.get("authorization").is_some(),
+                            body["stream"]
+                        )
+                    },
+                    "finish_reason": "stop"
+                }],
+                "usage": { "prompt_tokens": 4, "completion_tokens": 2, "total_tokens": 6 }
+            }))
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let app = Router::new().route("/v1/chat/completions", post(chat));
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/v1", addr)
+    }
+
+    fn ollama_route(base_url: String) -> ProviderRoute {
+        ProviderRoute {
+            provider: UpstreamProviderConfig {
+                id: "ollama".to_string(),
+                name: "Local Ollama".to_string(),
+                enabled: true,
+                base_url,
+                api_key: String::new(),
+                auth_style: ProviderAuthStyle::None,
+                protocol: ProviderProtocol::OpenaiChat,
+                dispatch_mode: ZaiDispatchMode::Exclusive,
+                model_mapping: HashMap::new(),
+                extra_headers: HashMap::new(),
+                query_params: HashMap::new(),
+            },
+            upstream_model: "llama3.1".to_string(),
+            explicit: true,
+        }
+    }
+
+    async fn body_string(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_forward_anthropic_to_local_ollama() {
+        let http = build_client(None, 30).unwrap();
+        let route = ollama_route(spawn_mock_ollama().await);
+        let body = json!({
+            "model": "ollama:llama3.1",
+            "max_tokens": 32,
+            "messages": [{ "role": "user", "content": "ping" }]
+        });
+
+        let response = forward_with_client(
+            &http,
+            &route,
+            ClientProtocol::Anthropic,
+            &body,
+            false,
+            "ollama:llama3.1",
+            &HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["X-Upstream-Provider"], "ollama");
+        assert_eq!(response.headers()["X-Mapped-Model"], "llama3.1");
+
+        let json: Value = serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(json["type"], "message");
+        assert_eq!(json["model"], "ollama:llama3.1");
+        assert_eq!(
+            json["content"][0]["text"],
+            "echo:ping|auth:false|stream:false"
+        );
+        assert_eq!(json["usage"]["input_tokens"], 4);
+
+        // 流式请求: 上游仍为非流式，本地合成 Anthropic SSE
+        let response = forward_with_client(
+            &http,
+            &route,
+            ClientProtocol::Anthropic,
+            &body,
+            true,
+            "ollama:llama3.1",
+            &HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let sse = body_string(response).await;
+        assert!(sse.contains("event: message_start"));
+        assert!(sse.contains("echo:ping"));
+    }
+
+    #[tokio::test]
+    async fn test_forward_openai_passthrough_and_unreachable() {
+        let http = build_client(None, 30).unwrap();
+        let route = ollama_route(spawn_mock_ollama().await);
+        let body =
+            json!({ "model": "llama3.1", "messages": [{ "role": "user", "content": "hi" }] });
+
+        let response = forward_with_client(
+            &http,
+            &route,
+            ClientProtocol::OpenAI,
+            &body,
+            false,
+            "llama3.1",
+            &HeaderMap::new(),
+        )
+        .await;
+        let json: Value = serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(
+            json["choices"][0]["message"]["content"],
+            "echo:hi|auth:false|stream:false"
+        );
+
+        let gemini_body = json!({ "contents": [{ "role": "user", "parts": [{ "text": "yo" }] }] });
+        let response = forward_with_client(
+            &http,
+            &route,
+            ClientProtocol::Gemini,
+            &gemini_body,
+            false,
+            "llama3.1",
+            &HeaderMap::new(),
+        )
+        .await;
+        let json: Value = serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(
+            json["candidates"][0]["content"]["parts"][0]["text"],
+            "echo:yo|auth:false|stream:false"
+        );
+
+        let dead = ollama_route("http://127.0.0.1:1/v1".to_string());
+        let response = forward_with_client(
+            &http,
+            &dead,
+            ClientProtocol::Anthropic,
+            &body,
+            false,
+            "llama3.1",
+            &HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let json: Value = serde_json::from_str(&body_string(response).await).unwrap();
+        assert_eq!(json["type"], "error");
+    }
+}
@@ -1 +1,4 @@
+pub mod compat;
+pub mod generic;
+pub mod registry;
 pub mod zai_anthropic;
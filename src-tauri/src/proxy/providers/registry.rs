@@ -0,0 +1,257 @@
+//! 通用上游提供商注册表
+//! 根据模型名与调度模式决定请求是否交给某个 OpenAI / Anthropic 兼容上游处理
+
+use std::sync::atomic::Ordering;
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::{get_upstream_providers, UpstreamProviderConfig};
+use crate::proxy::server::AppState;
+use crate::proxy::ZaiDispatchMode;
+
+/// 一次路由决策: 目标提供商及发往上游的模型名
+#[derive(Debug, Clone)]
+pub struct ProviderRoute {
+    pub provider: UpstreamProviderConfig,
+    pub upstream_model: String,
+    /// 是否通过 `id:model` 前缀显式指定 (忽略调度模式)
+    pub explicit: bool,
+}
+
+fn is_active(provider: &UpstreamProviderConfig) -> bool {
+    provider.enabled
+        && provider.dispatch_mode != ZaiDispatchMode::Off
+        && !provider.base_url.trim().is_empty()
+}
+
+fn resolve_target(target: &str, model: &str) -> String {
+    let target = target.trim();
+    if target.is_empty() || target == "*" {
+        model.to_string()
+    } else {
+        target.to_string()
+    }
+}
+
+/// 在提供商列表中查找接管该模型的提供商 (不考虑调度模式)
+///
+/// 优先级: `id:model` 显式前缀 > 精确映射 > 最具体的通配符映射
+///
+/// 同等具体的通配符: 先按提供商列表顺序，同一提供商内按模式字典序 (model_mapping 为 HashMap，
+/// 迭代顺序不固定，需显式决胜以保证路由结果稳定)
+pub fn match_provider(model: &str, providers: &[UpstreamProviderConfig]) -> Option<ProviderRoute> {
+    let active: Vec<&UpstreamProviderConfig> = providers.iter().filter(|p| is_active(p)).collect();
+
+    if let Some((prefix, rest)) = model.split_once(':') {
+        if let Some(provider) = active.iter().find(|p| p.id.eq_ignore_ascii_case(prefix)) {
+            let upstream_model = provider
+                .model_mapping
+                .get(rest)
+                .map(|t| resolve_target(t, rest))
+                .unwrap_or_else(|| rest.to_string());
+            return Some(ProviderRoute {
+                provider: (*provider).clone(),
+                upstream_model,
+                explicit: true,
+            });
+        }
+    }
+
+    for provider in &active {
+        if let Some(target) = provider.model_mapping.get(model) {
+            return Some(ProviderRoute {
+                provider: (*provider).clone(),
+                upstream_model: resolve_target(target, model),
+                explicit: false,
+            });
+        }
+    }
+
+    let mut best: Option<(&UpstreamProviderConfig, &str, &str, usize)> = None;
+    for provider in &active {
+        for (pattern, target) in &provider.model_mapping {
+            if pattern.contains('*') && wildcard_match(pattern, model) {
+                let specificity = pattern.chars().count() - pattern.matches('*').count();
+                let better = match best {
+                    None => true,
+                    Some((best_provider, best_pattern, _, s)) => {
+                        specificity > s
+                            || (specificity == s
+                                && std::ptr::eq(best_provider, *provider)
+                                && pattern.as_str() < best_pattern)
+                    }
+                };
+                if better {
+                    best = Some((provider, pattern.as_str(), target.as_str(), specificity));
+                }
+            }
+        }
+    }
+
+    best.map(|(provider, _, target, _)| ProviderRoute {
+        provider: provider.clone(),
+        upstream_model: resolve_target(target, model),
+        explicit: false,
+    })
+}
+
+/// 结合调度模式决定本次请求是否路由到通用提供商
+///
+/// `account_group` 为 `has_available_account` 使用的配额分组 ("claude" / "gemini" 等)
+pub async fn select_provider_route(
+    state: &AppState,
+    model: &str,
+    account_group: &str,
+) -> Option<ProviderRoute> {
+    let providers = get_upstream_providers();
+    if providers.is_empty() {
+        return None;
+    }
+
+    let route = match_provider(model, &providers)?;
+    if route.explicit {
+        return Some(route);
+    }
+
+    let google_accounts = state.token_manager.len();
+    let selected = match route.provider.dispatch_mode {
+        ZaiDispatchMode::Off => false,
+        ZaiDispatchMode::Exclusive => true,
+        ZaiDispatchMode::Fallback => {
+            if google_accounts == 0 {
+                true
+            } else {
+                let normalized =
+                    crate::proxy::common::model_mapping::normalize_to_standard_id(model)
+                        .unwrap_or_else(|| model.to_string());
+                !state
+                    .token_manager
+                    .has_available_account(account_group, &normalized)
+                    .await
+            }
+        }
+        ZaiDispatchMode::Pooled => {
+            // 与 z.ai 相同: 提供商占号池中的一个槽位
+            let total = google_accounts.saturating_add(1).max(1);
+            state
+                .provider_rr
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(total)
+        }
+    };
+
+    if selected {
+        tracing::info!(
+            "[Provider] Routing model {} -> {} ({:?})",
+            model,
+            route.provider.id,
+            route.provider.dispatch_mode
+        );
+        Some(route)
+    } else {
+        None
+    }
+}
+
+/// 提供商声明的具体模型 (用于 /v1/models)
+pub fn list_provider_models() -> Vec<String> {
+    let mut models: Vec<String> = get_upstream_providers()
+        .iter()
+        .filter(|p| is_active(p))
+        .flat_map(|p| {
+            p.model_mapping
+                .keys()
+                .filter(|k| !k.contains('*'))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    models.sort();
+    models.dedup();
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn provider(id: &str, mapping: &[(&str, &str)]) -> UpstreamProviderConfig {
+        UpstreamProviderConfig {
+            id: id.to_string(),
+            name: String::new(),
+            enabled: true,
+            base_url: "http://127.0.0.1:11434/v1".to_string(),
+            api_key: String::new(),
+            auth_style: Default::default(),
+            protocol: Default::default(),
+            dispatch_mode: ZaiDispatchMode::Exclusive,
+            model_mapping: mapping
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            extra_headers: HashMap::new(),
+            query_params: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_match_provider() {
+        let mut disabled = provider("off", &[("gpt-4o", "")]);
+        disabled.enabled = false;
+        let providers = vec![
+            disabled,
+            provider(
+                "deepseek",
+                &[("deepseek-*", "*"), ("gpt-4o", "deepseek-chat")],
+            ),
+            provider(
+                "ollama",
+                &[("deepseek-r1*", "deepseek-r1:14b"), ("llama*", "")],
+            ),
+        ];
+
+        let route = match_provider("gpt-4o", &providers).unwrap();
+        assert_eq!(route.provider.id, "deepseek");
+        assert_eq!(route.upstream_model, "deepseek-chat");
+
+        // 更具体的通配符优先
+        let route = match_provider("deepseek-r1-lite", &providers).unwrap();
+        assert_eq!(route.provider.id, "ollama");
+        assert_eq!(route.upstream_model, "deepseek-r1:14b");
+
+        let route = match_provider("deepseek-coder", &providers).unwrap();
+        assert_eq!(route.provider.id, "deepseek");
+        assert_eq!(route.upstream_model, "deepseek-coder");
+
+        // 显式前缀即使没有映射也会路由
+        let route = match_provider("ollama:qwen2.5:7b", &providers).unwrap();
+        assert!(route.explicit);
+        assert_eq!(route.upstream_model, "qwen2.5:7b");
+
+        assert!(match_provider("claude-sonnet-4-5", &providers).is_none());
+        assert!(match_provider("off:gpt-4o", &providers).is_none());
+    }
+
+    #[test]
+    fn test_match_provider_wildcard_tie_is_deterministic() {
+        // 同一提供商内两个同等具体的通配符 ("qwen*" 与 "*-max" 均为 4 个字面字符)
+        // 每次重建 HashMap (随机哈希种子)，结果应保持一致
+        for _ in 0..20 {
+            let providers = vec![
+                provider("first", &[("qwen*", "by-prefix"), ("*-max", "by-suffix")]),
+                provider("second", &[("q*max", "other")]),
+            ];
+            let route = match_provider("qwen-max", &providers).unwrap();
+            assert_eq!(route.provider.id, "first");
+            assert_eq!(route.upstream_model, "by-suffix");
+        }
+
+        // 跨提供商同等具体时按提供商顺序
+        let providers = vec![
+            provider("a", &[("glm-*", "from-a")]),
+            provider("b", &[("*lm-4", "from-b")]),
+        ];
+        let route = match_provider("glm-4", &providers).unwrap();
+        assert_eq!(route.provider.id, "a");
+    }
+}
@@ -595,6 +595,24 @@ impl RateLimitTracker {
         }
     }
 
+    /// 获取当前生效的限流锁定: (账号ID, 模型, 剩余秒数)，用于 /metrics 导出
+    pub fn active_lockouts(&self) -> Vec<(String, Option<String>, u64)> {
+        let now = SystemTime::now();
+        self.limits
+            .iter()
+            .filter_map(|entry| {
+                let remaining = entry.value().reset_time.duration_since(now).ok()?;
+                let account_id = entry
+                    .key()
+                    .split_once(':')
+                    .map(|(id, _)| id)
+                    .unwrap_or(entry.key())
+                    .to_string();
+                Some((account_id, entry.value().model.clone(), remaining.as_secs()))
+            })
+            .collect()
+    }
+
     /// 清除过期的限流记录
     #[allow(dead_code)]
     pub fn cleanup_expired(&self) -> usize {
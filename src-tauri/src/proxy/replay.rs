@@ -0,0 +1,388 @@
+// 请求重放 (Request Replay)
+// 从监控日志取出原始请求，按当前映射与配置重新提交到代理路由 (可选固定账号)，
+// 并对比新旧响应的状态码、映射模型、账号、Token 与响应体
+use axum::{
+    body::Body,
+    http::{header, Method, Request},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+use crate::proxy::monitor::ProxyRequestLog;
+
+const MAX_REPLAY_RESPONSE_SIZE: usize = 100 * 1024 * 1024; // 与监控日志上限一致
+const MAX_BODY_DIFF_ENTRIES: usize = 500;
+
+/// 每次请求必然变化的字段，对比响应体时忽略
+const VOLATILE_KEYS: &[&str] = &[
+    "id",
+    "created",
+    "created_at",
+    "responseId",
+    "system_fingerprint",
+    "thinking_signature",
+    "signature",
+    "thoughtSignature",
+];
+
+tokio::task_local! {
+    /// 当前重放请求固定使用的账号 (账号 ID 或邮箱)
+    static PINNED_ACCOUNT: String;
+}
+
+/// 获取当前任务固定的账号 (仅在重放请求中存在)
+pub fn pinned_account() -> Option<String> {
+    PINNED_ACCOUNT.try_with(|account| account.clone()).ok()
+}
+
+struct ReplayTarget {
+    router: Router,
+    is_running: Arc<RwLock<bool>>,
+}
+
+static REPLAY_TARGET: OnceLock<std::sync::RwLock<Option<ReplayTarget>>> = OnceLock::new();
+
+/// 注册重放使用的代理路由 (不含鉴权与 IP 过滤层)，服务器启动时调用
+pub fn set_replay_router(router: Router, is_running: Arc<RwLock<bool>>) {
+    let lock = REPLAY_TARGET.get_or_init(|| std::sync::RwLock::new(None));
+    if let Ok(mut target) = lock.write() {
+        *target = Some(ReplayTarget { router, is_running });
+    }
+}
+
+fn get_replay_target() -> Option<(Router, Arc<RwLock<bool>>)> {
+    REPLAY_TARGET
+        .get()?
+        .read()
+        .ok()?
+        .as_ref()
+        .map(|t| (t.router.clone(), t.is_running.clone()))
+}
+
+/// 重放请求的监控日志回填槽 (由监控中间件在记录日志时写入)
+#[derive(Clone, Default)]
+pub struct ReplayCapture(Arc<Mutex<Option<ProxyRequestLog>>>);
+
+impl ReplayCapture {
+    pub fn record(&self, log: &ProxyRequestLog) {
+        if let Ok(mut slot) = self.0.lock() {
+            *slot = Some(log.clone());
+        }
+    }
+
+    fn take(&self) -> Option<ProxyRequestLog> {
+        self.0.lock().ok().and_then(|mut slot| slot.take())
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReplayOptions {
+    /// 固定使用的账号 (账号 ID 或邮箱)，为空时按当前调度策略选择
+    #[serde(default, alias = "accountId", alias = "account_id")]
+    pub account: Option<String>,
+}
+
+/// 单侧 (原始 / 重放) 响应摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaySide {
+    pub log_id: Option<String>,
+    pub status: u16,
+    pub duration: u64,
+    pub model: Option<String>,
+    pub mapped_model: Option<String>,
+    pub account_email: Option<String>,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cache_hit: bool,
+    /// 响应体 (可解析为 JSON 时为对象，否则为原始文本)
+    pub body: Option<Value>,
+}
+
+impl From<&ProxyRequestLog> for ReplaySide {
+    fn from(log: &ProxyRequestLog) -> Self {
+        Self {
+            log_id: Some(log.id.clone()),
+            status: log.status,
+            duration: log.duration,
+            model: log.model.clone(),
+            mapped_model: log.mapped_model.clone(),
+            account_email: log.account_email.clone(),
+            input_tokens: log.input_tokens,
+            output_tokens: log.output_tokens,
+            cache_hit: log.cache_hit,
+            body: parse_body(log.response_body.as_deref()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayResult {
+    pub method: String,
+    pub url: String,
+    pub pinned_account: Option<String>,
+    pub original: ReplaySide,
+    pub replay: ReplaySide,
+    /// 概要字段差异 [{field, before, after}]
+    pub changes: Vec<Value>,
+    /// 响应体差异 [{path, before, after}]
+    pub body_diff: Vec<Value>,
+}
+
+fn parse_body(body: Option<&str>) -> Option<Value> {
+    body.map(|s| serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.to_string())))
+}
+
+/// 对比两侧的概要字段
+pub fn summary_changes(original: &ReplaySide, replay: &ReplaySide) -> Vec<Value> {
+    let fields = [
+        ("status", json!(original.status), json!(replay.status)),
+        (
+            "mapped_model",
+            json!(original.mapped_model),
+            json!(replay.mapped_model),
+        ),
+        (
+            "account_email",
+            json!(original.account_email),
+            json!(replay.account_email),
+        ),
+        (
+            "input_tokens",
+            json!(original.input_tokens),
+            json!(replay.input_tokens),
+        ),
+        (
+            "output_tokens",
+            json!(original.output_tokens),
+            json!(replay.output_tokens),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| json!({ "field": field, "before": before, "after": after }))
+        .collect()
+}
+
+/// 计算响应体的结构化差异 (对象按键、数组按下标)，忽略每次必然变化的字段
+pub fn body_diff(path: &str, before: &Value, after: &Value, out: &mut Vec<Value>) {
+    if out.len() >= MAX_BODY_DIFF_ENTRIES {
+        return;
+    }
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                if VOLATILE_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                body_diff(
+                    &child,
+                    a.get(key).unwrap_or(&Value::Null),
+                    b.get(key).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                body_diff(
+                    &format!("{}[{}]", path, i),
+                    a.get(i).unwrap_or(&Value::Null),
+                    b.get(i).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        _ if before != after => {
+            out.push(json!({ "path": path, "before": before, "after": after }));
+        }
+        _ => {}
+    }
+}
+
+/// 按当前配置重放一条监控日志中的请求
+pub async fn replay_log(log_id: &str, options: ReplayOptions) -> Result<ReplayResult, String> {
+    let (router, is_running) =
+        get_replay_target().ok_or_else(|| "Proxy server is not started".to_string())?;
+    if !*is_running.read().await {
+        return Err("Proxy service is currently disabled".to_string());
+    }
+
+    let id = log_id.to_string();
+    let original =
+        tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log_detail(&id))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Log {} not found: {}", log_id, e))?;
+
+    if !original.url.starts_with("/v1") {
+        return Err(format!("Request {} cannot be replayed", original.url));
+    }
+    let method = Method::from_bytes(original.method.as_bytes()).map_err(|e| e.to_string())?;
+    let body = match original.request_body.as_deref() {
+        Some("[Binary Request Data]") => {
+            return Err("Binary request bodies (e.g. audio uploads) cannot be replayed".to_string())
+        }
+        Some(body) => Body::from(body.to_string()),
+        None if method == Method::POST => {
+            return Err("The original request body was not recorded".to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let capture = ReplayCapture::default();
+    let mut request = Request::builder()
+        .method(method)
+        .uri(&original.url)
+        .header(header::CONTENT_TYPE, "application/json")
+        // 重放需要真实请求上游，跳过响应缓存
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(body)
+        .map_err(|e| e.to_string())?;
+    request.extensions_mut().insert(capture.clone());
+
+    let account = options.account.filter(|a| !a.trim().is_empty());
+    tracing::info!(
+        "[Replay] Replaying {} {} (log {}, account: {})",
+        original.method,
+        original.url,
+        log_id,
+        account.as_deref().unwrap_or("auto")
+    );
+
+    let started = std::time::Instant::now();
+    let response = match account.clone() {
+        Some(account) => PINNED_ACCOUNT.scope(account, router.oneshot(request)).await,
+        None => router.oneshot(request).await,
+    }
+    .unwrap_or_else(|e| match e {});
+
+    // 读完响应体后监控中间件已完成日志记录 (流式响应在后台任务结束时才关闭)
+    let (parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_REPLAY_RESPONSE_SIZE)
+        .await
+        .map_err(|e| format!("Failed to read replay response: {}", e))?;
+
+    let replay = match capture.take() {
+        Some(log) => ReplaySide::from(&log),
+        None => {
+            let header_str = |name: &str| {
+                parts
+                    .headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string())
+            };
+            ReplaySide {
+                log_id: None,
+                status: parts.status.as_u16(),
+                duration: started.elapsed().as_millis() as u64,
+                model: original.model.clone(),
+                mapped_model: header_str("X-Mapped-Model"),
+                account_email: header_str("X-Account-Email"),
+                input_tokens: None,
+                output_tokens: None,
+                cache_hit: false,
+                body: parse_body(Some(&String::from_utf8_lossy(&bytes))),
+            }
+        }
+    };
+    let original_side = ReplaySide::from(&original);
+
+    let changes = summary_changes(&original_side, &replay);
+    let mut diff = Vec::new();
+    body_diff(
+        "",
+        original_side.body.as_ref().unwrap_or(&Value::Null),
+        replay.body.as_ref().unwrap_or(&Value::Null),
+        &mut diff,
+    );
+
+    Ok(ReplayResult {
+        method: original.method,
+        url: original.url,
+        pinned_account: account,
+        original: original_side,
+        replay,
+        changes,
+        body_diff: diff,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_diff() {
+        let side = |status: u16, mapped: &str, output: u32, body: Value| ReplaySide {
+            log_id: None,
+            status,
+            duration: 0,
+            model: Some("gpt-4o".to_string()),
+            mapped_model: Some(mapped.to_string()),
+            account_email: Some("a@example.com".to_string()),
+            input_tokens: Some(10),
+            output_tokens: Some(output),
+            cache_hit: false,
+            body: Some(body),
+        };
+        let before = side(
+            200,
+            "gemini-2.5-flash",
+            5,
+            json!({ "id": "chatcmpl-1", "choices": [{ "message": { "content": "hi" } }] }),
+        );
+        let after = side(
+            200,
+            "gemini-3-flash",
+            7,
+            json!({ "id": "chatcmpl-2", "choices": [{ "message": { "content": "hello" } }] }),
+        );
+
+        assert_eq!(
+            summary_changes(&before, &after),
+            vec![
+                json!({ "field": "mapped_model", "before": "gemini-2.5-flash", "after": "gemini-3-flash" }),
+                json!({ "field": "output_tokens", "before": 5, "after": 7 }),
+            ]
+        );
+
+        let mut diff = Vec::new();
+        body_diff(
+            "",
+            before.body.as_ref().unwrap(),
+            after.body.as_ref().unwrap(),
+            &mut diff,
+        );
+        assert_eq!(
+            diff,
+            vec![json!({ "path": "choices[0].message.content", "before": "hi", "after": "hello" })]
+        );
+
+        assert_eq!(
+            parse_body(Some("[Binary Response Data]")),
+            Some(Value::String("[Binary Response Data]".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pinned_account_scope() {
+        assert_eq!(pinned_account(), None);
+        let inner = PINNED_ACCOUNT
+            .scope("acc1".to_string(), async { pinned_account() })
+            .await;
+        assert_eq!(inner.as_deref(), Some("acc1"));
+    }
+}
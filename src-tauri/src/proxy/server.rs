@@ -2,7 +2,10 @@ use crate::models::AppConfig;
 use crate::modules::{account, config, logger, migration, proxy_db, security_db, token_stats};
 use crate::proxy::TokenManager;
 use axum::{
-    extract::{DefaultBodyLimit, Path, Query, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Path, Query, State,
+    },
     http::{HeaderMap, StatusCode},
     response::{Html, IntoResponse, Json, Response},
     routing::{any, delete, get, post},
@@ -15,6 +18,8 @@ use std::sync::Arc;
 use std::sync::OnceLock;
 use tokio::sync::oneshot;
 use tokio::sync::RwLock;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::StreamExt;
 use tracing::{debug, error};
 
 // [FIX] 全局待重新加载账号队列
@@ -211,6 +216,7 @@ fn to_account_response(
 #[derive(Clone)]
 pub struct AxumServer {
     shutdown_tx: Arc<tokio::sync::Mutex<Option<oneshot::Sender<()>>>>,
+    background_cancel: tokio_util::sync::CancellationToken, // [NEW] 后台任务 (批处理执行器 / Webhook 分发器) 停止信号
     custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
     proxy_state: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
     upstream: Arc<crate::proxy::upstream::client::UpstreamClient>,
@@ -232,6 +238,8 @@ impl AxumServer {
             let mut m = self.custom_mapping.write().await;
             *m = config.custom_mapping.clone();
         }
+        // [NEW] 模型降级链随映射一起热更新
+        crate::proxy::update_model_fallbacks(config.model_fallbacks.clone());
         tracing::debug!("模型映射 (Custom) 已全量热更新");
     }
 
@@ -259,6 +267,12 @@ impl AxumServer {
         let mut zai = self.zai_state.write().await;
         *zai = config.zai.clone();
         tracing::info!("z.ai 配置已热更新");
+        // [NEW] 通用上游提供商与 z.ai 一同热更新
+        crate::proxy::update_upstream_providers(config.providers.clone());
+    }
+
+    pub fn update_batch(&self, config: &crate::proxy::config::ProxyConfig) {
+        crate::proxy::update_batch_config(config.batch.clone());
     }
 
     pub async fn update_experimental(&self, config: &crate::proxy::config::ProxyConfig) {
@@ -304,7 +318,11 @@ impl AxumServer {
         integration: crate::modules::integration::SystemManager,
         cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
         proxy_pool_config: crate::proxy::config::ProxyPoolConfig, // [NEW]
+        tls_config: crate::proxy::config::TlsConfig,              // [NEW] TLS / HTTP/2
+        unix_socket_config: crate::proxy::config::UnixSocketConfig, // [NEW] Unix 域套接字
     ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
+        // [NEW] 启动时生成内部端点 (/internal/*) 的进程级密钥
+        let _ = crate::proxy::middleware::auth::internal_secret();
         let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
         let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
         let proxy_pool_state = Arc::new(tokio::sync::RwLock::new(proxy_pool_config));
@@ -362,12 +380,13 @@ impl AxumServer {
         // 构建路由 - 使用新架构的 handlers！
         use crate::proxy::handlers;
         use crate::proxy::middleware::{
-            admin_auth_middleware, auth_middleware, cors_layer, ip_filter_middleware,
-            monitor_middleware, service_status_middleware,
+            admin_auth_middleware, admin_ip_filter_middleware, auth_middleware, cors_layer,
+            ip_filter_middleware, monitor_middleware, response_cache::response_cache_middleware,
+            service_status_middleware,
         };
 
         // 1. 构建主 AI 代理路由 (遵循 auth_mode 配置)
-        let proxy_routes = Router::new()
+        let proxy_handlers = Router::new()
             .route("/health", get(health_check_handler))
             .route("/healthz", get(health_check_handler))
             // OpenAI Protocol
@@ -380,7 +399,43 @@ impl AxumServer {
                 "/v1/completions",
                 post(handlers::openai::handle_completions),
             )
-            .route("/v1/responses", post(handlers::openai::handle_completions)) // 兼容 Codex CLI
+            .route(
+                "/v1/responses",
+                post(handlers::responses::handle_create_response),
+            ) // Responses API (Codex CLI / Agents SDK)
+            .route(
+                "/v1/responses/:id",
+                get(handlers::responses::handle_get_response)
+                    .delete(handlers::responses::handle_delete_response),
+            )
+            // OpenAI Files & Batch API
+            .route(
+                "/v1/files",
+                post(handlers::batches::handle_upload_file)
+                    .get(handlers::batches::handle_list_files),
+            )
+            .route(
+                "/v1/files/:file_id",
+                get(handlers::batches::handle_get_file)
+                    .delete(handlers::batches::handle_delete_file),
+            )
+            .route(
+                "/v1/files/:file_id/content",
+                get(handlers::batches::handle_get_file_content),
+            )
+            .route(
+                "/v1/batches",
+                post(handlers::batches::handle_create_batch)
+                    .get(handlers::batches::handle_list_batches),
+            )
+            .route(
+                "/v1/batches/:batch_id",
+                get(handlers::batches::handle_get_batch),
+            )
+            .route(
+                "/v1/batches/:batch_id/cancel",
+                post(handlers::batches::handle_cancel_batch),
+            )
             .route(
                 "/v1/images/generations",
                 post(handlers::openai::handle_images_generations),
@@ -389,6 +444,10 @@ impl AxumServer {
                 "/v1/images/edits",
                 post(handlers::openai::handle_images_edits),
             ) // 图像编辑 API
+            .route(
+                "/v1/embeddings",
+                post(handlers::embeddings::handle_embeddings),
+            ) // 向量化 API
             .route(
                 "/v1/audio/transcriptions",
                 post(handlers::audio::handle_audio_transcription),
@@ -399,6 +458,24 @@ impl AxumServer {
                 "/v1/messages/count_tokens",
                 post(handlers::claude::handle_count_tokens),
             )
+            .route(
+                "/v1/messages/batches",
+                post(handlers::message_batches::handle_create_message_batch)
+                    .get(handlers::message_batches::handle_list_message_batches),
+            )
+            .route(
+                "/v1/messages/batches/:batch_id",
+                get(handlers::message_batches::handle_get_message_batch)
+                    .delete(handlers::message_batches::handle_delete_message_batch),
+            )
+            .route(
+                "/v1/messages/batches/:batch_id/cancel",
+                post(handlers::message_batches::handle_cancel_message_batch),
+            )
+            .route(
+                "/v1/messages/batches/:batch_id/results",
+                get(handlers::message_batches::handle_message_batch_results),
+            )
             .route(
                 "/v1/models/claude",
                 get(handlers::claude::handle_list_models),
@@ -428,9 +505,28 @@ impl AxumServer {
                 "/v1/models/detect",
                 post(handlers::common::handle_detect_model),
             )
-            .route("/internal/warmup", post(handlers::warmup::handle_warmup)) // 内部预热端点
+            .route("/internal/warmup", post(handlers::warmup::handle_warmup)) // 内部预热端点 (需回环地址 + 进程密钥)
             .route("/v1/api/event_logging/batch", post(silent_ok_handler))
             .route("/v1/api/event_logging", post(silent_ok_handler))
+            // [NEW] 响应缓存 (位于监控层之内，命中时监控日志标记 cache_hit)
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                response_cache_middleware,
+            ));
+
+        // [NEW] 请求重放路由: 仅保留监控层 (管理接口已完成鉴权)，以便记录并对比新响应
+        crate::proxy::replay::set_replay_router(
+            proxy_handlers
+                .clone()
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    monitor_middleware,
+                ))
+                .with_state(state.clone()),
+            state.is_running.clone(),
+        );
+
+        let proxy_routes = proxy_handlers
             // 应用 AI 服务特定的层
             // 注意：Axum layer 执行顺序是从下往上（洋葱模型）
             // 请求: ip_filter -> auth -> monitor -> handler
@@ -542,6 +638,14 @@ impl AxumServer {
                 "/proxy/session-bindings/clear",
                 post(admin_clear_proxy_session_bindings),
             )
+            .route(
+                "/proxy/sessions",
+                get(admin_list_proxy_sessions).delete(admin_purge_proxy_sessions),
+            )
+            .route(
+                "/proxy/sessions/:sessionId",
+                get(admin_get_proxy_session).delete(admin_purge_proxy_session),
+            )
             .route("/proxy/rate-limits", delete(admin_clear_all_rate_limits))
             .route(
                 "/proxy/rate-limits/:accountId",
@@ -573,10 +677,13 @@ impl AxumServer {
             .route("/proxy/cloudflared/stop", post(admin_cloudflared_stop))
             .route("/system/open-folder", post(admin_open_folder))
             .route("/proxy/stats", get(admin_get_proxy_stats))
+            .route("/events", get(admin_event_stream))
+            .route("/webhooks/test", post(admin_test_webhook))
             .route("/logs", get(admin_get_proxy_logs_filtered))
             .route("/logs/count", get(admin_get_proxy_logs_count_filtered))
             .route("/logs/clear", post(admin_clear_proxy_logs))
             .route("/logs/:logId", get(admin_get_proxy_log_detail))
+            .route("/logs/:logId/replay", post(admin_replay_proxy_log))
             // Debug Console (Log Bridge)
             .route("/debug/enable", post(admin_enable_debug_console))
             .route("/debug/disable", post(admin_disable_debug_console))
@@ -611,6 +718,7 @@ impl AxumServer {
             )
             .route("/accounts/bulk-delete", post(admin_delete_accounts))
             .route("/accounts/export", post(admin_export_accounts))
+            .route("/accounts/import-bundle", post(admin_open_account_bundle))
             .route("/accounts/reorder", post(admin_reorder_accounts))
             .route("/accounts/:accountId/quota", get(admin_fetch_account_quota))
             .route(
@@ -676,6 +784,7 @@ impl AxumServer {
                 "/security/config",
                 get(admin_get_security_config).post(admin_update_security_config),
             )
+            .route("/security/auto-bans", get(admin_get_auto_bans))
             // User Tokens
             .route(
                 "/user-tokens",
@@ -689,10 +798,25 @@ impl AxumServer {
             )
             // OAuth (Web) - Admin 接口
             .route("/auth/url", get(admin_prepare_oauth_url_web))
+            // [NEW] 审计日志查询
+            .route("/audit/logs", get(admin_get_audit_logs))
+            // [NEW] 响应缓存
+            .route("/response-cache/stats", get(admin_get_response_cache_stats))
+            .route("/response-cache/clear", post(admin_clear_response_cache))
             // 应用管理特定鉴权层 (强制校验)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 admin_auth_middleware,
+            ))
+            // [NEW] 审计层位于鉴权之外，未通过鉴权的变更尝试同样会被记录
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::proxy::middleware::audit::admin_audit_middleware,
+            ))
+            // [NEW] 黑名单 / 自动封禁位于最外层，被封禁的 IP 不会进入审计与鉴权
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                admin_ip_filter_middleware,
             ));
 
         // 3. 整合并应用全局层
@@ -703,9 +827,22 @@ impl AxumServer {
             .unwrap_or(100 * 1024 * 1024); // 默认 100MB
         tracing::info!("请求体大小限制: {} MB", max_body_size / 1024 / 1024);
 
+        // Prometheus 指标 (遵循管理接口鉴权)
+        let metrics_routes = Router::new()
+            .route("/metrics", get(metrics_handler))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                admin_auth_middleware,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                admin_ip_filter_middleware,
+            ));
+
         let app = Router::new()
             .nest("/api", admin_routes)
             .merge(proxy_routes)
+            .merge(metrics_routes)
             // 公开路由 (无需鉴权)
             .route("/auth/callback", get(handle_oauth_callback))
             // 应用全局监控与状态层 (外层)
@@ -728,19 +865,60 @@ impl AxumServer {
             app
         };
 
-        // 绑定地址
-        let addr = format!("{}:{}", host, port);
-        let listener = tokio::net::TcpListener::bind(&addr)
-            .await
-            .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
+        // [NEW] TLS 终止 (证书加载失败时拒绝启动，避免意外以明文暴露)
+        let tls = crate::proxy::tls::TlsTerminator::from_config(&tls_config, &host)?;
+        let http_protocol = if tls_config.http2 {
+            crate::proxy::tls::HttpProtocol::Auto
+        } else {
+            crate::proxy::tls::HttpProtocol::Http1
+        };
+
+        // [NEW] Unix 域套接字 (exclusive 模式下不绑定 TCP 端口)
+        let unix_listener = if unix_socket_config.enabled {
+            match crate::proxy::unix_socket::LocalListener::bind(&unix_socket_config) {
+                Ok(listener) => {
+                    tracing::info!("反代服务器监听 Unix 套接字 {}", listener.path().display());
+                    Some(listener)
+                }
+                Err(e) if !unix_socket_config.exclusive => {
+                    tracing::warn!("Unix 套接字监听失败，仅使用 TCP: {}", e);
+                    None
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            None
+        };
 
-        tracing::info!("反代服务器启动在 http://{}", addr);
+        // 绑定地址
+        let tcp_listener = if unix_listener.is_some() && unix_socket_config.exclusive {
+            None
+        } else {
+            let addr = format!("{}:{}", host, port);
+            let listener = tokio::net::TcpListener::bind(&addr)
+                .await
+                .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
+            let scheme = if tls.is_some() { "https" } else { "http" };
+            tracing::info!("反代服务器启动在 {}://{}", scheme, addr);
+            Some(listener)
+        };
 
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
 
+        // [NEW] 启动批处理后台执行器 (/v1/batches, /v1/messages/batches)，随服务器停止
+        let background_cancel = tokio_util::sync::CancellationToken::new();
+        crate::proxy::batch_worker::spawn(state.clone(), background_cancel.clone());
+        // [NEW] 启动 Webhook 分发器 (账号 / 配额生命周期事件)
+        crate::modules::webhook::start_dispatcher(background_cancel.clone());
+        // [NEW] 证书文件变更自动重新加载
+        if let Some(tls) = &tls {
+            tls.start_watcher(background_cancel.clone());
+        }
+
         let server_instance = Self {
             shutdown_tx: Arc::new(tokio::sync::Mutex::new(Some(shutdown_tx))),
+            background_cancel,
             custom_mapping: custom_mapping_state.clone(),
             proxy_state,
             upstream: state.upstream.clone(),
@@ -757,33 +935,46 @@ impl AxumServer {
 
         // 在新任务中启动服务器
         let handle = tokio::spawn(async move {
-            use hyper::server::conn::http1;
+            use crate::proxy::tls::AcceptedStream;
             use hyper_util::rt::TokioIo;
             use hyper_util::service::TowerToHyperService;
 
+            // 注入 ConnectInfo (用于获取真实 IP)
+            let make_service = |remote_addr: std::net::SocketAddr| {
+                use hyper::body::Incoming;
+                use tower::ServiceExt;
+                TowerToHyperService::new(app.clone().map_request(
+                    move |mut req: axum::http::Request<Incoming>| {
+                        req.extensions_mut()
+                            .insert(axum::extract::ConnectInfo(remote_addr));
+                        req
+                    },
+                ))
+            };
+
             loop {
                 tokio::select! {
-                    res = listener.accept() => {
+                    res = accept_tcp(tcp_listener.as_ref()) => {
                         match res {
                             Ok((stream, remote_addr)) => {
-                                let io = TokioIo::new(stream);
-
-                                // 注入 ConnectInfo (用于获取真实 IP)
-                                use tower::ServiceExt;
-                                use hyper::body::Incoming;
-                                let app_with_info = app.clone().map_request(move |mut req: axum::http::Request<Incoming>| {
-                                    req.extensions_mut().insert(axum::extract::ConnectInfo(remote_addr));
-                                    req
-                                });
-
-                                let service = TowerToHyperService::new(app_with_info);
+                                let service = make_service(remote_addr);
+                                let tls = tls.clone();
 
                                 tokio::task::spawn(async move {
-                                    if let Err(err) = http1::Builder::new()
-                                        .serve_connection(io, service)
-                                        .with_upgrades() // 支持 WebSocket (如果以后需要)
-                                        .await
-                                    {
+                                    let result = match tls {
+                                        None => serve_connection(TokioIo::new(stream), service, http_protocol).await,
+                                        // TLS 握手在连接任务中进行，避免阻塞 accept 循环
+                                        Some(tls) => match tls.accept(stream, remote_addr).await {
+                                            Some(AcceptedStream::Tls(stream, protocol)) => {
+                                                serve_connection(TokioIo::new(stream), service, protocol).await
+                                            }
+                                            Some(AcceptedStream::Plain(stream)) => {
+                                                serve_connection(TokioIo::new(stream), service, http_protocol).await
+                                            }
+                                            None => return,
+                                        },
+                                    };
+                                    if let Err(err) = result {
                                         debug!("连接处理结束或出错: {:?}", err);
                                     }
                                 });
@@ -793,6 +984,22 @@ impl AxumServer {
                             }
                         }
                     }
+                    // [NEW] Unix 套接字连接 (本机访问，不经过 TLS)
+                    res = accept_local(unix_listener.as_ref()) => {
+                        match res {
+                            Ok(stream) => {
+                                let service = make_service(crate::proxy::unix_socket::peer_addr());
+                                tokio::task::spawn(async move {
+                                    if let Err(err) = serve_connection(TokioIo::new(stream), service, http_protocol).await {
+                                        debug!("连接处理结束或出错: {:?}", err);
+                                    }
+                                });
+                            }
+                            Err(e) => {
+                                error!("接收 Unix 套接字连接失败: {:?}", e);
+                            }
+                        }
+                    }
                     _ = &mut shutdown_rx => {
                         tracing::info!("反代服务器停止监听");
                         break;
@@ -806,6 +1013,7 @@ impl AxumServer {
 
     /// 停止服务器
     pub fn stop(&self) {
+        self.background_cancel.cancel();
         let tx_mutex = self.shutdown_tx.clone();
         tokio::spawn(async move {
             let mut lock = tx_mutex.lock().await;
@@ -817,6 +1025,59 @@ impl AxumServer {
     }
 }
 
+/// 接受 TCP 连接 (未绑定 TCP 端口时永远挂起)
+async fn accept_tcp(
+    listener: Option<&tokio::net::TcpListener>,
+) -> std::io::Result<(tokio::net::TcpStream, std::net::SocketAddr)> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 接受 Unix 套接字连接 (未启用时永远挂起)
+async fn accept_local(
+    listener: Option<&crate::proxy::unix_socket::LocalListener>,
+) -> std::io::Result<crate::proxy::unix_socket::LocalStream> {
+    match listener {
+        Some(listener) => listener.accept().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 按协议处理单个连接 (HTTP/1.1 支持升级，HTTP/2 由 ALPN 或 h2c 前导确定)
+async fn serve_connection<I, S, B>(
+    io: hyper_util::rt::TokioIo<I>,
+    service: S,
+    protocol: crate::proxy::tls::HttpProtocol,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    I: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+    S: hyper::service::Service<
+            axum::http::Request<hyper::body::Incoming>,
+            Response = axum::http::Response<B>,
+        > + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    B: axum::body::HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    use crate::proxy::tls::HttpProtocol;
+    use hyper_util::rt::TokioExecutor;
+    use hyper_util::server::conn::auto;
+
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let builder = match protocol {
+        HttpProtocol::Http1 => builder.http1_only(),
+        HttpProtocol::Http2 => builder.http2_only(),
+        HttpProtocol::Auto => builder,
+    };
+    builder.serve_connection_with_upgrades(io, service).await
+}
+
 // ===== API 处理器 (旧代码已移除，由 src/proxy/handlers/* 接管) =====
 
 /// 健康检查处理器
@@ -828,6 +1089,18 @@ async fn health_check_handler() -> Response {
     .into_response()
 }
 
+/// Prometheus 指标处理器
+async fn metrics_handler(State(state): State<AppState>) -> Response {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        crate::proxy::metrics::render(&state).await,
+    )
+        .into_response()
+}
+
 /// 静默成功处理器 (用于拦截遥测日志等)
 async fn silent_ok_handler() -> Response {
     StatusCode::OK.into_response()
@@ -903,13 +1176,32 @@ async fn admin_list_accounts(
 #[serde(rename_all = "camelCase")]
 struct ExportAccountsRequest {
     account_ids: Vec<String>,
+    #[serde(default)]
+    passphrase: Option<String>,
 }
 
 async fn admin_export_accounts(
     State(_state): State<AppState>,
     Json(payload): Json<ExportAccountsRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let response = account::export_accounts_by_ids(&payload.account_ids).map_err(|e| {
+    let passphrase = payload.passphrase.unwrap_or_default();
+    if passphrase.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "export_passphrase_required".to_string(),
+            }),
+        ));
+    }
+
+    // 口令密钥派生为 CPU 密集操作，避免阻塞异步工作线程
+    let response = tokio::task::spawn_blocking(move || {
+        account::export_accounts_by_ids(&payload.account_ids, &passphrase)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|result| result)
+    .map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: e }),
@@ -919,6 +1211,33 @@ async fn admin_export_accounts(
     Ok(Json(response))
 }
 
+/// Decrypt an encrypted account export bundle
+#[derive(Deserialize)]
+struct OpenAccountBundleRequest {
+    bundle: crate::models::EncryptedAccountBundle,
+    passphrase: String,
+}
+
+async fn admin_open_account_bundle(
+    Json(payload): Json<OpenAccountBundleRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // 迭代次数已在解密前限制范围，KDF 仍放入阻塞线程池执行
+    let items = tokio::task::spawn_blocking(move || {
+        account::open_account_bundle(&payload.bundle, &payload.passphrase)
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(items))
+}
+
 async fn admin_get_current_account(
     State(state): State<AppState>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -1099,6 +1418,117 @@ async fn admin_switch_account(
     }
 }
 
+// [NEW] 实时事件流: 携带 Upgrade 头时走 WebSocket，否则返回 SSE
+// 查询参数: types (逗号分隔，支持 "proxy://*")、account、user_token
+async fn admin_event_stream(
+    ws: Option<WebSocketUpgrade>,
+    Query(filter): Query<crate::modules::event_bus::EventFilter>,
+) -> Response {
+    match ws {
+        Some(ws) => ws
+            .on_upgrade(move |socket| forward_events_to_websocket(socket, filter))
+            .into_response(),
+        None => {
+            let stream = BroadcastStream::new(crate::modules::event_bus::subscribe())
+                .filter_map(move |item| match item {
+                    Ok(event) if filter.matches(&event) => Some(
+                        axum::response::sse::Event::default()
+                            .event(event.event_type.clone())
+                            .data(serde_json::to_string(&event).unwrap_or_default()),
+                    ),
+                    Ok(_) => None,
+                    Err(BroadcastStreamRecvError::Lagged(skipped)) => Some(
+                        axum::response::sse::Event::default()
+                            .event("lagged")
+                            .data(skipped.to_string()),
+                    ),
+                })
+                .map(Ok::<_, std::convert::Infallible>);
+            axum::response::sse::Sse::new(stream)
+                .keep_alive(axum::response::sse::KeepAlive::default())
+                .into_response()
+        }
+    }
+}
+
+async fn forward_events_to_websocket(
+    mut socket: WebSocket,
+    filter: crate::modules::event_bus::EventFilter,
+) {
+    let mut rx = crate::modules::event_bus::subscribe();
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Ok(event) => {
+                    if !filter.matches(&event) {
+                        continue;
+                    }
+                    let text = serde_json::to_string(&event).unwrap_or_default();
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!("[EventStream] WebSocket subscriber lagged, skipped {} events", skipped);
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => break,
+                _ => {}
+            },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TestWebhookRequest {
+    /// 已保存的 Webhook ID
+    #[serde(default)]
+    id: Option<String>,
+    /// 或直接提供待测试的 (未保存) Webhook 配置
+    #[serde(default)]
+    webhook: Option<crate::proxy::config::WebhookConfig>,
+}
+
+// [NEW] 向 Webhook 发送一条测试事件，返回投递结果
+async fn admin_test_webhook(
+    Json(payload): Json<TestWebhookRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let webhook = match (payload.webhook, payload.id) {
+        (Some(webhook), _) => webhook,
+        (None, Some(id)) => crate::proxy::config::get_webhooks()
+            .into_iter()
+            .find(|w| w.id == id)
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: format!("Webhook not found: {}", id),
+                    }),
+                )
+            })?,
+        (None, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Either id or webhook is required".to_string(),
+                }),
+            ))
+        }
+    };
+    if webhook.url.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Webhook url is empty".to_string(),
+            }),
+        ));
+    }
+
+    Ok(Json(crate::modules::webhook::fire_test(&webhook).await))
+}
+
 async fn admin_refresh_all_quotas() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)>
 {
     logger::log_info("[API] Starting refresh of all account quotas");
@@ -1109,6 +1539,11 @@ async fn admin_refresh_all_quotas() -> Result<impl IntoResponse, (StatusCode, Js
         )
     })?;
 
+    crate::modules::event_bus::publish(crate::modules::event_bus::AppEvent::new(
+        crate::modules::event_bus::EVENT_ACCOUNTS_REFRESHED,
+        (),
+    ));
+
     Ok(Json(stats))
 }
 
@@ -1315,6 +1750,7 @@ async fn admin_save_config(
         let mut mapping = state.custom_mapping.write().await;
         *mapping = new_config.clone().proxy.custom_mapping;
     }
+    crate::proxy::update_model_fallbacks(new_config.proxy.model_fallbacks.clone());
 
     // 更新上游代理
     {
@@ -1333,6 +1769,11 @@ async fn admin_save_config(
         let mut zai = state.zai.write().await;
         *zai = new_config.clone().proxy.zai;
     }
+    crate::proxy::update_upstream_providers(new_config.proxy.providers.clone());
+    crate::proxy::update_batch_config(new_config.proxy.batch.clone());
+    crate::proxy::update_webhooks(new_config.proxy.webhooks.clone());
+    crate::proxy::update_audit_log_config(new_config.proxy.audit_log.clone());
+    crate::proxy::update_response_cache_config(new_config.proxy.response_cache.clone());
 
     // 更新实验性配置
     {
@@ -1346,6 +1787,17 @@ async fn admin_save_config(
         *pool = new_config.clone().proxy.proxy_pool;
     }
 
+    // [NEW] 更新调度配置 (含会话持久化开关)
+    state
+        .token_manager
+        .update_sticky_config(new_config.proxy.scheduling.clone())
+        .await;
+
+    crate::modules::event_bus::publish(crate::modules::event_bus::AppEvent::new(
+        crate::modules::event_bus::EVENT_CONFIG_UPDATED,
+        (),
+    ));
+
     Ok(StatusCode::OK)
 }
 
@@ -1500,6 +1952,7 @@ async fn admin_update_model_mapping(
         let mut mapping = state.custom_mapping.write().await;
         *mapping = config.custom_mapping.clone();
     }
+    crate::proxy::update_model_fallbacks(config.model_fallbacks.clone());
 
     // 2. 持久化到硬盘 (修复 #1149)
     // 加载当前配置，更新 mapping，然后保存
@@ -1511,6 +1964,7 @@ async fn admin_update_model_mapping(
     })?;
 
     app_config.proxy.custom_mapping = config.custom_mapping;
+    app_config.proxy.model_fallbacks = config.model_fallbacks;
 
     crate::modules::config::save_app_config(&app_config).map_err(|e| {
         (
@@ -1534,6 +1988,96 @@ async fn admin_clear_proxy_session_bindings(State(state): State<AppState>) -> im
     StatusCode::OK
 }
 
+/// 会话缓存条目 (粘性绑定 + 思维签名)
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProxySessionEntry {
+    session_id: String,
+    account_id: Option<String>,
+    signature: Option<crate::proxy::signature_cache::SessionSignatureInfo>,
+}
+
+fn collect_proxy_sessions(state: &AppState) -> Vec<ProxySessionEntry> {
+    let mut bindings: std::collections::HashMap<String, String> = state
+        .token_manager
+        .list_session_bindings()
+        .into_iter()
+        .collect();
+    let mut entries: Vec<ProxySessionEntry> = crate::proxy::SignatureCache::global()
+        .list_session_signatures()
+        .into_iter()
+        .map(|sig| ProxySessionEntry {
+            session_id: sig.session_id.clone(),
+            account_id: bindings.remove(&sig.session_id),
+            signature: Some(sig),
+        })
+        .collect();
+    entries.extend(
+        bindings
+            .into_iter()
+            .map(|(session_id, account_id)| ProxySessionEntry {
+                session_id,
+                account_id: Some(account_id),
+                signature: None,
+            }),
+    );
+    entries
+}
+
+async fn admin_list_proxy_sessions(State(state): State<AppState>) -> impl IntoResponse {
+    let persistent = state
+        .token_manager
+        .get_sticky_config()
+        .await
+        .persist_sessions;
+    Json(serde_json::json!({
+        "persistent": persistent,
+        "sessions": collect_proxy_sessions(&state),
+    }))
+}
+
+async fn admin_get_proxy_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let account_id = state.token_manager.get_session_binding(&session_id);
+    let signature = crate::proxy::SignatureCache::global().get_session_signature_info(&session_id);
+    if account_id.is_none() && signature.is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Session not found: {}", session_id),
+            }),
+        ));
+    }
+    Ok(Json(ProxySessionEntry {
+        session_id,
+        account_id,
+        signature,
+    }))
+}
+
+async fn admin_purge_proxy_session(
+    State(state): State<AppState>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let unbound = state.token_manager.clear_session_binding(&session_id);
+    let dropped = crate::proxy::SignatureCache::global().delete_session_signature(&session_id);
+    if unbound || dropped {
+        logger::log_info(&format!("[API] 已清除会话缓存: {}", session_id));
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn admin_purge_proxy_sessions(State(state): State<AppState>) -> impl IntoResponse {
+    state.token_manager.clear_all_sessions();
+    crate::proxy::SignatureCache::global().purge_all();
+    logger::log_info("[API] 已清除所有会话缓存 (绑定 + 签名)");
+    StatusCode::OK
+}
+
 async fn admin_clear_all_rate_limits(State(state): State<AppState>) -> impl IntoResponse {
     state.token_manager.clear_all_rate_limits();
     logger::log_info("[API] 已清除所有限流记录");
@@ -1716,6 +2260,18 @@ async fn admin_get_proxy_log_detail(
     }
 }
 
+// [NEW] 按当前映射与配置重放一条监控日志中的请求，返回新旧响应对比
+async fn admin_replay_proxy_log(
+    Path(log_id): Path<String>,
+    payload: Option<Json<crate::proxy::replay::ReplayOptions>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let options = payload.map(|Json(o)| o).unwrap_or_default();
+    crate::proxy::replay::replay_log(&log_id, options)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))
+}
+
 #[derive(Deserialize, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 struct LogsFilterQuery {
@@ -3147,6 +3703,58 @@ struct IpAccessLogResponse {
     total: usize,
 }
 
+// [NEW] 管理接口审计日志 (按时间倒序，支持 since / until / principal / client_ip / method / route / status 过滤)
+async fn admin_get_audit_logs(
+    Query(filter): Query<crate::modules::audit_db::AuditLogFilter>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let (total, items) =
+        tokio::task::spawn_blocking(move || crate::modules::audit_db::query_entries(&filter))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r)
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse { error: e }),
+                )
+            })?;
+
+    Ok(Json(serde_json::json!({ "total": total, "items": items })))
+}
+
+// [NEW] 响应缓存统计
+async fn admin_get_response_cache_stats(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let stats = tokio::task::spawn_blocking(crate::modules::response_cache_db::get_stats)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e }),
+            )
+        })?;
+    Ok(Json(stats))
+}
+
+// [NEW] 清空响应缓存
+async fn admin_clear_response_cache() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)>
+{
+    let removed = tokio::task::spawn_blocking(crate::modules::response_cache_db::clear_cache)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e }),
+            )
+        })?;
+    logger::log_info(&format!("[API] 已清空响应缓存 ({} 条)", removed));
+    Ok(Json(serde_json::json!({ "removed": removed })))
+}
+
 async fn admin_get_ip_access_logs(
     Query(q): Query<IpAccessLogQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -3226,6 +3834,29 @@ async fn admin_get_ip_token_stats(
     Ok(Json(stats))
 }
 
+#[derive(Deserialize)]
+struct AutoBanQuery {
+    #[serde(default = "default_active_only")]
+    active_only: bool,
+    limit: Option<usize>,
+}
+
+fn default_active_only() -> bool {
+    true
+}
+
+async fn admin_get_auto_bans(
+    Query(q): Query<AutoBanQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let bans = security_db::get_auto_bans(q.active_only, q.limit.unwrap_or(200)).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(bans))
+}
+
 async fn admin_get_ip_blacklist() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let list = security_db::get_blacklist().map_err(|e| {
         (
@@ -3247,6 +3878,9 @@ struct AddBlacklistRequest {
 async fn admin_add_ip_to_blacklist(
     Json(req): Json<AddBlacklistRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // [NEW] 校验 IP 模式 (IPv4 / IPv6 / CIDR)
+    security_db::normalize_ip_pattern(&req.ip_pattern)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
     security_db::add_to_blacklist(
         &req.ip_pattern,
         req.reason.as_deref(),
@@ -3355,6 +3989,9 @@ struct AddWhitelistRequest {
 async fn admin_add_ip_to_whitelist(
     Json(req): Json<AddWhitelistRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // [NEW] 校验 IP 模式 (IPv4 / IPv6 / CIDR)
+    security_db::normalize_ip_pattern(&req.ip_pattern)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
     security_db::add_to_whitelist(&req.ip_pattern, req.description.as_deref()).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1,6 +1,11 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Mutex, OnceLock};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::modules::session_cache_db::{
+    self, PersistedSignature, LAYER_FAMILY, LAYER_SESSION, LAYER_TOOL,
+};
 
 // Node.js proxy uses 2 hours TTL
 const SIGNATURE_TTL: Duration = Duration::from_secs(2 * 60 * 60);
@@ -33,9 +38,25 @@ impl<T> CacheEntry<T> {
         }
     }
 
+    /// Rebuild an entry from a persisted Unix timestamp (keeps the original TTL)
+    fn restored(data: T, updated_at: i64) -> Self {
+        Self {
+            data,
+            timestamp: UNIX_EPOCH + Duration::from_secs(updated_at.max(0) as u64),
+        }
+    }
+
     fn is_expired(&self) -> bool {
         self.timestamp.elapsed().unwrap_or(Duration::ZERO) > SIGNATURE_TTL
     }
+
+    fn age_secs(&self) -> u64 {
+        self.timestamp.elapsed().unwrap_or(Duration::ZERO).as_secs()
+    }
+}
+
+fn unix_now() -> i64 {
+    chrono::Utc::now().timestamp()
 }
 
 /// Triple-layer signature cache to handle:
@@ -58,6 +79,32 @@ pub struct SignatureCache {
     /// Value: The most recent valid thought signature for this session
     /// This prevents signature pollution between different conversations
     session_signatures: Mutex<HashMap<String, CacheEntry<SessionSignatureEntry>>>,
+
+    /// Lookup counters across all layers (exported via /metrics)
+    hits: AtomicU64,
+    misses: AtomicU64,
+
+    /// Write-behind to SQLite (session_cache.db) so sessions survive restarts
+    persistence_enabled: AtomicBool,
+}
+
+/// Snapshot of signature cache usage
+#[derive(Debug, Clone, Default)]
+pub struct SignatureCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub tool_entries: usize,
+    pub family_entries: usize,
+    pub session_entries: usize,
+}
+
+/// Layer 3 entry as exposed to the admin API
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionSignatureInfo {
+    pub session_id: String,
+    pub message_count: usize,
+    pub signature_length: usize,
+    pub age_secs: u64,
 }
 
 impl SignatureCache {
@@ -66,9 +113,113 @@ impl SignatureCache {
             tool_signatures: Mutex::new(HashMap::new()),
             thinking_families: Mutex::new(HashMap::new()),
             session_signatures: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            persistence_enabled: AtomicBool::new(false),
         }
     }
 
+    /// Record a lookup result and pass it through
+    fn record_lookup<T>(&self, result: Option<T>) -> Option<T> {
+        let counter = if result.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    /// Current hit/miss counters and entry counts per layer
+    pub fn stats(&self) -> SignatureCacheStats {
+        SignatureCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            tool_entries: self.tool_signatures.lock().map(|c| c.len()).unwrap_or(0),
+            family_entries: self.thinking_families.lock().map(|c| c.len()).unwrap_or(0),
+            session_entries: self.session_signatures.lock().map(|c| c.len()).unwrap_or(0),
+        }
+    }
+
+    /// Enable or disable SQLite persistence. Enabling restores non-expired entries.
+    pub fn set_persistence_enabled(&self, enabled: bool) {
+        let was_enabled = self.persistence_enabled.swap(enabled, Ordering::Relaxed);
+        if enabled && !was_enabled {
+            self.restore_persisted();
+        }
+    }
+
+    pub fn is_persistence_enabled(&self) -> bool {
+        self.persistence_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Load persisted entries into memory (respecting TTL and per-layer limits)
+    fn restore_persisted(&self) {
+        let ttl = SIGNATURE_TTL.as_secs() as i64;
+        let load = |layer: &str, limit: usize| {
+            let _ = session_cache_db::prune_signatures(layer, ttl, limit);
+            session_cache_db::load_signatures(layer, ttl, limit).unwrap_or_else(|e| {
+                tracing::warn!("[SignatureCache] Failed to restore {} layer: {}", layer, e);
+                Vec::new()
+            })
+        };
+
+        let tools = load(LAYER_TOOL, TOOL_CACHE_LIMIT);
+        let families = load(LAYER_FAMILY, FAMILY_CACHE_LIMIT);
+        let sessions = load(LAYER_SESSION, SESSION_CACHE_LIMIT);
+        let counts = (tools.len(), families.len(), sessions.len());
+
+        if let Ok(mut cache) = self.tool_signatures.lock() {
+            for e in tools {
+                cache
+                    .entry(e.key)
+                    .or_insert_with(|| CacheEntry::restored(e.value, e.updated_at));
+            }
+        }
+        if let Ok(mut cache) = self.thinking_families.lock() {
+            for e in families {
+                cache
+                    .entry(e.key)
+                    .or_insert_with(|| CacheEntry::restored(e.value, e.updated_at));
+            }
+        }
+        if let Ok(mut cache) = self.session_signatures.lock() {
+            for e in sessions {
+                cache.entry(e.key).or_insert_with(|| {
+                    CacheEntry::restored(
+                        SessionSignatureEntry {
+                            signature: e.value,
+                            message_count: e.message_count,
+                        },
+                        e.updated_at,
+                    )
+                });
+            }
+        }
+
+        tracing::info!(
+            "[SignatureCache] Restored persisted entries: tool={}, family={}, session={}",
+            counts.0,
+            counts.1,
+            counts.2
+        );
+    }
+
+    /// Queue a single entry for the background writer when persistence is enabled
+    /// (never touches SQLite on the request path; the layer is pruned periodically)
+    fn persist(&self, layer: &str, key: &str, value: &str, message_count: usize, limit: usize) {
+        if !self.is_persistence_enabled() {
+            return;
+        }
+        let entry = PersistedSignature {
+            key: key.to_string(),
+            value: value.to_string(),
+            message_count,
+            updated_at: unix_now(),
+        };
+        session_cache_db::queue_signature(layer, entry, SIGNATURE_TTL.as_secs() as i64, limit);
+    }
+
     /// Global singleton instance
     pub fn global() -> &'static SignatureCache {
         static INSTANCE: OnceLock<SignatureCache> = OnceLock::new();
@@ -86,7 +237,7 @@ impl SignatureCache {
                 "[SignatureCache] Caching tool signature for id: {}",
                 tool_use_id
             );
-            cache.insert(tool_use_id.to_string(), CacheEntry::new(signature));
+            cache.insert(tool_use_id.to_string(), CacheEntry::new(signature.clone()));
 
             // Clean up expired entries when limit is reached
             if cache.len() > TOOL_CACHE_LIMIT {
@@ -102,6 +253,8 @@ impl SignatureCache {
                 }
             }
         }
+
+        self.persist(LAYER_TOOL, tool_use_id, &signature, 0, TOOL_CACHE_LIMIT);
     }
 
     /// Retrieve a signature for a tool_use_id
@@ -113,11 +266,11 @@ impl SignatureCache {
                         "[SignatureCache] Hit tool signature for id: {}",
                         tool_use_id
                     );
-                    return Some(entry.data.clone());
+                    return self.record_lookup(Some(entry.data.clone()));
                 }
             }
         }
-        None
+        self.record_lookup(None)
     }
 
     /// Store model family for a signature
@@ -132,7 +285,7 @@ impl SignatureCache {
                 signature.len(),
                 family
             );
-            cache.insert(signature, CacheEntry::new(family));
+            cache.insert(signature.clone(), CacheEntry::new(family.clone()));
 
             if cache.len() > FAMILY_CACHE_LIMIT {
                 let before = cache.len();
@@ -147,6 +300,8 @@ impl SignatureCache {
                 }
             }
         }
+
+        self.persist(LAYER_FAMILY, &signature, &family, 0, FAMILY_CACHE_LIMIT);
     }
 
     /// Get model family for a signature
@@ -154,13 +309,13 @@ impl SignatureCache {
         if let Ok(cache) = self.thinking_families.lock() {
             if let Some(entry) = cache.get(signature) {
                 if !entry.is_expired() {
-                    return Some(entry.data.clone());
+                    return self.record_lookup(Some(entry.data.clone()));
                 } else {
                     tracing::debug!("[SignatureCache] Signature family entry expired");
                 }
             }
         }
-        None
+        self.record_lookup(None)
     }
 
     // ===== Layer 3: Session-based Signature Storage =====
@@ -182,6 +337,7 @@ impl SignatureCache {
             return;
         }
 
+        let mut stored = false;
         if let Ok(mut cache) = self.session_signatures.lock() {
             let should_store = match cache.get(session_id) {
                 None => true,
@@ -219,10 +375,11 @@ impl SignatureCache {
                 cache.insert(
                     session_id.to_string(),
                     CacheEntry::new(SessionSignatureEntry {
-                        signature,
+                        signature: signature.clone(),
                         message_count,
                     }),
                 );
+                stored = true;
             }
 
             // Cleanup when limit is reached (Session cache has largest limit)
@@ -240,6 +397,16 @@ impl SignatureCache {
                 }
             }
         }
+
+        if stored {
+            self.persist(
+                LAYER_SESSION,
+                session_id,
+                &signature,
+                message_count,
+                SESSION_CACHE_LIMIT,
+            );
+        }
     }
 
     /// Retrieve the latest thinking signature for a session.
@@ -253,30 +420,77 @@ impl SignatureCache {
                         session_id,
                         entry.data.signature.len()
                     );
-                    return Some(entry.data.signature.clone());
+                    return self.record_lookup(Some(entry.data.signature.clone()));
                 } else {
                     tracing::debug!("[SignatureCache] Session {} -> EXPIRED", session_id);
                 }
             }
         }
-        None
+        self.record_lookup(None)
     }
 
-    /// 删除指定会话的缓存签名
-    #[allow(dead_code)] // 预留给管理接口或调试使用
-    pub fn delete_session_signature(&self, session_id: &str) {
+    /// 列出所有未过期的会话签名 (供管理接口查看)
+    pub fn list_session_signatures(&self) -> Vec<SessionSignatureInfo> {
+        let mut list: Vec<SessionSignatureInfo> = match self.session_signatures.lock() {
+            Ok(cache) => cache
+                .iter()
+                .filter(|(_, v)| !v.is_expired())
+                .map(|(k, v)| SessionSignatureInfo {
+                    session_id: k.clone(),
+                    message_count: v.data.message_count,
+                    signature_length: v.data.signature.len(),
+                    age_secs: v.age_secs(),
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        list.sort_by_key(|i| i.age_secs);
+        list
+    }
+
+    /// 获取指定会话的签名信息 (不计入命中统计)
+    pub fn get_session_signature_info(&self, session_id: &str) -> Option<SessionSignatureInfo> {
+        let cache = self.session_signatures.lock().ok()?;
+        let entry = cache.get(session_id).filter(|e| !e.is_expired())?;
+        Some(SessionSignatureInfo {
+            session_id: session_id.to_string(),
+            message_count: entry.data.message_count,
+            signature_length: entry.data.signature.len(),
+            age_secs: entry.age_secs(),
+        })
+    }
+
+    /// 删除指定会话的缓存签名 (内存 + 持久化记录)
+    pub fn delete_session_signature(&self, session_id: &str) -> bool {
+        let mut removed = false;
         if let Ok(mut cache) = self.session_signatures.lock() {
             if cache.remove(session_id).is_some() {
                 tracing::debug!(
                     "[SignatureCache] Deleted session signature for: {}",
                     session_id
                 );
+                removed = true;
             }
         }
+        // 即使持久化当前关闭也删除，避免之后重新开启时恢复已清除的会话
+        // (先等待排队中的写入落盘，防止被随后写入的旧条目覆盖)
+        session_cache_db::flush_writes();
+        match session_cache_db::delete_signature(LAYER_SESSION, session_id) {
+            Ok(deleted) => removed || deleted,
+            Err(_) => removed,
+        }
+    }
+
+    /// 清空所有层 (内存 + 持久化记录)
+    pub fn purge_all(&self) {
+        self.clear();
+        session_cache_db::flush_writes();
+        if let Err(e) = session_cache_db::clear_signatures() {
+            tracing::warn!("[SignatureCache] Failed to purge persisted entries: {}", e);
+        }
     }
 
-    /// Clear all caches (for testing or manual reset)
-    #[allow(dead_code)] // Used in tests
+    /// Clear all in-memory caches (for testing or manual reset)
     pub fn clear(&self) {
         if let Ok(mut cache) = self.tool_signatures.lock() {
             cache.clear();
@@ -366,6 +580,29 @@ mod tests {
         assert!(cache.get_session_signature("sid-other").is_none());
     }
 
+    #[test]
+    fn test_restored_entry_keeps_ttl() {
+        let now = unix_now();
+        let fresh = CacheEntry::restored("sig".to_string(), now - 60);
+        assert!(!fresh.is_expired());
+        assert!(fresh.age_secs() >= 60);
+
+        let stale = CacheEntry::restored("sig".to_string(), now - 3 * 60 * 60);
+        assert!(stale.is_expired());
+    }
+
+    #[test]
+    fn test_session_signature_info() {
+        let cache = SignatureCache::new();
+        cache.cache_session_signature("sid-info", "s".repeat(70), 4);
+
+        let info = cache.get_session_signature_info("sid-info").unwrap();
+        assert_eq!(info.message_count, 4);
+        assert_eq!(info.signature_length, 70);
+        assert_eq!(cache.list_session_signatures().len(), 1);
+        assert!(cache.get_session_signature_info("sid-missing").is_none());
+    }
+
     #[test]
     fn test_clear_all_caches() {
         let cache = SignatureCache::new();
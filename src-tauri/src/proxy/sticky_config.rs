@@ -25,6 +25,8 @@ pub struct StickySessionConfig {
     pub mode: SchedulingMode,
     /// 缓存优先模式下的最大等待时间 (秒)
     pub max_wait_seconds: u64,
+    /// 是否将会话绑定与思维签名缓存持久化到 SQLite (重启后恢复长会话)
+    pub persist_sessions: bool,
 }
 
 impl Default for StickySessionConfig {
@@ -32,6 +34,7 @@ impl Default for StickySessionConfig {
         Self {
             mode: SchedulingMode::Balance,
             max_wait_seconds: 60,
+            persist_sessions: false,
         }
     }
 }
@@ -37,6 +37,8 @@ mod tests {
             output_config: None,
             size: None,
             quality: None,
+            tool_choice: None,
+            stop_sequences: None,
         };
 
         // 2. 执行转换
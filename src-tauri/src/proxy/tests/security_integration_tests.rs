@@ -122,14 +122,14 @@ mod integration_tests {
 
         // 添加已过期的临时封禁
         let _ = add_to_blacklist(
-            "expired.ban.test",
+            "198.51.100.61",
             Some("Temporary ban - should be expired"),
             Some(now - 60), // 1分钟前过期
             "test",
         );
 
         // 查询时应该触发过期清理
-        let is_blocked = security_db::is_ip_in_blacklist("expired.ban.test").unwrap();
+        let is_blocked = security_db::is_ip_in_blacklist("198.51.100.61").unwrap();
         assert!(!is_blocked, "Expired ban should not block");
 
         cleanup_test_data();
@@ -199,14 +199,14 @@ mod integration_tests {
 
         // 添加临时封禁（2小时后过期）
         let _ = add_to_blacklist(
-            "temp.ban.message",
+            "198.51.100.62",
             Some("Rate limit exceeded"),
             Some(now + 7200), // 2小时后
             "rate_limiter",
         );
 
         // 获取封禁详情
-        let entry = security_db::get_blacklist_entry_for_ip("temp.ban.message")
+        let entry = security_db::get_blacklist_entry_for_ip("198.51.100.62")
             .unwrap()
             .unwrap();
 
@@ -283,7 +283,7 @@ mod integration_tests {
 
         // 添加一些黑名单条目
         for i in 0..50 {
-            let _ = add_to_blacklist(&format!("perf.test.{}", i), None, None, "test");
+            let _ = add_to_blacklist(&format!("192.0.2.{}", i), None, None, "test");
         }
 
         // 添加一些 CIDR 规则
@@ -330,15 +330,15 @@ mod integration_tests {
         cleanup_test_data();
 
         // 添加数据
-        let _ = add_to_blacklist("persist.test.ip", Some("Persistence test"), None, "test");
-        let _ = add_to_whitelist("persist.white.ip", Some("Persistence test"));
+        let _ = add_to_blacklist("198.51.100.51", Some("Persistence test"), None, "test");
+        let _ = add_to_whitelist("198.51.100.52", Some("Persistence test"));
 
         // 重新初始化（实际上只是验证数据仍然可读）
         let _ = init_db();
 
         // 验证数据仍然存在
-        assert!(security_db::is_ip_in_blacklist("persist.test.ip").unwrap());
-        assert!(security_db::is_ip_in_whitelist("persist.white.ip").unwrap());
+        assert!(security_db::is_ip_in_blacklist("198.51.100.51").unwrap());
+        assert!(security_db::is_ip_in_whitelist("198.51.100.52").unwrap());
 
         cleanup_test_data();
     }
@@ -379,7 +379,7 @@ mod stress_tests {
         let start = Instant::now();
         for i in 0..count {
             let _ = add_to_blacklist(
-                &format!("stress.{}.{}.{}.{}", i / 256, (i / 16) % 16, i % 16, i),
+                &format!("10.{}.{}.{}", i / 256, (i / 16) % 16, i % 16),
                 None,
                 None,
                 "stress",
@@ -391,13 +391,7 @@ mod stress_tests {
         // 随机查找测试
         let start = Instant::now();
         for i in 0..100 {
-            let _ = is_ip_in_blacklist(&format!(
-                "stress.{}.{}.{}.{}",
-                i / 256,
-                (i / 16) % 16,
-                i % 16,
-                i
-            ));
+            let _ = is_ip_in_blacklist(&format!("10.{}.{}.{}", i / 256, (i / 16) % 16, i % 16));
         }
         let lookup_duration = start.elapsed();
         println!("100 lookups in large blacklist took {:?}", lookup_duration);
@@ -468,7 +462,7 @@ mod stress_tests {
                 thread::spawn(move || {
                     for i in 0..ops_per_thread {
                         // 每个线程添加-查询-删除
-                        let ip = format!("concurrent.{}.{}", t, i);
+                        let ip = format!("10.200.{}.{}", t, i);
                         if let Ok(entry) = add_to_blacklist(&ip, None, None, "concurrent") {
                             let _ = is_ip_in_blacklist(&ip);
                             let _ = remove_from_blacklist(&entry.id);
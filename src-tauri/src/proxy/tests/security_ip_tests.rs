@@ -270,14 +270,14 @@ mod security_db_tests {
 
         // 添加一个已过期的条目
         let _ = add_to_blacklist(
-            "expired.test.ip",
+            "198.51.100.11",
             Some("Already expired"),
             Some(now_timestamp() - 60), // 1分钟前过期
             "test",
         );
 
         // 过期条目应该被自动清理
-        let is_blocked = is_ip_in_blacklist("expired.test.ip");
+        let is_blocked = is_ip_in_blacklist("198.51.100.11");
         // 注意：取决于实现，过期条目可能在查询时被清理
         // 根据 security_db.rs 的实现，get_blacklist_entry_for_ip 会先清理过期条目
         assert!(!is_blocked.unwrap(), "Expired entry should be cleaned up");
@@ -292,14 +292,14 @@ mod security_db_tests {
 
         // 添加一个未过期的条目
         let _ = add_to_blacklist(
-            "not.expired.ip",
+            "198.51.100.12",
             Some("Will expire later"),
             Some(now_timestamp() + 3600), // 1小时后过期
             "test",
         );
 
         // 未过期条目应该仍然生效
-        assert!(is_ip_in_blacklist("not.expired.ip").unwrap());
+        assert!(is_ip_in_blacklist("198.51.100.12").unwrap());
 
         cleanup_test_data();
     }
@@ -311,14 +311,14 @@ mod security_db_tests {
 
         // 添加永久封禁 (无过期时间)
         let _ = add_to_blacklist(
-            "permanent.block.ip",
+            "198.51.100.13",
             Some("Permanent ban"),
             None, // 无过期时间
             "test",
         );
 
         // 永久封禁应该始终生效
-        assert!(is_ip_in_blacklist("permanent.block.ip").unwrap());
+        assert!(is_ip_in_blacklist("198.51.100.13").unwrap());
 
         cleanup_test_data();
     }
@@ -485,9 +485,9 @@ mod security_db_tests {
         }
 
         // 添加黑名单和白名单条目
-        let _ = add_to_blacklist("stats.black.1", None, None, "test");
-        let _ = add_to_blacklist("stats.black.2", None, None, "test");
-        let _ = add_to_whitelist("stats.white.1", None);
+        let _ = add_to_blacklist("198.51.100.21", None, None, "test");
+        let _ = add_to_blacklist("198.51.100.22", None, None, "test");
+        let _ = add_to_whitelist("198.51.100.23", None);
 
         // 获取统计
         let stats = get_ip_stats();
@@ -580,7 +580,7 @@ mod security_db_tests {
             .map(|i| {
                 thread::spawn(move || {
                     // 每个线程添加不同的 IP
-                    let ip = format!("concurrent.test.{}", i);
+                    let ip = format!("198.51.100.{}", 30 + i);
                     let _ = add_to_blacklist(&ip, Some("Concurrent test"), None, "test");
 
                     // 验证自己添加的 IP
@@ -610,11 +610,11 @@ mod security_db_tests {
         cleanup_test_data();
 
         // 第一次添加应该成功
-        let result1 = add_to_blacklist("duplicate.test.ip", Some("First"), None, "test");
+        let result1 = add_to_blacklist("198.51.100.41", Some("First"), None, "test");
         assert!(result1.is_ok());
 
         // 第二次添加相同 IP 应该失败 (UNIQUE constraint)
-        let result2 = add_to_blacklist("duplicate.test.ip", Some("Second"), None, "test");
+        let result2 = add_to_blacklist("198.51.100.41", Some("Second"), None, "test");
         assert!(result2.is_err(), "Duplicate IP should fail");
 
         cleanup_test_data();
@@ -625,11 +625,9 @@ mod security_db_tests {
         let _ = init_db();
         cleanup_test_data();
 
-        // 空 IP 模式应该仍然可以添加 (取决于业务需求)
-        // 这里只测试不会 panic
+        // 空 IP 模式在写入时被拒绝
         let result = add_to_blacklist("", Some("Empty IP"), None, "test");
-        // 结果可能成功或失败，但不应该 panic
-        let _ = result;
+        assert!(result.is_err(), "Empty IP pattern should be rejected");
 
         cleanup_test_data();
     }
@@ -641,10 +639,10 @@ mod security_db_tests {
 
         // 测试包含特殊字符的原因
         let reason = "Test with 'quotes' and \"double quotes\" and emoji 🚫";
-        let result = add_to_blacklist("special.char.test", Some(reason), None, "test");
+        let result = add_to_blacklist("198.51.100.42", Some(reason), None, "test");
         assert!(result.is_ok());
 
-        let entry = get_blacklist_entry_for_ip("special.char.test")
+        let entry = get_blacklist_entry_for_ip("198.51.100.42")
             .unwrap()
             .unwrap();
         assert_eq!(entry.reason.as_deref(), Some(reason));
@@ -658,16 +656,16 @@ mod security_db_tests {
         cleanup_test_data();
 
         // 添加一个黑名单条目
-        let _ = add_to_blacklist("hit.count.test", Some("Count test"), None, "test");
+        let _ = add_to_blacklist("198.51.100.43", Some("Count test"), None, "test");
 
         // 多次查询应该增加 hit_count
         for _ in 0..5 {
-            let _ = get_blacklist_entry_for_ip("hit.count.test");
+            let _ = get_blacklist_entry_for_ip("198.51.100.43");
         }
 
         // 检查 hit_count
         let blacklist = get_blacklist().unwrap();
-        let entry = blacklist.iter().find(|e| e.ip_pattern == "hit.count.test");
+        let entry = blacklist.iter().find(|e| e.ip_pattern == "198.51.100.43");
         assert!(entry.is_some());
         assert!(
             entry.unwrap().hit_count >= 5,
@@ -731,13 +729,13 @@ mod performance_benchmarks {
         }
 
         for i in 0..100 {
-            let _ = add_to_blacklist(&format!("bench.ip.{}", i), Some("Benchmark"), None, "test");
+            let _ = add_to_blacklist(&format!("203.0.113.{}", i), Some("Benchmark"), None, "test");
         }
 
         // 执行 1000 次查找
         let start = Instant::now();
         for _ in 0..1000 {
-            let _ = is_ip_in_blacklist("bench.ip.50");
+            let _ = is_ip_in_blacklist("203.0.113.50");
         }
         let duration = start.elapsed();
 
@@ -0,0 +1,340 @@
+//! 监听端口 TLS 终止
+//!
+//! - 证书来自 TlsConfig 中的 PEM 路径，未配置时在数据目录下生成自签名证书
+//! - 通过 ALPN 协商 h2 / http/1.1
+//! - 证书文件变更后自动重新加载，新连接立即使用新证书 (加载失败时保留旧证书)
+//! - 启用 TLS 后，本机回环地址仍可使用明文 HTTP (内部预热、cloudflared 隧道)
+
+use crate::proxy::config::TlsConfig;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{
+    self,
+    crypto::ring as ring_provider,
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+
+/// TLS 握手 (含首字节探测) 超时
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 证书文件轮询间隔
+const WATCH_INTERVAL_SECS: u64 = 5;
+/// TLS 记录层 handshake 类型
+const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+
+const PLAINTEXT_REJECTED_RESPONSE: &[u8] = b"HTTP/1.1 400 Bad Request\r\n\
+Content-Type: text/plain\r\n\
+Connection: close\r\n\
+Content-Length: 40\r\n\
+\r\n\
+This server requires TLS, use https://\r\n";
+
+/// 连接使用的 HTTP 协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpProtocol {
+    Http1,
+    Http2,
+    /// 根据连接前导自动识别 (HTTP/1.1 或 h2c prior knowledge)
+    Auto,
+}
+
+/// 握手后的连接
+pub enum AcceptedStream {
+    Tls(Box<TlsStream<TcpStream>>, HttpProtocol),
+    Plain(TcpStream),
+}
+
+/// 可热替换的证书
+#[derive(Debug)]
+struct ReloadableCert {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for ReloadableCert {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        self.current.read().ok().map(|cert| cert.clone())
+    }
+}
+
+/// 监听端口的 TLS 终止器
+pub struct TlsTerminator {
+    acceptor: TlsAcceptor,
+    cert: Arc<ReloadableCert>,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    allow_loopback_plaintext: bool,
+}
+
+impl TlsTerminator {
+    /// 根据配置构建 TLS 终止器，未启用 TLS 时返回 None
+    pub fn from_config(config: &TlsConfig, host: &str) -> Result<Option<Arc<Self>>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let (cert_path, key_path) = resolve_cert_paths(config, host)?;
+        let cert = Arc::new(ReloadableCert {
+            current: RwLock::new(Arc::new(load_certified_key(&cert_path, &key_path)?)),
+        });
+
+        let mut server_config =
+            ServerConfig::builder_with_provider(Arc::new(ring_provider::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|e| format!("TLS 协议配置失败: {}", e))?
+                .with_no_client_auth()
+                .with_cert_resolver(cert.clone());
+        server_config.alpn_protocols = if config.http2 {
+            vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+        } else {
+            vec![b"http/1.1".to_vec()]
+        };
+
+        tracing::info!(
+            "[TLS] 已启用 TLS (证书: {}, HTTP/2: {})",
+            cert_path.display(),
+            config.http2
+        );
+
+        Ok(Some(Arc::new(Self {
+            acceptor: TlsAcceptor::from(Arc::new(server_config)),
+            cert,
+            cert_path,
+            key_path,
+            allow_loopback_plaintext: config.allow_loopback_plaintext,
+        })))
+    }
+
+    /// 接受连接: TLS 握手，或在允许时放行回环地址的明文连接
+    pub async fn accept(&self, stream: TcpStream, peer: SocketAddr) -> Option<AcceptedStream> {
+        let mut first = [0u8; 1];
+        match tokio::time::timeout(HANDSHAKE_TIMEOUT, stream.peek(&mut first)).await {
+            Ok(Ok(1)) => {}
+            _ => return None,
+        }
+
+        if first[0] != TLS_HANDSHAKE_RECORD {
+            if self.allow_loopback_plaintext && is_loopback(peer.ip()) {
+                return Some(AcceptedStream::Plain(stream));
+            }
+            tracing::debug!("[TLS] 拒绝来自 {} 的明文连接", peer);
+            let mut stream = stream;
+            let _ = stream.write_all(PLAINTEXT_REJECTED_RESPONSE).await;
+            let _ = stream.shutdown().await;
+            return None;
+        }
+
+        match tokio::time::timeout(HANDSHAKE_TIMEOUT, self.acceptor.accept(stream)).await {
+            Ok(Ok(tls_stream)) => {
+                let protocol = match tls_stream.get_ref().1.alpn_protocol() {
+                    Some(b"h2") => HttpProtocol::Http2,
+                    _ => HttpProtocol::Http1,
+                };
+                Some(AcceptedStream::Tls(Box::new(tls_stream), protocol))
+            }
+            Ok(Err(e)) => {
+                tracing::debug!("[TLS] 与 {} 握手失败: {}", peer, e);
+                None
+            }
+            Err(_) => {
+                tracing::debug!("[TLS] 与 {} 握手超时", peer);
+                None
+            }
+        }
+    }
+
+    /// 轮询证书文件，内容变化时重新加载
+    pub fn start_watcher(self: &Arc<Self>, cancel: CancellationToken) {
+        let terminator = self.clone();
+        tokio::spawn(async move {
+            let mut last = read_pair(&terminator.cert_path, &terminator.key_path);
+            let mut interval = tokio::time::interval(Duration::from_secs(WATCH_INTERVAL_SECS));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = interval.tick() => {}
+                }
+                let current = read_pair(&terminator.cert_path, &terminator.key_path);
+                // 文件暂时缺失 (证书续期替换中) 时保留当前证书
+                if current.is_none() || current == last {
+                    continue;
+                }
+                last = current;
+
+                if let Err(e) = terminator.reload() {
+                    tracing::error!("[TLS] 证书重新加载失败，继续使用当前证书: {}", e);
+                }
+            }
+        });
+    }
+
+    fn reload(&self) -> Result<(), String> {
+        let key = load_certified_key(&self.cert_path, &self.key_path)?;
+        let mut current = self
+            .cert
+            .current
+            .write()
+            .map_err(|_| "证书锁已损坏".to_string())?;
+        *current = Arc::new(key);
+        tracing::info!("[TLS] 证书已重新加载: {}", self.cert_path.display());
+        Ok(())
+    }
+}
+
+fn is_loopback(ip: IpAddr) -> bool {
+    crate::modules::security_db::normalize_ip(ip).is_loopback()
+}
+
+fn read_pair(cert_path: &Path, key_path: &Path) -> Option<(Vec<u8>, Vec<u8>)> {
+    Some((
+        std::fs::read(cert_path).ok()?,
+        std::fs::read(key_path).ok()?,
+    ))
+}
+
+/// 确定证书与私钥路径，必要时生成自签名证书
+fn resolve_cert_paths(config: &TlsConfig, host: &str) -> Result<(PathBuf, PathBuf), String> {
+    let non_empty = |p: &Option<String>| {
+        p.as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    };
+    match (non_empty(&config.cert_path), non_empty(&config.key_path)) {
+        (Some(cert), Some(key)) => Ok((cert, key)),
+        (None, None) if config.self_signed => {
+            let dir = crate::modules::account::get_data_dir()?.join("tls");
+            let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+            if !cert.exists() || !key.exists() {
+                generate_self_signed(&dir, &cert, &key, host)?;
+            }
+            Ok((cert, key))
+        }
+        (None, None) => Err("TLS 已启用，但未配置证书 (cert_path / key_path)".to_string()),
+        _ => Err("TLS 的 cert_path 与 key_path 必须同时配置".to_string()),
+    }
+}
+
+/// 生成自签名证书 (SAN: localhost、回环地址与监听地址)
+fn generate_self_signed(dir: &Path, cert: &Path, key: &Path, host: &str) -> Result<(), String> {
+    let mut names = vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ];
+    let host = host.trim_matches(|c| c == '[' || c == ']');
+    let is_unspecified = host
+        .parse::<IpAddr>()
+        .map(|ip| ip.is_unspecified())
+        .unwrap_or(false);
+    if !host.is_empty() && !is_unspecified && !names.iter().any(|n| n == host) {
+        names.push(host.to_string());
+    }
+
+    let generated = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("生成自签名证书失败: {}", e))?;
+
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建证书目录失败: {}", e))?;
+    std::fs::write(cert, generated.cert.pem()).map_err(|e| format!("写入证书失败: {}", e))?;
+    std::fs::write(key, generated.key_pair.serialize_pem())
+        .map_err(|e| format!("写入私钥失败: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(key, std::fs::Permissions::from_mode(0o600));
+    }
+
+    tracing::warn!(
+        "[TLS] 已生成自签名证书: {} (客户端需信任该证书或跳过校验)",
+        cert.display()
+    );
+    Ok(())
+}
+
+/// 读取 PEM 证书链与私钥
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, String> {
+    let cert_pem = std::fs::read(cert_path)
+        .map_err(|e| format!("读取证书 {} 失败: {}", cert_path.display(), e))?;
+    let key_pem = std::fs::read(key_path)
+        .map_err(|e| format!("读取私钥 {} 失败: {}", key_path.display(), e))?;
+
+    let certs = CertificateDer::pem_slice_iter(&cert_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("解析证书 {} 失败: {}", cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("证书 {} 中未找到 PEM 证书", cert_path.display()));
+    }
+    let key = PrivateKeyDer::from_pem_slice(&key_pem)
+        .map_err(|e| format!("解析私钥 {} 失败: {}", key_path.display(), e))?;
+    let signing_key = ring_provider::sign::any_supported_type(&key)
+        .map_err(|e| format!("不支持的私钥类型: {}", e))?;
+
+    let certified = CertifiedKey::new(certs, signing_key);
+    match certified.keys_match() {
+        Ok(()) | Err(rustls::Error::InconsistentKeys(rustls::InconsistentKeys::Unknown)) => {
+            Ok(certified)
+        }
+        Err(e) => Err(format!("证书与私钥不匹配: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_signed_generation_and_reload() {
+        let dir = std::env::temp_dir().join(format!("abv-tls-{}", uuid::Uuid::new_v4()));
+        let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+        generate_self_signed(&dir, &cert, &key, "0.0.0.0").unwrap();
+        let first = load_certified_key(&cert, &key).unwrap();
+
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: Some(cert.to_string_lossy().to_string()),
+            key_path: Some(key.to_string_lossy().to_string()),
+            ..TlsConfig::default()
+        };
+        let terminator = TlsTerminator::from_config(&config, "0.0.0.0")
+            .unwrap()
+            .unwrap();
+
+        // 替换为新证书后重新加载
+        std::fs::remove_file(&cert).unwrap();
+        std::fs::remove_file(&key).unwrap();
+        generate_self_signed(&dir, &cert, &key, "192.168.1.20").unwrap();
+        terminator.reload().unwrap();
+        let current = terminator.cert.current.read().unwrap().clone();
+        assert_ne!(current.cert[0].as_ref(), first.cert[0].as_ref());
+
+        // 证书与私钥不匹配时拒绝加载，保留当前证书
+        let other = dir.join("other");
+        generate_self_signed(&other, &other.join("c.pem"), &other.join("k.pem"), "").unwrap();
+        std::fs::copy(other.join("k.pem"), &key).unwrap();
+        assert!(terminator.reload().is_err());
+        let still = terminator.cert.current.read().unwrap().clone();
+        assert_eq!(still.cert[0].as_ref(), current.cert[0].as_ref());
+
+        let missing_key = TlsConfig {
+            enabled: true,
+            cert_path: Some(cert.to_string_lossy().to_string()),
+            ..TlsConfig::default()
+        };
+        assert!(TlsTerminator::from_config(&missing_key, "").is_err());
+        assert!(TlsTerminator::from_config(&TlsConfig::default(), "")
+            .unwrap()
+            .is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
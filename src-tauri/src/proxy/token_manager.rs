@@ -2,7 +2,7 @@
 use dashmap::DashMap;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
@@ -16,6 +16,32 @@ enum OnDiskAccountState {
     Unknown,
 }
 
+/// [NEW] get_token 失败原因 (模型降级等调用方据此判断，而不是匹配错误文本)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    /// 号池为空
+    PoolEmpty,
+    /// 目标模型的账号已全部耗尽 (无配额 / 全部限流 / 全部失败)
+    ModelExhausted(String),
+    /// 其他错误 (获取超时、刷新失败、固定账号不可用等)
+    Other(String),
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenError::PoolEmpty => write!(f, "Token pool is empty"),
+            TokenError::ModelExhausted(msg) | TokenError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<TokenError> for String {
+    fn from(e: TokenError) -> Self {
+        e.to_string()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ProxyToken {
     pub account_id: String,
@@ -36,6 +62,23 @@ pub struct ProxyToken {
     pub model_quotas: HashMap<String, i32>, // [OPTIMIZATION] In-memory cache for model-specific quotas
 }
 
+/// 账号状态快照 (用于 /metrics 导出)
+#[derive(Debug, Clone)]
+pub struct AccountMetricsSnapshot {
+    pub email: String,
+    pub available: bool,
+    pub health_score: f32,
+    pub remaining_quota: Option<i32>,
+    pub model_quotas: HashMap<String, i32>,
+    /// 生效中的限流锁定: (模型, 剩余秒数)，模型为 None 表示账号级锁定
+    pub lockouts: Vec<(Option<String>, u64)>,
+}
+
+/// 持久化会话绑定的有效期 (与签名缓存 TTL 一致)
+const SESSION_BINDING_TTL_SECS: i64 = 2 * 60 * 60;
+/// 启动时最多恢复的会话绑定数量
+const SESSION_BINDING_LIMIT: usize = 1000;
+
 pub struct TokenManager {
     tokens: Arc<DashMap<String, ProxyToken>>, // account_id -> ProxyToken
     current_index: Arc<AtomicUsize>,
@@ -44,6 +87,7 @@ pub struct TokenManager {
     rate_limit_tracker: Arc<RateLimitTracker>, // 新增: 限流跟踪器
     sticky_config: Arc<tokio::sync::RwLock<StickySessionConfig>>, // 新增：调度配置
     session_accounts: Arc<DashMap<String, String>>, // 新增：会话与账号映射 (SessionID -> AccountID)
+    session_persistence: Arc<AtomicBool>,      // [NEW] 会话绑定是否持久化到 SQLite
     preferred_account_id: Arc<tokio::sync::RwLock<Option<String>>>, // [FIX #820] 优先使用的账号ID（固定账号模式）
     health_scores: Arc<DashMap<String, f32>>,                       // account_id -> health_score
     circuit_breaker_config: Arc<tokio::sync::RwLock<crate::models::CircuitBreakerConfig>>, // [NEW] 熔断配置缓存
@@ -63,6 +107,7 @@ impl TokenManager {
             rate_limit_tracker: Arc::new(RateLimitTracker::new()),
             sticky_config: Arc::new(tokio::sync::RwLock::new(StickySessionConfig::default())),
             session_accounts: Arc::new(DashMap::new()),
+            session_persistence: Arc::new(AtomicBool::new(false)),
             preferred_account_id: Arc::new(tokio::sync::RwLock::new(None)), // [FIX #820]
             health_scores: Arc::new(DashMap::new()),
             circuit_breaker_config: Arc::new(tokio::sync::RwLock::new(
@@ -208,7 +253,7 @@ impl TokenManager {
         self.clear_rate_limit(account_id);
 
         // 4. 清理涉及该账号的所有会话绑定
-        self.session_accounts.retain(|_, v| v != account_id);
+        self.unbind_account_sessions(account_id);
 
         // 5. 如果是当前优先账号，也需要清理
         if let Ok(mut preferred) = self.preferred_account_id.try_write() {
@@ -436,15 +481,18 @@ impl TokenManager {
 
         let token_obj = account["token"].as_object().ok_or("缺少 token 字段")?;
 
-        let access_token = token_obj["access_token"]
-            .as_str()
-            .ok_or("缺少 access_token")?
-            .to_string();
+        // [NEW] Token 字段可能已落盘加密，读取时解密 (兼容旧版明文)
+        let access_token = crate::utils::crypto::decrypt_token(
+            token_obj["access_token"]
+                .as_str()
+                .ok_or("缺少 access_token")?,
+        )?;
 
-        let refresh_token = token_obj["refresh_token"]
-            .as_str()
-            .ok_or("缺少 refresh_token")?
-            .to_string();
+        let refresh_token = crate::utils::crypto::decrypt_token(
+            token_obj["refresh_token"]
+                .as_str()
+                .ok_or("缺少 refresh_token")?,
+        )?;
 
         let expires_in = token_obj["expires_in"].as_i64().ok_or("缺少 expires_in")?;
 
@@ -775,6 +823,17 @@ impl TokenManager {
             // [FIX] 触发 TokenManager 的账号重新加载信号，确保内存中的 protected_models 同步
             crate::proxy::server::trigger_account_reload(account_id);
 
+            publish_account_event(
+                crate::modules::event_bus::EVENT_QUOTA_PROTECTED,
+                account_id,
+                account_json,
+                serde_json::json!({
+                    "model": model_name,
+                    "percentage": current_val,
+                    "threshold": threshold,
+                }),
+            );
+
             return Ok(true);
         }
 
@@ -998,7 +1057,7 @@ impl TokenManager {
         force_rotate: bool,
         session_id: Option<&str>,
         target_model: &str,
-    ) -> Result<(String, String, String, String, u64), String> {
+    ) -> Result<(String, String, String, String, u64), TokenError> {
         // [FIX] 检查并处理待重新加载的账号（配额保护同步）
         let pending_reload = crate::proxy::server::take_pending_reload_accounts();
         for account_id in pending_reload {
@@ -1022,6 +1081,14 @@ impl TokenManager {
             );
         }
 
+        // [NEW] 请求重放固定账号时跳过调度
+        if let Some(pinned) = crate::proxy::replay::pinned_account() {
+            return self
+                .get_pinned_token(&pinned)
+                .await
+                .map_err(TokenError::Other);
+        }
+
         // 【优化 Issue #284】添加 5 秒超时，防止死锁
         let timeout_duration = std::time::Duration::from_secs(5);
         match tokio::time::timeout(
@@ -1031,9 +1098,9 @@ impl TokenManager {
         .await
         {
             Ok(result) => result,
-            Err(_) => Err(
+            Err(_) => Err(TokenError::Other(
                 "Token acquisition timeout (5s) - system too busy or deadlock detected".to_string(),
-            ),
+            )),
         }
     }
 
@@ -1044,12 +1111,12 @@ impl TokenManager {
         force_rotate: bool,
         session_id: Option<&str>,
         target_model: &str,
-    ) -> Result<(String, String, String, String, u64), String> {
+    ) -> Result<(String, String, String, String, u64), TokenError> {
         let mut tokens_snapshot: Vec<ProxyToken> =
             self.tokens.iter().map(|e| e.value().clone()).collect();
         let mut total = tokens_snapshot.len();
         if total == 0 {
-            return Err("Token pool is empty".to_string());
+            return Err(TokenError::PoolEmpty);
         }
 
         // [NEW] 1. 动态能力过滤 (Capability Filter)
@@ -1077,12 +1144,12 @@ impl TokenManager {
                     "No accounts have satisfied quota for model: {}",
                     normalized_target
                 );
-                return Err(format!(
+                return Err(TokenError::ModelExhausted(format!(
                     "No accounts available with quota for model: {}",
                     normalized_target
-                ));
+                )));
             }
-            return Err("Token pool is empty".to_string());
+            return Err(TokenError::PoolEmpty);
         }
 
         tokens_snapshot.sort_by(|a, b| {
@@ -1199,7 +1266,7 @@ impl TokenManager {
                         }
 
                         if total == 0 {
-                            return Err("Token pool is empty".to_string());
+                            return Err(TokenError::PoolEmpty);
                         }
                     }
                     OnDiskAccountState::Unknown => {
@@ -1211,7 +1278,7 @@ impl TokenManager {
                         tokens_snapshot.retain(|t| t.account_id != preferred_token.account_id);
                         total = tokens_snapshot.len();
                         if total == 0 {
-                            return Err("Token pool is empty".to_string());
+                            return Err(TokenError::PoolEmpty);
                         }
                     }
                     OnDiskAccountState::Enabled => {
@@ -1374,7 +1441,7 @@ impl TokenManager {
                                 "Sticky Session: Bound account {} is rate-limited ({}s), unbinding and switching.",
                                 bound_token.email, reset_sec
                             );
-                            self.session_accounts.remove(sid);
+                            self.unbind_session(sid);
                         } else if !attempted.contains(&bound_id)
                             && !(quota_protection_enabled
                                 && bound_token.protected_models.contains(&normalized_target))
@@ -1382,11 +1449,12 @@ impl TokenManager {
                             // 3. 账号可用且未被标记为尝试失败，优先复用
                             tracing::debug!("Sticky Session: Successfully reusing bound account {} for session {}", bound_token.email, sid);
                             target_token = Some(bound_token.clone());
+                            self.persist_session_binding(sid, &bound_id);
                         } else if quota_protection_enabled
                             && bound_token.protected_models.contains(&normalized_target)
                         {
                             tracing::debug!("Sticky Session: Bound account {} is quota-protected for model {} [{}], unbinding and switching.", bound_token.email, normalized_target, target_model);
-                            self.session_accounts.remove(sid);
+                            self.unbind_session(sid);
                         }
                     } else {
                         // 绑定的账号已不存在（可能被删除），解绑
@@ -1394,7 +1462,7 @@ impl TokenManager {
                             "Sticky Session: Bound account not found for session {}, unbinding",
                             sid
                         );
-                        self.session_accounts.remove(sid);
+                        self.unbind_session(sid);
                     }
                 }
             }
@@ -1468,8 +1536,7 @@ impl TokenManager {
                         // 如果是会话首次分配且需要粘性，在此建立绑定
                         if let Some(sid) = session_id {
                             if scheduling.mode != SchedulingMode::PerformanceFirst {
-                                self.session_accounts
-                                    .insert(sid.to_string(), selected.account_id.clone());
+                                self.bind_session(sid, &selected.account_id);
                                 tracing::debug!(
                                     "Sticky Session: Bound new account {} to session {}",
                                     selected.email,
@@ -1572,16 +1639,21 @@ impl TokenManager {
                                     );
                                     t.clone()
                                 } else {
-                                    return Err(
-                                        "All accounts failed after optimistic reset.".to_string()
-                                    );
+                                    return Err(TokenError::ModelExhausted(
+                                        "All accounts failed after optimistic reset.".to_string(),
+                                    ));
                                 }
                             }
                         } else {
-                            return Err(format!("All accounts limited. Wait {}s.", wait_sec));
+                            return Err(TokenError::ModelExhausted(format!(
+                                "All accounts limited. Wait {}s.",
+                                wait_sec
+                            )));
                         }
                     } else {
-                        return Err("All accounts failed or unhealthy.".to_string());
+                        return Err(TokenError::ModelExhausted(
+                            "All accounts failed or unhealthy.".to_string(),
+                        ));
                     }
                 }
             };
@@ -1734,7 +1806,10 @@ impl TokenManager {
             ));
         }
 
-        Err(last_error.unwrap_or_else(|| "All accounts failed".to_string()))
+        Err(match last_error {
+            Some(e) => TokenError::Other(e),
+            None => TokenError::ModelExhausted("All accounts failed".to_string()),
+        })
     }
 
     async fn disable_account(&self, account_id: &str, reason: &str) -> Result<(), String> {
@@ -1763,6 +1838,12 @@ impl TokenManager {
         self.tokens.remove(account_id);
 
         tracing::warn!("Account disabled: {} ({:?})", account_id, path);
+        publish_account_event(
+            crate::modules::event_bus::EVENT_ACCOUNT_DISABLED,
+            account_id,
+            &content,
+            serde_json::json!({ "reason": truncate_reason(reason, 800) }),
+        );
         Ok(())
     }
 
@@ -1803,8 +1884,9 @@ impl TokenManager {
 
         let now = chrono::Utc::now().timestamp();
 
-        content["token"]["access_token"] =
-            serde_json::Value::String(token_response.access_token.clone());
+        content["token"]["access_token"] = serde_json::Value::String(
+            crate::utils::crypto::encrypt_token(&token_response.access_token)?,
+        );
         content["token"]["expires_in"] =
             serde_json::Value::Number(token_response.expires_in.into());
         content["token"]["expiry_timestamp"] =
@@ -1821,6 +1903,20 @@ impl TokenManager {
         self.tokens.len()
     }
 
+    /// 获取重放请求固定的账号 Token（参数为账号 ID 或邮箱）
+    async fn get_pinned_token(
+        &self,
+        account: &str,
+    ) -> Result<(String, String, String, String, u64), String> {
+        let email = self
+            .tokens
+            .iter()
+            .find(|entry| entry.key() == account || entry.value().email == account)
+            .map(|entry| entry.value().email.clone())
+            .ok_or_else(|| format!("Pinned account {} is not available in the pool", account))?;
+        self.get_token_by_email(&email).await
+    }
+
     /// 通过 email 获取指定账号的 Token（用于预热等需要指定账号的场景）
     /// 此方法会自动刷新过期的 token
     pub async fn get_token_by_email(
@@ -1982,6 +2078,38 @@ impl TokenManager {
             .map(|entry| entry.value().account_id.clone())
     }
 
+    /// 导出所有已加载账号的可用性、配额与限流锁定状态
+    pub fn account_metrics(&self) -> Vec<AccountMetricsSnapshot> {
+        let now = chrono::Utc::now().timestamp();
+        let lockouts = self.rate_limit_tracker.active_lockouts();
+
+        self.tokens
+            .iter()
+            .map(|entry| {
+                let token = entry.value();
+                let account_lockouts: Vec<(Option<String>, u64)> = lockouts
+                    .iter()
+                    .filter(|(id, _, _)| id == &token.account_id)
+                    .map(|(_, model, secs)| (model.clone(), *secs))
+                    .collect();
+                let blocked = token.validation_blocked && token.validation_blocked_until > now;
+                let account_locked = account_lockouts.iter().any(|(m, _)| m.is_none());
+                AccountMetricsSnapshot {
+                    email: token.email.clone(),
+                    available: !blocked && !account_locked,
+                    health_score: self
+                        .health_scores
+                        .get(&token.account_id)
+                        .map(|score| *score)
+                        .unwrap_or(token.health_score),
+                    remaining_quota: token.remaining_quota,
+                    model_quotas: token.model_quotas.clone(),
+                    lockouts: account_lockouts,
+                }
+            })
+            .collect()
+    }
+
     /// 清除指定账号的限流记录
     pub fn clear_rate_limit(&self, account_id: &str) -> bool {
         self.rate_limit_tracker.clear(account_id)
@@ -2333,9 +2461,86 @@ impl TokenManager {
 
     /// 更新调度配置
     pub async fn update_sticky_config(&self, new_config: StickySessionConfig) {
-        let mut config = self.sticky_config.write().await;
-        *config = new_config;
-        tracing::debug!("Scheduling configuration updated: {:?}", *config);
+        let persist = new_config.persist_sessions;
+        {
+            let mut config = self.sticky_config.write().await;
+            *config = new_config;
+            tracing::debug!("Scheduling configuration updated: {:?}", *config);
+        }
+        self.set_session_persistence(persist);
+    }
+
+    // ===== [NEW] 会话持久化 (签名缓存 + 粘性绑定) =====
+
+    /// 开启/关闭会话持久化，开启时从 SQLite 恢复未过期的绑定与签名
+    pub fn set_session_persistence(&self, enabled: bool) {
+        let was_enabled = self.session_persistence.swap(enabled, Ordering::Relaxed);
+        crate::proxy::SignatureCache::global().set_persistence_enabled(enabled);
+        if !enabled || was_enabled {
+            return;
+        }
+
+        match crate::modules::session_cache_db::load_bindings(
+            SESSION_BINDING_TTL_SECS,
+            SESSION_BINDING_LIMIT,
+        ) {
+            Ok(bindings) => {
+                let count = bindings.len();
+                for b in bindings {
+                    self.session_accounts
+                        .entry(b.session_id)
+                        .or_insert(b.account_id);
+                }
+                tracing::info!(
+                    "Sticky Session: Restored {} persisted session bindings",
+                    count
+                );
+            }
+            Err(e) => tracing::warn!("Sticky Session: Failed to restore bindings: {}", e),
+        }
+    }
+
+    /// 建立会话绑定
+    fn bind_session(&self, session_id: &str, account_id: &str) {
+        self.session_accounts
+            .insert(session_id.to_string(), account_id.to_string());
+        self.persist_session_binding(session_id, account_id);
+    }
+
+    /// 写入/刷新持久化绑定 (仅在开启持久化时，由后台写线程异步落盘)
+    fn persist_session_binding(&self, session_id: &str, account_id: &str) {
+        if self.session_persistence.load(Ordering::Relaxed) {
+            crate::modules::session_cache_db::queue_binding(session_id, account_id);
+        }
+    }
+
+    /// 解除会话绑定
+    fn unbind_session(&self, session_id: &str) {
+        self.session_accounts.remove(session_id);
+        if self.session_persistence.load(Ordering::Relaxed) {
+            crate::modules::session_cache_db::queue_delete_binding(session_id);
+        }
+    }
+
+    /// 解除某账号的所有会话绑定
+    fn unbind_account_sessions(&self, account_id: &str) {
+        self.session_accounts.retain(|_, v| v != account_id);
+        if self.session_persistence.load(Ordering::Relaxed) {
+            crate::modules::session_cache_db::queue_delete_account_bindings(account_id);
+        }
+    }
+
+    /// 列出当前所有会话绑定 (SessionID -> AccountID)
+    pub fn list_session_bindings(&self) -> Vec<(String, String)> {
+        self.session_accounts
+            .iter()
+            .map(|e| (e.key().clone(), e.value().clone()))
+            .collect()
+    }
+
+    /// 获取指定会话绑定的账号
+    pub fn get_session_binding(&self, session_id: &str) -> Option<String> {
+        self.session_accounts.get(session_id).map(|v| v.clone())
     }
 
     /// [NEW] 更新熔断器配置
@@ -2350,15 +2555,21 @@ impl TokenManager {
         self.circuit_breaker_config.read().await.clone()
     }
 
-    /// 清除特定会话的粘性映射
-    #[allow(dead_code)]
-    pub fn clear_session_binding(&self, session_id: &str) {
-        self.session_accounts.remove(session_id);
+    /// 清除特定会话的粘性映射 (含持久化记录)
+    pub fn clear_session_binding(&self, session_id: &str) -> bool {
+        let removed = self.session_accounts.remove(session_id).is_some();
+        crate::modules::session_cache_db::flush_writes();
+        let deleted = crate::modules::session_cache_db::delete_binding(session_id).unwrap_or(false);
+        removed || deleted
     }
 
-    /// 清除所有会话的粘性映射
+    /// 清除所有会话的粘性映射 (含持久化记录)
     pub fn clear_all_sessions(&self) {
         self.session_accounts.clear();
+        crate::modules::session_cache_db::flush_writes();
+        if let Err(e) = crate::modules::session_cache_db::clear_bindings() {
+            tracing::debug!("Sticky Session: Failed to clear persisted bindings: {}", e);
+        }
     }
 
     // ===== [FIX #820] 固定账号模式相关方法 =====
@@ -2559,7 +2770,7 @@ impl TokenManager {
         account["validation_blocked_reason"] = serde_json::Value::String(reason.to_string());
 
         // Clear sticky session if blocked
-        self.session_accounts.retain(|_, v| *v != account_id);
+        self.unbind_account_sessions(account_id);
 
         let json_str = serde_json::to_string_pretty(&account)
             .map_err(|e| format!("Failed to serialize account JSON: {}", e))?;
@@ -2573,6 +2784,12 @@ impl TokenManager {
             block_until,
             reason
         );
+        publish_account_event(
+            crate::modules::event_bus::EVENT_ACCOUNT_VALIDATION_BLOCKED,
+            account_id,
+            &account,
+            serde_json::json!({ "reason": reason, "blocked_until": block_until }),
+        );
 
         Ok(())
     }
@@ -2618,7 +2835,7 @@ impl TokenManager {
         }
 
         // Clear sticky session if forbidden
-        self.session_accounts.retain(|_, v| *v != account_id);
+        self.unbind_account_sessions(account_id);
 
         let json_str = serde_json::to_string_pretty(&account)
             .map_err(|e| format!("Failed to serialize account JSON: {}", e))?;
@@ -2634,11 +2851,37 @@ impl TokenManager {
             account_id,
             truncate_reason(reason, 100)
         );
+        publish_account_event(
+            crate::modules::event_bus::EVENT_ACCOUNT_FORBIDDEN,
+            account_id,
+            &account,
+            serde_json::json!({ "reason": truncate_reason(reason, 800) }),
+        );
 
         Ok(())
     }
 }
 
+/// 发布账号生命周期事件 (Webhook / /api/events)，`extra` 中的字段合并进 payload
+fn publish_account_event(
+    event_type: &str,
+    account_id: &str,
+    account_json: &serde_json::Value,
+    extra: serde_json::Value,
+) {
+    let email = account_json
+        .get("email")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    let mut payload = serde_json::json!({ "account_id": account_id, "email": email });
+    if let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra.as_object()) {
+        payload.extend(extra.clone());
+    }
+    crate::modules::event_bus::publish(
+        crate::modules::event_bus::AppEvent::new(event_type, payload).with_account(email),
+    );
+}
+
 /// 截断过长的原因字符串
 fn truncate_reason(reason: &str, max_len: usize) -> String {
     if reason.len() <= max_len {
@@ -3138,6 +3381,17 @@ mod tests {
         assert_eq!(tokens[1].email, "a@test.com");
     }
 
+    #[tokio::test]
+    async fn test_get_token_empty_pool_is_typed_error() {
+        let manager = TokenManager::new(PathBuf::from("/tmp/test"));
+        let err = manager
+            .get_token("agent", false, None, "gemini-3-flash")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TokenError::PoolEmpty));
+        assert_eq!(String::from(err), "Token pool is empty");
+    }
+
     #[test]
     fn test_extract_earliest_reset_time() {
         let manager = TokenManager::new(PathBuf::from("/tmp/test"));
@@ -0,0 +1,236 @@
+//! Unix 域套接字监听 (本机单用户场景)
+//!
+//! - 与 TCP 监听共用同一路由、中间件与停止信号
+//! - 套接字连接视为本机回环访问 (注入 127.0.0.1 作为对端地址)，访问控制依赖文件权限
+//! - exclusive 模式下不绑定 TCP 端口，内部预热等本机调用改走套接字
+
+use crate::proxy::config::UnixSocketConfig;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+/// 套接字 base URL 前缀 (unix:///path/to/proxy.sock)
+pub const SOCKET_URL_SCHEME: &str = "unix://";
+
+const DEFAULT_SOCKET_FILE: &str = "proxy.sock";
+
+#[cfg(unix)]
+pub type LocalStream = tokio::net::UnixStream;
+/// 非 Unix 平台不会产生套接字连接，仅用于保持类型一致
+#[cfg(not(unix))]
+pub type LocalStream = tokio::net::TcpStream;
+
+/// 套接字连接注入的对端地址
+pub fn peer_addr() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 0))
+}
+
+/// 解析套接字路径 (为空时使用数据目录下的 proxy.sock)
+pub fn socket_path(config: &UnixSocketConfig) -> Result<PathBuf, String> {
+    match config.path.as_deref().map(str::trim) {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Ok(crate::modules::account::get_data_dir()?.join(DEFAULT_SOCKET_FILE)),
+    }
+}
+
+/// 解析八进制权限 ("600" / "0600" / "0o600")
+pub fn parse_mode(mode: &str) -> Result<u32, String> {
+    let digits = mode.trim().trim_start_matches("0o");
+    u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|m| *m <= 0o777)
+        .ok_or_else(|| format!("无效的套接字权限: {:?}", mode))
+}
+
+/// 套接字形式的 base URL，suffix 为 API 前缀 (如 "/v1")
+pub fn socket_base_url(path: &Path, suffix: &str) -> String {
+    format!("{}{}{}", SOCKET_URL_SCHEME, path.display(), suffix)
+}
+
+pub fn is_socket_url(url: &str) -> bool {
+    url.trim().starts_with(SOCKET_URL_SCHEME)
+}
+
+/// exclusive 模式下的套接字路径 (此时本机调用无法使用 TCP 端口)
+pub fn exclusive_socket_path() -> Option<PathBuf> {
+    if !cfg!(unix) {
+        return None;
+    }
+    let config = crate::modules::config::load_app_config()
+        .ok()?
+        .proxy
+        .unix_socket;
+    if config.enabled && config.exclusive {
+        socket_path(&config).ok()
+    } else {
+        None
+    }
+}
+
+/// Unix 域套接字监听器，释放时删除套接字文件
+pub struct LocalListener {
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    path: PathBuf,
+}
+
+impl LocalListener {
+    #[cfg(unix)]
+    pub fn bind(config: &UnixSocketConfig) -> Result<Self, String> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        let path = socket_path(config)?;
+        let mode = parse_mode(&config.mode)?;
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("创建套接字目录失败: {}", e))?;
+                let _ = std::fs::set_permissions(parent, std::fs::Permissions::from_mode(0o700));
+            }
+        }
+
+        // 清理上次异常退出遗留的套接字，仍有进程监听时拒绝启动
+        if let Ok(meta) = std::fs::symlink_metadata(&path) {
+            if !meta.file_type().is_socket() {
+                return Err(format!("{} 已存在且不是套接字文件", path.display()));
+            }
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Err(format!("套接字 {} 正在被其他进程使用", path.display()));
+            }
+            std::fs::remove_file(&path).map_err(|e| format!("删除遗留套接字失败: {}", e))?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(&path)
+            .map_err(|e| format!("套接字 {} 绑定失败: {}", path.display(), e))?;
+        if let Err(e) = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)) {
+            let _ = std::fs::remove_file(&path);
+            return Err(format!("设置套接字权限失败: {}", e));
+        }
+
+        Ok(Self { listener, path })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_config: &UnixSocketConfig) -> Result<Self, String> {
+        Err("Unix 域套接字仅支持 Unix 平台 (Linux / macOS)".to_string())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn accept(&self) -> std::io::Result<LocalStream> {
+        #[cfg(unix)]
+        {
+            self.listener.accept().await.map(|(stream, _)| stream)
+        }
+        #[cfg(not(unix))]
+        {
+            std::future::pending().await
+        }
+    }
+}
+
+impl Drop for LocalListener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// 通过套接字发送单个 HTTP/1.1 请求，返回状态码与响应文本
+#[cfg(unix)]
+pub async fn send_request(
+    path: &Path,
+    request: axum::http::Request<axum::body::Body>,
+) -> Result<(axum::http::StatusCode, String), String> {
+    let stream = tokio::net::UnixStream::connect(path)
+        .await
+        .map_err(|e| format!("连接套接字 {} 失败: {}", path.display(), e))?;
+    let (mut sender, connection) =
+        hyper::client::conn::http1::handshake(hyper_util::rt::TokioIo::new(stream))
+            .await
+            .map_err(|e| e.to_string())?;
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let response = sender
+        .send_request(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let body = axum::body::to_bytes(axum::body::Body::new(response.into_body()), usize::MAX)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((status, String::from_utf8_lossy(&body).to_string()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_parse_mode_and_urls() {
+        assert_eq!(parse_mode("600"), Ok(0o600));
+        assert_eq!(parse_mode("0660"), Ok(0o660));
+        assert_eq!(parse_mode("0o700"), Ok(0o700));
+        assert!(parse_mode("800").is_err());
+        assert!(parse_mode("1777").is_err());
+
+        let url = socket_base_url(Path::new("/run/abv/proxy.sock"), "/v1");
+        assert_eq!(url, "unix:///run/abv/proxy.sock/v1");
+        assert!(is_socket_url(&url));
+        assert!(!is_socket_url("http://127.0.0.1:8045"));
+    }
+
+    #[tokio::test]
+    async fn test_socket_listener_roundtrip() {
+        let dir = std::env::temp_dir().join(format!(
+            "abv-sock-{}",
+            &uuid::Uuid::new_v4().to_string()[..8]
+        ));
+        let config = UnixSocketConfig {
+            enabled: true,
+            path: Some(dir.join("proxy.sock").to_string_lossy().to_string()),
+            ..UnixSocketConfig::default()
+        };
+
+        // 非套接字文件不会被删除
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("proxy.sock"), "keep").unwrap();
+        assert!(LocalListener::bind(&config).is_err());
+        std::fs::remove_file(dir.join("proxy.sock")).unwrap();
+
+        let listener = LocalListener::bind(&config).unwrap();
+        let path = listener.path().to_path_buf();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        // 正在监听时拒绝重复绑定
+        assert!(LocalListener::bind(&config).is_err());
+
+        let app = axum::Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let server = tokio::spawn(async move {
+            // 第一个连接来自上面的重复绑定探测
+            for _ in 0..2 {
+                let stream = listener.accept().await.unwrap();
+                let service = hyper_util::service::TowerToHyperService::new(app.clone());
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+                    .await;
+            }
+        });
+
+        let request = axum::http::Request::get("/health")
+            .header("host", "localhost")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (status, body) = send_request(&path, request).await.unwrap();
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body, "ok");
+
+        server.await.unwrap();
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -407,6 +407,43 @@ impl UpstreamClient {
 
     // 已移除弃用的辅助方法 (parse_duration_ms)
 
+    /// 调用 v1internal countTokens 获取精确的输入 token 数
+    ///
+    /// `contents` 为 Gemini 格式的消息数组 (systemInstruction 等需由调用方自行折叠进来)
+    pub async fn count_tokens(
+        &self,
+        access_token: &str,
+        model: &str,
+        contents: Vec<Value>,
+        account_id: Option<&str>,
+    ) -> Result<u32, String> {
+        let body = serde_json::json!({
+            "request": {
+                "model": format!("models/{}", model),
+                "contents": contents,
+            }
+        });
+
+        let result = self
+            .call_v1_internal("countTokens", access_token, body, None, account_id)
+            .await?;
+        let status = result.response.status();
+        if !status.is_success() {
+            let text = result.response.text().await.unwrap_or_default();
+            return Err(format!("countTokens returned {}: {}", status, text));
+        }
+
+        let json: Value = result
+            .response
+            .json()
+            .await
+            .map_err(|e| format!("Parse json failed: {}", e))?;
+        json.get("totalTokens")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32)
+            .ok_or_else(|| format!("countTokens response missing totalTokens: {}", json))
+    }
+
     /// 获取可用模型列表
     ///
     /// 获取远端模型列表，支持多端点自动 Fallback
@@ -3,12 +3,43 @@ use aes_gcm::{
     Aes256Gcm, Nonce,
 };
 use base64::{engine::general_purpose, Engine as _};
+use rand::RngCore;
 use serde::{Deserialize, Deserializer, Serializer};
 use sha2::Digest;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 
 const FIXED_NONCE: &[u8; 12] = b"antigravsalt";
 const ENCRYPTED_PREFIX: &str = "ag_enc_";
 
+/// [NEW] 账号 Token 落盘加密前缀 (m: 设备密钥, s: 用户口令派生密钥，盐值随密文保存)
+const TOKEN_PREFIX_MACHINE: &str = "ag_tok_m_";
+const TOKEN_PREFIX_PASSPHRASE: &str = "ag_tok_s_";
+/// 旧版口令加密前缀 (所有安装共用固定盐值)，仅用于解密，迁移时重新加密
+const LEGACY_TOKEN_PREFIX_PASSPHRASE: &str = "ag_tok_p_";
+const LEGACY_TOKEN_KDF_SALT: &[u8] = b"antigravity-token-at-rest";
+
+/// 可选的用户口令：设置后账号 Token 使用口令派生的密钥加密，而不仅依赖设备 ID
+pub const TOKEN_PASSPHRASE_ENV: &str = "ABV_TOKEN_PASSPHRASE";
+
+/// 本机口令加密使用的随机盐值文件 (位于数据目录，首次使用时生成)
+const TOKEN_KDF_SALT_FILE: &str = "token_kdf.salt";
+
+/// 口令派生密钥的盐值长度
+pub const KDF_SALT_LEN: usize = 16;
+
+/// 口令派生密钥 (PBKDF2-HMAC-SHA256) 的迭代次数
+pub const PASSPHRASE_KDF_ITERATIONS: u32 = 600_000;
+
+/// 解密时接受的迭代次数范围 (导出包中的迭代次数不可信，需限制上限防止 CPU 耗尽)
+pub const PASSPHRASE_KDF_MIN_ITERATIONS: u32 = 100_000;
+pub const PASSPHRASE_KDF_MAX_ITERATIONS: u32 = 2_000_000;
+
+static TOKEN_PASSPHRASE: OnceLock<Option<String>> = OnceLock::new();
+static TOKEN_KDF_SALT: OnceLock<[u8; KDF_SALT_LEN]> = OnceLock::new();
+/// 按盐值缓存口令派生的密钥 (KDF 开销大，每个盐值只派生一次)
+static TOKEN_PASSPHRASE_KEYS: OnceLock<Mutex<HashMap<Vec<u8>, [u8; 32]>>> = OnceLock::new();
+
 /// 生成加密密钥 (基于设备 ID)
 fn get_encryption_key() -> [u8; 32] {
     // 使用设备唯一标识生成密钥
@@ -103,6 +134,213 @@ fn decrypt_string_internal(encrypted_base64: &str) -> Result<String, String> {
     String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion failed: {}", e))
 }
 
+// ============================================================================
+// [NEW] 账号 Token 落盘加密 (随机 nonce)
+// ============================================================================
+
+/// PBKDF2-HMAC-SHA256 派生口令密钥 (CPU 密集，异步上下文中需放入 spawn_blocking)
+fn derive_passphrase_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    pbkdf2::pbkdf2_hmac_array::<sha2::Sha256, 32>(passphrase.as_bytes(), salt, iterations)
+}
+
+/// 生成随机 KDF 盐值
+pub fn random_kdf_salt() -> [u8; KDF_SALT_LEN] {
+    let mut salt = [0u8; KDF_SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// 读取 `ABV_TOKEN_PASSPHRASE` (进程内缓存，未设置时为 None)
+fn token_passphrase() -> Option<&'static str> {
+    TOKEN_PASSPHRASE
+        .get_or_init(|| {
+            std::env::var(TOKEN_PASSPHRASE_ENV)
+                .ok()
+                .filter(|p| !p.is_empty())
+        })
+        .as_deref()
+}
+
+/// 使用指定盐值派生口令密钥 (未设置口令时为 None)
+fn token_passphrase_key(salt: &[u8]) -> Option<[u8; 32]> {
+    let passphrase = token_passphrase()?;
+    let keys = TOKEN_PASSPHRASE_KEYS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(key) = keys.lock().ok()?.get(salt) {
+        return Some(*key);
+    }
+    let key = derive_passphrase_key(passphrase, salt, PASSPHRASE_KDF_ITERATIONS);
+    keys.lock().ok()?.insert(salt.to_vec(), key);
+    Some(key)
+}
+
+/// 本机的口令加密盐值: 读取数据目录中的盐值文件，不存在时随机生成
+fn token_kdf_salt() -> Result<[u8; KDF_SALT_LEN], String> {
+    if let Some(salt) = TOKEN_KDF_SALT.get() {
+        return Ok(*salt);
+    }
+
+    let path = crate::modules::account::get_data_dir()?.join(TOKEN_KDF_SALT_FILE);
+    let encoded = general_purpose::STANDARD.encode(random_kdf_salt());
+    // create_new: 并发初始化时以先写入的盐值为准
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+    {
+        Ok(mut file) => std::io::Write::write_all(&mut file, encoded.as_bytes())
+            .map_err(|e| format!("Failed to write token KDF salt: {}", e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create token KDF salt: {}", e)),
+    }
+
+    let stored = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read token KDF salt: {}", e))?;
+    let salt: [u8; KDF_SALT_LEN] = general_purpose::STANDARD
+        .decode(stored.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("Invalid token KDF salt file: {:?}", path))?;
+    Ok(*TOKEN_KDF_SALT.get_or_init(|| salt))
+}
+
+/// 口令加密: 输出 `ag_tok_s_<base64(salt)>.<base64(nonce || ciphertext)>`
+fn seal_token_with_salt(key: &[u8; 32], salt: &[u8], plaintext: &str) -> Result<String, String> {
+    Ok(format!(
+        "{}{}.{}",
+        TOKEN_PREFIX_PASSPHRASE,
+        general_purpose::STANDARD.encode(salt),
+        seal(key, plaintext.as_bytes())?
+    ))
+}
+
+/// 拆分口令加密的 Token，返回 (盐值, 密文)
+fn split_salted_token(body: &str) -> Result<(Vec<u8>, &str), String> {
+    let (salt, sealed) = body
+        .split_once('.')
+        .ok_or_else(|| "Missing token KDF salt".to_string())?;
+    let salt = general_purpose::STANDARD
+        .decode(salt)
+        .map_err(|e| format!("Invalid token KDF salt: {}", e))?;
+    Ok((salt, sealed))
+}
+
+fn passphrase_required() -> String {
+    format!(
+        "Token is passphrase-encrypted but {} is not set",
+        TOKEN_PASSPHRASE_ENV
+    )
+}
+
+/// AES-GCM 加密，输出 base64(nonce || ciphertext)
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce_bytes), plaintext)
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+
+    let mut sealed = nonce_bytes.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(general_purpose::STANDARD.encode(sealed))
+}
+
+fn open(key: &[u8; 32], sealed_base64: &str) -> Result<Vec<u8>, String> {
+    let sealed = general_purpose::STANDARD
+        .decode(sealed_base64)
+        .map_err(|e| format!("Base64 decode failed: {}", e))?;
+    if sealed.len() < 12 {
+        return Err("Ciphertext too short".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(12);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| format!("Decryption failed: {}", e))
+}
+
+/// 加密账号 Token (设置了口令时使用口令密钥，否则使用设备密钥)
+pub fn encrypt_token(plaintext: &str) -> Result<String, String> {
+    if plaintext.is_empty() {
+        return Ok(String::new());
+    }
+    if token_passphrase().is_some() {
+        let salt = token_kdf_salt()?;
+        let key = token_passphrase_key(&salt).ok_or_else(passphrase_required)?;
+        return seal_token_with_salt(&key, &salt, plaintext);
+    }
+    Ok(format!(
+        "{}{}",
+        TOKEN_PREFIX_MACHINE,
+        seal(&get_encryption_key(), plaintext.as_bytes())?
+    ))
+}
+
+/// 解密账号 Token；不带加密前缀的值视为旧版明文原样返回
+pub fn decrypt_token(value: &str) -> Result<String, String> {
+    let plaintext = if let Some(body) = value.strip_prefix(TOKEN_PREFIX_PASSPHRASE) {
+        let (salt, sealed) = split_salted_token(body)?;
+        let key = token_passphrase_key(&salt).ok_or_else(passphrase_required)?;
+        open(&key, sealed)?
+    } else if let Some(body) = value.strip_prefix(LEGACY_TOKEN_PREFIX_PASSPHRASE) {
+        let key = token_passphrase_key(LEGACY_TOKEN_KDF_SALT).ok_or_else(passphrase_required)?;
+        open(&key, body)?
+    } else if let Some(body) = value.strip_prefix(TOKEN_PREFIX_MACHINE) {
+        open(&get_encryption_key(), body)?
+    } else {
+        return Ok(value.to_string());
+    };
+
+    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion failed: {}", e))
+}
+
+/// Token 是否需要 (重新) 加密：明文、旧版固定盐值，或加密方式/盐值与当前配置不一致
+pub fn token_needs_reencrypt(value: &str) -> bool {
+    if value.is_empty() {
+        return false;
+    }
+    if token_passphrase().is_none() {
+        return !value.starts_with(TOKEN_PREFIX_MACHINE);
+    }
+    match token_kdf_salt() {
+        Ok(salt) => !value.starts_with(&format!(
+            "{}{}.",
+            TOKEN_PREFIX_PASSPHRASE,
+            general_purpose::STANDARD.encode(salt)
+        )),
+        Err(_) => !value.starts_with(TOKEN_PREFIX_PASSPHRASE),
+    }
+}
+
+/// 使用口令加密任意数据 (用于账号导出包)，返回 base64(nonce || ciphertext)
+pub fn seal_with_passphrase(
+    plaintext: &[u8],
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<String, String> {
+    seal(
+        &derive_passphrase_key(passphrase, salt, iterations),
+        plaintext,
+    )
+}
+
+/// 使用口令解密，迭代次数超出允许范围时直接拒绝
+pub fn open_with_passphrase(
+    sealed_base64: &str,
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<Vec<u8>, String> {
+    if !(PASSPHRASE_KDF_MIN_ITERATIONS..=PASSPHRASE_KDF_MAX_ITERATIONS).contains(&iterations) {
+        return Err(format!("KDF iterations out of range: {}", iterations));
+    }
+    open(
+        &derive_passphrase_key(passphrase, salt, iterations),
+        sealed_base64,
+    )
+}
+
 #[allow(dead_code)]
 pub fn decrypt_string(encrypted: &str) -> Result<String, String> {
     if encrypted.starts_with(ENCRYPTED_PREFIX) {
@@ -144,4 +382,67 @@ mod tests {
         let decrypted = decrypt_string(&legacy_encrypted).unwrap();
         assert_eq!(password, decrypted);
     }
+
+    #[test]
+    fn test_token_encryption_roundtrip() {
+        let encrypted = encrypt_token("1//refresh-token").unwrap();
+        assert!(encrypted.starts_with("ag_tok_"));
+        assert!(!token_needs_reencrypt(&encrypted));
+        // 随机 nonce: 相同明文两次加密结果不同
+        assert_ne!(encrypted, encrypt_token("1//refresh-token").unwrap());
+        assert_eq!(decrypt_token(&encrypted).unwrap(), "1//refresh-token");
+
+        // 旧版明文原样返回，且需要迁移
+        assert_eq!(decrypt_token("1//plain").unwrap(), "1//plain");
+        assert!(token_needs_reencrypt("1//plain"));
+        assert!(!token_needs_reencrypt(""));
+    }
+
+    #[test]
+    fn test_salted_passphrase_token() {
+        let salt = random_kdf_salt();
+        let key = derive_passphrase_key("hunter2", &salt, 1);
+        let sealed = seal_token_with_salt(&key, &salt, "1//refresh-token").unwrap();
+
+        // 盐值随密文保存，其他安装 (不同盐值) 凭口令即可解密
+        let body = sealed.strip_prefix(TOKEN_PREFIX_PASSPHRASE).unwrap();
+        let (stored_salt, ciphertext) = split_salted_token(body).unwrap();
+        assert_eq!(stored_salt, salt);
+        let reopened = open(
+            &derive_passphrase_key("hunter2", &stored_salt, 1),
+            ciphertext,
+        );
+        assert_eq!(reopened.unwrap(), b"1//refresh-token");
+
+        // 不同安装的盐值不同，相同口令派生出不同密钥
+        let other_salt = random_kdf_salt();
+        assert_ne!(salt, other_salt);
+        assert_ne!(key, derive_passphrase_key("hunter2", &other_salt, 1));
+        assert!(split_salted_token("no-salt").is_err());
+    }
+
+    #[test]
+    fn test_passphrase_seal() {
+        let iterations = PASSPHRASE_KDF_MIN_ITERATIONS;
+        let sealed = seal_with_passphrase(b"bundle", "hunter2", b"salt", iterations).unwrap();
+        assert_eq!(
+            open_with_passphrase(&sealed, "hunter2", b"salt", iterations).unwrap(),
+            b"bundle"
+        );
+        assert!(open_with_passphrase(&sealed, "wrong", b"salt", iterations).is_err());
+        // 导出包中的迭代次数不可信: 超出范围直接拒绝，不执行 KDF
+        assert!(open_with_passphrase(&sealed, "hunter2", b"salt", u32::MAX).is_err());
+        assert!(open_with_passphrase(&sealed, "hunter2", b"salt", 1).is_err());
+    }
+
+    #[test]
+    fn test_derive_passphrase_key_pbkdf2_vector() {
+        // RFC 7914 §11 PBKDF2-HMAC-SHA256 测试向量 (P="passwd", S="salt", c=1)
+        let key = derive_passphrase_key("passwd", b"salt", 1);
+        let hex: String = key.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        );
+    }
 }
@@ -1,14 +1,12 @@
 import React, { useEffect, useState, useRef, useMemo } from 'react';
-import { listen } from '@tauri-apps/api/event';
 import ModalDialog from '../common/ModalDialog';
 import { useTranslation } from 'react-i18next';
-import { request as invoke } from '../../utils/request';
+import { request as invoke, subscribeEvent } from '../../utils/request';
 import { Trash2, Search, X, Copy, CheckCircle, ChevronLeft, ChevronRight, RefreshCw, User } from 'lucide-react';
 
 import { AppConfig } from '../../types/config';
 import { formatCompactNumber } from '../../utils/format';
 import { useAccountStore } from '../../stores/useAccountStore';
-import { isTauri } from '../../utils/env';
 import { copyToClipboard } from '../../utils/clipboard';
 
 
@@ -28,6 +26,7 @@ interface ProxyRequestLog {
     output_tokens?: number;
     account_email?: string;
     protocol?: string;  // "openai" | "anthropic" | "gemini"
+    cache_hit?: boolean; // 是否由响应缓存直接返回
 }
 
 interface ProxyStats {
@@ -316,7 +315,6 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
         let updateTimeout: number | null = null;
 
         const setupListener = async () => {
-            if (!isTauri()) return;
             // Prevent duplicate listener registration (React 18 StrictMode)
             if (listenerSetupRef.current) {
                 console.debug('[ProxyMonitor] Listener already set up, skipping...');
@@ -325,11 +323,10 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
             listenerSetupRef.current = true;
 
             console.debug('[ProxyMonitor] Setting up event listener for proxy://request');
-            unlistenFn = await listen<ProxyRequestLog>('proxy://request', (event) => {
+            // [NEW] Web 模式通过 /api/events 实时推送，不再需要轮询
+            unlistenFn = await subscribeEvent<ProxyRequestLog>('proxy://request', (newLog) => {
                 if (!isMountedRef.current) return;
 
-                const newLog = event.payload;
-
                 // 移除 body 以减少内存占用
                 const logSummary = {
                     ...newLog,
@@ -384,24 +381,11 @@ export const ProxyMonitor: React.FC<ProxyMonitorProps> = ({ className }) => {
         };
         setupListener();
 
-        // Web 模式補強：如果不是 Tauri 環境，則啟用定時輪詢
-        let pollInterval: number | null = null;
-        if (!isTauri()) {
-            console.debug('[ProxyMonitor] Web mode detected, starting auto-poll (10s)');
-            pollInterval = window.setInterval(() => {
-                if (isMountedRef.current && !loading) {
-                    // [FIX] 使用 ref.current 获取最新的筛选条件
-                    loadData(currentPageRef.current, filterRef.current, accountFilterRef.current);
-                }
-            }, 10000);
-        }
-
         return () => {
             isMountedRef.current = false;
             listenerSetupRef.current = false;
             if (unlistenFn) unlistenFn();
             if (updateTimeout) clearTimeout(updateTimeout);
-            if (pollInterval) clearInterval(pollInterval);
         };
     }, []);
 
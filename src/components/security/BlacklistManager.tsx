@@ -73,7 +73,7 @@ export const BlacklistManager: React.FC<Props> = ({ refreshKey }) => {
             if (errorMsg.includes('UNIQUE constraint')) {
                 alert(t('security.blacklist.error_duplicate') || 'This IP is already in the blacklist');
             } else if (errorMsg.includes('Invalid IP pattern')) {
-                alert(t('security.blacklist.error_invalid_ip') || 'Invalid IP format. Please use IP address or CIDR notation (e.g., 192.168.1.0/24)');
+                alert(t('security.blacklist.error_invalid_ip') || 'Invalid IP format. Please use IP address or CIDR notation (e.g., 192.168.1.0/24 or 2001:db8::/32)');
             } else {
                 alert(t('security.blacklist.error_add_failed') || 'Failed to add IP: ' + e);
             }
@@ -1,7 +1,7 @@
 import React, { useEffect, useState } from 'react';
 import { useTranslation } from 'react-i18next';
 import { request as invoke } from '../../utils/request';
-import { Save, AlertTriangle, Shield, ShieldCheck } from 'lucide-react';
+import { Save, AlertTriangle, Shield, ShieldCheck, Network, Ban } from 'lucide-react';
 import { showToast } from '../common/ToastContainer';
 
 interface IpBlacklistConfig {
@@ -14,9 +14,34 @@ interface IpWhitelistConfig {
     whitelist_priority: boolean;
 }
 
+interface TrustedProxyConfig {
+    proxies: string[];
+    trust_cloudflared: boolean;
+}
+
+interface AutoBanRule {
+    enabled: boolean;
+    threshold: number;
+    window_seconds: number;
+}
+
+interface AutoBanConfig {
+    enabled: boolean;
+    auth_failures: AutoBanRule;
+    request_flood: AutoBanRule;
+    admin_errors: AutoBanRule;
+    ban_seconds: number;
+    ban_multiplier: number;
+    max_ban_seconds: number;
+}
+
+type AutoBanRuleKey = 'auth_failures' | 'request_flood' | 'admin_errors';
+
 interface SecurityMonitorConfig {
     blacklist: IpBlacklistConfig;
     whitelist: IpWhitelistConfig;
+    trusted_proxy?: TrustedProxyConfig;
+    auto_ban?: AutoBanConfig;
 }
 
 export const SecurityConfig: React.FC = () => {
@@ -24,6 +49,7 @@ export const SecurityConfig: React.FC = () => {
     const [config, setConfig] = useState<SecurityMonitorConfig | null>(null);
     const [loading, setLoading] = useState(false);
     const [saving, setSaving] = useState(false);
+    const [trustedProxyText, setTrustedProxyText] = useState('');
 
     useEffect(() => {
         loadConfig();
@@ -34,6 +60,7 @@ export const SecurityConfig: React.FC = () => {
         try {
             const data = await invoke<SecurityMonitorConfig>('get_security_config');
             setConfig(data);
+            setTrustedProxyText((data.trusted_proxy?.proxies ?? []).join('\n'));
         } catch (e) {
             console.error('Failed to load security config', e);
             showToast(t('security.config.load_error'), 'error');
@@ -56,6 +83,30 @@ export const SecurityConfig: React.FC = () => {
         }
     };
 
+    const trustedProxy: TrustedProxyConfig = config?.trusted_proxy ?? {
+        proxies: ['127.0.0.0/8', '::1'],
+        trust_cloudflared: true,
+    };
+
+    const autoBan: AutoBanConfig = config?.auto_ban ?? {
+        enabled: false,
+        auth_failures: { enabled: true, threshold: 10, window_seconds: 300 },
+        request_flood: { enabled: true, threshold: 600, window_seconds: 60 },
+        admin_errors: { enabled: true, threshold: 30, window_seconds: 300 },
+        ban_seconds: 600,
+        ban_multiplier: 4,
+        max_ban_seconds: 7 * 24 * 3600,
+    };
+
+    const updateAutoBan = (patch: Partial<AutoBanConfig>) => {
+        if (!config) return;
+        setConfig({ ...config, auto_ban: { ...autoBan, ...patch } });
+    };
+
+    const updateAutoBanRule = (key: AutoBanRuleKey, patch: Partial<AutoBanRule>) => {
+        updateAutoBan({ [key]: { ...autoBan[key], ...patch } } as Partial<AutoBanConfig>);
+    };
+
     if (loading) {
         return <div className="p-10 text-center"><span className="loading loading-spinner"></span></div>;
     }
@@ -170,6 +221,159 @@ export const SecurityConfig: React.FC = () => {
                     </div>
                 </div>
             </div>
+
+            {/* Trusted Proxy Settings */}
+            <div className="card bg-base-100 border border-gray-200 dark:border-base-300 shadow-sm">
+                <div className="card-body">
+                    <h3 className="card-title flex items-center gap-2 text-blue-500">
+                        <Network size={24} />
+                        {t('security.config.trusted_proxy_title')}
+                    </h3>
+                    <p className="text-sm text-gray-500 mb-4">{t('security.config.trusted_proxy_desc')}</p>
+
+                    <div className="form-control w-full">
+                        <label className="label">
+                            <span className="label-text">{t('security.config.trusted_proxies_label')}</span>
+                        </label>
+                        <textarea
+                            className="textarea textarea-bordered w-full font-mono text-sm"
+                            rows={4}
+                            value={trustedProxyText}
+                            onChange={(e) => {
+                                setTrustedProxyText(e.target.value);
+                                setConfig({
+                                    ...config,
+                                    trusted_proxy: {
+                                        ...trustedProxy,
+                                        proxies: e.target.value.split('\n').map(s => s.trim()).filter(Boolean)
+                                    }
+                                });
+                            }}
+                        />
+                        <label className="label">
+                            <span className="label-text-alt text-gray-400">{t('security.config.trusted_proxies_desc')}</span>
+                        </label>
+                    </div>
+
+                    <div className="form-control mt-2">
+                        <label className="label cursor-pointer justify-start gap-4">
+                            <input
+                                type="checkbox"
+                                className="checkbox checkbox-info"
+                                checked={trustedProxy.trust_cloudflared}
+                                onChange={(e) => setConfig({
+                                    ...config,
+                                    trusted_proxy: { ...trustedProxy, trust_cloudflared: e.target.checked }
+                                })}
+                            />
+                            <span className="label-text font-medium">{t('security.config.trust_cloudflared')}</span>
+                        </label>
+                    </div>
+                </div>
+            </div>
+
+            {/* Auto-ban Settings */}
+            <div className="card bg-base-100 border border-gray-200 dark:border-base-300 shadow-sm">
+                <div className="card-body">
+                    <h3 className="card-title flex items-center gap-2 text-orange-500">
+                        <Ban size={24} />
+                        {t('security.config.auto_ban_title')}
+                    </h3>
+                    <p className="text-sm text-gray-500 mb-4">{t('security.config.auto_ban_desc')}</p>
+
+                    <div className="form-control">
+                        <label className="label cursor-pointer justify-start gap-4">
+                            <input
+                                type="checkbox"
+                                className="toggle toggle-warning"
+                                checked={autoBan.enabled}
+                                onChange={(e) => updateAutoBan({ enabled: e.target.checked })}
+                            />
+                            <span className="label-text font-medium">{t('security.config.enable_auto_ban')}</span>
+                        </label>
+                    </div>
+
+                    <div className="mt-4 space-y-2">
+                        {(['auth_failures', 'request_flood', 'admin_errors'] as AutoBanRuleKey[]).map((key) => (
+                            <div key={key} className="flex flex-wrap items-center gap-3">
+                                <label className="label cursor-pointer justify-start gap-3 w-56">
+                                    <input
+                                        type="checkbox"
+                                        className="checkbox checkbox-warning checkbox-sm"
+                                        checked={autoBan[key].enabled}
+                                        disabled={!autoBan.enabled}
+                                        onChange={(e) => updateAutoBanRule(key, { enabled: e.target.checked })}
+                                    />
+                                    <span className="label-text">{t(`security.config.auto_ban_rule_${key}`)}</span>
+                                </label>
+                                <input
+                                    type="number"
+                                    min={1}
+                                    className="input input-bordered input-sm w-24"
+                                    value={autoBan[key].threshold}
+                                    disabled={!autoBan.enabled}
+                                    onChange={(e) => updateAutoBanRule(key, { threshold: Number(e.target.value) || 1 })}
+                                />
+                                <span className="text-sm text-gray-500">{t('security.config.auto_ban_within')}</span>
+                                <input
+                                    type="number"
+                                    min={1}
+                                    className="input input-bordered input-sm w-24"
+                                    value={autoBan[key].window_seconds}
+                                    disabled={!autoBan.enabled}
+                                    onChange={(e) => updateAutoBanRule(key, { window_seconds: Number(e.target.value) || 1 })}
+                                />
+                                <span className="text-sm text-gray-500">{t('security.config.auto_ban_seconds')}</span>
+                            </div>
+                        ))}
+                    </div>
+
+                    <div className="grid grid-cols-1 md:grid-cols-3 gap-4 mt-4">
+                        <div className="form-control">
+                            <label className="label">
+                                <span className="label-text">{t('security.config.auto_ban_duration')}</span>
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                className="input input-bordered input-sm"
+                                value={autoBan.ban_seconds}
+                                disabled={!autoBan.enabled}
+                                onChange={(e) => updateAutoBan({ ban_seconds: Number(e.target.value) || 1 })}
+                            />
+                        </div>
+                        <div className="form-control">
+                            <label className="label">
+                                <span className="label-text">{t('security.config.auto_ban_multiplier')}</span>
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                className="input input-bordered input-sm"
+                                value={autoBan.ban_multiplier}
+                                disabled={!autoBan.enabled}
+                                onChange={(e) => updateAutoBan({ ban_multiplier: Number(e.target.value) || 1 })}
+                            />
+                        </div>
+                        <div className="form-control">
+                            <label className="label">
+                                <span className="label-text">{t('security.config.auto_ban_max_duration')}</span>
+                            </label>
+                            <input
+                                type="number"
+                                min={1}
+                                className="input input-bordered input-sm"
+                                value={autoBan.max_ban_seconds}
+                                disabled={!autoBan.enabled}
+                                onChange={(e) => updateAutoBan({ max_ban_seconds: Number(e.target.value) || 1 })}
+                            />
+                        </div>
+                    </div>
+                    <label className="label">
+                        <span className="label-text-alt text-gray-400">{t('security.config.auto_ban_exempt_desc')}</span>
+                    </label>
+                </div>
+            </div>
         </div>
     );
 };
@@ -21,7 +21,11 @@ import DeviceFingerprintDialog from "../components/accounts/DeviceFingerprintDia
 import ModalDialog from "../components/common/ModalDialog";
 import Pagination from "../components/common/Pagination";
 import { showToast } from "../components/common/ToastContainer";
-import { exportAccounts } from "../services/accountService";
+import {
+  exportAccounts,
+  isEncryptedAccountBundle,
+  openAccountBundle,
+} from "../services/accountService";
 import { useAccountStore } from "../stores/useAccountStore";
 import { useConfigStore } from "../stores/useConfigStore";
 import { Account } from "../types/account";
@@ -543,17 +547,33 @@ function Accounts() {
         return;
       }
 
-      // 1. Get export data from API (contains refresh_token)
+      // [NEW] 导出口令 (必填)：refresh_token 仅以加密包形式导出
+      const passphrase = window.prompt(
+        t("accounts.export_passphrase_prompt", {
+          defaultValue: "Enter a passphrase to encrypt the export",
+        }),
+      );
+      if (passphrase === null) return; // Cancelled
+      if (!passphrase) {
+        showToast(
+          t("accounts.export_passphrase_required", {
+            defaultValue: "A passphrase is required to export accounts",
+          }),
+          "warning",
+        );
+        return;
+      }
+
+      // 1. Get encrypted export bundle from API (contains refresh_token)
       const accountIds = accountsToExport.map((acc) => acc.id);
-      const response = await exportAccounts(accountIds);
+      const response = await exportAccounts(accountIds, passphrase);
 
-      if (!response.accounts || response.accounts.length === 0) {
+      if (response.count === 0) {
         showToast(t("dashboard.toast.export_no_accounts"), "warning");
         return;
       }
 
-      const exportData = response.accounts;
-      const content = JSON.stringify(exportData, null, 2);
+      const content = JSON.stringify(response.bundle, null, 2);
       const fileName = `antigravity_accounts_${new Date().toISOString().split("T")[0]}.json`;
 
       // 2. Determine Path & Export
@@ -631,6 +651,22 @@ function Accounts() {
       return;
     }
 
+    // [NEW] 加密导出包：先输入口令解密
+    if (isEncryptedAccountBundle(importData)) {
+      const passphrase = window.prompt(
+        t("accounts.import_passphrase_prompt", {
+          defaultValue: "Enter the passphrase for this encrypted export",
+        }),
+      );
+      if (!passphrase) return;
+      try {
+        importData = await openAccountBundle(importData, passphrase);
+      } catch (error) {
+        showToast(`${t("common.error")}: ${error}`, "error");
+        return;
+      }
+    }
+
     if (!Array.isArray(importData) || importData.length === 0) {
       showToast(t("accounts.import_invalid_format"), "error");
       return;
@@ -1870,6 +1870,21 @@ print(response.text)`;
                                                 </div>
                                             </div>
 
+                                            <div className="bg-slate-100 dark:bg-slate-800/80 rounded-xl p-4 border border-slate-200 dark:border-slate-700">
+                                                <div className="flex items-center justify-between">
+                                                    <label className="text-xs font-medium text-gray-700 dark:text-gray-300 inline-flex items-center gap-1">
+                                                        {t('proxy.config.scheduling.persist_sessions', { defaultValue: 'Persist Sessions' })}
+                                                        <HelpTooltip text={t('proxy.config.scheduling.persist_sessions_tooltip', { defaultValue: 'Store session bindings and thinking signatures in SQLite so long-running sessions survive proxy restarts.' })} />
+                                                    </label>
+                                                    <input
+                                                        type="checkbox"
+                                                        className="toggle toggle-sm toggle-primary"
+                                                        checked={!!appConfig.proxy.scheduling?.persist_sessions}
+                                                        onChange={(e) => updateSchedulingConfig({ persist_sessions: e.target.checked })}
+                                                    />
+                                                </div>
+                                            </div>
+
                                             <div className="p-3 bg-amber-50 dark:bg-amber-900/10 border border-amber-100 dark:border-amber-900/20 rounded-xl">
                                                 <p className="text-[10px] text-amber-700 dark:text-amber-500 leading-relaxed">
                                                     <strong>{t('common.info')}:</strong> {t('proxy.config.scheduling.subtitle')}
@@ -119,17 +119,28 @@ function Dashboard() {
                 return;
             }
 
-            // Get export data from API (contains refresh_token)
+            // [NEW] 导出口令 (必填)：refresh_token 仅以加密包形式导出
+            const passphrase = window.prompt(t('accounts.export_passphrase_prompt', {
+                defaultValue: 'Enter a passphrase to encrypt the export',
+            }));
+            if (passphrase === null) return;
+            if (!passphrase) {
+                showToast(t('accounts.export_passphrase_required', {
+                    defaultValue: 'A passphrase is required to export accounts',
+                }), 'warning');
+                return;
+            }
+
+            // Get encrypted export bundle from API (contains refresh_token)
             const accountIds = accountsToExport.map(acc => acc.id);
-            const response = await exportAccounts(accountIds);
+            const response = await exportAccounts(accountIds, passphrase);
 
-            if (!response.accounts || response.accounts.length === 0) {
+            if (response.count === 0) {
                 showToast(t('dashboard.toast.export_no_accounts'), 'warning');
                 return;
             }
 
-            const exportData = response.accounts;
-            const content = JSON.stringify(exportData, null, 2);
+            const content = JSON.stringify(response.bundle, null, 2);
             const fileName = `antigravity_accounts_${new Date().toISOString().split('T')[0]}.json`;
 
             if (isTauri()) {
@@ -22,6 +22,12 @@ interface UserToken {
     last_used_at?: number;
     total_requests: number;
     total_tokens_used: number;
+    rate_limit_rpm: number;
+    daily_token_limit: number;
+    monthly_token_limit: number;
+    total_token_limit: number;
+    allowed_models: string[];
+    denied_models: string[];
 }
 
 interface UserTokenStats {
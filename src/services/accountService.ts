@@ -182,12 +182,30 @@ export interface ExportAccountItem {
     refresh_token: string;
 }
 
+// [NEW] 口令加密的导出包
+export interface EncryptedAccountBundle {
+    format: 'antigravity-accounts';
+    version: number;
+    iterations: number;
+    salt: string;
+    data: string;
+}
+
 export interface ExportAccountsResponse {
-    accounts: ExportAccountItem[];
+    count: number;
+    bundle: EncryptedAccountBundle;
+}
+
+export async function exportAccounts(accountIds: string[], passphrase: string): Promise<ExportAccountsResponse> {
+    return await invoke('export_accounts', { accountIds, passphrase });
+}
+
+export function isEncryptedAccountBundle(data: unknown): data is EncryptedAccountBundle {
+    return !!data && typeof data === 'object' && (data as any).format === 'antigravity-accounts';
 }
 
-export async function exportAccounts(accountIds: string[]): Promise<ExportAccountsResponse> {
-    return await invoke('export_accounts', { accountIds });
+export async function openAccountBundle(bundle: EncryptedAccountBundle, passphrase: string): Promise<ExportAccountItem[]> {
+    return await invoke('open_account_bundle', { bundle, passphrase });
 }
 
 // 自定义标签相关
@@ -31,6 +31,14 @@ export interface ProxyConfig {
     image_thinking_mode?: 'enabled' | 'disabled'; // [NEW] 图像思维模式开关
     claude_thinking_mapping?: boolean; // [NEW] Claude thinking 映射开关
     endpoint_proxy?: EndpointProxyConfig; // [NEW] 端点代理配置
+    model_fallbacks?: Record<string, string[]>; // [NEW] 模型降级链
+    providers?: UpstreamProviderConfig[]; // [NEW] 通用上游提供商
+    batch?: BatchConfig; // [NEW] 批处理执行器 (/v1/batches)
+    webhooks?: WebhookConfig[]; // [NEW] 出站 Webhook (账号 / 配额生命周期事件)
+    audit_log?: AuditLogConfig; // [NEW] 管理接口审计日志
+    response_cache?: ResponseCacheConfig; // [NEW] 确定性请求响应缓存
+    tls?: TlsConfig; // [NEW] 监听端口 TLS / HTTP/2 (需重启服务生效)
+    unix_socket?: UnixSocketConfig; // [NEW] Unix 域套接字监听 (需重启服务生效)
     proxy_pool?: ProxyPoolConfig;
 }
 
@@ -102,6 +110,7 @@ export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst';
 export interface StickySessionConfig {
     mode: SchedulingMode;
     max_wait_seconds: number;
+    persist_sessions?: boolean;
 }
 
 export type ZaiDispatchMode = 'off' | 'exclusive' | 'pooled' | 'fallback';
@@ -129,6 +138,73 @@ export interface ZaiConfig {
     mcp: ZaiMcpConfig;
 }
 
+export type ProviderProtocol = 'openai_chat' | 'anthropic_messages';
+
+export type ProviderAuthStyle = 'bearer' | 'x_api_key' | 'api_key' | 'none';
+
+export interface UpstreamProviderConfig {
+    id: string;
+    name?: string;
+    enabled: boolean;
+    base_url: string;
+    api_key?: string;
+    auth_style?: ProviderAuthStyle;
+    protocol?: ProviderProtocol;
+    dispatch_mode?: ZaiDispatchMode;
+    model_mapping?: Record<string, string>;
+    extra_headers?: Record<string, string>;
+    query_params?: Record<string, string>;
+}
+
+export interface BatchConfig {
+    max_concurrency: number;
+    rate_limit_backoff_secs: number;
+    max_attempts: number;
+}
+
+export type WebhookFormat = 'generic' | 'slack' | 'discord';
+
+export interface WebhookConfig {
+    id: string;
+    name?: string;
+    enabled: boolean;
+    url: string;
+    format?: WebhookFormat;
+    secret?: string;
+    events?: string[]; // 支持前缀通配，如 "account://*"；留空订阅全部生命周期事件
+    max_retries?: number;
+    headers?: Record<string, string>;
+}
+
+export interface AuditLogConfig {
+    enabled: boolean;
+    retention_days: number; // 0 表示永久保留
+}
+
+export interface ResponseCacheConfig {
+    enabled: boolean;
+    ttl_seconds: number;
+    max_entries: number;
+    max_entry_bytes: number;
+    deterministic_only: boolean; // 仅缓存 temperature 为 0 的请求
+}
+
+export interface TlsConfig {
+    enabled: boolean;
+    cert_path?: string; // PEM 证书链
+    key_path?: string; // PEM 私钥
+    self_signed: boolean; // 未配置证书时自动生成自签名证书
+    http2: boolean;
+    allow_loopback_plaintext: boolean; // 本机回环地址允许明文 HTTP
+}
+
+export interface UnixSocketConfig {
+    enabled: boolean;
+    path?: string; // 为空时使用数据目录下的 proxy.sock
+    mode: string; // 八进制权限，如 "600"
+    exclusive: boolean; // 仅监听套接字，不绑定 TCP 端口
+}
+
 export interface ScheduledWarmupConfig {
     enabled: boolean;
     monitored_models: string[];
@@ -20,6 +20,7 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'warm_up_account': { url: '/api/accounts/:accountId/warmup', method: 'POST' },
   'update_account_label': { url: '/api/accounts/:accountId/label', method: 'POST' },
   'export_accounts': { url: '/api/accounts/export', method: 'POST' },
+  'open_account_bundle': { url: '/api/accounts/import-bundle', method: 'POST' },
   'bind_device_profile': { url: '/api/accounts/:accountId/bind-device', method: 'POST' },
   'get_device_profiles': { url: '/api/accounts/:accountId/device-profiles', method: 'GET' },
   'list_device_versions': { url: '/api/accounts/:accountId/device-versions', method: 'GET' },
@@ -53,6 +54,7 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'get_proxy_logs_count_filtered': { url: '/api/logs/count', method: 'GET' },
   'clear_proxy_logs': { url: '/api/logs/clear', method: 'POST' },
   'get_proxy_log_detail': { url: '/api/logs/:logId', method: 'GET' },
+  'replay_proxy_log': { url: '/api/logs/:logId/replay', method: 'POST' },
 
   // Debug Console
   'enable_debug_console': { url: '/api/debug/enable', method: 'POST' },
@@ -144,6 +146,7 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'check_ip_in_whitelist': { url: '/api/security/whitelist/check', method: 'GET' },
   'get_security_config': { url: '/api/security/config', method: 'GET' },
   'update_security_config': { url: '/api/security/config', method: 'POST' },
+  'get_auto_bans': { url: '/api/security/auto-bans', method: 'GET' },
   // User Tokens
   'list_user_tokens': { url: '/api/user-tokens', method: 'GET' },
   'get_user_token_summary': { url: '/api/user-tokens/summary', method: 'GET' },
@@ -264,3 +267,27 @@ export async function request<T>(cmd: string, args?: any): Promise<T> {
     throw error;
   }
 }
+
+// [NEW] 订阅后端事件：Tauri 环境使用 listen，Web 环境使用 /api/events (SSE)
+export async function subscribeEvent<T>(event: string, handler: (payload: T) => void): Promise<() => void> {
+  if (isTauri) {
+    const { listen } = await import('@tauri-apps/api/event');
+    return listen<T>(event, (e) => handler(e.payload));
+  }
+
+  // EventSource 无法设置请求头，凭证通过 token 查询参数传递
+  const apiKey = typeof window !== 'undefined' ? sessionStorage.getItem('abv_admin_api_key') : null;
+  const params = new URLSearchParams({ types: event });
+  if (apiKey) params.set('token', apiKey);
+
+  const source = new EventSource(`/api/events?${params.toString()}`);
+  source.addEventListener(event, (e) => {
+    try {
+      const data = JSON.parse((e as MessageEvent).data);
+      handler(data.payload as T);
+    } catch (error) {
+      console.warn(`Failed to parse event [${event}]:`, error);
+    }
+  });
+  return () => source.close();
+}